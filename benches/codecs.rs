@@ -0,0 +1,43 @@
+//! Cost of saving and loading recordings with the built-in codecs.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use egui_replay::codec::{BincodeCodec, JsonCodec, ReplayCodec};
+use egui_replay::replay_events::FrameEvents;
+use egui_replay::replay_file::ReplayFile;
+use egui_replay::timestamp::{NanoDelta, NanoTimestamp};
+
+// A minute of input at 60 frames per second.
+fn sample_file() -> ReplayFile {
+    let frames = (0..3_600i64)
+        .map(|i| {
+            let time = NanoTimestamp::zero() + NanoDelta::from_millis_safe(16) * i;
+            let pos = egui::pos2((i % 800) as f32, (i % 600) as f32);
+            let events = vec![egui::Event::PointerMoved(pos), egui::Event::Text("a".to_string())];
+            FrameEvents::new(time, events)
+        })
+        .collect();
+    ReplayFile::new(frames)
+}
+
+fn codecs(c: &mut Criterion) {
+    let file = sample_file();
+    let codecs: [(&str, &dyn ReplayCodec); 2] = [("bincode", &BincodeCodec), ("json", &JsonCodec)];
+    for (name, codec) in codecs {
+        let mut encoded = Vec::new();
+        codec.encode(&file, &mut encoded).unwrap();
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_function("encode", |b| {
+            b.iter(|| {
+                let mut bytes = Vec::with_capacity(encoded.len());
+                codec.encode(&file, &mut bytes).unwrap();
+                bytes
+            })
+        });
+        group.bench_function("decode", |b| b.iter(|| codec.decode(&encoded).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, codecs);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+//! Cost of `ReplayManager::on_raw_input_update` per frame.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use egui_replay::replay_events::ReplayManager;
+use egui_replay::timestamp::NanoTimestamp;
+
+// Input of a frame in an input-heavy app, moving the pointer while typing.
+fn busy_raw_input() -> egui::RawInput {
+    let mut events: Vec<egui::Event> = (0..16)
+        .map(|i| egui::Event::PointerMoved(egui::pos2(i as f32, 2.0 * i as f32)))
+        .collect();
+    events.push(egui::Event::Key {
+        key: egui::Key::A,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: egui::Modifiers::NONE,
+    });
+    events.push(egui::Event::Text("a".to_string()));
+    egui::RawInput {
+        events,
+        ..Default::default()
+    }
+}
+
+fn on_raw_input_update(c: &mut Criterion) {
+    let ctx = egui::Context::default();
+    let raw_input = busy_raw_input();
+    let now = NanoTimestamp::from_secs_safe(1);
+    let mut group = c.benchmark_group("on_raw_input_update");
+
+    group.bench_function("idle", |b| {
+        let mut manager = ReplayManager::new();
+        b.iter_batched_ref(
+            || raw_input.clone(),
+            |raw_input| manager.on_raw_input_update(now, &ctx, raw_input),
+            BatchSize::SmallInput,
+        )
+    });
+    // A new manager per frame, so that the recording doesn't grow.
+    group.bench_function("recording", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut manager = ReplayManager::new();
+                manager.start_recording(NanoTimestamp::zero());
+                (manager, raw_input.clone())
+            },
+            |(manager, raw_input)| manager.on_raw_input_update(now, &ctx, raw_input),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, on_raw_input_update);
+criterion_main!(benches);
@@ -0,0 +1,256 @@
+//! Aggregate analytics over many recordings, e.g. of a usability study.
+//!
+//! [`ReplayAnalytics::from_dir`] loads every recording in a directory and
+//! computes where users click most, how long their sessions are, which types
+//! of events they produce and how much time passes between their key presses
+//! and clicks. The analytics can be shown in a panel or written as CSV.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use egui::Rect;
+
+use crate::codec::registered_codecs;
+use crate::heatmap::InteractionHeatmap;
+use crate::replay_events::event_kind;
+use crate::replay_file::{ReplayError, ReplayFile};
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// Number of regions listed by [`ReplayAnalytics::show`] and
+/// [`ReplayAnalytics::to_csv`].
+pub const NUM_TOP_REGIONS: usize = 10;
+
+/// Analytics of a set of recordings, see the module docs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayAnalytics {
+    pub num_recordings: usize,
+    /// Recordings which failed to load, with the error.
+    pub failed: Vec<(PathBuf, String)>,
+    /// Recorded time of all sessions, without their pauses.
+    pub total_duration: NanoDelta,
+    /// Number of events of each type, keyed by [`event_kind`].
+    pub events_per_type: BTreeMap<&'static str, usize>,
+    /// Time between consecutive key presses and clicks within a session,
+    /// without pauses.
+    pub interaction_intervals: Vec<NanoDelta>,
+    pub heatmap: InteractionHeatmap,
+}
+
+impl ReplayAnalytics {
+    /// Analytics of all recordings in `dir`, i.e. the files with the
+    /// extension of a registered codec. Recordings which fail to load are
+    /// listed in [`ReplayAnalytics::failed`].
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let codecs = registered_codecs();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && codecs.codec_for_path(path).is_ok())
+            .collect();
+        paths.sort();
+        let mut analytics = Self::default();
+        for path in paths {
+            match ReplayFile::load(&path) {
+                Ok(recording) => analytics.add_recording(&recording),
+                Err(err) => {
+                    log::warn!("Skipping {} in the analytics: {}", path.display(), err);
+                    analytics.failed.push((path, err.to_string()));
+                }
+            }
+        }
+        Ok(analytics)
+    }
+
+    pub fn add_recording(&mut self, recording: &ReplayFile) {
+        self.num_recordings += 1;
+        if let (Some(start), Some(end)) = (recording.start_time(), recording.end_time()) {
+            self.total_duration = self.total_duration + (end - start - recording.paused_between(start, end));
+        }
+        let mut last_interaction: Option<NanoTimestamp> = None;
+        for frame in recording.frames.iter() {
+            for event in frame.events.iter() {
+                *self.events_per_type.entry(event_kind(event)).or_default() += 1;
+                if !is_interaction(event) {
+                    continue;
+                }
+                if let Some(last) = last_interaction {
+                    let interval = frame.time - last - recording.paused_between(last, frame.time);
+                    self.interaction_intervals.push(interval);
+                }
+                last_interaction = Some(frame.time);
+            }
+        }
+        self.heatmap.add_frames(&recording.frames);
+    }
+
+    pub fn average_session_length(&self) -> NanoDelta {
+        match self.num_recordings {
+            0 => NanoDelta::zero(),
+            n => self.total_duration / n as i64,
+        }
+    }
+
+    pub fn average_interaction_interval(&self) -> Option<NanoDelta> {
+        let total: i64 = self.interaction_intervals.iter().map(NanoDelta::as_nanos).sum();
+        let len = self.interaction_intervals.len() as i64;
+        (len > 0).then(|| NanoDelta::from_nanos(total) / len)
+    }
+
+    pub fn median_interaction_interval(&self) -> Option<NanoDelta> {
+        let mut intervals = self.interaction_intervals.clone();
+        intervals.sort();
+        intervals.get(intervals.len() / 2).copied()
+    }
+
+    /// Share of all events of each type, from 0 to 1.
+    pub fn event_type_shares(&self) -> BTreeMap<&'static str, f32> {
+        let total: usize = self.events_per_type.values().sum();
+        self.events_per_type
+            .iter()
+            .map(|(kind, count)| (*kind, *count as f32 / total as f32))
+            .collect()
+    }
+
+    /// The regions with the most clicks, most clicked first.
+    pub fn most_clicked_regions(&self) -> Vec<(Rect, u32)> {
+        self.heatmap.most_clicked(NUM_TOP_REGIONS)
+    }
+
+    /// The analytics as CSV with the columns `metric`, `name` and `value`.
+    /// Durations are in milliseconds, regions are `min_x min_y max_x max_y`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        // Writing into a String cannot fail.
+        let _ = writeln!(csv, "metric,name,value");
+        let _ = writeln!(csv, "recordings,,{}", self.num_recordings);
+        let _ = writeln!(csv, "failed_recordings,,{}", self.failed.len());
+        let _ = writeln!(csv, "total_duration_ms,,{}", self.total_duration.as_millis());
+        let _ = writeln!(
+            csv,
+            "average_session_length_ms,,{}",
+            self.average_session_length().as_millis()
+        );
+        if let Some(average) = self.average_interaction_interval() {
+            let _ = writeln!(csv, "average_interaction_interval_ms,,{}", average.as_millis());
+        }
+        if let Some(median) = self.median_interaction_interval() {
+            let _ = writeln!(csv, "median_interaction_interval_ms,,{}", median.as_millis());
+        }
+        for (kind, count) in self.events_per_type.iter() {
+            let _ = writeln!(csv, "events,{},{}", kind, count);
+        }
+        for (rect, clicks) in self.most_clicked_regions() {
+            let (min, max) = (rect.min, rect.max);
+            let _ = writeln!(csv, "clicked_region,{} {} {} {},{}", min.x, min.y, max.x, max.y, clicks);
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        std::fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+
+    /// Show the analytics as a two column grid, with the heatmap below.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let millis = |delta: Option<NanoDelta>| match delta {
+            Some(delta) => format!("{} ms", delta.as_millis()),
+            None => "-".to_string(),
+        };
+        egui::Grid::new("replay_analytics").num_columns(2).show(ui, |ui| {
+            ui.label("Recordings");
+            ui.label(self.num_recordings.to_string());
+            ui.end_row();
+            if !self.failed.is_empty() {
+                ui.label("Failed to load");
+                ui.label(self.failed.len().to_string());
+                ui.end_row();
+            }
+            ui.label("Average session");
+            ui.label(format!(
+                "{:.1} s",
+                self.average_session_length().as_millis() as f64 / 1000.0
+            ));
+            ui.end_row();
+            ui.label("Between interactions");
+            ui.label(format!(
+                "{} average, {} median",
+                millis(self.average_interaction_interval()),
+                millis(self.median_interaction_interval())
+            ));
+            ui.end_row();
+            for (kind, share) in self.event_type_shares() {
+                ui.label(kind);
+                ui.label(format!("{:.1} %", 100.0 * share));
+                ui.end_row();
+            }
+        });
+        egui::CollapsingHeader::new("Most clicked regions").show(ui, |ui| {
+            for (rect, clicks) in self.most_clicked_regions() {
+                ui.label(format!("{} clicks at {:?}", clicks, rect));
+            }
+        });
+        self.heatmap.show(ui);
+    }
+}
+
+// Key presses and clicks, without key repeats.
+fn is_interaction(event: &egui::Event) -> bool {
+    matches!(
+        event,
+        egui::Event::Key {
+            pressed: true,
+            repeat: false,
+            ..
+        } | egui::Event::PointerButton { pressed: true, .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+
+    fn frame(time_ms: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time_ms * 1_000_000), events)
+    }
+
+    fn click(x: f32) -> egui::Event {
+        egui::Event::PointerButton {
+            pos: egui::pos2(x, 1.0),
+            button: egui::PointerButton::Primary,
+            pressed: true,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn recordings_in_a_directory_are_aggregated() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = ReplayFile::new(vec![
+            frame(0, vec![click(1.0)]),
+            frame(100, vec![egui::Event::Text("a".to_string())]),
+            frame(300, vec![click(1.0)]),
+        ]);
+        let second = ReplayFile::new(vec![frame(0, vec![click(40.0)]), frame(1000, vec![click(1.0)])]);
+        first.save(dir.path().join("first.json")).unwrap();
+        second.save(dir.path().join("second.bin")).unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let analytics = ReplayAnalytics::from_dir(dir.path()).unwrap();
+
+        assert_eq!(analytics.num_recordings, 2);
+        assert_eq!(analytics.failed.len(), 1);
+        assert_eq!(analytics.average_session_length(), NanoDelta::from_millis_safe(650));
+        assert_eq!(analytics.events_per_type.get("PointerButton"), Some(&4));
+        assert_eq!(analytics.event_type_shares().get("Text"), Some(&0.2));
+        assert_eq!(
+            analytics.median_interaction_interval(),
+            Some(NanoDelta::from_millis_safe(1000))
+        );
+        assert_eq!(analytics.most_clicked_regions()[0].1, 3);
+        assert!(analytics.to_csv().contains("\nclicked_region,0 0 16 16,3\n"));
+    }
+}
@@ -1,20 +1,35 @@
-use crate::clock::{Clock, SystemClock};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::clock::{Clock, MonotonicClock};
 use crate::timestamp::NanoTimestamp;
 use crate::replay_events::ReplayManager;
+use crate::report::ReplayStatus;
 
 pub struct ReplayApp {
     replay_manager: ReplayManager,
     check_states: [bool; 10],
+    // Outcome of the replay on startup, set when the app exits.
+    replay_status: Rc<Cell<Option<ReplayStatus>>>,
 }
 
 impl ReplayApp {
-    /// Called once before the first frame.
-    pub fn new() -> Self {
+    /// Called once before the first frame, restores the state saved by
+    /// `eframe::App::save`.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         Self {
-            replay_manager: ReplayManager::new(),
+            replay_manager: ReplayManager::from_storage(cc).with_auto_replay_from_env(),
             check_states: [false; 10],
+            replay_status: Rc::default(),
         }
     }
+
+    /// Set `status` to the outcome of the replay on startup when the app
+    /// exits, see [`ReplayManager::auto_replay_status`].
+    pub fn with_replay_status(mut self, status: Rc<Cell<Option<ReplayStatus>>>) -> Self {
+        self.replay_status = status;
+        self
+    }
 }
 
 impl eframe::App for ReplayApp {
@@ -28,9 +43,10 @@ impl eframe::App for ReplayApp {
             .show(ctx, |ui| {
                 let recording_label = if self.replay_manager.is_recording() {
                     format!(
-                        "Recording UI: ON, {} frames, {} events recorded",
+                        "Recording UI: ON, {} frames, {} events recorded, {:.1} MB in memory",
                         self.replay_manager.num_recorded_frames(),
-                        self.replay_manager.num_recorded_events()
+                        self.replay_manager.num_recorded_events(),
+                        self.replay_manager.recording_memory_usage() as f64 / 1_000_000.0
                     )
                 } else {
                     "Recording UI: OFF, press F1 to start/stop".to_string()
@@ -50,10 +66,21 @@ impl eframe::App for ReplayApp {
                 ui.checkbox(&mut self.check_states[i], "Checked");
             }
         });
+
+        self.replay_manager.show_hud(ctx);
+        self.replay_manager.on_frame_end(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.replay_manager.save_state(storage);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.replay_status.set(self.replay_manager.auto_replay_status());
     }
 
     fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
-        let now: NanoTimestamp = SystemClock.now();
+        let now: NanoTimestamp = MonotonicClock::shared().now();
         self.replay_manager.on_raw_input_update(now, ctx, raw_input);
     }
 }
@@ -0,0 +1,253 @@
+//! Assertions embedded in recordings.
+//!
+//! An assertion is recorded at a point in time and checked during replay once
+//! all frames up to that time were replayed, by an [`AssertionRunner`].
+//! Failed assertions are collected in an [`AssertionReport`].
+
+use std::collections::HashMap;
+
+use bincode::{Decode, Encode};
+use egui::Context;
+
+use crate::timestamp::NanoTimestamp;
+
+/// A check provided by the host application, registered under a name.
+pub type AssertionCheck = Box<dyn Fn(&Context) -> bool>;
+
+/// The condition checked by an assertion.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AssertionKind {
+    /// A widget with the given id was shown in the last frame.
+    WidgetExists { id: egui::Id },
+    /// The last text copied to the clipboard contains the given text.
+    ClipboardContains { text: String },
+    /// A check registered by the application under the given name.
+    Custom { name: String },
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct Assertion {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub time: NanoTimestamp,
+    #[bincode(with_serde)]
+    pub kind: AssertionKind,
+}
+
+/// State of the replayed application that assertions are checked against.
+pub struct AssertionContext<'a> {
+    pub ctx: &'a Context,
+    pub last_copied_text: Option<&'a str>,
+    pub custom_checks: &'a HashMap<String, AssertionCheck>,
+}
+
+impl Assertion {
+    /// Check the assertion, returning a description of the failure if it
+    /// does not hold.
+    pub fn check(&self, context: &AssertionContext<'_>) -> Result<(), String> {
+        match &self.kind {
+            AssertionKind::WidgetExists { id } => {
+                if context.ctx.read_response(*id).is_some() {
+                    Ok(())
+                } else {
+                    Err(format!("Widget {:?} does not exist", id))
+                }
+            }
+            AssertionKind::ClipboardContains { text } => match context.last_copied_text {
+                Some(copied) if copied.contains(text.as_str()) => Ok(()),
+                Some(copied) => Err(format!("Clipboard {:?} does not contain {:?}", copied, text)),
+                None => Err(format!("Clipboard is empty, expected {:?}", text)),
+            },
+            AssertionKind::Custom { name } => match context.custom_checks.get(name) {
+                Some(check) if check(context.ctx) => Ok(()),
+                Some(_) => Err(format!("Custom check {} failed", name)),
+                None => Err(format!("Custom check {} is not registered", name)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssertionFailure {
+    pub assertion: Assertion,
+    pub message: String,
+}
+
+/// Results of the assertions checked during a replay.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssertionReport {
+    pub passed: usize,
+    pub failures: Vec<AssertionFailure>,
+}
+
+impl AssertionReport {
+    pub fn record(&mut self, assertion: &Assertion, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.passed += 1,
+            Err(message) => {
+                log::error!("Replay assertion failed: {}", message);
+                self.failures.push(AssertionFailure {
+                    assertion: assertion.clone(),
+                    message,
+                });
+            }
+        }
+    }
+
+    pub fn num_failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failures.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.passed = 0;
+        self.failures.clear();
+    }
+}
+
+/// Checks the assertions of a recording while it is replayed.
+#[derive(Default)]
+pub struct AssertionRunner {
+    custom_checks: HashMap<String, AssertionCheck>,
+    // Index of the next assertion of the recording to schedule.
+    next_index: usize,
+    // Assertions to check with the output of the next frame, after the frame
+    // they belong to was processed.
+    due: Vec<Assertion>,
+    report: AssertionReport,
+}
+
+impl AssertionRunner {
+    /// Register a check for [`AssertionKind::Custom`] assertions.
+    pub fn register_check(&mut self, name: impl Into<String>, check: AssertionCheck) {
+        self.custom_checks.insert(name.into(), check);
+    }
+
+    /// Start over for a new replay, keeping the registered checks.
+    pub fn restart(&mut self) {
+        self.next_index = 0;
+        self.due.clear();
+        self.report.clear();
+    }
+
+    /// Schedule the assertions recorded up to the given time, or all the
+    /// remaining ones without a time, to be checked with the next output.
+    pub fn schedule_until(&mut self, assertions: &[Assertion], time: Option<NanoTimestamp>) {
+        while let Some(assertion) = assertions.get(self.next_index) {
+            if time.is_some_and(|time| assertion.time > time) {
+                break;
+            }
+            self.due.push(assertion.clone());
+            self.next_index += 1;
+        }
+    }
+
+    /// Whether assertions are waiting for the output of the next frame.
+    pub fn has_due(&self) -> bool {
+        !self.due.is_empty()
+    }
+
+    /// Check the scheduled assertions against the last output.
+    pub fn check_due(&mut self, ctx: &Context, last_copied_text: Option<&str>) {
+        let context = AssertionContext {
+            ctx,
+            last_copied_text,
+            custom_checks: &self.custom_checks,
+        };
+        for assertion in self.due.drain(..) {
+            self.report.record(&assertion, assertion.check(&context));
+        }
+    }
+
+    /// Results of the assertions checked since the last restart.
+    pub fn report(&self) -> &AssertionReport {
+        &self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(
+        kind: AssertionKind,
+        last_copied_text: Option<&str>,
+        custom_checks: &HashMap<String, AssertionCheck>,
+    ) -> Result<(), String> {
+        let ctx = Context::default();
+        let assertion = Assertion {
+            time: NanoTimestamp::zero(),
+            kind,
+        };
+        assertion.check(&AssertionContext {
+            ctx: &ctx,
+            last_copied_text,
+            custom_checks,
+        })
+    }
+
+    #[test]
+    fn clipboard_assertion() {
+        let checks = HashMap::new();
+        let kind = AssertionKind::ClipboardContains {
+            text: "world".to_string(),
+        };
+
+        assert!(check(kind.clone(), Some("hello world"), &checks).is_ok());
+        assert!(check(kind.clone(), Some("hello"), &checks).is_err());
+        assert!(check(kind, None, &checks).is_err());
+    }
+
+    #[test]
+    fn custom_assertion() {
+        let mut checks: HashMap<String, AssertionCheck> = HashMap::new();
+        checks.insert("always".to_string(), Box::new(|_: &Context| true));
+        checks.insert("never".to_string(), Box::new(|_: &Context| false));
+
+        let custom = |name: &str| AssertionKind::Custom { name: name.to_string() };
+        assert!(check(custom("always"), None, &checks).is_ok());
+        assert!(check(custom("never"), None, &checks).is_err());
+        assert!(check(custom("unknown"), None, &checks).is_err());
+    }
+
+    #[test]
+    fn report_counts_results() {
+        let assertion = Assertion {
+            time: NanoTimestamp::zero(),
+            kind: AssertionKind::Custom { name: "a".to_string() },
+        };
+        let mut report = AssertionReport::default();
+
+        report.record(&assertion, Ok(()));
+        report.record(&assertion, Err("failed".to_string()));
+
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.num_failed(), 1);
+        assert!(report.has_failures());
+    }
+
+    #[test]
+    fn assertions_are_checked_once_due() {
+        let assertion = |time: i64| Assertion {
+            time: NanoTimestamp::from(time),
+            kind: AssertionKind::ClipboardContains { text: "a".to_string() },
+        };
+        let assertions = [assertion(1), assertion(2)];
+        let ctx = Context::default();
+        let mut runner = AssertionRunner::default();
+
+        runner.schedule_until(&assertions, Some(NanoTimestamp::from(1)));
+        runner.check_due(&ctx, Some("a"));
+        runner.schedule_until(&assertions, None);
+        runner.check_due(&ctx, None);
+
+        assert!(!runner.has_due());
+        assert_eq!(runner.report().passed, 1);
+        assert_eq!(runner.report().num_failed(), 1);
+        runner.restart();
+        assert_eq!(runner.report(), &AssertionReport::default());
+    }
+}
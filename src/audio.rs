@@ -0,0 +1,90 @@
+//! Audio narration of recordings.
+//!
+//! Bug reporters can narrate what they are doing while recording, with any
+//! audio recorder. The [`AudioTrack`] of a recording references the audio
+//! file, or embeds it with the `embedded-audio` feature, together with the
+//! time at which the audio started on the clock of the recording. Replays
+//! pass the track to [`crate::replay_events::ReplayManager::on_replay_audio`]
+//! so that the application can play it along.
+
+use std::path::{Path, PathBuf};
+
+use bincode::{Decode, Encode};
+
+use crate::replay_file::ReplayError;
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// Where the audio of an [`AudioTrack`] is stored.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub enum AudioSource {
+    /// Path of an external audio file, in any format.
+    File(String),
+    /// An Ogg Opus file embedded in the recording.
+    Opus(Vec<u8>),
+}
+
+/// Audio attached to a recording, see the module docs.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct AudioTrack {
+    pub source: AudioSource,
+    /// Time at which the audio started, on the clock of the recording.
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub start: NanoTimestamp,
+}
+
+impl AudioTrack {
+    /// Reference the audio file at `path`, which started at `start`.
+    pub fn file(path: impl AsRef<Path>, start: NanoTimestamp) -> Self {
+        Self {
+            source: AudioSource::File(path.as_ref().to_string_lossy().to_string()),
+            start,
+        }
+    }
+
+    /// Embed the Ogg Opus file at `path`, which started at `start`, so that
+    /// the recording can be shared as a single file.
+    #[cfg(feature = "embedded-audio")]
+    pub fn embed_opus(path: impl AsRef<Path>, start: NanoTimestamp) -> Result<Self, ReplayError> {
+        Ok(Self {
+            source: AudioSource::Opus(std::fs::read(path)?),
+            start,
+        })
+    }
+
+    /// Position in the audio which was heard at `time`, `None` if the audio
+    /// had not started yet.
+    pub fn position_at(&self, time: NanoTimestamp) -> Option<NanoDelta> {
+        let position = time - self.start;
+        (position.as_nanos() >= 0).then_some(position)
+    }
+
+    /// Path of a file with the audio, for players which need one. Embedded
+    /// audio is written to `dir` first.
+    pub fn audio_file(&self, dir: impl AsRef<Path>) -> Result<PathBuf, ReplayError> {
+        match &self.source {
+            AudioSource::File(path) => Ok(PathBuf::from(path)),
+            AudioSource::Opus(data) => {
+                let path = dir.as_ref().join(format!("{}.opus", self.start.as_file_name()));
+                std::fs::write(&path, data)?;
+                Ok(path)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_is_relative_to_the_start_of_the_audio() {
+        let track = AudioTrack::file("narration.ogg", NanoTimestamp::from_secs_safe(10));
+
+        assert_eq!(track.position_at(NanoTimestamp::from_secs_safe(9)), None);
+        assert_eq!(
+            track.position_at(NanoTimestamp::from_millis_safe(12_500)),
+            Some(NanoDelta::from_millis_safe(2_500))
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! Saving and loading recordings on a background thread.
+//!
+//! Encoding, decoding and file IO of large recordings can take seconds, which
+//! would freeze the UI. [`save_in_background`] and [`load_in_background`]
+//! return at once with an [`IoTask`], which is polled every frame until its
+//! thread is done. With
+//! [`crate::replay_events::ReplayManager::with_background_io`], the manager
+//! saves and loads recordings like this, keeping the tasks in a
+//! [`BackgroundIo`], and reports the finished tasks as [`IoOutcome`]s.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::chapters::{chapter_paths, load_chapters_with};
+use crate::codec::registered_codecs;
+use crate::replay_file::{write_atomically, LoadDiagnostics, ReplayError, ReplayFile};
+
+// Files are read and written in chunks of this size, to update the progress.
+const CHUNK_SIZE: usize = 1 << 20;
+
+// Finished saves and loads kept until polled, older ones are dropped.
+const MAX_OUTCOMES: usize = 32;
+
+// Bytes read or written so far, updated by the thread.
+#[derive(Default)]
+struct Progress {
+    done: AtomicU64,
+    total: AtomicU64,
+}
+
+impl Progress {
+    fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        let done = self.done.load(Ordering::Relaxed);
+        (done as f64 / total as f64).min(1.0) as f32
+    }
+
+    fn add_done(&self, bytes: usize) {
+        self.done.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// A save or load running on a background thread, see the module docs.
+pub struct IoTask<T> {
+    path: PathBuf,
+    progress: Arc<Progress>,
+    receiver: Receiver<Result<T, ReplayError>>,
+}
+
+impl<T: Send + 'static> IoTask<T> {
+    fn spawn(path: PathBuf, run: impl FnOnce(&Path, &Progress) -> Result<T, ReplayError> + Send + 'static) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = channel();
+        let thread_path = path.clone();
+        let thread_progress = progress.clone();
+        std::thread::spawn(move || {
+            // Nobody waits for the result if the task was dropped.
+            let _ = sender.send(run(&thread_path, &thread_progress));
+        });
+        Self {
+            path,
+            progress,
+            receiver,
+        }
+    }
+}
+
+impl<T> IoTask<T> {
+    /// The file saved or loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fraction of the file written or read so far, between 0 and 1.
+    /// Encoding and decoding are not included.
+    pub fn progress(&self) -> f32 {
+        self.progress.fraction()
+    }
+
+    /// The result once the thread is done, `None` while it is running. Call
+    /// until it returns the result, but not afterwards.
+    pub fn poll(&self) -> Option<Result<T, ReplayError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(thread_panicked())),
+        }
+    }
+
+    /// Block until the thread is done, e.g. to finish saving before exiting.
+    pub fn wait(self) -> Result<T, ReplayError> {
+        self.receiver.recv().unwrap_or_else(|_| Err(thread_panicked()))
+    }
+}
+
+/// A finished background save or load.
+#[derive(Debug)]
+pub enum IoOutcome {
+    Saved(PathBuf),
+    Loaded(PathBuf),
+    Failed(PathBuf, ReplayError),
+}
+
+/// A finished background load, see [`BackgroundIo::finished_load`].
+pub struct FinishedLoad {
+    pub path: PathBuf,
+    pub result: Result<(ReplayFile, LoadDiagnostics), ReplayError>,
+    /// Whether the loaded recording is replayed once loaded.
+    pub starts_replay: bool,
+}
+
+/// The running and finished background saves and loads of the manager. Saves
+/// still running when it is dropped are waited for, as the recordings would
+/// be lost if the process exited before they were written.
+#[derive(Default)]
+pub struct BackgroundIo {
+    // Whether to save and load on a background thread.
+    enabled: bool,
+    // With the recording being saved, kept to retry if the save fails.
+    saves: Vec<(IoTask<()>, Arc<ReplayFile>)>,
+    load: Option<IoTask<(ReplayFile, LoadDiagnostics)>>,
+    load_starts_replay: bool,
+    outcomes: VecDeque<IoOutcome>,
+}
+
+impl BackgroundIo {
+    pub fn new(enabled: bool) -> Self {
+        // Fields can't be taken from a default value, as it implements Drop.
+        Self {
+            enabled,
+            saves: Vec::new(),
+            load: None,
+            load_starts_replay: false,
+            outcomes: VecDeque::new(),
+        }
+    }
+
+    /// Whether recordings are saved and loaded on a background thread.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether a save or load is running.
+    pub fn is_pending(&self) -> bool {
+        !self.saves.is_empty() || self.load.is_some()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.load.is_some()
+    }
+
+    /// Save `file` to `path`, see [`save_in_background`].
+    pub fn save(&mut self, file: Arc<ReplayFile>, path: impl Into<PathBuf>) {
+        let task = save_in_background(file.clone(), path);
+        self.saves.push((task, file));
+    }
+
+    /// Load the recording at `path`, see [`load_in_background`], replacing
+    /// the running load. `starts_replay` is returned with the loaded
+    /// recording.
+    pub fn load(&mut self, path: impl Into<PathBuf>, lenient: bool, starts_replay: bool) {
+        self.load = Some(load_in_background(path, lenient));
+        self.load_starts_replay = starts_replay;
+    }
+
+    /// Take the finished saves, with the recordings of the failed ones.
+    pub fn finished_saves(&mut self) -> Vec<(IoOutcome, Option<ReplayFile>)> {
+        let mut finished = Vec::new();
+        self.saves.retain(|(task, file)| {
+            let Some(result) = task.poll() else {
+                return true;
+            };
+            let path = task.path().to_path_buf();
+            finished.push(match result {
+                Ok(()) => (IoOutcome::Saved(path), None),
+                Err(err) => (IoOutcome::Failed(path, err), Some(file.clone())),
+            });
+            false
+        });
+        finished
+            .into_iter()
+            .map(|(outcome, file)| {
+                // The thread is done, so the recording is only shared with
+                // the other saves of the same recording, if any.
+                let file = file.map(|file| Arc::try_unwrap(file).unwrap_or_else(|file| (*file).clone()));
+                (outcome, file)
+            })
+            .collect()
+    }
+
+    /// Take the finished load, if any.
+    pub fn finished_load(&mut self) -> Option<FinishedLoad> {
+        let result = self.load.as_ref()?.poll()?;
+        let task = self.load.take()?;
+        Some(FinishedLoad {
+            path: task.path().to_path_buf(),
+            result,
+            starts_replay: std::mem::take(&mut self.load_starts_replay),
+        })
+    }
+
+    /// Keep a finished save or load until it is polled.
+    pub fn push_outcome(&mut self, outcome: IoOutcome) {
+        self.outcomes.push_back(outcome);
+        while self.outcomes.len() > MAX_OUTCOMES {
+            self.outcomes.pop_front();
+        }
+    }
+
+    /// Take the oldest finished save or load.
+    pub fn poll(&mut self) -> Option<IoOutcome> {
+        self.outcomes.pop_front()
+    }
+
+    /// Show the progress of the running saves and loads.
+    pub fn show_progress(&self, ui: &mut egui::Ui) {
+        let show_task = |ui: &mut egui::Ui, action: &str, path: &Path, progress: f32| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} {}", action, path.display()));
+                ui.add(egui::ProgressBar::new(progress).show_percentage());
+            });
+        };
+        for (task, _) in self.saves.iter() {
+            show_task(ui, "Saving", task.path(), task.progress());
+        }
+        if let Some(task) = &self.load {
+            show_task(ui, "Loading", task.path(), task.progress());
+        }
+    }
+}
+
+impl Drop for BackgroundIo {
+    fn drop(&mut self) {
+        for (task, _) in self.saves.drain(..) {
+            if let Err(err) = task.wait() {
+                log::error!("Failed to save UI events: {}", err);
+            }
+        }
+    }
+}
+
+fn thread_panicked() -> ReplayError {
+    ReplayError::Io(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "The background thread panicked",
+    ))
+}
+
+/// Save `file` like [`ReplayFile::save`], on a background thread. The file is
+/// shared, so that the caller can keep it to save it again if this fails.
+pub fn save_in_background(file: Arc<ReplayFile>, path: impl Into<PathBuf>) -> IoTask<()> {
+    IoTask::spawn(path.into(), move |path, progress| {
+        let codec = registered_codecs().codec_for_path(path)?;
+        let mut bytes = Vec::new();
+        codec.encode(&file, &mut bytes)?;
+        progress.total.store(bytes.len() as u64, Ordering::Relaxed);
+        write_atomically(path, |writer| {
+            for chunk in bytes.chunks(CHUNK_SIZE) {
+                writer.write_all(chunk)?;
+                progress.add_done(chunk.len());
+            }
+            Ok(())
+        })?;
+        log::info!(
+            "Saved {} frames, {} events, to {}",
+            file.num_frames(),
+            file.num_events(),
+            path.display()
+        );
+        Ok(())
+    })
+}
+
+/// Load the recording at `path` and its following chapters, like
+/// [`crate::chapters::load_chapters`], on a background thread. With
+/// `lenient`, events which fail to load are skipped, see
+/// [`ReplayFile::load_lenient`].
+pub fn load_in_background(path: impl Into<PathBuf>, lenient: bool) -> IoTask<(ReplayFile, LoadDiagnostics)> {
+    IoTask::spawn(path.into(), move |path, progress| {
+        let total = chapter_paths(path)
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        progress.total.store(total, Ordering::Relaxed);
+        let (file, diagnostics) = load_chapters_with(path, |path| {
+            let codec = registered_codecs().codec_for_path(path)?;
+            let bytes = read_with_progress(path, progress)?;
+            if lenient {
+                codec.decode_lenient(&bytes)
+            } else {
+                Ok((codec.decode(&bytes)?, LoadDiagnostics::default()))
+            }
+        })?;
+        log::info!(
+            "Loaded {} frames, {} events, from {}",
+            file.num_frames(),
+            file.num_events(),
+            path.display()
+        );
+        Ok((file, diagnostics))
+    })
+}
+
+fn read_with_progress(path: &Path, progress: &Progress) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let len = file.read(&mut chunk)?;
+        if len == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&chunk[..len]);
+        progress.add_done(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn saved_recordings_load_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("background.bin");
+        let file = ReplayFile::new(vec![FrameEvents::new(NanoTimestamp::from(1), vec![egui::Event::Copy])]);
+
+        let saved = save_in_background(Arc::new(file.clone()), &path).wait();
+        let task = load_in_background(&path, false);
+        let loaded = loop {
+            if let Some(result) = task.poll() {
+                break result;
+            }
+            std::thread::yield_now();
+        };
+
+        assert!(saved.is_ok());
+        assert_eq!(task.progress(), 1.0);
+        assert_eq!(loaded.unwrap().0, file);
+    }
+}
@@ -0,0 +1,312 @@
+//! Command line tool for managing UI event recordings.
+//!
+//! Usage:
+//! ```text
+//! egui-replay inspect <file>
+//! egui-replay convert <in> <out>
+//! egui-replay trim <in> <out> <start_frame> <end_frame>
+//! egui-replay merge <out> <in>...
+//! egui-replay anonymize <in> <out>
+//! egui-replay script <in> <out>
+//! egui-replay import <raw_input_dump.json> <out>
+//! egui-replay validate <file>
+//! egui-replay repair <in> <out>
+//! egui-replay diff <a> <b> [<tolerance_ms>]
+//! egui-replay audio <file> <audio_file> <offset_ms>
+//! egui-replay analytics <dir> [<out.csv>]
+//! ```
+//!
+//! The format of each file is chosen by its extension (`.bin` or `.json`, or
+//! `.msgpack` and `.cbor` with the `msgpack` and `cbor` features).
+//! Scripts are written as Rust code (`.rs`) or YAML (`.yaml`, `.yml`).
+
+use std::process::ExitCode;
+
+use egui_replay::analytics::ReplayAnalytics;
+use egui_replay::audio::{AudioSource, AudioTrack};
+use egui_replay::diff::{diff_replays, FrameAlignment};
+use egui_replay::import::import_raw_input_dump;
+use egui_replay::replay_events::FrameEvents;
+use egui_replay::replay_file::{convert_replay, ReplayFile};
+use egui_replay::script::ScriptFormat;
+use egui_replay::stats::ReplayStats;
+use egui_replay::timestamp::NanoDelta;
+use egui_replay::validate::{repair_key_releases, validate_replay};
+
+const USAGE: &str = "\
+Usage:
+  egui-replay inspect <file>
+  egui-replay convert <in> <out>
+  egui-replay trim <in> <out> <start_frame> <end_frame>
+  egui-replay merge <out> <in>...
+  egui-replay anonymize <in> <out>
+  egui-replay script <in> <out>
+  egui-replay import <raw_input_dump.json> <out>
+  egui-replay validate <file>
+  egui-replay repair <in> <out>
+  egui-replay diff <a> <b> [<tolerance_ms>]
+  egui-replay audio <file> <audio_file> <offset_ms>
+  egui-replay analytics <dir> [<out.csv>]";
+
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["inspect", file] => inspect(file),
+        ["convert", input, output] => convert(input, output),
+        ["trim", input, output, start, end] => trim(input, output, start, end),
+        ["merge", output, inputs @ ..] if !inputs.is_empty() => merge(output, inputs),
+        ["anonymize", input, output] => anonymize(input, output),
+        ["script", input, output] => script(input, output),
+        ["import", input, output] => import(input, output),
+        ["validate", file] => validate(file),
+        ["repair", input, output] => repair(input, output),
+        ["diff", a, b] => diff(a, b, None),
+        ["diff", a, b, tolerance] => diff(a, b, Some(tolerance)),
+        ["audio", file, audio_file, offset] => audio(file, audio_file, offset),
+        ["analytics", dir] => analytics(dir, None),
+        ["analytics", dir, output] => analytics(dir, Some(output)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn inspect(file: &str) -> CliResult {
+    let replay_file = ReplayFile::load(file)?;
+    let stats = ReplayStats::from_frames(&replay_file.frames);
+
+    println!("File:     {}", file);
+    println!("Frames:   {}", stats.num_frames);
+    println!("Events:   {}", stats.num_events);
+    if let (Some(start), Some(end)) = (replay_file.start_time(), replay_file.end_time()) {
+        println!("Start:    {}", start.as_rfc3339());
+        println!("End:      {}", end.as_rfc3339());
+    }
+    println!("Duration: {} ms", stats.duration.as_millis());
+    println!("Events / frame: {:.2}", stats.average_events_per_frame());
+    println!("Pointer path:   {:.0} pt", stats.pointer_path_length);
+
+    println!("Events by type:");
+    for (kind, count) in stats.events_per_type.iter() {
+        println!("  {:<16} {}", kind, count);
+    }
+    if !replay_file.markers.is_empty() {
+        println!("Markers:");
+        for marker in replay_file.markers.iter() {
+            println!("  {:<16} {}", marker.name, marker.time.as_rfc3339());
+        }
+    }
+    if !replay_file.annotations.is_empty() {
+        println!("Annotations:");
+        let start_time = replay_file.start_time().unwrap_or_default();
+        for annotation in replay_file.annotations.iter() {
+            let millis = (annotation.time - start_time).as_millis();
+            println!("  {:>8} ms {}", millis, annotation.text);
+        }
+    }
+    if let Some(audio) = &replay_file.audio {
+        match &audio.source {
+            AudioSource::File(path) => println!("Audio:    {}", path),
+            AudioSource::Opus(data) => println!("Audio:    embedded, {} bytes", data.len()),
+        }
+        println!("Audio start: {}", audio.start.as_rfc3339());
+    }
+    Ok(())
+}
+
+fn convert(input: &str, output: &str) -> CliResult {
+    convert_replay(input, output)?;
+    Ok(())
+}
+
+fn trim(input: &str, output: &str, start: &str, end: &str) -> CliResult {
+    let replay_file = ReplayFile::load(input)?;
+    let start: usize = start.parse()?;
+    let end: usize = end.parse()?;
+    let trimmed = replay_file.slice(start..end);
+    if trimmed.num_frames() == 0 {
+        return Err(format!("Empty frame range {}..{}", start, end).into());
+    }
+    trimmed.save(output)?;
+    Ok(())
+}
+
+// Recordings are appended one after another. Timestamps of each following
+// recording are shifted so that it starts where the previous one ended.
+fn merge(output: &str, inputs: &[&str]) -> CliResult {
+    let mut merged = ReplayFile::default();
+    for input in inputs {
+        merged = merged.concat(ReplayFile::load(input)?);
+    }
+    merged.save(output)?;
+    Ok(())
+}
+
+fn mask_text(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_whitespace() { c } else { 'x' })
+        .collect()
+}
+
+// Replace all typed text by placeholder characters. Character keys pressed
+// without a command modifier are replaced as well, since they reveal the
+// typed text too. Shortcuts (e.g. Ctrl+S) are kept intact.
+fn anonymize_event(event: egui::Event) -> egui::Event {
+    match event {
+        egui::Event::Text(text) => egui::Event::Text(mask_text(&text)),
+        egui::Event::Paste(text) => egui::Event::Paste(mask_text(&text)),
+        egui::Event::Ime(egui::ImeEvent::Preedit(text)) => {
+            egui::Event::Ime(egui::ImeEvent::Preedit(mask_text(&text)))
+        }
+        egui::Event::Ime(egui::ImeEvent::Commit(text)) => {
+            egui::Event::Ime(egui::ImeEvent::Commit(mask_text(&text)))
+        }
+        egui::Event::Key {
+            key,
+            physical_key,
+            pressed,
+            repeat,
+            modifiers,
+        } => {
+            let is_character_key = key.name().chars().count() == 1
+                && key.name().chars().all(char::is_alphanumeric);
+            let is_shortcut = modifiers.ctrl || modifiers.alt || modifiers.command || modifiers.mac_cmd;
+            if is_character_key && !is_shortcut {
+                egui::Event::Key {
+                    key: egui::Key::X,
+                    physical_key: None,
+                    pressed,
+                    repeat,
+                    modifiers,
+                }
+            } else {
+                egui::Event::Key {
+                    key,
+                    physical_key,
+                    pressed,
+                    repeat,
+                    modifiers,
+                }
+            }
+        }
+        event => event,
+    }
+}
+
+fn anonymize(input: &str, output: &str) -> CliResult {
+    let frames: Vec<FrameEvents> = ReplayFile::load(input)?
+        .frames
+        .into_iter()
+        .map(|mut frame| {
+            frame.events = frame.events.into_iter().map(anonymize_event).collect();
+            frame
+        })
+        .collect();
+    ReplayFile::new(frames).save(output)?;
+    Ok(())
+}
+
+fn script(input: &str, output: &str) -> CliResult {
+    let format = if output.ends_with(".rs") {
+        ScriptFormat::Rust
+    } else if output.ends_with(".yaml") || output.ends_with(".yml") {
+        ScriptFormat::Yaml
+    } else {
+        return Err(format!("Unknown script extension: {}", output).into());
+    };
+    ReplayFile::load(input)?.export_script(output, format)?;
+    Ok(())
+}
+
+// Convert a dump of serialized egui::RawInput into a recording.
+fn import(input: &str, output: &str) -> CliResult {
+    import_raw_input_dump(input)?.save(output)?;
+    Ok(())
+}
+
+// Print the problems found in the recording, failing if there are any.
+fn validate(file: &str) -> CliResult {
+    let warnings = validate_replay(file)?;
+    for warning in warnings.iter() {
+        println!("{}", warning);
+    }
+    if !warnings.is_empty() {
+        return Err(format!("Found {} problems in {}", warnings.len(), file).into());
+    }
+    Ok(())
+}
+
+// Insert the missing key releases found by `validate`.
+fn repair(input: &str, output: &str) -> CliResult {
+    let mut replay_file = ReplayFile::load(input)?;
+    let num_repaired = repair_key_releases(&mut replay_file);
+    replay_file.save(output)?;
+    println!("Added {} missing key releases", num_repaired);
+    Ok(())
+}
+
+// Frames are paired by index, or by time within the tolerance if given.
+fn diff(a: &str, b: &str, tolerance_ms: Option<&str>) -> CliResult {
+    let alignment = match tolerance_ms {
+        Some(tolerance_ms) => FrameAlignment::Time {
+            tolerance: NanoDelta::from_millis(tolerance_ms.parse()?)?,
+        },
+        None => FrameAlignment::Index,
+    };
+    let diff = diff_replays(&ReplayFile::load(a)?, &ReplayFile::load(b)?, alignment);
+    for frame in diff.frames.iter() {
+        let index = |index: Option<usize>| index.map_or("-".to_string(), |index| index.to_string());
+        println!("Frame {} / {}:", index(frame.a_index), index(frame.b_index));
+        for change in frame.changes.iter() {
+            println!("  {}", change);
+        }
+    }
+    println!(
+        "{} added, {} removed, {} changed events",
+        diff.num_added(),
+        diff.num_removed(),
+        diff.num_changed()
+    );
+    Ok(())
+}
+
+// Attach an audio file starting `offset_ms` after the first frame, negative
+// if it started before.
+fn audio(file: &str, audio_file: &str, offset_ms: &str) -> CliResult {
+    let mut replay_file = ReplayFile::load(file)?;
+    let Some(start_time) = replay_file.start_time() else {
+        return Err("No frames to attach the audio to".into());
+    };
+    let start = start_time + NanoDelta::from_millis(offset_ms.parse()?)?;
+    replay_file.audio = Some(AudioTrack::file(audio_file, start));
+    replay_file.save(file)?;
+    Ok(())
+}
+
+// Print the analytics of the recordings in `dir` as CSV, or write them to a
+// file.
+fn analytics(dir: &str, output: Option<&str>) -> CliResult {
+    let analytics = ReplayAnalytics::from_dir(dir)?;
+    for (path, err) in analytics.failed.iter() {
+        eprintln!("Skipped {}: {}", path.display(), err);
+    }
+    match output {
+        Some(output) => analytics.write_csv(output)?,
+        None => print!("{}", analytics.to_csv()),
+    }
+    Ok(())
+}
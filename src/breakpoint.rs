@@ -0,0 +1,109 @@
+//! Conditional breakpoints of replays.
+//!
+//! A [`Breakpoint`] pauses the replay right before a matching frame is
+//! injected, see [`crate::replay_events::ReplayManager::add_breakpoint`]. The
+//! replay can then be stepped through frame by frame to find where it starts
+//! to misbehave.
+
+use crate::replay_events::FrameEvents;
+
+/// Predicate of [`BreakCondition::Frame`].
+pub type FramePredicate = Box<dyn Fn(usize, &FrameEvents) -> bool>;
+
+/// When a [`Breakpoint`] pauses the replay.
+pub enum BreakCondition {
+    /// Before the frame with the given index, counted from zero.
+    FrameIndex(usize),
+    /// Before a frame with an event matching the predicate.
+    Event(Box<dyn Fn(&egui::Event) -> bool>),
+    /// Before a frame matching the predicate, given the index of the frame.
+    Frame(FramePredicate),
+}
+
+/// A named condition pausing the replay, see the module docs.
+pub struct Breakpoint {
+    pub name: String,
+    pub condition: BreakCondition,
+    /// Disabled breakpoints are kept, but never pause the replay.
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    pub fn new(name: impl Into<String>, condition: BreakCondition) -> Self {
+        Self {
+            name: name.into(),
+            condition,
+            enabled: true,
+        }
+    }
+
+    pub fn at_frame(index: usize) -> Self {
+        Self::new(format!("Frame {}", index + 1), BreakCondition::FrameIndex(index))
+    }
+
+    pub fn on_event(name: impl Into<String>, predicate: impl Fn(&egui::Event) -> bool + 'static) -> Self {
+        Self::new(name, BreakCondition::Event(Box::new(predicate)))
+    }
+
+    pub fn on_frame(name: impl Into<String>, predicate: impl Fn(usize, &FrameEvents) -> bool + 'static) -> Self {
+        Self::new(name, BreakCondition::Frame(Box::new(predicate)))
+    }
+
+    /// Before a pointer button is pressed or released inside `rect`.
+    pub fn on_click_in(rect: egui::Rect) -> Self {
+        let name = format!("Click in {:?}", rect);
+        Self::on_event(name, move |event| match event {
+            egui::Event::PointerButton { pos, .. } => rect.contains(*pos),
+            _ => false,
+        })
+    }
+
+    /// Whether the breakpoint pauses the replay before the frame at `index`.
+    pub fn matches(&self, index: usize, frame: &FrameEvents) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match &self.condition {
+            BreakCondition::FrameIndex(frame_index) => index == *frame_index,
+            BreakCondition::Event(predicate) => frame.events.iter().any(predicate),
+            BreakCondition::Frame(predicate) => predicate(index, frame),
+        }
+    }
+}
+
+impl std::fmt::Debug for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Breakpoint")
+            .field("name", &self.name)
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn breakpoints_match_frames() {
+        let click = egui::Event::PointerButton {
+            pos: egui::pos2(5.0, 5.0),
+            button: egui::PointerButton::Primary,
+            pressed: true,
+            modifiers: egui::Modifiers::NONE,
+        };
+        let frame = FrameEvents::new(NanoTimestamp::from(0), vec![click]);
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(10.0, 10.0));
+
+        assert!(Breakpoint::on_click_in(rect).matches(0, &frame));
+        assert!(!Breakpoint::on_click_in(rect.translate(egui::vec2(20.0, 0.0))).matches(0, &frame));
+        assert!(Breakpoint::at_frame(3).matches(3, &frame));
+        assert!(!Breakpoint::at_frame(3).matches(2, &frame));
+
+        assert!(!Breakpoint::on_frame("Many events", |_, frame| frame.events.len() > 1).matches(0, &frame));
+        let mut breakpoint = Breakpoint::at_frame(0);
+        breakpoint.enabled = false;
+        assert!(!breakpoint.matches(0, &frame));
+    }
+}
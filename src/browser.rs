@@ -0,0 +1,249 @@
+//! Browser of the recordings in the output directory, shown in the replay
+//! modal.
+//!
+//! Lists the recordings with their size, modification date and number of
+//! frames, sortable by each column. Recordings can be selected for replay or
+//! deleted. With the `rfd` feature, recordings elsewhere can be picked with a
+//! native file dialog.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+
+use crate::output::RecordingOutput;
+use crate::replay_file::ReplayFile;
+
+/// Column the recordings are sorted by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortColumn {
+    Name,
+    Size,
+    #[default]
+    Modified,
+    Frames,
+}
+
+/// A recording in the output directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordingEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// `None` if the recording failed to load.
+    pub num_frames: Option<usize>,
+}
+
+impl RecordingEntry {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        )
+    }
+}
+
+/// What the user did in the browser.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BrowserAction {
+    Select(PathBuf),
+    Delete(PathBuf),
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Lists and sorts recordings, see the module docs.
+#[derive(Default)]
+pub struct RecordingBrowser {
+    entries: Vec<RecordingEntry>,
+    sort_column: SortColumn,
+    ascending: bool,
+    // Frame counts by path, valid while the file is not modified. Loading a
+    // recording is expensive, so they are kept across refreshes.
+    num_frames_cache: HashMap<PathBuf, (Option<SystemTime>, Option<usize>)>,
+}
+
+impl RecordingBrowser {
+    pub fn entries(&self) -> &[RecordingEntry] {
+        &self.entries
+    }
+
+    /// List the recordings in the output directory again.
+    pub fn refresh(&mut self, output: &RecordingOutput) {
+        self.entries = output
+            .recordings()
+            .into_iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(&path).ok();
+                let modified = metadata.as_ref().and_then(|metadata| metadata.modified().ok());
+                let num_frames = match self.num_frames_cache.get(&path) {
+                    Some((cached_modified, num_frames)) if *cached_modified == modified => *num_frames,
+                    _ => {
+                        let num_frames = ReplayFile::load(&path).ok().map(|file| file.num_frames());
+                        self.num_frames_cache.insert(path.clone(), (modified, num_frames));
+                        num_frames
+                    }
+                };
+                RecordingEntry {
+                    size: metadata.map_or(0, |metadata| metadata.len()),
+                    path,
+                    modified,
+                    num_frames,
+                }
+            })
+            .collect();
+        self.sort();
+    }
+
+    /// Sort by `column`, or reverse the order if already sorted by it.
+    pub fn sort_by(&mut self, column: SortColumn) {
+        if self.sort_column == column {
+            self.ascending = !self.ascending;
+        } else {
+            self.sort_column = column;
+            self.ascending = true;
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        let column = self.sort_column;
+        self.entries.sort_by(|a, b| {
+            let ordering = match column {
+                SortColumn::Name => a.path.cmp(&b.path),
+                SortColumn::Size => a.size.cmp(&b.size),
+                SortColumn::Modified => a.modified.cmp(&b.modified),
+                SortColumn::Frames => a.num_frames.cmp(&b.num_frames),
+            };
+            ordering.then_with(|| a.path.cmp(&b.path))
+        });
+        if !self.ascending {
+            self.entries.reverse();
+        }
+    }
+
+    /// Delete the recording and remove it from the list.
+    pub fn delete(&mut self, path: &Path) -> std::io::Result<()> {
+        std::fs::remove_file(path)?;
+        self.entries.retain(|entry| entry.path != path);
+        self.num_frames_cache.remove(path);
+        Ok(())
+    }
+
+    fn header(&mut self, ui: &mut egui::Ui, column: SortColumn, text: &str) {
+        let text = if self.sort_column != column {
+            text.to_string()
+        } else if self.ascending {
+            format!("{} ⏶", text)
+        } else {
+            format!("{} ⏷", text)
+        };
+        if ui.button(egui::RichText::new(text).strong()).clicked() {
+            self.sort_by(column);
+        }
+    }
+
+    /// Show the recordings as a table, highlighting the `selected` one.
+    pub fn show(&mut self, ui: &mut egui::Ui, selected: &str) -> Option<BrowserAction> {
+        let mut action = None;
+        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+            egui::Grid::new("recording_browser")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    self.header(ui, SortColumn::Name, "Name");
+                    self.header(ui, SortColumn::Size, "Size");
+                    self.header(ui, SortColumn::Modified, "Modified");
+                    self.header(ui, SortColumn::Frames, "Frames");
+                    ui.label("");
+                    ui.end_row();
+
+                    for entry in &self.entries {
+                        let is_selected = entry.path.to_string_lossy() == selected;
+                        if ui.selectable_label(is_selected, entry.file_name()).clicked() {
+                            action = Some(BrowserAction::Select(entry.path.clone()));
+                        }
+                        ui.label(format_size(entry.size));
+                        ui.label(entry.modified.map_or_else(String::new, |modified| {
+                            DateTime::<Local>::from(modified).format("%Y-%m-%d %H:%M").to_string()
+                        }));
+                        ui.label(entry.num_frames.map_or_else(|| "?".to_string(), |n| n.to_string()));
+                        if ui.small_button("🗑").on_hover_text("Delete the recording").clicked() {
+                            action = Some(BrowserAction::Delete(entry.path.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+            if self.entries.is_empty() {
+                ui.label("No recordings found");
+            }
+        });
+        action
+    }
+}
+
+/// Pick a recording with a native file dialog, starting in `dir`.
+#[cfg(feature = "rfd")]
+pub fn pick_recording(dir: &Path) -> Option<PathBuf> {
+    let codecs = crate::codec::registered_codecs();
+    rfd::FileDialog::new()
+        .set_directory(dir)
+        .add_filter("Recordings", &codecs.extensions())
+        .pick_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn sizes_are_human_readable() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn lists_and_sorts_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        output.create_dir().unwrap();
+        let frame = |time| FrameEvents::new(NanoTimestamp::from(time), vec![]);
+        ReplayFile::new(vec![frame(0)])
+            .save(dir.path().join("egui_replay_a.json"))
+            .unwrap();
+        ReplayFile::new(vec![frame(0), frame(1)])
+            .save(dir.path().join("egui_replay_b.json"))
+            .unwrap();
+        std::fs::write(dir.path().join("other.json"), "[]").unwrap();
+
+        let mut browser = RecordingBrowser::default();
+        browser.refresh(&output);
+        browser.sort_by(SortColumn::Frames);
+        let frames: Vec<_> = browser.entries().iter().map(|entry| entry.num_frames).collect();
+        assert_eq!(frames, vec![Some(1), Some(2)]);
+        browser.sort_by(SortColumn::Frames);
+        assert_eq!(browser.entries()[0].file_name(), "egui_replay_b.json");
+
+        browser.delete(&dir.path().join("egui_replay_b.json")).unwrap();
+        assert_eq!(browser.entries().len(), 1);
+        browser.refresh(&output);
+        assert_eq!(browser.entries().len(), 1);
+    }
+}
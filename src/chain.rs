@@ -0,0 +1,85 @@
+//! Recordings spanning restarts of the app (session chaining).
+//!
+//! Some bugs only show once the app restarts, e.g. with persisted state.
+//! [`crate::replay_events::ReplayManager::restart_recording`] saves the
+//! recording in progress with a [`RESTART_MARKER`] at the restart, and writes
+//! a [`ChainState`] sidecar file into the output directory. The relaunched
+//! process continues the recording with
+//! [`crate::replay_events::ReplayManager::resume_chained_recording`].
+//!
+//! Replays of such recordings pause at each restart marker and ask the harness
+//! to restart the app, see
+//! [`crate::replay_events::ReplayManager::on_replay_restart`]. The relaunched
+//! process continues the replay with
+//! [`crate::replay_events::ReplayManager::resume_chained_replay`].
+
+use std::path::{Path, PathBuf};
+
+use crate::replay_file::ReplayError;
+
+/// Name of the markers at the restarts of the app.
+pub const RESTART_MARKER: &str = "Restart";
+
+/// Name of the sidecar file in the output directory. Not a recording, so
+/// it's never listed with them.
+pub const CHAIN_STATE_FILE_NAME: &str = "egui_replay.chain";
+
+pub fn chain_state_path(dir: &Path) -> PathBuf {
+    dir.join(CHAIN_STATE_FILE_NAME)
+}
+
+/// What the relaunched process continues, see the module docs.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ChainState {
+    /// Append to the recording saved at `path`.
+    Recording { path: PathBuf },
+    /// Replay the recording at `path` from the frame with the given index.
+    Replay { path: PathBuf, frame_index: usize },
+}
+
+impl ChainState {
+    /// Save the state into `dir`, replacing the previous one.
+    pub fn save(&self, dir: &Path) -> Result<(), ReplayError> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(chain_state_path(dir), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The state saved in `dir`, if any.
+    pub fn load(dir: &Path) -> Result<Option<Self>, ReplayError> {
+        match std::fs::read(chain_state_path(dir)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove the state saved in `dir`, once it was continued.
+    pub fn clear(dir: &Path) -> Result<(), ReplayError> {
+        match std::fs::remove_file(chain_state_path(dir)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_are_saved_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = ChainState::Replay {
+            path: dir.path().join("recording.bin"),
+            frame_index: 3,
+        };
+
+        assert_eq!(ChainState::load(dir.path()).unwrap(), None);
+        state.save(dir.path()).unwrap();
+        assert_eq!(ChainState::load(dir.path()).unwrap(), Some(state));
+        ChainState::clear(dir.path()).unwrap();
+        assert_eq!(ChainState::load(dir.path()).unwrap(), None);
+        ChainState::clear(dir.path()).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! Recordings split into chapter files.
+//!
+//! With [`crate::replay_events::ReplayManager::with_chapters`], long
+//! recordings roll over to a new file every chapter, like rotated logs, e.g.
+//! `egui_replay_{timestamp}_part001.bin`, `egui_replay_{timestamp}_part002.bin`.
+//! [`load_chapters`] loads the chapters back as a single recording.
+
+use std::path::{Path, PathBuf};
+
+use crate::replay_file::{LoadDiagnostics, ReplayError, ReplayFile};
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+const PART_SUFFIX: &str = "_part";
+
+/// Length of each chapter of a recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChapterLength {
+    /// Start a new chapter after the given number of frames.
+    Frames(usize),
+    /// Start a new chapter once the chapter spans the given time.
+    Duration(NanoDelta),
+}
+
+/// The chapters of the recording in progress.
+pub struct ChapterRecorder {
+    length: ChapterLength,
+    // Path of the whole recording, the number of the current chapter and
+    // when it started, and the number of frames saved in the previous
+    // chapters.
+    path: PathBuf,
+    number: usize,
+    started_at: NanoTimestamp,
+    saved_frames: usize,
+}
+
+impl ChapterRecorder {
+    /// Start the first chapter of the recording at `path`.
+    pub fn new(length: ChapterLength, path: PathBuf, now: NanoTimestamp) -> Self {
+        Self {
+            length,
+            path,
+            number: 1,
+            started_at: now,
+            saved_frames: 0,
+        }
+    }
+
+    /// Path of the current chapter.
+    pub fn current_path(&self) -> PathBuf {
+        chapter_path(&self.path, self.number)
+    }
+
+    /// Whether the current chapter, with the given number of frames, reached
+    /// its length.
+    pub fn is_full(&self, num_frames: usize, now: NanoTimestamp) -> bool {
+        match self.length {
+            ChapterLength::Frames(max_frames) => num_frames >= max_frames,
+            ChapterLength::Duration(max_duration) => now - self.started_at >= max_duration,
+        }
+    }
+
+    /// Start the next chapter, after the current one was saved with the given
+    /// number of frames.
+    pub fn next(&mut self, num_frames: usize, now: NanoTimestamp) {
+        self.saved_frames += num_frames;
+        self.number += 1;
+        self.started_at = now;
+    }
+
+    /// Frames saved in the previous chapters.
+    pub fn saved_frames(&self) -> usize {
+        self.saved_frames
+    }
+}
+
+/// Path of the chapter with the given number, counted from 1, of the
+/// recording at `path`, e.g. `a/b_part002.bin` for `a/b.bin`.
+pub fn chapter_path(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}{}{:03}.{}", stem, PART_SUFFIX, number, extension.to_string_lossy()),
+        None => format!("{}{}{:03}", stem, PART_SUFFIX, number),
+    };
+    path.with_file_name(file_name)
+}
+
+// The path of the whole recording and the chapter number, if `path` is a
+// chapter.
+fn split_chapter_path(path: &Path) -> Option<(PathBuf, usize)> {
+    let stem = path.file_stem()?.to_str()?;
+    let (base, number) = stem.rsplit_once(PART_SUFFIX)?;
+    if number.len() != 3 || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}.{}", base, extension.to_string_lossy()),
+        None => base.to_string(),
+    };
+    Some((path.with_file_name(file_name), number.parse().ok()?))
+}
+
+/// The chapter at `path` and the chapters following it, in order. Just `path`
+/// if it is not a chapter.
+pub fn chapter_paths(path: &Path) -> Vec<PathBuf> {
+    let Some((recording_path, number)) = split_chapter_path(path) else {
+        return vec![path.to_path_buf()];
+    };
+    (number..)
+        .map(|number| chapter_path(&recording_path, number))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+/// Load the chapter at `path` and the chapters following it as a single
+/// recording. Loads just `path` if it is not a chapter.
+pub fn load_chapters(path: impl AsRef<Path>) -> Result<ReplayFile, ReplayError> {
+    let (recording, _) = load_chapters_with(path.as_ref(), |path| {
+        let chapter = ReplayFile::load(path)?;
+        Ok((chapter, LoadDiagnostics::default()))
+    })?;
+    Ok(recording)
+}
+
+/// Like [`load_chapters`], skipping events which fail to load, see
+/// [`ReplayFile::load_lenient`].
+pub fn load_chapters_lenient(path: impl AsRef<Path>) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+    load_chapters_with(path.as_ref(), |path| ReplayFile::load_lenient(path))
+}
+
+// Load the chapters with `load`, appending them and their diagnostics.
+pub(crate) fn load_chapters_with(
+    path: &Path,
+    mut load: impl FnMut(&Path) -> Result<(ReplayFile, LoadDiagnostics), ReplayError>,
+) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+    let mut recording: Option<ReplayFile> = None;
+    let mut diagnostics = LoadDiagnostics::default();
+    for path in chapter_paths(path) {
+        let (chapter, chapter_diagnostics) = load(&path)?;
+        let frame_offset = recording.as_ref().map_or(0, ReplayFile::num_frames);
+        diagnostics
+            .skipped_events
+            .extend(chapter_diagnostics.skipped_events.into_iter().map(|mut skipped| {
+                skipped.frame_index += frame_offset;
+                skipped
+            }));
+        recording = Some(match recording {
+            Some(recording) => recording.append(chapter),
+            None => chapter,
+        });
+    }
+    Ok((recording.unwrap_or_default(), diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+
+    #[test]
+    fn chapter_paths_round_trip() {
+        let path = Path::new("recordings/egui_replay.bin");
+        let chapter = chapter_path(path, 12);
+
+        assert_eq!(chapter, Path::new("recordings/egui_replay_part012.bin"));
+        assert_eq!(split_chapter_path(&chapter), Some((path.to_path_buf(), 12)));
+        assert_eq!(split_chapter_path(path), None);
+    }
+
+    #[test]
+    fn chapters_load_in_sequence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+        for number in 1..=3 {
+            let frame = FrameEvents::new(
+                NanoTimestamp::from(number as i64),
+                vec![egui::Event::Text(number.to_string())],
+            );
+            ReplayFile::new(vec![frame]).save(chapter_path(&path, number)).unwrap();
+        }
+
+        let times = |file: ReplayFile| -> Vec<i64> { file.frames.iter().map(|frame| frame.time.as_nanos()).collect() };
+        let all = load_chapters(chapter_path(&path, 1)).unwrap();
+        let from_second = load_chapters(chapter_path(&path, 2)).unwrap();
+
+        assert_eq!(times(all), vec![1, 2, 3]);
+        assert_eq!(times(from_second), vec![2, 3]);
+    }
+}
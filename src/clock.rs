@@ -4,15 +4,31 @@
 //! - A `Clock` trait for abstracting time providers.
 //! - `SystemClock`: A `Clock` implementation using the system's real-time
 //!   clock.
+//! - `MonotonicClock`: A `Clock` implementation which measures the time since
+//!   it was created, and therefore never jumps with adjustments of the system
+//!   clock.
+//! - `ScaledClock`: A `Clock` wrapper which runs faster or slower than the
+//!   clock it wraps, for slow motion and fast-forward.
+//! - `OffsetClock`: A `Clock` wrapper which is ahead or behind the clock it
+//!   wraps by a fixed delta, e.g. to replay at the dates of the recording.
+//! - `FrozenClock`: A `Clock` implementation which stands still until moved.
 //! - `ManualClock`: A mockable `Clock` implementation that allows manual
 //!   advancement of time, useful for testing time-dependent logic.
-//! - `Stopwatch`: A utility to measure elapsed time using a `Clock`.
+//! - `Stopwatch`: A utility to measure elapsed time using a `Clock`, in laps
+//!   and with pauses.
 //! - `Timer`: A utility built upon `Stopwatch` to check if a specific duration
 //!   has elapsed (timeout).
-//!
-//! TODO #217: add monotonic clock
+//! - `IntervalTimer`: A utility built upon `Stopwatch` which ticks every
+//!   interval.
+//! - `Deadline`: A point in time to expire at, checked against a `Clock`.
+//! - `FrameScheduler`: A utility which paces frames recorded at given times
+//!   using a `Clock`, e.g. to replay in real time.
 
+use std::collections::VecDeque;
 use std::fmt;
+use std::ops::{Add, Range, Sub};
+use std::sync::OnceLock;
+use std::time::Instant;
 
 use crate::timestamp::{NanoDelta, NanoTimestamp};
 
@@ -21,23 +37,93 @@ pub trait Clock: Send + Sync {
     fn now(&self) -> NanoTimestamp;
 }
 
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> NanoTimestamp {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Box<C> {
+    fn now(&self) -> NanoTimestamp {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> NanoTimestamp {
+        (**self).now()
+    }
+}
+
 /// A time provider that uses the system's clock.
 #[derive(Clone, Debug)]
 pub struct SystemClock;
 
 impl Clock for SystemClock {
     fn now(&self) -> NanoTimestamp {
-        // We use chrono here as it is platform agnostic.
-        NanoTimestamp::try_from(chrono::Utc::now()).unwrap()
+        NanoTimestamp::now()
+    }
+}
+
+/// A time provider that counts the time elapsed since its creation from the
+/// system time at that point. Unlike [`SystemClock`], it neither goes
+/// backwards nor jumps when the system clock is adjusted, e.g. by NTP, so
+/// recordings keep their frame timing during such adjustments.
+#[derive(Clone, Copy, Debug)]
+pub struct MonotonicClock {
+    epoch: NanoTimestamp,
+    anchor: Instant,
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: SystemClock.now(),
+            anchor: Instant::now(),
+        }
+    }
+
+    /// The clock shared by the whole process, created on first use. Separate
+    /// clocks created with [`MonotonicClock::new`] may disagree by the
+    /// adjustments of the system clock between their creation.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<MonotonicClock> = OnceLock::new();
+        *SHARED.get_or_init(Self::new)
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> NanoTimestamp {
+        self.epoch + NanoDelta::from_nanos(self.anchor.elapsed().as_nanos() as i64)
     }
 }
 
 use std::sync::{Arc, Mutex};
 
-/// A time provider that can be mocked to advance time.
-#[derive(Clone, Debug, Default)]
+type AdvanceCallback = Box<dyn FnMut(NanoTimestamp) + Send>;
+
+/// A time provider that can be mocked to advance time. Clones share the time,
+/// the queued steps and the callback.
+#[derive(Clone, Default)]
 pub struct ManualClock {
     current_time: Arc<Mutex<NanoTimestamp>>,
+    steps: Arc<Mutex<VecDeque<NanoTimestamp>>>,
+    on_advance: Arc<Mutex<Option<AdvanceCallback>>>,
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManualClock")
+            .field("current_time", &self.now())
+            .field("steps", &self.steps.lock().unwrap())
+            .finish()
+    }
 }
 
 impl ManualClock {
@@ -45,18 +131,77 @@ impl ManualClock {
         let zero_time = NanoTimestamp::zero();
         Self {
             current_time: Arc::new(Mutex::new(zero_time)),
+            steps: Arc::default(),
+            on_advance: Arc::default(),
         }
     }
 
     pub fn advance_by(&self, duration: NanoDelta) {
         assert!(duration > NanoDelta::zero());
-        let mut time = self.current_time.lock().unwrap();
-        *time = *time + duration;
+        let time = {
+            let mut time = self.current_time.lock().unwrap();
+            *time = *time + duration;
+            *time
+        };
+        self.notify_advance(time);
     }
 
     pub fn advance_to(&self, time: NanoTimestamp) {
-        let mut current_time = self.current_time.lock().unwrap();
-        *current_time = time;
+        *self.current_time.lock().unwrap() = time;
+        self.notify_advance(time);
+    }
+
+    /// Call `callback` with the new time whenever the clock is advanced,
+    /// replacing the previous callback. The callback must not advance the
+    /// clock.
+    pub fn on_advance(&self, callback: impl FnMut(NanoTimestamp) + Send + 'static) {
+        *self.on_advance.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    fn notify_advance(&self, time: NanoTimestamp) {
+        if let Some(callback) = self.on_advance.lock().unwrap().as_mut() {
+            callback(time);
+        }
+    }
+
+    /// Queue times for [`ManualClock::step`] to advance to, in order.
+    pub fn push_steps(&self, times: impl IntoIterator<Item = NanoTimestamp>) {
+        self.steps.lock().unwrap().extend(times);
+    }
+
+    /// Advance to the next queued time, returning it. Returns `None` and
+    /// leaves the time as it is when no steps are queued.
+    pub fn step(&self) -> Option<NanoTimestamp> {
+        let time = self.steps.lock().unwrap().pop_front()?;
+        self.advance_to(time);
+        Some(time)
+    }
+
+    /// Advance until `predicate` holds for the current time, to the queued
+    /// steps first and then by `step` at a time, but not past `deadline`.
+    /// Returns whether the predicate held.
+    pub fn advance_until(
+        &self,
+        step: NanoDelta,
+        deadline: Deadline,
+        mut predicate: impl FnMut(NanoTimestamp) -> bool,
+    ) -> bool {
+        loop {
+            let now = self.now();
+            if predicate(now) {
+                return true;
+            }
+            let next_step = self.steps.lock().unwrap().front().copied();
+            let next = next_step.unwrap_or_else(|| (Deadline::at(now) + step).time());
+            if next > deadline.time() {
+                return false;
+            }
+            if next_step.is_some() {
+                self.step();
+            } else {
+                self.advance_by(step);
+            }
+        }
     }
 }
 
@@ -66,48 +211,252 @@ impl Clock for ManualClock {
     }
 }
 
-/// Measure elapsed time.
-pub struct Stopwatch {
-    clock: Box<dyn Clock>,
+#[derive(Debug)]
+struct ScaledState {
+    // Time of the wrapped clock and of the scaled clock when the factor was
+    // last set.
+    origin: NanoTimestamp,
+    scaled_origin: NanoTimestamp,
+    factor: f64,
+}
+
+/// A time provider whose time passes `factor` times as fast as the time of the
+/// clock it wraps, e.g. twice as fast with factor 2. Clones share the factor,
+/// so changing it affects all of them.
+#[derive(Clone)]
+pub struct ScaledClock {
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<ScaledState>>,
+}
+
+impl fmt::Debug for ScaledClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScaledClock").field("state", &self.state).finish()
+    }
+}
+
+impl ScaledClock {
+    /// Scale the time of `clock` from now on. The scaled time starts at the
+    /// current time of `clock`.
+    pub fn new(clock: Arc<dyn Clock>, factor: f64) -> Self {
+        let origin = clock.now();
+        Self {
+            clock,
+            state: Arc::new(Mutex::new(ScaledState {
+                origin,
+                scaled_origin: origin,
+                factor,
+            })),
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.state.lock().unwrap().factor
+    }
+
+    /// Change how fast the time passes from now on, without a jump in time.
+    pub fn set_factor(&self, factor: f64) {
+        let mut state = self.state.lock().unwrap();
+        let origin = self.clock.now();
+        state.scaled_origin = scale(&state, origin);
+        state.origin = origin;
+        state.factor = factor;
+    }
+}
+
+fn scale(state: &ScaledState, time: NanoTimestamp) -> NanoTimestamp {
+    state.scaled_origin + (time - state.origin) * state.factor
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> NanoTimestamp {
+        let state = self.state.lock().unwrap();
+        scale(&state, self.clock.now())
+    }
+}
+
+/// A time provider which is `offset` ahead of the clock it wraps, or behind
+/// it for negative offsets.
+#[derive(Clone)]
+pub struct OffsetClock {
+    clock: Arc<dyn Clock>,
+    offset: NanoDelta,
+}
+
+impl fmt::Debug for OffsetClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OffsetClock").field("offset", &self.offset).finish()
+    }
+}
+
+impl OffsetClock {
+    pub fn new(clock: Arc<dyn Clock>, offset: NanoDelta) -> Self {
+        Self { clock, offset }
+    }
+
+    /// Offset `clock` so that its current time becomes `start`, e.g. the
+    /// start of a recording, so that a replay sees the dates of the original
+    /// session.
+    pub fn starting_at(clock: Arc<dyn Clock>, start: NanoTimestamp) -> Self {
+        let offset = start - clock.now();
+        Self::new(clock, offset)
+    }
+
+    pub fn offset(&self) -> NanoDelta {
+        self.offset
+    }
+}
+
+impl Clock for OffsetClock {
+    fn now(&self) -> NanoTimestamp {
+        self.clock.now() + self.offset
+    }
+}
+
+/// A time provider which returns the same time until moved. Unlike
+/// [`ManualClock`], it can be moved backwards. Clones share the time.
+#[derive(Clone, Debug)]
+pub struct FrozenClock {
+    time: Arc<Mutex<NanoTimestamp>>,
+}
+
+impl FrozenClock {
+    pub fn new(time: NanoTimestamp) -> Self {
+        Self {
+            time: Arc::new(Mutex::new(time)),
+        }
+    }
+
+    /// Freeze the clock at the current time of `clock`.
+    pub fn at_now_of(clock: &dyn Clock) -> Self {
+        Self::new(clock.now())
+    }
+
+    pub fn move_to(&self, time: NanoTimestamp) {
+        *self.time.lock().unwrap() = time;
+    }
+
+    pub fn move_by(&self, delta: NanoDelta) {
+        let mut time = self.time.lock().unwrap();
+        *time = *time + delta;
+    }
+}
+
+impl Clock for FrozenClock {
+    fn now(&self) -> NanoTimestamp {
+        *self.time.lock().unwrap()
+    }
+}
+
+/// Measure elapsed time. Share a clock between stopwatches with `&C` or
+/// `Arc<C>`.
+pub struct Stopwatch<C = Box<dyn Clock>> {
+    clock: C,
     start_time: NanoTimestamp,
+    paused_at: Option<NanoTimestamp>,
+    // Total time of the previous pauses.
+    paused: NanoDelta,
+    laps: Vec<NanoDelta>,
+    // Elapsed time at the end of the last lap.
+    last_lap_end: NanoDelta,
 }
 
-impl fmt::Debug for Stopwatch {
+/// A [`Stopwatch`] with any clock.
+pub type BoxedStopwatch = Stopwatch<Box<dyn Clock>>;
+
+impl<C: Clock> fmt::Debug for Stopwatch<C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Stopwatch")
             .field("clock", &self.clock.now()) // Just show a placeholder
             .field("start_time", &self.start_time)
+            .field("paused_at", &self.paused_at)
+            .field("paused", &self.paused)
+            .field("laps", &self.laps)
             .finish()
     }
 }
 
-impl Stopwatch {
-    pub fn new(clock: Box<dyn Clock>) -> Self {
+impl<C: Clock> Stopwatch<C> {
+    pub fn new(clock: C) -> Self {
         Self {
             start_time: clock.now(),
             clock,
+            paused_at: None,
+            paused: NanoDelta::zero(),
+            laps: Vec::new(),
+            last_lap_end: NanoDelta::zero(),
         }
     }
 
+    /// Time elapsed since the start, without the pauses.
     pub fn elapsed(&self) -> NanoDelta {
-        self.clock.now() - self.start_time
+        let now = self.paused_at.unwrap_or_else(|| self.clock.now());
+        now - self.start_time - self.paused
     }
 
+    /// Restart from zero, unpaused and without laps.
     pub fn reset(&mut self) {
         self.start_time = self.clock.now();
+        self.paused_at = None;
+        self.paused = NanoDelta::zero();
+        self.laps.clear();
+        self.last_lap_end = NanoDelta::zero();
+    }
+
+    /// Stop counting the time until [`Stopwatch::resume`]. Does nothing if
+    /// already paused.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused = self.paused + (self.clock.now() - paused_at);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// End the current lap, returning its elapsed time.
+    pub fn lap(&mut self) -> NanoDelta {
+        let elapsed = self.elapsed();
+        let lap = elapsed - self.last_lap_end;
+        self.last_lap_end = elapsed;
+        self.laps.push(lap);
+        lap
+    }
+
+    /// Elapsed times of the finished laps, in order.
+    pub fn laps(&self) -> &[NanoDelta] {
+        &self.laps
     }
 }
 
 /// A timer that can be used to measure the elapsed time and check if timeout
 /// has occurred.
-#[derive(Debug)]
-pub struct Timer {
-    stopwatch: Stopwatch,
+pub struct Timer<C = Box<dyn Clock>> {
+    stopwatch: Stopwatch<C>,
     duration: NanoDelta,
 }
 
-impl Timer {
-    pub fn new(clock: Box<dyn Clock>, duration: NanoDelta) -> Self {
+/// A [`Timer`] with any clock.
+pub type BoxedTimer = Timer<Box<dyn Clock>>;
+
+impl<C: Clock> fmt::Debug for Timer<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("stopwatch", &self.stopwatch)
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
+
+impl<C: Clock> Timer<C> {
+    pub fn new(clock: C, duration: NanoDelta) -> Self {
         Self {
             duration,
             stopwatch: Stopwatch::new(clock),
@@ -127,6 +476,201 @@ impl Timer {
     }
 }
 
+/// A point in time to expire at, for code which passes timeouts around
+/// rather than a [`Timer`] holding a clock. Arithmetic saturates, so that
+/// e.g. a deadline after [`Deadline::never`] is still never.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline(NanoTimestamp);
+
+impl Deadline {
+    pub const fn at(time: NanoTimestamp) -> Self {
+        Self(time)
+    }
+
+    /// `timeout` from the current time of `clock`.
+    pub fn after<C: Clock + ?Sized>(clock: &C, timeout: NanoDelta) -> Self {
+        Self(clock.now()) + timeout
+    }
+
+    /// A deadline which never expires.
+    pub const fn never() -> Self {
+        Self(NanoTimestamp::from_nanos(i64::MAX))
+    }
+
+    pub const fn time(&self) -> NanoTimestamp {
+        self.0
+    }
+
+    /// Time left until the deadline, zero once expired.
+    pub fn remaining<C: Clock + ?Sized>(&self, clock: &C) -> NanoDelta {
+        let remaining = self.0.as_nanos().saturating_sub(clock.now().as_nanos());
+        NanoDelta::from_nanos(remaining.max(0))
+    }
+
+    pub fn is_expired<C: Clock + ?Sized>(&self, clock: &C) -> bool {
+        clock.now() >= self.0
+    }
+
+    /// The deadline which expires first.
+    pub fn earliest(self, other: Deadline) -> Self {
+        self.min(other)
+    }
+}
+
+impl Add<NanoDelta> for Deadline {
+    type Output = Deadline;
+
+    fn add(self, delta: NanoDelta) -> Self::Output {
+        Self(self.0.saturating_add(delta))
+    }
+}
+
+impl Sub<NanoDelta> for Deadline {
+    type Output = Deadline;
+
+    fn sub(self, delta: NanoDelta) -> Self::Output {
+        Self(self.0.saturating_sub(delta))
+    }
+}
+
+/// A timer that fires repeatedly, every `interval`, e.g. to take periodic
+/// screenshots or save periodically.
+pub struct IntervalTimer<C = Box<dyn Clock>> {
+    stopwatch: Stopwatch<C>,
+    interval: NanoDelta,
+    // Ticks returned by `poll_tick` or skipped by it.
+    ticks_polled: u64,
+}
+
+impl<C: Clock> fmt::Debug for IntervalTimer<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntervalTimer")
+            .field("stopwatch", &self.stopwatch)
+            .field("interval", &self.interval)
+            .field("ticks_polled", &self.ticks_polled)
+            .finish()
+    }
+}
+
+impl<C: Clock> IntervalTimer<C> {
+    /// The first tick is one `interval` from now. Panics if the interval is
+    /// not positive.
+    pub fn new(clock: C, interval: NanoDelta) -> Self {
+        assert!(interval > NanoDelta::zero());
+        Self {
+            stopwatch: Stopwatch::new(clock),
+            interval,
+            ticks_polled: 0,
+        }
+    }
+
+    /// Number of ticks since the start.
+    pub fn ticks_elapsed(&self) -> u64 {
+        (self.stopwatch.elapsed().as_nanos() / self.interval.as_nanos()).max(0) as u64
+    }
+
+    /// Whether a tick is due since the last call. Ticks missed since then, e.g.
+    /// because of a slow frame, are coalesced into one.
+    pub fn poll_tick(&mut self) -> bool {
+        let ticks = self.ticks_elapsed();
+        let is_due = ticks > self.ticks_polled;
+        self.ticks_polled = ticks;
+        is_due
+    }
+
+    /// Time until the next tick.
+    pub fn until_next_tick(&self) -> NanoDelta {
+        let next_tick = self.interval * (self.ticks_elapsed() as i64 + 1);
+        next_tick - self.stopwatch.elapsed()
+    }
+
+    /// Start over from now.
+    pub fn reset(&mut self) {
+        self.stopwatch.reset();
+        self.ticks_polled = 0;
+    }
+}
+
+/// Paces frames recorded at the given times, so that they are due as far
+/// apart as they were recorded. The schedule starts with the first frame
+/// asked for, which is due immediately.
+pub struct FrameScheduler<C = Box<dyn Clock>> {
+    clock: C,
+    targets: Vec<NanoTimestamp>,
+    // Time of the clock minus the recorded time, once started.
+    offset: Option<NanoDelta>,
+    // First frame not returned by `due_frames` yet.
+    next: usize,
+}
+
+impl<C: Clock> fmt::Debug for FrameScheduler<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameScheduler")
+            .field("num_targets", &self.targets.len())
+            .field("offset", &self.offset)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<C: Clock> FrameScheduler<C> {
+    /// Schedule frames recorded at `targets`, in ascending order.
+    pub fn new(clock: C, targets: Vec<NanoTimestamp>) -> Self {
+        Self {
+            clock,
+            targets,
+            offset: None,
+            next: 0,
+        }
+    }
+
+    /// Start the schedule with the frame at `index` due now, e.g. to start in
+    /// the middle of a recording.
+    pub fn start_at(&mut self, index: usize) {
+        self.next = index;
+        self.offset = self.targets.get(index).map(|target| self.clock.now() - *target);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// When the frame at `index` is due, `None` before the start.
+    pub fn due_at(&self, index: usize) -> Option<NanoTimestamp> {
+        Some(*self.targets.get(index)? + self.offset?)
+    }
+
+    /// Time until the frame at `index` is due, zero if it is already due or
+    /// the schedule hasn't started yet.
+    pub fn until_due(&self, index: usize) -> NanoDelta {
+        match self.due_at(index) {
+            Some(due_at) => (due_at - self.clock.now()).max(NanoDelta::zero()),
+            None => NanoDelta::zero(),
+        }
+    }
+
+    /// The frames which became due since the last call, starting the
+    /// schedule if needed.
+    pub fn due_frames(&mut self) -> Range<usize> {
+        if !self.is_started() {
+            self.start_at(self.next);
+        }
+        let start = self.next;
+        while self.next < self.targets.len() && self.until_due(self.next) == NanoDelta::zero() {
+            self.next += 1;
+        }
+        start..self.next
+    }
+
+    /// Time until the next frame not returned by
+    /// [`FrameScheduler::due_frames`] is due, e.g. for
+    /// `egui::Context::request_repaint_after`. `None` once all frames were
+    /// returned.
+    pub fn until_next(&self) -> Option<NanoDelta> {
+        (self.next < self.targets.len()).then(|| self.until_due(self.next))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -177,6 +721,30 @@ pub mod tests {
         assert_eq!(actual_b_times_nanos, expected_b_times_nanos);
     }
 
+    #[test]
+    fn manual_clock_steps_and_advances_until() {
+        // Arrange
+        let clock = ManualClock::new();
+        let advances = Arc::new(Mutex::new(Vec::new()));
+        let recorded = advances.clone();
+        clock.on_advance(move |time| recorded.lock().unwrap().push(time.as_nanos()));
+        clock.push_steps([NanoTimestamp::from(2), NanoTimestamp::from(5)]);
+
+        // Act
+        let first_step = clock.step();
+        let reached = clock.advance_until(NanoDelta::from(10), Deadline::at(NanoTimestamp::from(100)), |now| {
+            now >= NanoTimestamp::from(20)
+        });
+        let timed_out = !clock.advance_until(NanoDelta::from(10), Deadline::at(NanoTimestamp::from(40)), |_| false);
+
+        // Assert
+        assert_eq!(first_step, Some(NanoTimestamp::from(2)));
+        assert!(reached);
+        assert!(timed_out);
+        assert_eq!(clock.step(), None);
+        assert_eq!(*advances.lock().unwrap(), vec![2, 5, 15, 25, 35]);
+    }
+
     #[test]
     fn advance_time_across_threads_simplified() {
         // Arrange
@@ -233,6 +801,85 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn monotonic_clock_never_goes_backwards() {
+        // Arrange
+        let clock = MonotonicClock::shared();
+        let system_time = SystemClock.now();
+
+        // Act
+        let times: Vec<NanoTimestamp> = (0..100).map(|_| clock.now()).collect();
+
+        // Assert
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!((times[0] - system_time).as_secs().abs() < 60);
+    }
+
+    #[test]
+    fn scaled_clock_changes_speed_without_jumps() {
+        // Arrange
+        let clock = ManualClock::new();
+        let scaled = ScaledClock::new(Arc::new(clock.clone()), 2.0);
+
+        // Act & Assert: Twice as fast
+        clock.advance_by(NanoDelta::from(5));
+        assert_eq!(scaled.now(), NanoTimestamp::from(10));
+
+        // Act & Assert: Half as fast from the current time on
+        scaled.set_factor(0.5);
+        assert_eq!(scaled.now(), NanoTimestamp::from(10));
+        clock.advance_by(NanoDelta::from(4));
+        assert_eq!(scaled.now(), NanoTimestamp::from(12));
+    }
+
+    #[test]
+    fn offset_clock_starts_at_the_given_time() {
+        // Arrange
+        let clock = ManualClock::new();
+        clock.advance_to(NanoTimestamp::from(100));
+        let start = NanoTimestamp::from(1_000);
+
+        // Act
+        let offset = OffsetClock::starting_at(Arc::new(clock.clone()), start);
+        clock.advance_by(NanoDelta::from(5));
+
+        // Assert
+        assert_eq!(offset.offset(), NanoDelta::from(900));
+        assert_eq!(offset.now(), NanoTimestamp::from(1_005));
+    }
+
+    #[test]
+    fn frozen_clock_stands_still_until_moved() {
+        // Arrange
+        let clock = FrozenClock::new(NanoTimestamp::from(10));
+        let shared = clock.clone();
+
+        // Act & Assert
+        assert_eq!(shared.now(), NanoTimestamp::from(10));
+        clock.move_by(NanoDelta::from(-3));
+        assert_eq!(shared.now(), NanoTimestamp::from(7));
+        clock.move_to(NanoTimestamp::from(20));
+        assert_eq!(shared.now(), NanoTimestamp::from(20));
+    }
+
+    #[test]
+    fn timers_share_a_clock() {
+        // Arrange
+        let clock = ManualClock::new();
+        let short = Timer::new(&clock, NanoDelta::from(5));
+        let long = Timer::new(&clock, NanoDelta::from(10));
+        let shared_clock: Box<dyn Clock> = Box::new(Arc::new(clock.clone()));
+        let boxed: BoxedTimer = Timer::new(shared_clock, NanoDelta::from(5));
+
+        // Act
+        clock.advance_by(NanoDelta::from(7));
+
+        // Assert
+        assert!(short.is_timeout());
+        assert!(!long.is_timeout());
+        assert!(boxed.is_timeout());
+    }
+
     #[test]
     fn stopwatch_new_and_elapsed_initial() {
         // Arrange
@@ -296,6 +943,31 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn stopwatch_laps_exclude_pauses() {
+        // Arrange
+        let clock = ManualClock::new();
+        let mut stopwatch = Stopwatch::new(&clock);
+
+        // Act
+        clock.advance_by(NanoDelta::from(3));
+        let first_lap = stopwatch.lap();
+        clock.advance_by(NanoDelta::from(2));
+        stopwatch.pause();
+        clock.advance_by(NanoDelta::from(10));
+        let elapsed_while_paused = stopwatch.elapsed();
+        stopwatch.resume();
+        clock.advance_by(NanoDelta::from(4));
+        let second_lap = stopwatch.lap();
+
+        // Assert
+        assert_eq!(first_lap, NanoDelta::from(3));
+        assert_eq!(elapsed_while_paused, NanoDelta::from(5));
+        assert_eq!(second_lap, NanoDelta::from(6));
+        assert_eq!(stopwatch.laps(), &[NanoDelta::from(3), NanoDelta::from(6)]);
+        assert_eq!(stopwatch.elapsed(), NanoDelta::from(9));
+    }
+
     #[test]
     fn timer_new_and_initial_state() {
         // Arrange
@@ -420,4 +1092,71 @@ pub mod tests {
         );
         assert_eq!(timer.elapsed(), NanoDelta::from(5));
     }
+
+    #[test]
+    fn deadline_remaining_and_saturation() {
+        // Arrange
+        let clock = ManualClock::new();
+        let deadline = Deadline::after(&clock, NanoDelta::from(10));
+
+        // Act
+        clock.advance_by(NanoDelta::from(4));
+        let remaining = deadline.remaining(&clock);
+        clock.advance_by(NanoDelta::from(8));
+
+        // Assert
+        assert_eq!(remaining, NanoDelta::from(6));
+        assert!(deadline.is_expired(&clock));
+        assert_eq!(deadline.remaining(&clock), NanoDelta::zero());
+        assert_eq!(Deadline::never() + NanoDelta::from(1), Deadline::never());
+        assert_eq!(deadline.earliest(Deadline::never()), deadline);
+    }
+
+    #[test]
+    fn frame_scheduler_paces_recorded_frames() {
+        // Arrange
+        let clock = ManualClock::new();
+        clock.advance_to(NanoTimestamp::from(1_000));
+        let targets = [5, 15, 16, 40].map(NanoTimestamp::from).to_vec();
+        let mut scheduler = FrameScheduler::new(&clock, targets);
+
+        // Act & Assert: The first frame is due immediately
+        assert_eq!(scheduler.due_frames(), 0..1);
+        assert_eq!(scheduler.until_next(), Some(NanoDelta::from(10)));
+
+        // Act & Assert: Frames due at once are returned together
+        clock.advance_by(NanoDelta::from(12));
+        assert_eq!(scheduler.due_frames(), 1..3);
+        assert_eq!(scheduler.until_next(), Some(NanoDelta::from(23)));
+        assert_eq!(scheduler.due_at(3), Some(NanoTimestamp::from(1_035)));
+
+        // Act & Assert: Nothing is left
+        clock.advance_by(NanoDelta::from(100));
+        assert_eq!(scheduler.due_frames(), 3..4);
+        assert_eq!(scheduler.until_next(), None);
+    }
+
+    #[test]
+    fn interval_timer_ticks() {
+        // Arrange
+        let clock = ManualClock::new();
+        let mut timer = IntervalTimer::new(&clock, NanoDelta::from(10));
+
+        // Act & Assert: Before the first tick
+        clock.advance_by(NanoDelta::from(9));
+        assert!(!timer.poll_tick());
+        assert_eq!(timer.until_next_tick(), NanoDelta::from(1));
+
+        // Act & Assert: Each tick is polled once
+        clock.advance_by(NanoDelta::from(1));
+        assert!(timer.poll_tick());
+        assert!(!timer.poll_tick());
+
+        // Act & Assert: Missed ticks are coalesced
+        clock.advance_by(NanoDelta::from(25));
+        assert_eq!(timer.ticks_elapsed(), 3);
+        assert!(timer.poll_tick());
+        assert!(!timer.poll_tick());
+        assert_eq!(timer.until_next_tick(), NanoDelta::from(5));
+    }
 }
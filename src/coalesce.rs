@@ -0,0 +1,143 @@
+//! Coalescing of recorded scroll and zoom events.
+//!
+//! High resolution trackpads send hundreds of mouse wheel and zoom events per
+//! second. egui sums the scroll deltas and multiplies the zoom factors of a
+//! frame anyway, so [`ScrollCoalescing`] merges consecutive events into one
+//! when recordings are saved. Apps which count individual wheel ticks can
+//! turn it off.
+
+use crate::replay_events::FrameEvents;
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// How scroll and zoom events are coalesced, see
+/// [`crate::replay_events::ReplayManager::with_scroll_coalescing`]. Only
+/// consecutive events of the same viewport are merged, wheel events only if
+/// their unit and modifiers match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ScrollCoalescing {
+    /// Record every event.
+    Off,
+    /// Merge the events within a frame, which doesn't change how egui
+    /// scrolls or zooms.
+    #[default]
+    Frame,
+    /// Merge the events within the given time window into the first frame
+    /// of the window, even if the frames in between had no other events.
+    Window(NanoDelta),
+}
+
+/// Merge the scroll and zoom events of the frames, see [`ScrollCoalescing`].
+/// Frames are kept even if all their events were merged into an earlier
+/// frame. Returns the number of merged events.
+pub fn coalesce_scroll_events(frames: &mut [FrameEvents], coalescing: ScrollCoalescing) -> usize {
+    let window = match coalescing {
+        ScrollCoalescing::Off => return 0,
+        ScrollCoalescing::Frame => None,
+        ScrollCoalescing::Window(window) => Some(window),
+    };
+    let mut num_merged = 0;
+    // Frame index, event index and frame time of the last kept event, if it
+    // can absorb the next events.
+    let mut last: Option<(usize, usize, NanoTimestamp)> = None;
+    for frame_index in 0..frames.len() {
+        if window.is_none() {
+            last = None;
+        }
+        let events = std::mem::take(&mut frames[frame_index].events);
+        for event in events {
+            if let Some((last_frame_index, last_event_index, last_time)) = last {
+                let last_frame = &frames[last_frame_index];
+                let frame = &frames[frame_index];
+                let same_input = last_frame.viewport_id == frame.viewport_id && last_frame.modifiers == frame.modifiers;
+                let within_window = match window {
+                    Some(window) => frame
+                        .time
+                        .as_nanos()
+                        .checked_sub(last_time.as_nanos())
+                        .is_some_and(|elapsed| elapsed <= window.as_nanos()),
+                    None => true,
+                };
+                let last_event = &mut frames[last_frame_index].events[last_event_index];
+                if same_input && within_window && merge_event(last_event, &event) {
+                    num_merged += 1;
+                    continue;
+                }
+            }
+            let frame = &mut frames[frame_index];
+            last = is_scroll(&event).then_some((frame_index, frame.events.len(), frame.time));
+            frame.events.push(event);
+        }
+    }
+    num_merged
+}
+
+fn is_scroll(event: &egui::Event) -> bool {
+    matches!(event, egui::Event::MouseWheel { .. } | egui::Event::Zoom(_))
+}
+
+// Merge `event` into `into` if they are of the same kind.
+fn merge_event(into: &mut egui::Event, event: &egui::Event) -> bool {
+    match (into, event) {
+        (
+            egui::Event::MouseWheel { unit, delta, modifiers },
+            egui::Event::MouseWheel {
+                unit: next_unit,
+                delta: next_delta,
+                modifiers: next_modifiers,
+            },
+        ) if unit == next_unit && modifiers == next_modifiers => {
+            *delta += *next_delta;
+            true
+        }
+        (egui::Event::Zoom(factor), egui::Event::Zoom(next_factor)) => {
+            *factor *= *next_factor;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wheel(delta: f32) -> egui::Event {
+        egui::Event::MouseWheel {
+            unit: egui::MouseWheelUnit::Point,
+            delta: egui::vec2(0.0, delta),
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    fn frames() -> Vec<FrameEvents> {
+        vec![
+            FrameEvents::new(
+                NanoTimestamp::from(0),
+                vec![wheel(1.0), wheel(2.0), egui::Event::Copy, wheel(4.0)],
+            ),
+            FrameEvents::new(NanoTimestamp::from(5_000_000), vec![wheel(8.0), egui::Event::Zoom(2.0)]),
+            FrameEvents::new(NanoTimestamp::from(6_000_000), vec![egui::Event::Zoom(3.0)]),
+        ]
+    }
+
+    #[test]
+    fn events_are_merged_within_frames() {
+        let mut frames = frames();
+
+        assert_eq!(coalesce_scroll_events(&mut frames, ScrollCoalescing::Frame), 1);
+        assert_eq!(frames[0].events, vec![wheel(3.0), egui::Event::Copy, wheel(4.0)]);
+        assert_eq!(frames[1].events.len(), 2);
+        assert_eq!(coalesce_scroll_events(&mut frames, ScrollCoalescing::Off), 0);
+    }
+
+    #[test]
+    fn events_are_merged_within_time_windows() {
+        let mut frames = frames();
+        let window = ScrollCoalescing::Window(NanoDelta::from_millis_safe(5));
+
+        assert_eq!(coalesce_scroll_events(&mut frames, window), 3);
+        assert_eq!(frames[0].events, vec![wheel(3.0), egui::Event::Copy, wheel(12.0)]);
+        assert_eq!(frames[1].events, vec![egui::Event::Zoom(6.0)]);
+        assert!(frames[2].events.is_empty());
+    }
+}
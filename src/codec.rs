@@ -0,0 +1,755 @@
+//! Serialization formats of recordings.
+//!
+//! Each format is a [`ReplayCodec`], registered for one or more file
+//! extensions. Recordings are loaded and saved with the codec registered for
+//! their extension, so applications can add their own formats with
+//! [`register_codec`]. Built-in codecs:
+//! - `.bin`: bincode, prefixed by a magic number and a format version.
+//! - `.json`: JSON object, useful for inspection and diffs.
+//! - `.ibin`: binary with an index of the frames, replayed lazily, see
+//!   [`crate::indexed`].
+//! - `.msgpack`: MessagePack, with the `msgpack` feature.
+//! - `.cbor`: CBOR, with the `cbor` feature.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use bincode::Decode;
+
+use crate::assertions::Assertion;
+use crate::audio::AudioTrack;
+use crate::event::{LoadedEvent, ReplayEvent, StoredEvent};
+use crate::indexed::IndexedCodec;
+use crate::logs::LogRecord;
+use crate::replay_events::{root_viewport_id, Annotation, FrameEvents, Gap, Marker, PointerAnchor, RecordedFile};
+use crate::replay_file::{LoadDiagnostics, ReplayError, ReplayFile, SkippedEvent};
+use crate::timestamp::NanoTimestamp;
+
+const BINARY_MAGIC: &[u8; 4] = b"EGRP";
+/// Format version of binary files written by [`BincodeCodec`].
+pub const BINARY_FORMAT_VERSION: u32 = 19;
+// The last version without pauses of the recording.
+const NO_GAPS_FORMAT_VERSION: u32 = 18;
+// The last version without audio.
+const NO_AUDIO_FORMAT_VERSION: u32 = 17;
+// The last version without annotations. Version 16 only added accessibility
+// actions to `ReplayEvent`, so its layout is the same as in version 15.
+const NO_ANNOTATIONS_FORMAT_VERSION: u32 = 16;
+// The last version without widget anchors of pointer events.
+const NO_POINTER_ANCHOR_FORMAT_VERSION: u32 = 14;
+// The last version without the screen rectangle.
+const NO_SCREEN_RECT_FORMAT_VERSION: u32 = 13;
+// The last version without the identity of the application.
+const NO_APP_IDENTITY_FORMAT_VERSION: u32 = 12;
+// The last version without the snapshot of the application state.
+const NO_STATE_SNAPSHOT_FORMAT_VERSION: u32 = 11;
+// The last version without the seeds of random number generators.
+const NO_RNG_SEEDS_FORMAT_VERSION: u32 = 10;
+// The last version without user data in frames.
+const NO_USER_DATA_FORMAT_VERSION: u32 = 9;
+// The last version without captured log lines.
+const NO_LOGS_FORMAT_VERSION: u32 = 8;
+// The last version storing `egui::Event`s instead of `ReplayEvent`s.
+const EGUI_EVENTS_FORMAT_VERSION: u32 = 7;
+
+/// A serialization format of recordings.
+pub trait ReplayCodec: Send + Sync {
+    /// File extensions handled by the codec, without the leading dot.
+    fn extensions(&self) -> &[&str];
+
+    fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError>;
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError>;
+
+    /// Decode the recording, skipping events which fail to load instead of
+    /// failing. Decodes strictly unless overridden.
+    fn decode_lenient(&self, bytes: &[u8]) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+        Ok((self.decode(bytes)?, LoadDiagnostics::default()))
+    }
+}
+
+// Frame layout of binary files written before versioning was introduced.
+#[derive(Decode)]
+struct LegacyFrameEvents {
+    #[bincode(with_serde)]
+    time: NanoTimestamp,
+    #[bincode(with_serde)]
+    events: Vec<egui::Event>,
+}
+
+impl From<LegacyFrameEvents> for FrameEvents {
+    fn from(frame: LegacyFrameEvents) -> Self {
+        Self::new(frame.time, frame.events)
+    }
+}
+
+// Frame layout of format version 7 and older JSON files, which stored
+// `egui::Event`s directly.
+#[derive(serde::Deserialize, Decode)]
+struct EguiFrameEvents {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    time: NanoTimestamp,
+    #[bincode(with_serde)]
+    events: Vec<egui::Event>,
+    #[serde(default)]
+    frame_hash: Option<u64>,
+    #[serde(default = "root_viewport_id")]
+    #[bincode(with_serde)]
+    viewport_id: egui::ViewportId,
+    #[serde(default)]
+    hovered_files: Option<Vec<RecordedFile>>,
+    #[serde(default)]
+    dropped_files: Vec<RecordedFile>,
+    #[serde(default)]
+    #[bincode(with_serde)]
+    modifiers: egui::Modifiers,
+    #[serde(default)]
+    focused: Option<bool>,
+}
+
+impl From<EguiFrameEvents> for FrameEvents {
+    fn from(frame: EguiFrameEvents) -> Self {
+        Self {
+            time: frame.time,
+            events: frame.events,
+            frame_hash: frame.frame_hash,
+            viewport_id: frame.viewport_id,
+            hovered_files: frame.hovered_files,
+            dropped_files: frame.dropped_files,
+            modifiers: frame.modifiers,
+            focused: frame.focused,
+            user_data: BTreeMap::new(),
+            pointer_anchor: None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Decode)]
+struct EguiReplayFile {
+    frames: Vec<EguiFrameEvents>,
+    #[serde(default)]
+    markers: Vec<Marker>,
+    #[serde(default)]
+    assertions: Vec<Assertion>,
+}
+
+impl From<EguiReplayFile> for ReplayFile {
+    fn from(file: EguiReplayFile) -> Self {
+        Self {
+            rng_seeds: BTreeMap::new(),
+            state_snapshot: None,
+            app_identity: None,
+            state_fingerprint: None,
+            screen_rect: None,
+            frames: file.frames.into_iter().map(FrameEvents::from).collect(),
+            markers: file.markers,
+            annotations: Vec::new(),
+            gaps: Vec::new(),
+            assertions: file.assertions,
+            logs: Vec::new(),
+            audio: None,
+        }
+    }
+}
+
+// Events of a frame, stored as `ReplayEvent`s since format version 8.
+#[derive(serde::Deserialize)]
+struct StoredEvents(#[serde(with = "crate::event::serde_events")] Vec<egui::Event>);
+
+// Reads a binary file of format version 8 to 18 field by field, in the order
+// of the current layout. Later versions only added fields, which are left at
+// their defaults when the file is older than them.
+struct OlderVersionReader<'a> {
+    version: u32,
+    bytes: &'a [u8],
+}
+
+impl OlderVersionReader<'_> {
+    fn field<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, ReplayError> {
+        let (value, len) = bincode::serde::decode_from_slice(self.bytes, bincode::config::standard())?;
+        self.bytes = &self.bytes[len..];
+        Ok(value)
+    }
+
+    // A field added after format version `last_without`.
+    fn field_added_after<T: serde::de::DeserializeOwned + Default>(
+        &mut self,
+        last_without: u32,
+    ) -> Result<T, ReplayError> {
+        if self.version > last_without {
+            self.field()
+        } else {
+            Ok(T::default())
+        }
+    }
+
+    fn frames(&mut self) -> Result<Vec<FrameEvents>, ReplayError> {
+        let num_frames: u64 = self.field()?;
+        (0..num_frames).map(|_| self.frame()).collect()
+    }
+
+    fn frame(&mut self) -> Result<FrameEvents, ReplayError> {
+        Ok(FrameEvents {
+            time: self.field()?,
+            events: self.field::<StoredEvents>()?.0,
+            frame_hash: self.field()?,
+            viewport_id: self.field()?,
+            hovered_files: self.field()?,
+            dropped_files: self.field()?,
+            modifiers: self.field()?,
+            focused: self.field()?,
+            user_data: self.field_added_after(NO_USER_DATA_FORMAT_VERSION)?,
+            pointer_anchor: self.field_added_after(NO_POINTER_ANCHOR_FORMAT_VERSION)?,
+        })
+    }
+
+    fn replay_file(mut self) -> Result<ReplayFile, ReplayError> {
+        Ok(ReplayFile {
+            rng_seeds: self.field_added_after(NO_RNG_SEEDS_FORMAT_VERSION)?,
+            state_snapshot: self.field_added_after(NO_STATE_SNAPSHOT_FORMAT_VERSION)?,
+            app_identity: self.field_added_after(NO_APP_IDENTITY_FORMAT_VERSION)?,
+            state_fingerprint: self.field_added_after(NO_APP_IDENTITY_FORMAT_VERSION)?,
+            screen_rect: self.field_added_after(NO_SCREEN_RECT_FORMAT_VERSION)?,
+            frames: self.frames()?,
+            markers: self.field()?,
+            annotations: self.field_added_after(NO_ANNOTATIONS_FORMAT_VERSION)?,
+            gaps: self.field_added_after(NO_GAPS_FORMAT_VERSION)?,
+            assertions: self.field()?,
+            logs: self.field_added_after(NO_LOGS_FORMAT_VERSION)?,
+            audio: self.field_added_after(NO_AUDIO_FORMAT_VERSION)?,
+        })
+    }
+}
+
+// Layout of the current format version with events which may fail to load,
+// for lenient loading. The fields are in the same order as in `ReplayFile`
+// and `FrameEvents`.
+#[derive(serde::Deserialize)]
+struct LenientFrameEvents<E> {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    time: NanoTimestamp,
+    events: Vec<E>,
+    #[serde(default)]
+    frame_hash: Option<u64>,
+    #[serde(default = "root_viewport_id")]
+    viewport_id: egui::ViewportId,
+    #[serde(default)]
+    hovered_files: Option<Vec<RecordedFile>>,
+    #[serde(default)]
+    dropped_files: Vec<RecordedFile>,
+    #[serde(default)]
+    modifiers: egui::Modifiers,
+    #[serde(default)]
+    focused: Option<bool>,
+    #[serde(default)]
+    user_data: BTreeMap<String, Vec<u8>>,
+    #[serde(default)]
+    pointer_anchor: Option<PointerAnchor>,
+}
+
+#[derive(serde::Deserialize)]
+struct LenientReplayFile<E> {
+    #[serde(default)]
+    rng_seeds: BTreeMap<String, u64>,
+    #[serde(default)]
+    state_snapshot: Option<Vec<u8>>,
+    #[serde(default)]
+    app_identity: Option<String>,
+    #[serde(default)]
+    state_fingerprint: Option<String>,
+    #[serde(default)]
+    screen_rect: Option<egui::Rect>,
+    frames: Vec<LenientFrameEvents<E>>,
+    #[serde(default)]
+    markers: Vec<Marker>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    #[serde(default)]
+    gaps: Vec<Gap>,
+    #[serde(default)]
+    assertions: Vec<Assertion>,
+    #[serde(default)]
+    logs: Vec<LogRecord>,
+    #[serde(default)]
+    audio: Option<AudioTrack>,
+}
+
+impl<E: LoadedEvent> LenientReplayFile<E> {
+    fn into_replay_file(self) -> (ReplayFile, LoadDiagnostics) {
+        let mut diagnostics = LoadDiagnostics::default();
+        let frames = self
+            .frames
+            .into_iter()
+            .enumerate()
+            .map(|(frame_index, frame)| {
+                let events = frame
+                    .events
+                    .into_iter()
+                    .filter_map(|event| match event.into_event() {
+                        Ok(event) => Some(event),
+                        Err(reason) => {
+                            diagnostics.skipped_events.push(SkippedEvent { frame_index, reason });
+                            None
+                        }
+                    })
+                    .collect();
+                FrameEvents {
+                    time: frame.time,
+                    events,
+                    frame_hash: frame.frame_hash,
+                    viewport_id: frame.viewport_id,
+                    hovered_files: frame.hovered_files,
+                    dropped_files: frame.dropped_files,
+                    modifiers: frame.modifiers,
+                    focused: frame.focused,
+                    user_data: frame.user_data,
+                    pointer_anchor: frame.pointer_anchor,
+                }
+            })
+            .collect();
+        let file = ReplayFile {
+            rng_seeds: self.rng_seeds,
+            state_snapshot: self.state_snapshot,
+            app_identity: self.app_identity,
+            state_fingerprint: self.state_fingerprint,
+            screen_rect: self.screen_rect,
+            frames,
+            markers: self.markers,
+            annotations: self.annotations,
+            gaps: self.gaps,
+            assertions: self.assertions,
+            logs: self.logs,
+            audio: self.audio,
+        };
+        (file, diagnostics)
+    }
+}
+
+/// Bincode files, encoded through serde so that events are stored as
+/// `ReplayEvent`s. Files of older format versions, and files written before
+/// the format was versioned (without the magic prefix) are still loadable.
+pub struct BincodeCodec;
+
+// The body of a binary file with the layout of the current format version,
+// if it is one.
+fn current_version_body(bytes: &[u8]) -> Result<Option<&[u8]>, ReplayError> {
+    let Some(body) = bytes.strip_prefix(BINARY_MAGIC.as_slice()) else {
+        return Ok(None);
+    };
+    let (version, version_len): (u32, usize) = bincode::decode_from_slice(body, bincode::config::standard())?;
+    Ok((version == BINARY_FORMAT_VERSION).then_some(&body[version_len..]))
+}
+
+/// Format version of a binary recording, `None` if it was written before the
+/// format was versioned.
+pub fn binary_format_version(bytes: &[u8]) -> Result<Option<u32>, ReplayError> {
+    let Some(body) = bytes.strip_prefix(BINARY_MAGIC.as_slice()) else {
+        return Ok(None);
+    };
+    let (version, _): (u32, usize) = bincode::decode_from_slice(body, bincode::config::standard())?;
+    Ok(Some(version))
+}
+
+impl ReplayCodec for BincodeCodec {
+    fn extensions(&self) -> &[&str] {
+        &["bin"]
+    }
+
+    fn encode(&self, file: &ReplayFile, mut writer: &mut dyn Write) -> Result<(), ReplayError> {
+        let config = bincode::config::standard();
+        writer.write_all(BINARY_MAGIC)?;
+        bincode::encode_into_std_write(BINARY_FORMAT_VERSION, &mut writer, config)?;
+        bincode::serde::encode_into_std_write(file, &mut writer, config)?;
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        let config = bincode::config::standard();
+        match bytes.strip_prefix(BINARY_MAGIC.as_slice()) {
+            Some(body) => {
+                let (version, version_len): (u32, usize) = bincode::decode_from_slice(body, config)?;
+                let body = &body[version_len..];
+                match version {
+                    BINARY_FORMAT_VERSION => {
+                        let (file, _): (ReplayFile, usize) = bincode::serde::decode_from_slice(body, config)?;
+                        Ok(file)
+                    }
+                    NO_LOGS_FORMAT_VERSION..=NO_GAPS_FORMAT_VERSION => {
+                        OlderVersionReader { version, bytes: body }.replay_file()
+                    }
+                    EGUI_EVENTS_FORMAT_VERSION => {
+                        let (file, _): (EguiReplayFile, usize) = bincode::decode_from_slice(body, config)?;
+                        Ok(file.into())
+                    }
+                    _ => Err(ReplayError::UnsupportedVersion(version)),
+                }
+            }
+            None => {
+                let (frames, _): (Vec<LegacyFrameEvents>, usize) = bincode::decode_from_slice(bytes, config)?;
+                Ok(ReplayFile::new(frames.into_iter().map(FrameEvents::from).collect()))
+            }
+        }
+    }
+
+    // Bincode is not self-describing, so only events with e.g. unknown keys
+    // can be skipped, not unknown event variants.
+    fn decode_lenient(&self, bytes: &[u8]) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+        match current_version_body(bytes)? {
+            Some(body) => {
+                let (file, _): (LenientReplayFile<ReplayEvent>, usize) =
+                    bincode::serde::decode_from_slice(body, bincode::config::standard())?;
+                Ok(file.into_replay_file())
+            }
+            // Older versions are decoded strictly, as their layouts differ.
+            None => Ok((self.decode(bytes)?, LoadDiagnostics::default())),
+        }
+    }
+}
+
+// JSON files are either the current object layout, the object layout with
+// `egui::Event`s, or a bare array of frames.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum JsonReplayFile {
+    Current(Box<ReplayFile>),
+    EguiEvents(EguiReplayFile),
+    Legacy(Vec<EguiFrameEvents>),
+}
+
+/// JSON files, with times as RFC 3339 strings. Files storing `egui::Event`s,
+/// and files with a bare array of frames, written before markers were added,
+/// are still loadable, as are times stored as nanoseconds.
+pub struct JsonCodec;
+
+impl ReplayCodec for JsonCodec {
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError> {
+        serde_json::to_writer(writer, file)?;
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        Ok(match serde_json::from_slice(bytes)? {
+            JsonReplayFile::Current(file) => *file,
+            JsonReplayFile::EguiEvents(file) => file.into(),
+            JsonReplayFile::Legacy(frames) => ReplayFile::new(frames.into_iter().map(FrameEvents::from).collect()),
+        })
+    }
+
+    fn decode_lenient(&self, bytes: &[u8]) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+        // Files of older layouts would otherwise load with all their events
+        // skipped as unknown.
+        if let Ok(file) = self.decode(bytes) {
+            return Ok((file, LoadDiagnostics::default()));
+        }
+        let file: LenientReplayFile<StoredEvent> = serde_json::from_slice(bytes)?;
+        Ok(file.into_replay_file())
+    }
+}
+
+/// MessagePack files, with struct fields stored by name like in JSON.
+#[cfg(feature = "msgpack")]
+pub struct MsgPackCodec;
+
+#[cfg(feature = "msgpack")]
+impl ReplayCodec for MsgPackCodec {
+    fn extensions(&self) -> &[&str] {
+        &["msgpack"]
+    }
+
+    fn encode(&self, file: &ReplayFile, mut writer: &mut dyn Write) -> Result<(), ReplayError> {
+        rmp_serde::encode::write_named(&mut writer, file)?;
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// CBOR files.
+#[cfg(feature = "cbor")]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl ReplayCodec for CborCodec {
+    fn extensions(&self) -> &[&str] {
+        &["cbor"]
+    }
+
+    fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError> {
+        ciborium::into_writer(file, writer)?;
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+/// Codecs by file extension.
+#[derive(Clone)]
+pub struct CodecRegistry {
+    codecs: HashMap<String, Arc<dyn ReplayCodec>>,
+}
+
+impl Default for CodecRegistry {
+    /// A registry with the built-in codecs.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(BincodeCodec);
+        registry.register(JsonCodec);
+        registry.register(IndexedCodec);
+        #[cfg(feature = "msgpack")]
+        registry.register(MsgPackCodec);
+        #[cfg(feature = "cbor")]
+        registry.register(CborCodec);
+        registry
+    }
+}
+
+impl CodecRegistry {
+    pub fn empty() -> Self {
+        Self { codecs: HashMap::new() }
+    }
+
+    /// Register the codec for all of its extensions, replacing the codecs
+    /// previously registered for them.
+    pub fn register(&mut self, codec: impl ReplayCodec + 'static) {
+        let codec: Arc<dyn ReplayCodec> = Arc::new(codec);
+        for extension in codec.extensions() {
+            self.codecs.insert(extension.to_string(), codec.clone());
+        }
+    }
+
+    /// The codec registered for the extension of the path.
+    pub fn codec_for_path(&self, path: &Path) -> Result<Arc<dyn ReplayCodec>, ReplayError> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.codecs.get(extension))
+            .cloned()
+            .ok_or_else(|| ReplayError::UnknownExtension(path.display().to_string()))
+    }
+
+    /// All registered extensions, sorted.
+    pub fn extensions(&self) -> Vec<&str> {
+        let mut extensions: Vec<&str> = self.codecs.keys().map(String::as_str).collect();
+        extensions.sort_unstable();
+        extensions
+    }
+}
+
+fn global_registry() -> &'static RwLock<CodecRegistry> {
+    static REGISTRY: OnceLock<RwLock<CodecRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(CodecRegistry::default()))
+}
+
+/// Register a codec used by [`ReplayFile::load`] and [`ReplayFile::save`].
+pub fn register_codec(codec: impl ReplayCodec + 'static) {
+    match global_registry().write() {
+        Ok(mut registry) => registry.register(codec),
+        Err(poisoned) => poisoned.into_inner().register(codec),
+    }
+}
+
+/// A copy of the codecs used by [`ReplayFile::load`] and
+/// [`ReplayFile::save`].
+pub fn registered_codecs() -> CodecRegistry {
+    match global_registry().read() {
+        Ok(registry) => registry.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertions::AssertionKind;
+    use crate::timestamp::NanoDelta;
+
+    // Stores only the number of frames, to check that codecs are looked up
+    // by extension.
+    struct CountCodec;
+
+    impl ReplayCodec for CountCodec {
+        fn extensions(&self) -> &[&str] {
+            &["count"]
+        }
+
+        fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError> {
+            write!(writer, "{}", file.num_frames())?;
+            Ok(())
+        }
+
+        fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+            let count: i64 = String::from_utf8_lossy(bytes)
+                .parse()
+                .map_err(|err| ReplayError::Codec(format!("Invalid count: {}", err)))?;
+            Ok(ReplayFile::new(
+                (0..count)
+                    .map(|i| FrameEvents::new(NanoTimestamp::from(i), Vec::new()))
+                    .collect(),
+            ))
+        }
+    }
+
+    #[test]
+    fn codecs_are_chosen_by_extension() {
+        let mut registry = CodecRegistry::default();
+        registry.register(CountCodec);
+
+        assert_eq!(registry.extensions(), vec!["bin", "count", "ibin", "json"]);
+        let codec = registry.codec_for_path(Path::new("a.count")).unwrap();
+        let mut bytes = Vec::new();
+        codec
+            .encode(
+                &ReplayFile::new(vec![FrameEvents::new(NanoTimestamp::from(0), Vec::new())]),
+                &mut bytes,
+            )
+            .unwrap();
+        assert_eq!(bytes, b"1");
+        assert!(matches!(
+            registry.codec_for_path(Path::new("a.txt")),
+            Err(ReplayError::UnknownExtension(_))
+        ));
+    }
+
+    // A recording with every field set that the layout of `version` has.
+    fn fixture_recording(version: u32) -> ReplayFile {
+        let has = |last_without: u32| version > last_without;
+        let time = NanoTimestamp::from(1_000);
+        let frame = FrameEvents {
+            time,
+            events: vec![egui::Event::Copy, egui::Event::Text("a".to_string())],
+            frame_hash: Some(7),
+            viewport_id: egui::ViewportId::ROOT,
+            hovered_files: Some(Vec::new()),
+            dropped_files: vec![RecordedFile {
+                path: Some("a.txt".to_string()),
+                name: "a.txt".to_string(),
+                mime: "text/plain".to_string(),
+                bytes: Some(vec![1, 2]),
+            }],
+            modifiers: egui::Modifiers::SHIFT,
+            focused: Some(true),
+            user_data: if has(NO_USER_DATA_FORMAT_VERSION) {
+                BTreeMap::from([("state".to_string(), vec![3])])
+            } else {
+                BTreeMap::new()
+            },
+            pointer_anchor: has(NO_POINTER_ANCHOR_FORMAT_VERSION).then(|| PointerAnchor {
+                widget_id: egui::Id::new("button"),
+                rect: egui::Rect::from_min_size(egui::pos2(1.0, 2.0), egui::vec2(3.0, 4.0)),
+            }),
+        };
+        let mut file = ReplayFile {
+            frames: vec![frame],
+            markers: vec![Marker {
+                name: "marker".to_string(),
+                time,
+            }],
+            assertions: vec![Assertion {
+                time,
+                kind: AssertionKind::ClipboardContains { text: "a".to_string() },
+            }],
+            ..Default::default()
+        };
+        if has(NO_RNG_SEEDS_FORMAT_VERSION) {
+            file.rng_seeds = BTreeMap::from([("rng".to_string(), 42)]);
+        }
+        if has(NO_STATE_SNAPSHOT_FORMAT_VERSION) {
+            file.state_snapshot = Some(vec![4]);
+        }
+        if has(NO_APP_IDENTITY_FORMAT_VERSION) {
+            file.app_identity = Some("app 1.0".to_string());
+            file.state_fingerprint = Some("abc".to_string());
+        }
+        if has(NO_SCREEN_RECT_FORMAT_VERSION) {
+            file.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0)));
+        }
+        if has(NO_ANNOTATIONS_FORMAT_VERSION) {
+            file.annotations = vec![Annotation {
+                text: "note".to_string(),
+                time,
+            }];
+        }
+        if has(NO_GAPS_FORMAT_VERSION) {
+            file.gaps = vec![Gap {
+                time,
+                duration: NanoDelta::from(5),
+            }];
+        }
+        if has(NO_LOGS_FORMAT_VERSION) {
+            file.logs = vec![LogRecord {
+                time,
+                level: "INFO".to_string(),
+                target: "app".to_string(),
+                message: "hello".to_string(),
+            }];
+        }
+        if has(NO_AUDIO_FORMAT_VERSION) {
+            file.audio = Some(AudioTrack::file("narration.ogg", time));
+        }
+        file
+    }
+
+    #[test]
+    fn fixtures_of_all_versions_load() {
+        let fixtures: [(u32, &[u8]); 13] = [
+            (7, include_bytes!("../tests/fixtures/format_v7.bin")),
+            (8, include_bytes!("../tests/fixtures/format_v8.bin")),
+            (9, include_bytes!("../tests/fixtures/format_v9.bin")),
+            (10, include_bytes!("../tests/fixtures/format_v10.bin")),
+            (11, include_bytes!("../tests/fixtures/format_v11.bin")),
+            (12, include_bytes!("../tests/fixtures/format_v12.bin")),
+            (13, include_bytes!("../tests/fixtures/format_v13.bin")),
+            (14, include_bytes!("../tests/fixtures/format_v14.bin")),
+            (15, include_bytes!("../tests/fixtures/format_v15.bin")),
+            (16, include_bytes!("../tests/fixtures/format_v16.bin")),
+            (17, include_bytes!("../tests/fixtures/format_v17.bin")),
+            (18, include_bytes!("../tests/fixtures/format_v18.bin")),
+            (19, include_bytes!("../tests/fixtures/format_v19.bin")),
+        ];
+
+        for (version, bytes) in fixtures {
+            assert_eq!(binary_format_version(bytes).unwrap(), Some(version));
+            let file = BincodeCodec.decode(bytes).unwrap();
+            assert_eq!(file, fixture_recording(version), "format version {}", version);
+            // Loaded files are saved in the current version.
+            let mut saved = Vec::new();
+            BincodeCodec.encode(&file, &mut saved).unwrap();
+            assert_eq!(BincodeCodec.decode(&saved).unwrap(), file);
+        }
+    }
+
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    fn assert_round_trip(codec: &dyn ReplayCodec) {
+        let file = ReplayFile::new(vec![FrameEvents::new(
+            NanoTimestamp::from(1_000),
+            vec![egui::Event::Text("hello".to_string())],
+        )]);
+        let mut bytes = Vec::new();
+
+        codec.encode(&file, &mut bytes).unwrap();
+
+        assert_eq!(codec.decode(&bytes).unwrap(), file);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_round_trip() {
+        assert_round_trip(&MsgPackCodec);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trip() {
+        assert_round_trip(&CborCodec);
+    }
+}
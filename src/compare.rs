@@ -0,0 +1,219 @@
+//! Frame by frame comparison of a replay with its recording.
+//!
+//! [`FrameComparison`] collects the recorded and replayed frame hash of each
+//! replayed frame, see [`crate::divergence`], and shows them side by side in a
+//! window once the replay finishes or pauses, with the first differing frame
+//! highlighted. With screenshots, the frame right before the first difference
+//! and the differing frame are captured too, to see what changed. Enable it
+//! with [`crate::replay_events::ReplayManager::with_comparison`].
+
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, RichText, TextureHandle};
+
+/// Id of the comparison window, which is left out of the frame hashes.
+pub const WINDOW_ID: &str = "replay_comparison";
+
+/// Recorded and replayed hash of a replayed frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComparedFrame {
+    pub frame_index: usize,
+    pub recorded: u64,
+    pub replayed: u64,
+}
+
+impl ComparedFrame {
+    pub fn matches(&self) -> bool {
+        self.recorded == self.replayed
+    }
+}
+
+// Tags the screenshots requested by the comparison with their frame.
+struct ScreenshotOf(usize);
+
+struct Screenshot {
+    frame_index: usize,
+    image: Arc<ColorImage>,
+    texture: Option<TextureHandle>,
+}
+
+impl Screenshot {
+    fn show(&mut self, ui: &mut egui::Ui, label: &str) {
+        ui.label(format!("{}: frame {}", label, self.frame_index + 1));
+        let texture = self.texture.get_or_insert_with(|| {
+            let name = format!("egui_replay_compare_{}", self.frame_index);
+            ui.ctx().load_texture(name, self.image.clone(), Default::default())
+        });
+        ui.add(egui::Image::from_texture(&*texture).shrink_to_fit());
+    }
+}
+
+/// Comparison of the running replay, see the module docs.
+pub struct FrameComparison {
+    capture_screenshots: bool,
+    frames: Vec<ComparedFrame>,
+    // Last frame matching the recording, and the first differing frame.
+    before: Option<Screenshot>,
+    after: Option<Screenshot>,
+    open: bool,
+}
+
+impl FrameComparison {
+    pub fn new(capture_screenshots: bool) -> Self {
+        Self {
+            capture_screenshots,
+            frames: Vec::new(),
+            before: None,
+            after: None,
+            open: true,
+        }
+    }
+
+    /// Start over, e.g. for a new replay.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.capture_screenshots);
+    }
+
+    pub fn frames(&self) -> &[ComparedFrame] {
+        &self.frames
+    }
+
+    pub fn first_difference(&self) -> Option<&ComparedFrame> {
+        self.frames.iter().find(|frame| !frame.matches())
+    }
+
+    pub fn compare(&mut self, frame_index: usize, recorded: u64, replayed: u64) {
+        self.frames.push(ComparedFrame {
+            frame_index,
+            recorded,
+            replayed,
+        });
+    }
+
+    /// Request a screenshot of the frame just replayed, until the first
+    /// difference was captured.
+    pub fn request_screenshot(&self, ctx: &egui::Context, frame_index: usize) {
+        if self.capture_screenshots && self.first_difference().is_none() {
+            let user_data = egui::UserData::new(ScreenshotOf(frame_index));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(user_data));
+        }
+    }
+
+    /// Keep the requested screenshots among the events, which arrive a few
+    /// frames after they were requested.
+    pub fn receive_screenshots(&mut self, events: &[egui::Event]) {
+        for event in events {
+            let egui::Event::Screenshot { user_data, image, .. } = event else {
+                continue;
+            };
+            let tag = user_data
+                .data
+                .as_ref()
+                .and_then(|data| data.downcast_ref::<ScreenshotOf>());
+            let Some(ScreenshotOf(frame_index)) = tag else {
+                continue;
+            };
+            let screenshot = Screenshot {
+                frame_index: *frame_index,
+                image: image.clone(),
+                texture: None,
+            };
+            match self.first_difference().map(|frame| frame.frame_index) {
+                Some(first) if *frame_index == first => self.after = Some(screenshot),
+                Some(first) if *frame_index > first => {}
+                _ => self.before = Some(screenshot),
+            }
+        }
+    }
+
+    /// Show the comparison in a window, until closed.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.frames.is_empty() || !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new("Compare replay")
+            .id(egui::Id::new(WINDOW_ID))
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| self.show_contents(ui));
+        self.open = open;
+    }
+
+    fn show_contents(&mut self, ui: &mut egui::Ui) {
+        let first = self.first_difference().copied();
+        match first {
+            Some(frame) => ui.colored_label(
+                Color32::RED,
+                format!(
+                    "First difference at frame {} of {} compared frames",
+                    frame.frame_index + 1,
+                    self.frames.len()
+                ),
+            ),
+            None => ui.label(format!("All {} compared frames match the recording", self.frames.len())),
+        };
+        if self.before.is_some() || self.after.is_some() {
+            ui.columns(2, |columns| {
+                if let Some(before) = &mut self.before {
+                    before.show(&mut columns[0], "Before");
+                }
+                if let Some(after) = &mut self.after {
+                    after.show(&mut columns[1], "After");
+                }
+            });
+        }
+        ui.separator();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show_rows(ui, row_height, self.frames.len(), |ui, rows| {
+                for frame in &self.frames[rows] {
+                    let text = format!(
+                        "{:>6}  {:016x}  {:016x}",
+                        frame.frame_index + 1,
+                        frame.recorded,
+                        frame.replayed
+                    );
+                    let text = RichText::new(text).monospace();
+                    let text = match frame.matches() {
+                        true => text,
+                        false if Some(*frame) == first => text.color(Color32::RED).strong(),
+                        false => text.color(Color32::RED),
+                    };
+                    ui.label(text);
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screenshot(frame_index: usize) -> egui::Event {
+        egui::Event::Screenshot {
+            viewport_id: egui::ViewportId::ROOT,
+            user_data: egui::UserData::new(ScreenshotOf(frame_index)),
+            image: Arc::new(ColorImage::new([1, 1], vec![Color32::WHITE])),
+        }
+    }
+
+    #[test]
+    fn screenshots_around_the_first_difference_are_kept() {
+        let mut comparison = FrameComparison::new(true);
+        comparison.compare(0, 1, 1);
+        comparison.receive_screenshots(&[screenshot(0)]);
+        comparison.compare(1, 2, 2);
+        comparison.compare(2, 3, 4);
+        comparison.compare(3, 5, 6);
+        comparison.receive_screenshots(&[screenshot(1), screenshot(2), screenshot(3)]);
+
+        assert_eq!(comparison.first_difference().map(|frame| frame.frame_index), Some(2));
+        assert_eq!(comparison.before.as_ref().map(|before| before.frame_index), Some(1));
+        assert_eq!(comparison.after.as_ref().map(|after| after.frame_index), Some(2));
+
+        comparison.clear();
+        assert!(comparison.frames().is_empty() && comparison.before.is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! Saving of the recording in progress when the app panics.
+//!
+//! The recorded frames are mirrored into a buffer shared with a panic hook.
+//! When the app panics while recording, the hook saves the frames captured so
+//! far next to where the recording would have been saved, with a `_crash`
+//! suffix, so the crash can be replayed.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::replay_events::FrameEvents;
+use crate::replay_file::ReplayFile;
+
+pub const CRASH_FILE_SUFFIX: &str = "_crash";
+
+/// Add [`CRASH_FILE_SUFFIX`] to the file stem, e.g. `a/b.bin` to
+/// `a/b_crash.bin`.
+pub fn crash_file_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}{}.{}", stem, CRASH_FILE_SUFFIX, extension.to_string_lossy()),
+        None => format!("{}{}", stem, CRASH_FILE_SUFFIX),
+    };
+    path.with_file_name(file_name)
+}
+
+#[derive(Default)]
+struct CrashState {
+    // Where to save the frames, `None` while not recording.
+    path: Option<PathBuf>,
+    rng_seeds: BTreeMap<String, u64>,
+    frames: Vec<FrameEvents>,
+}
+
+/// Recording in progress shared with the panic hook, see the module docs.
+#[derive(Clone, Default)]
+pub struct CrashAutosave {
+    state: Arc<Mutex<CrashState>>,
+}
+
+impl CrashAutosave {
+    /// Start mirroring a new recording, to be saved to `path` on panic.
+    pub fn start(&self, path: PathBuf, rng_seeds: BTreeMap<String, u64>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.path = Some(path);
+            state.rng_seeds = rng_seeds;
+            state.frames.clear();
+        }
+    }
+
+    pub fn push(&self, frame: FrameEvents) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.path.is_some() {
+                state.frames.push(frame);
+            }
+        }
+    }
+
+    /// Stop mirroring, e.g. because the recording was saved.
+    pub fn stop(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = CrashState::default();
+        }
+    }
+
+    /// Save the mirrored frames, if recording, returning the path they were
+    /// saved to.
+    pub fn save(&self) -> Option<PathBuf> {
+        // The lock is never held while calling into the app, but don't risk
+        // a deadlock in the panic hook.
+        let state = self.state.try_lock().ok()?;
+        let path = state.path.clone()?;
+        if state.frames.is_empty() {
+            return None;
+        }
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let replay_file = ReplayFile {
+            rng_seeds: state.rng_seeds.clone(),
+            ..ReplayFile::new(state.frames.clone())
+        };
+        match replay_file.save(&path) {
+            Ok(()) => Some(path),
+            Err(err) => {
+                log::error!("Failed to save the UI events to {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Save the recording in progress when the app panics. The previous panic
+    /// hook is still called afterwards.
+    pub fn install_panic_hook(&self) {
+        let autosave = self.clone();
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if let Some(path) = autosave.save() {
+                log::error!("Saved the UI events recorded before the panic to {}", path.display());
+            }
+            previous_hook(info);
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn crash_suffix_is_added_to_the_stem() {
+        assert_eq!(
+            crash_file_path(Path::new("out/egui_replay_1.bin")),
+            Path::new("out/egui_replay_1_crash.bin")
+        );
+        assert_eq!(crash_file_path(Path::new("recording")), Path::new("recording_crash"));
+    }
+
+    #[test]
+    fn saves_only_while_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crash.json");
+        let autosave = CrashAutosave::default();
+        let frame = FrameEvents::new(NanoTimestamp::from(1), vec![egui::Event::Text("a".to_string())]);
+
+        autosave.push(frame.clone());
+        assert_eq!(autosave.save(), None);
+
+        autosave.start(path.clone(), BTreeMap::new());
+        autosave.push(frame.clone());
+        assert_eq!(autosave.save(), Some(path.clone()));
+        assert_eq!(ReplayFile::load(&path).unwrap().frames, vec![frame]);
+
+        autosave.stop();
+        assert_eq!(autosave.save(), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Synthetic cursor painted during replays.
+//!
+//! Replayed pointer events don't move the real cursor, so screen recordings
+//! and viewers of a replay can't tell where it clicks. With
+//! [`crate::replay_events::ReplayManager::with_synthetic_cursor`], the
+//! manager paints a cursor at the replayed pointer position, and a ripple at
+//! each replayed click.
+
+use egui::{Color32, Context, Pos2};
+
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+// Duration of the click ripple animation.
+const CLICK_RIPPLE_DURATION: NanoDelta = NanoDelta::from_millis_safe(400);
+
+/// Pointer position and clicks of a replay, see the module docs.
+#[derive(Default)]
+pub struct SyntheticCursor {
+    pointer_pos: Option<Pos2>,
+    // Positions and times of the replayed clicks, animated as ripples.
+    click_ripples: Vec<(Pos2, NanoTimestamp)>,
+}
+
+impl SyntheticCursor {
+    /// Forget the pointer and clicks of the previous replay.
+    pub fn reset(&mut self) {
+        self.pointer_pos = None;
+        self.click_ripples.clear();
+    }
+
+    /// Follow the pointer events replayed at `now`.
+    pub fn track(&mut self, events: &[egui::Event], now: NanoTimestamp) {
+        for event in events {
+            match event {
+                egui::Event::PointerMoved(pos) => self.pointer_pos = Some(*pos),
+                egui::Event::PointerButton { pos, pressed, .. } => {
+                    self.pointer_pos = Some(*pos);
+                    if *pressed {
+                        self.click_ripples.push((*pos, now));
+                    }
+                }
+                egui::Event::PointerGone => self.pointer_pos = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Paint the ripples of the recent clicks, and the cursor while
+    /// replaying.
+    pub fn paint(&mut self, ctx: &Context, now: NanoTimestamp, is_replaying: bool) {
+        self.click_ripples
+            .retain(|(_, time)| now - *time < CLICK_RIPPLE_DURATION);
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Tooltip,
+            egui::Id::new("replay_synthetic_cursor"),
+        ));
+        for (pos, time) in self.click_ripples.iter() {
+            let progress = ((now - *time) / CLICK_RIPPLE_DURATION) as f32;
+            let color = Color32::from_rgb(255, 200, 0).gamma_multiply(1.0 - progress);
+            painter.circle_stroke(*pos, 4.0 + 20.0 * progress, egui::Stroke::new(2.0, color));
+        }
+        if !self.click_ripples.is_empty() {
+            ctx.request_repaint();
+        }
+        if let Some(pos) = self.pointer_pos.filter(|_| is_replaying) {
+            let arrow = vec![pos, pos + egui::vec2(0.0, 16.0), pos + egui::vec2(11.0, 11.0)];
+            painter.add(egui::Shape::convex_polygon(
+                arrow,
+                Color32::WHITE,
+                egui::Stroke::new(1.0, Color32::BLACK),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(pos: Pos2, pressed: bool) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn pointer_and_clicks_are_tracked() {
+        let mut cursor = SyntheticCursor::default();
+        let pos = egui::pos2(10.0, 20.0);
+        cursor.track(&[button(pos, true), button(pos, false)], NanoTimestamp::from(5));
+
+        assert_eq!(cursor.pointer_pos, Some(pos));
+        assert_eq!(cursor.click_ripples, vec![(pos, NanoTimestamp::from(5))]);
+
+        cursor.track(&[egui::Event::PointerGone], NanoTimestamp::from(6));
+        assert_eq!(cursor.pointer_pos, None);
+        cursor.reset();
+        assert!(cursor.click_ripples.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+//! Differences between the events of two recordings.
+//!
+//! [`diff_replays`] pairs the frames of two recordings, by index or by time,
+//! and compares the events of each pair. Useful e.g. to compare recordings of
+//! the same interaction before and after a fix, or to find out why recordings
+//! of the same scenario differ.
+
+use crate::replay_events::{event_kind, FrameEvents};
+use crate::replay_file::ReplayFile;
+use crate::timestamp::NanoDelta;
+
+/// How the frames of the two recordings are paired.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameAlignment {
+    /// Pair the frames with the same index.
+    #[default]
+    Index,
+    /// Pair the frames recorded at the same time since the start of their
+    /// recording, within `tolerance`.
+    Time { tolerance: NanoDelta },
+}
+
+/// A difference between the events of two paired frames.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventChange {
+    /// An event only in the second recording.
+    Added(egui::Event),
+    /// An event only in the first recording.
+    Removed(egui::Event),
+    /// An event of the same kind, with different values, e.g. a pointer
+    /// moved to another position.
+    Changed { from: egui::Event, to: egui::Event },
+}
+
+impl std::fmt::Display for EventChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventChange::Added(event) => write!(f, "+ {:?}", event),
+            EventChange::Removed(event) => write!(f, "- {:?}", event),
+            EventChange::Changed { from, to } => write!(f, "~ {:?} -> {:?}", from, to),
+        }
+    }
+}
+
+/// Differences of a pair of frames. A frame without a pair in the other
+/// recording has all its events added or removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameDiff {
+    /// Index of the frame in the first recording, if paired or removed.
+    pub a_index: Option<usize>,
+    /// Index of the frame in the second recording, if paired or added.
+    pub b_index: Option<usize>,
+    pub changes: Vec<EventChange>,
+}
+
+/// Result of [`diff_replays`], listing only the frames which differ.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayDiff {
+    pub frames: Vec<FrameDiff>,
+}
+
+impl ReplayDiff {
+    /// Whether the recordings have the same events.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn num_added(&self) -> usize {
+        self.count(|change| matches!(change, EventChange::Added(_)))
+    }
+
+    pub fn num_removed(&self) -> usize {
+        self.count(|change| matches!(change, EventChange::Removed(_)))
+    }
+
+    pub fn num_changed(&self) -> usize {
+        self.count(|change| matches!(change, EventChange::Changed { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&EventChange) -> bool) -> usize {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.changes.iter())
+            .filter(|change| predicate(change))
+            .count()
+    }
+}
+
+/// Compare the events of recording `a` with those of recording `b`.
+pub fn diff_replays(a: &ReplayFile, b: &ReplayFile, alignment: FrameAlignment) -> ReplayDiff {
+    let mut diff = ReplayDiff::default();
+    let mut push = |a_index: Option<usize>, b_index: Option<usize>| {
+        let a_events = a_index.map_or(&[][..], |index| a.frames[index].events.as_slice());
+        let b_events = b_index.map_or(&[][..], |index| b.frames[index].events.as_slice());
+        let changes = diff_events(a_events, b_events);
+        // Unpaired frames differ even without events.
+        if !changes.is_empty() || a_index.is_none() || b_index.is_none() {
+            diff.frames.push(FrameDiff {
+                a_index,
+                b_index,
+                changes,
+            });
+        }
+    };
+
+    match alignment {
+        FrameAlignment::Index => {
+            for index in 0..a.frames.len().max(b.frames.len()) {
+                push(
+                    (index < a.frames.len()).then_some(index),
+                    (index < b.frames.len()).then_some(index),
+                );
+            }
+        }
+        FrameAlignment::Time { tolerance } => {
+            let since_start = |frames: &[FrameEvents], index: usize| frames[index].time - frames[0].time;
+            let (mut i, mut j) = (0, 0);
+            while i < a.frames.len() || j < b.frames.len() {
+                if i == a.frames.len() {
+                    push(None, Some(j));
+                    j += 1;
+                } else if j == b.frames.len() {
+                    push(Some(i), None);
+                    i += 1;
+                } else {
+                    let (a_time, b_time) = (since_start(&a.frames, i), since_start(&b.frames, j));
+                    if (a_time - b_time).as_nanos().abs() <= tolerance.as_nanos() {
+                        push(Some(i), Some(j));
+                        i += 1;
+                        j += 1;
+                    } else if a_time < b_time {
+                        push(Some(i), None);
+                        i += 1;
+                    } else {
+                        push(None, Some(j));
+                        j += 1;
+                    }
+                }
+            }
+        }
+    }
+    diff
+}
+
+// Align the events by their longest common subsequence. Events outside of it
+// are reported as changed if they are of the same kind and at the same place
+// in both frames, otherwise as removed or added.
+fn diff_events(a: &[egui::Event], b: &[egui::Event]) -> Vec<EventChange> {
+    // Length of the longest common subsequence of `a[i..]` and `b[j..]`.
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len()
+            && j < b.len()
+            && event_kind(&a[i]) == event_kind(&b[j])
+            && lengths[i + 1][j + 1] == lengths[i][j]
+        {
+            changes.push(EventChange::Changed {
+                from: a[i].clone(),
+                to: b[j].clone(),
+            });
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(EventChange::Removed(a[i].clone()));
+            i += 1;
+        } else {
+            changes.push(EventChange::Added(b[j].clone()));
+            j += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    fn frame(time: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time), events)
+    }
+
+    fn moved(x: f32) -> egui::Event {
+        egui::Event::PointerMoved(egui::pos2(x, 0.0))
+    }
+
+    fn text(text: &str) -> egui::Event {
+        egui::Event::Text(text.to_string())
+    }
+
+    #[test]
+    fn diffs_events_of_frames_with_the_same_index() {
+        let a = ReplayFile::new(vec![frame(0, vec![moved(1.0), text("a")]), frame(1, vec![text("b")])]);
+        let b = ReplayFile::new(vec![
+            frame(0, vec![moved(2.0), text("a"), text("c")]),
+            frame(1, vec![text("b")]),
+        ]);
+
+        let diff = diff_replays(&a, &b, FrameAlignment::Index);
+
+        assert_eq!(
+            diff.frames,
+            vec![FrameDiff {
+                a_index: Some(0),
+                b_index: Some(0),
+                changes: vec![
+                    EventChange::Changed {
+                        from: moved(1.0),
+                        to: moved(2.0),
+                    },
+                    EventChange::Added(text("c")),
+                ],
+            }]
+        );
+        assert_eq!((diff.num_added(), diff.num_removed(), diff.num_changed()), (1, 0, 1));
+        assert!(diff_replays(&a, &a, FrameAlignment::Index).is_empty());
+    }
+
+    #[test]
+    fn pairs_frames_by_time_since_start() {
+        let a = ReplayFile::new(vec![frame(100, vec![text("a")]), frame(200, vec![text("b")])]);
+        let b = ReplayFile::new(vec![
+            frame(1000, vec![text("a")]),
+            frame(1050, vec![text("x")]),
+            frame(1101, vec![text("b")]),
+        ]);
+
+        let diff = diff_replays(
+            &a,
+            &b,
+            FrameAlignment::Time {
+                tolerance: NanoDelta::from(5),
+            },
+        );
+
+        assert_eq!(
+            diff.frames,
+            vec![FrameDiff {
+                a_index: None,
+                b_index: Some(1),
+                changes: vec![EventChange::Added(text("x"))],
+            }]
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! [`ReplayManager`] without recording and replay.
+//!
+//! Without the `replay` feature, [`crate::replay_events::ReplayManager`] is
+//! this stub. It has the same API, so that call sites compile unchanged, but
+//! every method does nothing: nothing is recorded, no window is shown and no
+//! replay starts. Requests to replay on startup fail, so that CI doesn't pass
+//! without replaying.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use egui::Context;
+
+use crate::assertions::{AssertionKind, AssertionReport};
+use crate::audio::AudioTrack;
+use crate::background::IoOutcome;
+use crate::breakpoint::Breakpoint;
+use crate::chapters::ChapterLength;
+use crate::coalesce::ScrollCoalescing;
+use crate::compare::FrameComparison;
+use crate::divergence::DivergenceReport;
+use crate::event_types::EventTypes;
+use crate::fuzz::FuzzSource;
+use crate::heatmap::InteractionHeatmap;
+use crate::indexed::IndexedReplayFile;
+use crate::keyboard::KeyboardMode;
+use crate::logs::LogCapture;
+use crate::metrics::RecordingMetrics;
+use crate::output::RecordingOutput;
+use crate::remote::RemoteControl;
+use crate::replay_events::{
+    Annotation, FlightRecorderLimit, FrameEvents, IdleFrames, Marker, ReplayAudioCallback, ReplayProgressCallback,
+    ReplaySpeed, ReplayStartedCallback, ReplayTransform, AUTO_REPLAY_ENV_VAR,
+};
+use crate::replay_file::{LoadDiagnostics, ReplayError, ReplayFile};
+use crate::report::{ReplayReport, ReplayStatus};
+use crate::settings::ReplaySettings;
+use crate::simplify::PointerSimplification;
+use crate::snapshot::ReplayStateSnapshot;
+use crate::stats::ReplayStats;
+use crate::stream::{StreamReceiver, StreamSender};
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// A manager which does nothing, see the module docs.
+#[derive(Default)]
+pub struct ReplayManager {
+    // Empty values returned by reference.
+    recording: ReplayFile,
+    load_diagnostics: LoadDiagnostics,
+    divergence_report: DivergenceReport,
+    assertion_report: AssertionReport,
+    // Failed background replays, until polled.
+    io_outcomes: VecDeque<IoOutcome>,
+    auto_replay_status: Option<ReplayStatus>,
+    // Whether the app is closed in the next input pass, as the replay on
+    // startup failed.
+    close_pending: bool,
+}
+
+impl ReplayManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_frame_hashes(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_comparison(self, _screenshots: bool) -> Self {
+        self
+    }
+
+    pub fn comparison(&self) -> Option<&FrameComparison> {
+        None
+    }
+
+    pub fn set_heatmap_overlay(&mut self, _heatmap: Option<InteractionHeatmap>) {}
+
+    pub fn with_widget_anchors(self, _enabled: bool) -> Self {
+        self
+    }
+
+    #[cfg(feature = "accesskit")]
+    pub fn with_semantic_recording(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn register_anchor(&mut self, _response: &egui::Response) {}
+
+    pub fn with_preserve_frame_boundaries(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_pointer_simplification(self, _simplification: PointerSimplification) -> Self {
+        self
+    }
+
+    pub fn with_keyboard_mode(self, _mode: KeyboardMode) -> Self {
+        self
+    }
+
+    pub fn with_scroll_coalescing(self, _coalescing: ScrollCoalescing) -> Self {
+        self
+    }
+
+    pub fn with_recorded_event_types(self, _types: EventTypes) -> Self {
+        self
+    }
+
+    pub fn with_simplify_touch_events(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_embedded_dropped_files(self, _max_size: usize) -> Self {
+        self
+    }
+
+    pub fn with_memory_cap(self, _max_bytes: usize) -> Self {
+        self
+    }
+
+    pub fn with_max_recording_duration(self, _max_duration: NanoDelta) -> Self {
+        self
+    }
+
+    pub fn with_max_recording_frames(self, _max_frames: usize) -> Self {
+        self
+    }
+
+    pub fn with_chapters(self, _length: ChapterLength) -> Self {
+        self
+    }
+
+    pub fn with_flight_recorder(self, _limit: FlightRecorderLimit) -> Self {
+        self
+    }
+
+    pub fn with_recording_output(self, _output: RecordingOutput) -> Self {
+        self
+    }
+
+    pub fn with_max_recent_recordings(self, _max_len: usize) -> Self {
+        self
+    }
+
+    pub fn install(self, _ctx: &Context) {}
+
+    pub fn load_state(&mut self, _storage: &dyn eframe::Storage) {}
+
+    pub fn from_storage(_cc: &eframe::CreationContext<'_>) -> Self {
+        Self::default()
+    }
+
+    pub fn save_state(&self, _storage: &mut dyn eframe::Storage) {}
+
+    pub fn settings(&self) -> ReplaySettings {
+        ReplaySettings::default()
+    }
+
+    pub fn apply_settings(&mut self, _settings: ReplaySettings) {}
+
+    pub fn with_settings(self, _settings: ReplaySettings) -> Self {
+        self
+    }
+
+    pub fn with_crash_autosave(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_log_capture(self, _capture: LogCapture) -> Self {
+        self
+    }
+
+    pub fn with_force_focused(self, _enabled: bool) -> Self {
+        self
+    }
+
+    /// Closes the app with a failed [`ReplayManager::auto_replay_status`],
+    /// as the recording can't be replayed.
+    pub fn with_auto_replay(mut self, path: impl Into<String>) -> Self {
+        log::error!("Cannot replay {}, replay is disabled in this build", path.into());
+        self.auto_replay_status = Some(ReplayStatus::Failed);
+        self.close_pending = true;
+        self
+    }
+
+    pub fn auto_replay_status(&self) -> Option<ReplayStatus> {
+        self.auto_replay_status
+    }
+
+    pub fn with_auto_replay_from_env(self) -> Self {
+        match std::env::var(AUTO_REPLAY_ENV_VAR) {
+            Ok(path) if !path.is_empty() => self.with_auto_replay(path),
+            _ => self,
+        }
+    }
+
+    pub fn with_report_file(self, _path: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn with_lenient_loading(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_background_io(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn save_error(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn retry_save(&mut self, _file_name: &str) -> Result<(), ReplayError> {
+        Err(ReplayError::Disabled)
+    }
+
+    pub fn discard_failed_save(&mut self) {}
+
+    pub fn with_dual_output(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn last_saved_files(&self) -> &[String] {
+        &[]
+    }
+
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(self, _secret: crate::encryption::RecordingSecret) -> Self {
+        self
+    }
+
+    pub fn with_recording_metrics(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn recording_metrics(&self) -> Option<RecordingMetrics> {
+        None
+    }
+
+    pub fn is_io_pending(&self) -> bool {
+        false
+    }
+
+    pub fn poll_io(&mut self) -> Option<IoOutcome> {
+        self.io_outcomes.pop_front()
+    }
+
+    pub fn load_diagnostics(&self) -> &LoadDiagnostics {
+        &self.load_diagnostics
+    }
+
+    pub fn with_replay_speed(self, _speed: ReplaySpeed) -> Self {
+        self
+    }
+
+    pub fn with_idle_frames(self, _idle_frames: IdleFrames) -> Self {
+        self
+    }
+
+    pub fn with_overlay(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_synthetic_cursor(self, _enabled: bool) -> Self {
+        self
+    }
+
+    #[cfg(feature = "gif")]
+    pub fn with_gif_export(self, _path: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn with_remote_control(self, _remote_control: RemoteControl) -> Self {
+        self
+    }
+
+    pub fn with_stream_sender(self, _sender: StreamSender) -> Self {
+        self
+    }
+
+    pub fn with_stream_receiver(self, _receiver: StreamReceiver) -> Self {
+        self
+    }
+
+    pub fn with_fuzzing(self, _source: FuzzSource) -> Self {
+        self
+    }
+
+    pub fn with_progress_badge(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn with_exit_on_completion(self, _settle_frames: usize) -> Self {
+        self
+    }
+
+    pub fn divergence_report(&self) -> &DivergenceReport {
+        &self.divergence_report
+    }
+
+    pub fn on_replay_finished(&mut self, _callback: Box<dyn FnMut(ReplayReport)>) {}
+
+    pub fn on_replay_progress(&mut self, _callback: ReplayProgressCallback) {}
+
+    pub fn on_replay_started(&mut self, _callback: ReplayStartedCallback) {}
+
+    pub fn on_replay_audio(&mut self, _callback: ReplayAudioCallback) {}
+
+    pub fn on_replay_restart(&mut self, _callback: Box<dyn FnMut(usize)>) {}
+
+    pub fn set_replay_transform(&mut self, _transform: ReplayTransform) {}
+
+    pub fn clear_replay_transform(&mut self) {}
+
+    pub fn add_breakpoint(&mut self, _breakpoint: Breakpoint) {}
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &[]
+    }
+
+    pub fn clear_breakpoints(&mut self) {}
+
+    pub fn pause_replay(&mut self) {}
+
+    pub fn is_replay_paused(&self) -> bool {
+        false
+    }
+
+    pub fn step_replay(&mut self) {}
+
+    pub fn resume_replay(&mut self) {}
+
+    pub fn resume_chained_replay(&mut self) -> Result<bool, ReplayError> {
+        Ok(false)
+    }
+
+    pub fn hit_breakpoint(&self) -> Option<(usize, &str)> {
+        None
+    }
+
+    pub fn attach_audio(&mut self, _track: AudioTrack) {}
+
+    pub fn register_rng_seed(&mut self, _name: impl Into<String>, _seed: u64) {}
+
+    pub fn replay_rng_seed(&self, _name: &str) -> Option<u64> {
+        None
+    }
+
+    pub fn replays_started(&self) -> u64 {
+        0
+    }
+
+    pub fn sync_state(&mut self, _state: &mut dyn ReplayStateSnapshot) {}
+
+    pub fn with_app_identity(self, _identity: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn set_state_fingerprint(&mut self, _fingerprint: impl Into<String>) {}
+
+    pub fn session_mismatches(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn recording(&self) -> &ReplayFile {
+        &self.recording
+    }
+
+    pub fn last_replay_report(&self) -> Option<&ReplayReport> {
+        None
+    }
+
+    pub fn start_replay(&mut self, _frames: Vec<FrameEvents>) {}
+
+    pub fn start_replay_from_file(&mut self, _path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        Err(ReplayError::Disabled)
+    }
+
+    pub fn start_replay_from_file_in_background(&mut self, path: impl AsRef<Path>) {
+        let outcome = IoOutcome::Failed(path.as_ref().to_path_buf(), ReplayError::Disabled);
+        self.io_outcomes.push_back(outcome);
+    }
+
+    pub fn start_replay_file(&mut self, _replay_file: ReplayFile) {}
+
+    pub fn start_lazy_replay(&mut self, _file: IndexedReplayFile) {}
+
+    pub fn restart_replay(&mut self) {}
+
+    pub fn open_window(&mut self) {}
+
+    pub fn close_window(&mut self) {}
+
+    pub fn is_replaying(&self) -> bool {
+        false
+    }
+
+    pub fn is_recording(&self) -> bool {
+        false
+    }
+
+    pub fn is_recording_paused(&self) -> bool {
+        false
+    }
+
+    pub fn pause_recording(&mut self, _now: NanoTimestamp) {}
+
+    pub fn resume_recording(&mut self, _now: NanoTimestamp) {}
+
+    pub fn num_recorded_frames(&self) -> usize {
+        0
+    }
+
+    pub fn num_recorded_events(&self) -> usize {
+        0
+    }
+
+    pub fn start_recording(&mut self, _now: NanoTimestamp) {}
+
+    pub fn stop_recording(&mut self, _now: NanoTimestamp) -> Result<String, ReplayError> {
+        Err(ReplayError::NotRecording)
+    }
+
+    pub fn restart_recording(&mut self, _now: NanoTimestamp) -> Result<String, ReplayError> {
+        Err(ReplayError::NotRecording)
+    }
+
+    pub fn resume_chained_recording(&mut self, _now: NanoTimestamp) -> Result<bool, ReplayError> {
+        Ok(false)
+    }
+
+    pub fn attach_data(&mut self, _key: impl Into<String>, _data: impl Into<Vec<u8>>) {}
+
+    pub fn replayed_data(&self, _key: &str) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn recording_memory_usage(&self) -> usize {
+        0
+    }
+
+    pub fn stats(&self) -> ReplayStats {
+        ReplayStats::default()
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &[]
+    }
+
+    pub fn save_flight_recorder(&mut self, _now: NanoTimestamp) -> Result<String, ReplayError> {
+        Err(ReplayError::NotRecording)
+    }
+
+    pub fn add_marker(&mut self, _name: impl Into<String>, _now: NanoTimestamp) {}
+
+    pub fn add_annotation(&mut self, _text: impl Into<String>, _now: NanoTimestamp) {}
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &[]
+    }
+
+    pub fn add_assertion(&mut self, _kind: AssertionKind, _now: NanoTimestamp) {}
+
+    pub fn register_assertion_check(&mut self, _name: impl Into<String>, _check: impl Fn(&Context) -> bool + 'static) {}
+
+    pub fn assertion_report(&self) -> &AssertionReport {
+        &self.assertion_report
+    }
+
+    pub fn replay_until_marker(&mut self, _name: &str) -> bool {
+        false
+    }
+
+    pub fn show_hud(&self, _ctx: &Context) {}
+
+    pub fn on_frame_update(&mut self, _ctx: &Context) {}
+
+    pub fn on_raw_input_update(&mut self, _now: NanoTimestamp, ctx: &Context, _raw_input: &mut egui::RawInput) {
+        if std::mem::take(&mut self.close_pending) {
+            ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, egui::ViewportCommand::Close);
+        }
+    }
+
+    pub fn on_frame_end(&mut self, _ctx: &Context) {}
+}
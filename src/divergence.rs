@@ -0,0 +1,137 @@
+//! Detection of rendering divergence between a recording and its replay.
+//!
+//! While recording, the shapes produced by each frame can be hashed and stored
+//! alongside the frame's events, at the end of the frame, see [`hash_pass`]. During replay the same hash is computed again
+//! and compared, so that the first frame where the UI renders differently can
+//! be pinpointed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write as _;
+use std::hash::Hasher;
+
+use egui::epaint::ClippedShape;
+use egui::{Context, Id, LayerId};
+
+/// Adapter that feeds formatted text straight into a hasher, without
+/// allocating an intermediate string.
+struct HashWriter<'a>(&'a mut DefaultHasher);
+
+impl std::fmt::Write for HashWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Compute a hash of the shapes produced by a single frame.
+///
+/// Shapes do not implement `Hash` (they contain floats), so their `Debug`
+/// representation is hashed instead. This is stable for identical output
+/// produced by the same build of the application.
+pub fn hash_shapes<'a>(shapes: impl IntoIterator<Item = &'a ClippedShape>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut writer = HashWriter(&mut hasher);
+    for shape in shapes {
+        // Writing into the hasher cannot fail.
+        let _ = write!(writer, "{:?}", shape);
+    }
+    hasher.finish()
+}
+
+/// Compute a hash of the shapes painted so far in the current pass of `ctx`,
+/// e.g. at the end of the update, leaving out the layers with the `skipped`
+/// ids.
+///
+/// Layers are hashed back to front, like egui draws them. Only the layers of
+/// areas and of widgets are included, so shapes painted directly onto other
+/// layers, like the cursor of replays, are left out.
+pub fn hash_pass(ctx: &Context, skipped: &[Id]) -> u64 {
+    let mut layer_ids: Vec<LayerId> = ctx.memory(|memory| memory.layer_ids().collect());
+    let mut widget_layer_ids: Vec<LayerId> = ctx.viewport(|viewport| viewport.this_pass.widgets.layer_ids().collect());
+    // Their order is arbitrary, e.g. for the background layer.
+    widget_layer_ids.sort_by_key(|layer_id| (layer_id.order, layer_id.id.value()));
+    for layer_id in widget_layer_ids {
+        if !layer_ids.contains(&layer_id) {
+            layer_ids.push(layer_id);
+        }
+    }
+    // The sort is stable, areas stay back to front within their order.
+    layer_ids.sort_by_key(|layer_id| layer_id.order);
+    layer_ids.retain(|layer_id| !skipped.contains(&layer_id.id));
+    ctx.graphics(|graphics| {
+        let paint_lists = layer_ids.iter().filter_map(|layer_id| graphics.get(*layer_id));
+        hash_shapes(paint_lists.flat_map(|paint_list| paint_list.all_entries()))
+    })
+}
+
+/// A single frame whose rendering differs between recording and replay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameDivergence {
+    /// Index of the replayed frame.
+    pub frame_index: usize,
+    /// Hash stored in the recording.
+    pub expected: u64,
+    /// Hash computed during replay.
+    pub actual: u64,
+}
+
+/// Result of comparing frame hashes during a replay.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DivergenceReport {
+    /// Number of frames for which a recorded hash was available and compared.
+    pub frames_compared: usize,
+    /// All frames whose hash did not match, in replay order.
+    pub mismatches: Vec<FrameDivergence>,
+}
+
+impl DivergenceReport {
+    /// Record the outcome of comparing a single frame.
+    pub fn compare(&mut self, frame_index: usize, expected: u64, actual: u64) {
+        self.frames_compared += 1;
+        if expected != actual {
+            self.mismatches.push(FrameDivergence {
+                frame_index,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    /// Whether any compared frame differed.
+    pub fn has_diverged(&self) -> bool {
+        !self.mismatches.is_empty()
+    }
+
+    /// The first frame where rendering diverged, if any.
+    pub fn first_divergence(&self) -> Option<&FrameDivergence> {
+        self.mismatches.first()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames_compared = 0;
+        self.mismatches.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_tracks_first_divergence() {
+        let mut report = DivergenceReport::default();
+        report.compare(0, 1, 1);
+        report.compare(1, 2, 3);
+        report.compare(2, 4, 5);
+
+        assert_eq!(report.frames_compared, 3);
+        assert!(report.has_diverged());
+        assert_eq!(report.first_divergence().unwrap().frame_index, 1);
+        assert_eq!(report.mismatches.len(), 2);
+    }
+
+    #[test]
+    fn empty_shapes_hash_is_stable() {
+        assert_eq!(hash_shapes(&[]), hash_shapes(&[]));
+    }
+}
@@ -0,0 +1,223 @@
+//! Encrypted recordings, with the `encryption` feature.
+//!
+//! Recordings contain everything the user typed, including passwords. `.enc`
+//! recordings are binary recordings encrypted and authenticated with
+//! ChaCha20-Poly1305, so they can be attached to bug reports and only be
+//! replayed with the key. The key is either given directly, or derived from a
+//! passphrase with Argon2id and a random salt stored in the file:
+//!
+//! ```text
+//! magic | key derivation | salt | nonce | encrypted binary recording
+//! ```
+//!
+//! The header is authenticated too. Register an [`EncryptedCodec`] with
+//! [`crate::codec::register_codec`] to load and save `.enc` files, or use
+//! [`crate::replay_events::ReplayManager::with_encryption`] to also record
+//! into them. Keys and decrypted bytes are zeroed once dropped.
+
+use std::io::Write;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::codec::{BincodeCodec, ReplayCodec};
+use crate::replay_file::{LoadDiagnostics, ReplayError, ReplayFile};
+
+const ENCRYPTED_MAGIC: &[u8; 8] = b"EGRPENC\0";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = ENCRYPTED_MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+// Key derivation stored in the header.
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+
+/// A 256-bit key, zeroed once dropped.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct RecordingKey([u8; 32]);
+
+impl RecordingKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// A new random key.
+    pub fn generate() -> Result<Self, ReplayError> {
+        let mut key = Self([0; 32]);
+        fill_random(&mut key.0)?;
+        Ok(key)
+    }
+
+    // Derive the key of a recording from the passphrase and its salt.
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, ReplayError> {
+        let mut key = Self([0; 32]);
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key.0)
+            .map_err(|err| ReplayError::Codec(format!("Failed to derive the key: {}", err)))?;
+        Ok(key)
+    }
+}
+
+impl std::fmt::Debug for RecordingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RecordingKey(..)")
+    }
+}
+
+/// What recordings are encrypted with.
+#[derive(Clone)]
+pub enum RecordingSecret {
+    Key(RecordingKey),
+    /// The key of each recording is derived from the passphrase, which is
+    /// slow on purpose.
+    Passphrase(Zeroizing<String>),
+}
+
+impl RecordingSecret {
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        Self::Passphrase(Zeroizing::new(passphrase.into()))
+    }
+}
+
+/// `.enc` files, see the module docs.
+#[derive(Clone)]
+pub struct EncryptedCodec {
+    secret: RecordingSecret,
+}
+
+impl EncryptedCodec {
+    pub fn new(secret: RecordingSecret) -> Self {
+        Self { secret }
+    }
+
+    // The key for the derivation and salt of a header.
+    fn key(&self, kdf: u8, salt: &[u8]) -> Result<RecordingKey, ReplayError> {
+        match (&self.secret, kdf) {
+            (RecordingSecret::Key(key), KDF_NONE) => Ok(key.clone()),
+            (RecordingSecret::Passphrase(passphrase), KDF_ARGON2ID) => RecordingKey::derive(passphrase, salt),
+            (RecordingSecret::Key(_), _) => Err(ReplayError::Codec(
+                "The recording is encrypted with a passphrase, not a key".to_string(),
+            )),
+            (RecordingSecret::Passphrase(_), _) => Err(ReplayError::Codec(
+                "The recording is encrypted with a key, not a passphrase".to_string(),
+            )),
+        }
+    }
+
+    // Decrypt the binary recording.
+    fn decrypt(&self, bytes: &[u8]) -> Result<Zeroizing<Vec<u8>>, ReplayError> {
+        if bytes.len() < HEADER_LEN || !bytes.starts_with(ENCRYPTED_MAGIC) {
+            return Err(ReplayError::Codec("Not an encrypted recording".to_string()));
+        }
+        let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+        let kdf = header[ENCRYPTED_MAGIC.len()];
+        let salt = &header[ENCRYPTED_MAGIC.len() + 1..][..SALT_LEN];
+        let nonce = &header[HEADER_LEN - NONCE_LEN..];
+        let key = self.key(kdf, salt)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.0));
+        let payload = Payload {
+            msg: ciphertext,
+            aad: header,
+        };
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| ReplayError::Decryption)?;
+        Ok(Zeroizing::new(plaintext))
+    }
+}
+
+impl ReplayCodec for EncryptedCodec {
+    fn extensions(&self) -> &[&str] {
+        &["enc"]
+    }
+
+    fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError> {
+        let mut plaintext = Zeroizing::new(Vec::new());
+        BincodeCodec.encode(file, &mut *plaintext)?;
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(ENCRYPTED_MAGIC);
+        let mut salt = [0; SALT_LEN];
+        let kdf = match self.secret {
+            RecordingSecret::Key(_) => KDF_NONE,
+            RecordingSecret::Passphrase(_) => {
+                fill_random(&mut salt)?;
+                KDF_ARGON2ID
+            }
+        };
+        header.push(kdf);
+        header.extend_from_slice(&salt);
+        let mut nonce = [0; NONCE_LEN];
+        fill_random(&mut nonce)?;
+        header.extend_from_slice(&nonce);
+
+        let key = self.key(kdf, &salt)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.0));
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &header,
+        };
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| ReplayError::Codec("Failed to encrypt the recording".to_string()))?;
+        writer.write_all(&header)?;
+        writer.write_all(&ciphertext)?;
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        BincodeCodec.decode(&self.decrypt(bytes)?)
+    }
+
+    fn decode_lenient(&self, bytes: &[u8]) -> Result<(ReplayFile, LoadDiagnostics), ReplayError> {
+        BincodeCodec.decode_lenient(&self.decrypt(bytes)?)
+    }
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<(), ReplayError> {
+    getrandom::getrandom(bytes).map_err(|err| ReplayError::Codec(format!("No random numbers: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+    use crate::timestamp::NanoTimestamp;
+
+    fn sample_file() -> ReplayFile {
+        let events = vec![egui::Event::Text("hunter2".to_string())];
+        ReplayFile::new(vec![FrameEvents::new(NanoTimestamp::from(1), events)])
+    }
+
+    fn encode(codec: &EncryptedCodec, file: &ReplayFile) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        codec.encode(file, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn recordings_decrypt_only_with_their_key() {
+        let file = sample_file();
+        let codec = EncryptedCodec::new(RecordingSecret::Key(RecordingKey::generate().unwrap()));
+        let other = EncryptedCodec::new(RecordingSecret::Key(RecordingKey::generate().unwrap()));
+        let mut bytes = encode(&codec, &file);
+
+        assert_eq!(codec.decode(&bytes).unwrap(), file);
+        assert!(!bytes.windows(7).any(|window| window == b"hunter2"));
+        assert!(matches!(other.decode(&bytes), Err(ReplayError::Decryption)));
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(matches!(codec.decode(&bytes), Err(ReplayError::Decryption)));
+    }
+
+    #[test]
+    fn recordings_decrypt_with_the_passphrase() {
+        let file = sample_file();
+        let codec = EncryptedCodec::new(RecordingSecret::passphrase("correct horse"));
+        let bytes = encode(&codec, &file);
+
+        assert_eq!(codec.decode(&bytes).unwrap(), file);
+        let wrong = EncryptedCodec::new(RecordingSecret::passphrase("battery staple"));
+        assert!(matches!(wrong.decode(&bytes), Err(ReplayError::Decryption)));
+    }
+}
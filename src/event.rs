@@ -0,0 +1,549 @@
+//! Stable representation of recorded events.
+//!
+//! Recordings don't store `egui::Event`s directly, since their serialized form
+//! changes between egui versions. Events are converted into [`ReplayEvent`]s
+//! when saved, and back when loaded.
+//!
+//! The serialized form of [`ReplayEvent`] is versioned by the recording format
+//! version. To keep old recordings loadable, variants and fields are only
+//! ever added at the end, and binary recordings bump the format version when
+//! they are.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::replay_events::event_kind;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum EventError {
+    #[error("{0} events are not recorded")]
+    Unsupported(&'static str),
+    #[error("Unknown key: {0}")]
+    UnknownKey(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayModifiers {
+    pub alt: bool,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub mac_cmd: bool,
+    pub command: bool,
+}
+
+impl From<egui::Modifiers> for ReplayModifiers {
+    fn from(modifiers: egui::Modifiers) -> Self {
+        Self {
+            alt: modifiers.alt,
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            mac_cmd: modifiers.mac_cmd,
+            command: modifiers.command,
+        }
+    }
+}
+
+impl From<ReplayModifiers> for egui::Modifiers {
+    fn from(modifiers: ReplayModifiers) -> Self {
+        Self {
+            alt: modifiers.alt,
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            mac_cmd: modifiers.mac_cmd,
+            command: modifiers.command,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayPointerButton {
+    Primary,
+    Secondary,
+    Middle,
+    Extra1,
+    Extra2,
+}
+
+impl From<egui::PointerButton> for ReplayPointerButton {
+    fn from(button: egui::PointerButton) -> Self {
+        match button {
+            egui::PointerButton::Primary => Self::Primary,
+            egui::PointerButton::Secondary => Self::Secondary,
+            egui::PointerButton::Middle => Self::Middle,
+            egui::PointerButton::Extra1 => Self::Extra1,
+            egui::PointerButton::Extra2 => Self::Extra2,
+        }
+    }
+}
+
+impl From<ReplayPointerButton> for egui::PointerButton {
+    fn from(button: ReplayPointerButton) -> Self {
+        match button {
+            ReplayPointerButton::Primary => Self::Primary,
+            ReplayPointerButton::Secondary => Self::Secondary,
+            ReplayPointerButton::Middle => Self::Middle,
+            ReplayPointerButton::Extra1 => Self::Extra1,
+            ReplayPointerButton::Extra2 => Self::Extra2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayTouchPhase {
+    Start,
+    Move,
+    End,
+    Cancel,
+}
+
+impl From<egui::TouchPhase> for ReplayTouchPhase {
+    fn from(phase: egui::TouchPhase) -> Self {
+        match phase {
+            egui::TouchPhase::Start => Self::Start,
+            egui::TouchPhase::Move => Self::Move,
+            egui::TouchPhase::End => Self::End,
+            egui::TouchPhase::Cancel => Self::Cancel,
+        }
+    }
+}
+
+impl From<ReplayTouchPhase> for egui::TouchPhase {
+    fn from(phase: ReplayTouchPhase) -> Self {
+        match phase {
+            ReplayTouchPhase::Start => Self::Start,
+            ReplayTouchPhase::Move => Self::Move,
+            ReplayTouchPhase::End => Self::End,
+            ReplayTouchPhase::Cancel => Self::Cancel,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayWheelUnit {
+    Point,
+    Line,
+    Page,
+}
+
+impl From<egui::MouseWheelUnit> for ReplayWheelUnit {
+    fn from(unit: egui::MouseWheelUnit) -> Self {
+        match unit {
+            egui::MouseWheelUnit::Point => Self::Point,
+            egui::MouseWheelUnit::Line => Self::Line,
+            egui::MouseWheelUnit::Page => Self::Page,
+        }
+    }
+}
+
+impl From<ReplayWheelUnit> for egui::MouseWheelUnit {
+    fn from(unit: ReplayWheelUnit) -> Self {
+        match unit {
+            ReplayWheelUnit::Point => Self::Point,
+            ReplayWheelUnit::Line => Self::Line,
+            ReplayWheelUnit::Page => Self::Page,
+        }
+    }
+}
+
+/// An accessibility action requested from a widget, e.g. by a screen reader
+/// or by a semantic recording, see `crate::semantic`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayAccessAction {
+    Click,
+    Focus,
+    Increment,
+    Decrement,
+    SetValue { value: String },
+    SetNumericValue { value: f64 },
+}
+
+#[cfg(feature = "accesskit")]
+impl ReplayAccessAction {
+    fn from_request(request: &egui::accesskit::ActionRequest) -> Option<Self> {
+        use egui::accesskit::{Action, ActionData};
+        Some(match (request.action, &request.data) {
+            (Action::Click, _) => Self::Click,
+            (Action::Focus, _) => Self::Focus,
+            (Action::Increment, _) => Self::Increment,
+            (Action::Decrement, _) => Self::Decrement,
+            (Action::SetValue, Some(ActionData::Value(value))) => Self::SetValue {
+                value: value.to_string(),
+            },
+            (Action::SetValue, Some(ActionData::NumericValue(value))) => Self::SetNumericValue { value: *value },
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn into_request(self, target: egui::accesskit::NodeId) -> egui::accesskit::ActionRequest {
+        use egui::accesskit::{Action, ActionData};
+        let (action, data) = match self {
+            Self::Click => (Action::Click, None),
+            Self::Focus => (Action::Focus, None),
+            Self::Increment => (Action::Increment, None),
+            Self::Decrement => (Action::Decrement, None),
+            Self::SetValue { value } => (Action::SetValue, Some(ActionData::Value(value.into()))),
+            Self::SetNumericValue { value } => (Action::SetValue, Some(ActionData::NumericValue(value))),
+        };
+        egui::accesskit::ActionRequest { action, target, data }
+    }
+}
+
+/// A recorded event, see the module docs. Positions and deltas are in points,
+/// keys are stored by their name (see `egui::Key::name`).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayEvent {
+    Copy,
+    Cut,
+    Paste {
+        text: String,
+    },
+    Text {
+        text: String,
+    },
+    Key {
+        key: String,
+        physical_key: Option<String>,
+        pressed: bool,
+        repeat: bool,
+        modifiers: ReplayModifiers,
+    },
+    PointerMoved {
+        pos: [f32; 2],
+    },
+    MouseMoved {
+        delta: [f32; 2],
+    },
+    PointerButton {
+        pos: [f32; 2],
+        button: ReplayPointerButton,
+        pressed: bool,
+        modifiers: ReplayModifiers,
+    },
+    PointerGone,
+    Zoom {
+        factor: f32,
+    },
+    ImeEnabled,
+    ImePreedit {
+        text: String,
+    },
+    ImeCommit {
+        text: String,
+    },
+    ImeDisabled,
+    Touch {
+        device_id: u64,
+        id: u64,
+        phase: ReplayTouchPhase,
+        pos: [f32; 2],
+        force: Option<f32>,
+    },
+    MouseWheel {
+        unit: ReplayWheelUnit,
+        delta: [f32; 2],
+        modifiers: ReplayModifiers,
+    },
+    WindowFocused {
+        focused: bool,
+    },
+    /// Accessibility action requested from the widget with the given
+    /// `egui::Id` value. Converts into an `egui::Event` only with the
+    /// `accesskit` feature.
+    AccessKitAction {
+        action: ReplayAccessAction,
+        target: u64,
+    },
+}
+
+fn key_from_name(name: &str) -> Result<egui::Key, EventError> {
+    egui::Key::from_name(name).ok_or_else(|| EventError::UnknownKey(name.to_string()))
+}
+
+impl TryFrom<&egui::Event> for ReplayEvent {
+    type Error = EventError;
+
+    fn try_from(event: &egui::Event) -> Result<Self, Self::Error> {
+        Ok(match event {
+            egui::Event::Copy => Self::Copy,
+            egui::Event::Cut => Self::Cut,
+            egui::Event::Paste(text) => Self::Paste { text: text.clone() },
+            egui::Event::Text(text) => Self::Text { text: text.clone() },
+            egui::Event::Key {
+                key,
+                physical_key,
+                pressed,
+                repeat,
+                modifiers,
+            } => Self::Key {
+                key: key.name().to_string(),
+                physical_key: physical_key.map(|key| key.name().to_string()),
+                pressed: *pressed,
+                repeat: *repeat,
+                modifiers: (*modifiers).into(),
+            },
+            egui::Event::PointerMoved(pos) => Self::PointerMoved { pos: [pos.x, pos.y] },
+            egui::Event::MouseMoved(delta) => Self::MouseMoved {
+                delta: [delta.x, delta.y],
+            },
+            egui::Event::PointerButton {
+                pos,
+                button,
+                pressed,
+                modifiers,
+            } => Self::PointerButton {
+                pos: [pos.x, pos.y],
+                button: (*button).into(),
+                pressed: *pressed,
+                modifiers: (*modifiers).into(),
+            },
+            egui::Event::PointerGone => Self::PointerGone,
+            egui::Event::Zoom(factor) => Self::Zoom { factor: *factor },
+            egui::Event::Ime(egui::ImeEvent::Enabled) => Self::ImeEnabled,
+            egui::Event::Ime(egui::ImeEvent::Preedit(text)) => Self::ImePreedit { text: text.clone() },
+            egui::Event::Ime(egui::ImeEvent::Commit(text)) => Self::ImeCommit { text: text.clone() },
+            egui::Event::Ime(egui::ImeEvent::Disabled) => Self::ImeDisabled,
+            egui::Event::Touch {
+                device_id,
+                id,
+                phase,
+                pos,
+                force,
+            } => Self::Touch {
+                device_id: device_id.0,
+                id: id.0,
+                phase: (*phase).into(),
+                pos: [pos.x, pos.y],
+                force: *force,
+            },
+            egui::Event::MouseWheel { unit, delta, modifiers } => Self::MouseWheel {
+                unit: (*unit).into(),
+                delta: [delta.x, delta.y],
+                modifiers: (*modifiers).into(),
+            },
+            egui::Event::WindowFocused(focused) => Self::WindowFocused { focused: *focused },
+            #[cfg(feature = "accesskit")]
+            egui::Event::AccessKitActionRequest(request) => match ReplayAccessAction::from_request(request) {
+                Some(action) => Self::AccessKitAction {
+                    action,
+                    target: request.target.0,
+                },
+                None => return Err(EventError::Unsupported(event_kind(event))),
+            },
+            event => return Err(EventError::Unsupported(event_kind(event))),
+        })
+    }
+}
+
+impl TryFrom<ReplayEvent> for egui::Event {
+    type Error = EventError;
+
+    fn try_from(event: ReplayEvent) -> Result<Self, Self::Error> {
+        Ok(match event {
+            ReplayEvent::Copy => Self::Copy,
+            ReplayEvent::Cut => Self::Cut,
+            ReplayEvent::Paste { text } => Self::Paste(text),
+            ReplayEvent::Text { text } => Self::Text(text),
+            ReplayEvent::Key {
+                key,
+                physical_key,
+                pressed,
+                repeat,
+                modifiers,
+            } => Self::Key {
+                key: key_from_name(&key)?,
+                physical_key: physical_key.as_deref().map(key_from_name).transpose()?,
+                pressed,
+                repeat,
+                modifiers: modifiers.into(),
+            },
+            ReplayEvent::PointerMoved { pos: [x, y] } => Self::PointerMoved(egui::pos2(x, y)),
+            ReplayEvent::MouseMoved { delta: [x, y] } => Self::MouseMoved(egui::vec2(x, y)),
+            ReplayEvent::PointerButton {
+                pos: [x, y],
+                button,
+                pressed,
+                modifiers,
+            } => Self::PointerButton {
+                pos: egui::pos2(x, y),
+                button: button.into(),
+                pressed,
+                modifiers: modifiers.into(),
+            },
+            ReplayEvent::PointerGone => Self::PointerGone,
+            ReplayEvent::Zoom { factor } => Self::Zoom(factor),
+            ReplayEvent::ImeEnabled => Self::Ime(egui::ImeEvent::Enabled),
+            ReplayEvent::ImePreedit { text } => Self::Ime(egui::ImeEvent::Preedit(text)),
+            ReplayEvent::ImeCommit { text } => Self::Ime(egui::ImeEvent::Commit(text)),
+            ReplayEvent::ImeDisabled => Self::Ime(egui::ImeEvent::Disabled),
+            ReplayEvent::Touch {
+                device_id,
+                id,
+                phase,
+                pos: [x, y],
+                force,
+            } => Self::Touch {
+                device_id: egui::TouchDeviceId(device_id),
+                id: egui::TouchId(id),
+                phase: phase.into(),
+                pos: egui::pos2(x, y),
+                force,
+            },
+            ReplayEvent::MouseWheel {
+                unit,
+                delta: [x, y],
+                modifiers,
+            } => Self::MouseWheel {
+                unit: unit.into(),
+                delta: egui::vec2(x, y),
+                modifiers: modifiers.into(),
+            },
+            ReplayEvent::WindowFocused { focused } => Self::WindowFocused(focused),
+            #[cfg(feature = "accesskit")]
+            ReplayEvent::AccessKitAction { action, target } => {
+                Self::AccessKitActionRequest(action.into_request(egui::accesskit::NodeId(target)))
+            }
+            #[cfg(not(feature = "accesskit"))]
+            ReplayEvent::AccessKitAction { .. } => return Err(EventError::Unsupported("AccessKitAction")),
+        })
+    }
+}
+
+/// An event loaded from a recording, which may fail to convert into an
+/// `egui::Event`.
+pub(crate) trait LoadedEvent {
+    fn into_event(self) -> Result<egui::Event, String>;
+}
+
+impl LoadedEvent for ReplayEvent {
+    fn into_event(self) -> Result<egui::Event, String> {
+        egui::Event::try_from(self).map_err(|err| err.to_string())
+    }
+}
+
+/// An event of a self-describing recording, which may be unknown to this
+/// version, e.g. because it was added by a newer version.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum StoredEvent {
+    Known(ReplayEvent),
+    Unknown(serde_json::Value),
+}
+
+impl LoadedEvent for StoredEvent {
+    fn into_event(self) -> Result<egui::Event, String> {
+        match self {
+            StoredEvent::Known(event) => event.into_event(),
+            StoredEvent::Unknown(value) => Err(format!("Unknown event: {}", value)),
+        }
+    }
+}
+
+/// Serde adapter storing `Vec<egui::Event>` fields as [`ReplayEvent`]s, for
+/// use with `#[serde(with = "crate::event::serde_events")]`. Events which are
+/// not recorded are left out.
+pub mod serde_events {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(events: &[egui::Event], serializer: S) -> Result<S::Ok, S::Error> {
+        let events: Vec<ReplayEvent> = events
+            .iter()
+            .filter_map(|event| match ReplayEvent::try_from(event) {
+                Ok(event) => Some(event),
+                Err(err) => {
+                    log::warn!("Not saving UI event: {}", err);
+                    None
+                }
+            })
+            .collect();
+        events.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<egui::Event>, D::Error> {
+        Vec::<ReplayEvent>::deserialize(deserializer)?
+            .into_iter()
+            .map(|event| egui::Event::try_from(event).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_convert_both_ways() {
+        let events = vec![
+            egui::Event::Key {
+                key: egui::Key::ArrowDown,
+                physical_key: Some(egui::Key::S),
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::CTRL,
+            },
+            egui::Event::PointerButton {
+                pos: egui::pos2(1.0, 2.0),
+                button: egui::PointerButton::Secondary,
+                pressed: false,
+                modifiers: egui::Modifiers::NONE,
+            },
+            egui::Event::Ime(egui::ImeEvent::Commit("你".to_string())),
+        ];
+
+        for event in events {
+            let replay_event = ReplayEvent::try_from(&event).unwrap();
+            assert_eq!(egui::Event::try_from(replay_event).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn serialized_form_is_stable() {
+        let event = ReplayEvent::try_from(&egui::Event::Key {
+            key: egui::Key::A,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        })
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"key":{"key":"A","physical_key":null,"pressed":true,"repeat":false,"modifiers":{"alt":false,"ctrl":false,"shift":false,"mac_cmd":false,"command":false}}}"#
+        );
+    }
+
+    #[cfg(feature = "accesskit")]
+    #[test]
+    fn access_kit_actions_convert_both_ways() {
+        let event = egui::Event::AccessKitActionRequest(
+            ReplayAccessAction::SetValue {
+                value: "hello".to_string(),
+            }
+            .into_request(egui::accesskit::NodeId(egui::Id::new("name").value())),
+        );
+
+        let replay_event = ReplayEvent::try_from(&event).unwrap();
+        assert_eq!(egui::Event::try_from(replay_event).unwrap(), event);
+    }
+
+    #[test]
+    fn unknown_key_fails_to_convert() {
+        let event = ReplayEvent::Key {
+            key: "NoSuchKey".to_string(),
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: ReplayModifiers::default(),
+        };
+
+        assert_eq!(
+            egui::Event::try_from(event),
+            Err(EventError::UnknownKey("NoSuchKey".to_string()))
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Which types of events are recorded.
+//!
+//! Recording every event makes recordings of specific problems large and
+//! hard to read. [`EventTypes`] is a set of event types, see
+//! [`crate::replay_events::ReplayManager::with_recorded_event_types`], e.g.
+//! to record only keys and text when debugging a text field.
+
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of event types, see the module docs.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct EventTypes(u32);
+
+impl EventTypes {
+    pub const NONE: Self = Self(0);
+    /// Pointer moves, and the pointer leaving the window.
+    pub const POINTER_MOVES: Self = Self(1 << 0);
+    pub const POINTER_BUTTONS: Self = Self(1 << 1);
+    pub const KEYS: Self = Self(1 << 2);
+    pub const TEXT: Self = Self(1 << 3);
+    /// Copy, cut and paste.
+    pub const CLIPBOARD: Self = Self(1 << 4);
+    /// Mouse wheel and trackpad scrolling.
+    pub const SCROLL: Self = Self(1 << 5);
+    pub const ZOOM: Self = Self(1 << 6);
+    pub const TOUCH: Self = Self(1 << 7);
+    pub const IME: Self = Self(1 << 8);
+    /// Focus changes of the window.
+    pub const FOCUS: Self = Self(1 << 9);
+    /// Files hovered over or dropped onto the window.
+    pub const FILES: Self = Self(1 << 10);
+    /// All other events, e.g. screenshots and AccessKit actions.
+    pub const OTHER: Self = Self(1 << 11);
+    pub const ALL: Self = Self((1 << 12) - 1);
+
+    /// Every single type with its name, e.g. for settings.
+    pub const NAMED: [(Self, &'static str); 12] = [
+        (Self::POINTER_MOVES, "Pointer moves"),
+        (Self::POINTER_BUTTONS, "Pointer buttons"),
+        (Self::KEYS, "Keys"),
+        (Self::TEXT, "Text"),
+        (Self::CLIPBOARD, "Clipboard"),
+        (Self::SCROLL, "Scroll"),
+        (Self::ZOOM, "Zoom"),
+        (Self::TOUCH, "Touch"),
+        (Self::IME, "IME"),
+        (Self::FOCUS, "Focus"),
+        (Self::FILES, "Files"),
+        (Self::OTHER, "Other"),
+    ];
+
+    /// The type of the event.
+    pub fn of(event: &egui::Event) -> Self {
+        match event {
+            egui::Event::PointerMoved(..) | egui::Event::MouseMoved(..) | egui::Event::PointerGone => {
+                Self::POINTER_MOVES
+            }
+            egui::Event::PointerButton { .. } => Self::POINTER_BUTTONS,
+            egui::Event::Key { .. } => Self::KEYS,
+            egui::Event::Text(..) => Self::TEXT,
+            egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(..) => Self::CLIPBOARD,
+            egui::Event::MouseWheel { .. } => Self::SCROLL,
+            egui::Event::Zoom(..) => Self::ZOOM,
+            egui::Event::Touch { .. } => Self::TOUCH,
+            egui::Event::Ime(..) => Self::IME,
+            egui::Event::WindowFocused(..) => Self::FOCUS,
+            _ => Self::OTHER,
+        }
+    }
+
+    pub const fn contains(&self, types: Self) -> bool {
+        self.0 & types.0 == types.0
+    }
+
+    /// Whether events of the type of `event` are in the set.
+    pub fn records(&self, event: &egui::Event) -> bool {
+        self.contains(Self::of(event))
+    }
+
+    pub fn set(&mut self, types: Self, enabled: bool) {
+        match enabled {
+            true => self.0 |= types.0,
+            false => self.0 &= !types.0,
+        }
+    }
+
+    /// Show a checkbox for every type, returning whether any changed.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            for (types, name) in Self::NAMED {
+                let mut enabled = self.contains(types);
+                if ui.checkbox(&mut enabled, name).changed() {
+                    self.set(types, enabled);
+                    changed = true;
+                }
+            }
+        });
+        changed
+    }
+}
+
+impl Default for EventTypes {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for EventTypes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for EventTypes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl std::fmt::Debug for EventTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = Self::NAMED
+            .iter()
+            .filter(|(types, _)| self.contains(*types))
+            .map(|(_, name)| *name);
+        f.debug_set().entries(names).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_recorded_by_type() {
+        let mut types = EventTypes::KEYS | EventTypes::TEXT;
+        assert!(types.records(&egui::Event::Text("a".to_string())));
+        assert!(!types.records(&egui::Event::PointerMoved(egui::Pos2::ZERO)));
+        assert!(!types.records(&egui::Event::Zoom(2.0)));
+
+        types.set(EventTypes::TEXT, false);
+        assert!(!types.records(&egui::Event::Text("a".to_string())));
+        assert!(EventTypes::default().records(&egui::Event::Zoom(2.0)));
+        assert_eq!(format!("{:?}", types), r#"{"Keys"}"#);
+    }
+}
@@ -0,0 +1,85 @@
+//! Export of replays to animated GIFs.
+//!
+//! A screenshot is taken after each replayed frame, and shown for as long as
+//! the recorded frame lasted, so the GIF plays at the recorded speed even if
+//! the replay was fast-forwarded. Requires the `gif` feature.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::timestamp::NanoTimestamp;
+
+/// Delay of the last frame of the GIF, in hundredths of a second.
+const LAST_FRAME_DELAY: u16 = 100;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to encode GIF: {0}")]
+    Gif(#[from] gif::EncodingError),
+    #[error("Screenshot of {width}x{height} is too large for a GIF")]
+    TooLarge { width: usize, height: usize },
+}
+
+/// Writes screenshots of the replayed frames into an animated GIF.
+pub struct GifExporter {
+    writer: Option<BufWriter<File>>,
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    // The last screenshot, written once the time of the next one is known.
+    pending: Option<(egui::ColorImage, NanoTimestamp)>,
+}
+
+impl GifExporter {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, ExportError> {
+        Ok(Self {
+            writer: Some(BufWriter::new(File::create(path)?)),
+            encoder: None,
+            pending: None,
+        })
+    }
+
+    /// Add the screenshot of the frame recorded at `time`.
+    pub fn add_frame(&mut self, image: &egui::ColorImage, time: NanoTimestamp) -> Result<(), ExportError> {
+        if let Some((previous, previous_time)) = self.pending.take() {
+            let delay = ((time - previous_time).as_millis() / 10).clamp(1, u16::MAX as i64) as u16;
+            self.write_frame(&previous, delay)?;
+        }
+        self.pending = Some((image.clone(), time));
+        Ok(())
+    }
+
+    /// Write the last screenshot and the end of the GIF.
+    pub fn finish(mut self) -> Result<(), ExportError> {
+        if let Some((last, _)) = self.pending.take() {
+            self.write_frame(&last, LAST_FRAME_DELAY)?;
+        }
+        // The encoder writes the end of the GIF when dropped.
+        drop(self.encoder.take());
+        Ok(())
+    }
+
+    fn write_frame(&mut self, image: &egui::ColorImage, delay: u16) -> Result<(), ExportError> {
+        let [width, height] = image.size;
+        let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(ExportError::TooLarge { width, height });
+        };
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => {
+                let writer = self.writer.take().expect("GIF writer is only taken once");
+                let mut encoder = gif::Encoder::new(writer, gif_width, gif_height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                self.encoder.insert(encoder)
+            }
+        };
+        let mut rgba: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.to_array()).collect();
+        let mut frame = gif::Frame::from_rgba_speed(gif_width, gif_height, &mut rgba, 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+        Ok(())
+    }
+}
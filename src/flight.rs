@@ -0,0 +1,72 @@
+//! Flight recorder, keeping the last frames while not recording.
+//!
+//! Bugs often show up before anybody thought of recording them. With
+//! [`crate::replay_events::ReplayManager::with_flight_recorder`], the manager
+//! captures the UI events continuously into a bounded buffer, which is saved
+//! to a recording on demand, see
+//! [`crate::replay_events::ReplayManager::save_flight_recorder`].
+
+use std::collections::VecDeque;
+
+use crate::replay_events::{FlightRecorderLimit, FrameEvents};
+
+/// Buffer of the last captured frames, bounded by a [`FlightRecorderLimit`].
+pub struct FlightRecorder {
+    limit: FlightRecorderLimit,
+    frames: VecDeque<FrameEvents>,
+}
+
+impl FlightRecorder {
+    pub fn new(limit: FlightRecorderLimit) -> Self {
+        Self {
+            limit,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Add the last captured frame, dropping the oldest frames beyond the
+    /// limit.
+    pub fn push(&mut self, frame: FrameEvents) {
+        let last_time = frame.time;
+        self.frames.push_back(frame);
+        match self.limit {
+            FlightRecorderLimit::Frames(max_frames) => {
+                while self.frames.len() > max_frames {
+                    self.frames.pop_front();
+                }
+            }
+            FlightRecorderLimit::Duration(max_duration) => {
+                while self
+                    .frames
+                    .front()
+                    .is_some_and(|first| last_time - first.time > max_duration)
+                {
+                    self.frames.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Take the buffered frames, emptying the buffer.
+    pub fn take_frames(&mut self) -> Vec<FrameEvents> {
+        self.frames.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+    #[test]
+    fn frames_older_than_the_duration_are_dropped() {
+        let mut recorder = FlightRecorder::new(FlightRecorderLimit::Duration(NanoDelta::from(10)));
+        for time in [0, 5, 12, 20] {
+            recorder.push(FrameEvents::new(NanoTimestamp::from(time), vec![egui::Event::Copy]));
+        }
+
+        let times: Vec<_> = recorder.take_frames().iter().map(|frame| frame.time).collect();
+        assert_eq!(times, vec![NanoTimestamp::from(12), NanoTimestamp::from(20)]);
+        assert!(recorder.take_frames().is_empty());
+    }
+}
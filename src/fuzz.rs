@@ -0,0 +1,224 @@
+//! Fuzzing of the app with random input events, for crash-hunting.
+//!
+//! A [`FuzzSource`] generates semi-random but valid frames: clicks and pointer
+//! moves inside the screen, scrolls, key presses and typed text. The same seed
+//! always generates the same frames. All generated frames are kept, so when
+//! the app panics they are saved as a normal recording which reproduces the
+//! crash when replayed.
+
+use std::sync::{Arc, Mutex};
+
+use crate::replay_events::FrameEvents;
+use crate::replay_file::{ReplayError, ReplayFile};
+use crate::timestamp::NanoTimestamp;
+
+/// Keys pressed by the fuzzer. The recording hotkeys are left out.
+const FUZZ_KEYS: [egui::Key; 16] = [
+    egui::Key::ArrowDown,
+    egui::Key::ArrowLeft,
+    egui::Key::ArrowRight,
+    egui::Key::ArrowUp,
+    egui::Key::Backspace,
+    egui::Key::Delete,
+    egui::Key::End,
+    egui::Key::Enter,
+    egui::Key::Escape,
+    egui::Key::Home,
+    egui::Key::PageDown,
+    egui::Key::PageUp,
+    egui::Key::Space,
+    egui::Key::Tab,
+    egui::Key::A,
+    egui::Key::Z,
+];
+/// Characters typed by the fuzzer.
+const FUZZ_CHARS: &[char] = &['a', 'Z', '0', '9', ' ', '.', '-', '/', 'é', 'ß', '中', '😀'];
+const MAX_TEXT_LEN: usize = 8;
+const MAX_SCROLL: f32 = 200.0;
+
+/// A small, seedable pseudo-random generator (SplitMix64). The fuzzer needs
+/// reproducible sequences, not statistical quality.
+#[derive(Clone, Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in `0.0..1.0`.
+    fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn pos_in(&mut self, rect: egui::Rect) -> egui::Pos2 {
+        rect.min + egui::vec2(self.unit() * rect.width(), self.unit() * rect.height())
+    }
+}
+
+/// Generates random frames, see the module docs.
+pub struct FuzzSource {
+    seed: u64,
+    rng: SplitMix64,
+    // Shared with the panic hook.
+    frames: Arc<Mutex<Vec<FrameEvents>>>,
+}
+
+impl FuzzSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SplitMix64(seed),
+            frames: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// File the generated frames are saved to when the app panics.
+    pub fn crash_file_name(&self) -> String {
+        format!("./egui_replay_fuzz_{}.bin", self.seed)
+    }
+
+    /// Number of frames generated so far.
+    pub fn num_frames(&self) -> usize {
+        self.frames.lock().map_or(0, |frames| frames.len())
+    }
+
+    /// Generate the next frame at `time`, with pointer events inside
+    /// `screen_rect`.
+    pub fn next_frame(&mut self, time: NanoTimestamp, screen_rect: egui::Rect) -> FrameEvents {
+        let rng = &mut self.rng;
+        let events = match rng.below(5) {
+            0 => vec![egui::Event::PointerMoved(rng.pos_in(screen_rect))],
+            1 => {
+                let pos = rng.pos_in(screen_rect);
+                let button = if rng.below(4) == 0 {
+                    egui::PointerButton::Secondary
+                } else {
+                    egui::PointerButton::Primary
+                };
+                let pointer_button = |pressed| egui::Event::PointerButton {
+                    pos,
+                    button,
+                    pressed,
+                    modifiers: egui::Modifiers::NONE,
+                };
+                vec![
+                    egui::Event::PointerMoved(pos),
+                    pointer_button(true),
+                    pointer_button(false),
+                ]
+            }
+            2 => vec![egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Point,
+                delta: egui::vec2(
+                    (rng.unit() - 0.5) * 2.0 * MAX_SCROLL,
+                    (rng.unit() - 0.5) * 2.0 * MAX_SCROLL,
+                ),
+                modifiers: egui::Modifiers::NONE,
+            }],
+            3 => {
+                let key = FUZZ_KEYS[rng.below(FUZZ_KEYS.len())];
+                let modifiers = if rng.below(4) == 0 {
+                    egui::Modifiers::COMMAND
+                } else {
+                    egui::Modifiers::NONE
+                };
+                let key_event = |pressed| egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed,
+                    repeat: false,
+                    modifiers,
+                };
+                vec![key_event(true), key_event(false)]
+            }
+            _ => {
+                let len = 1 + rng.below(MAX_TEXT_LEN);
+                let text = (0..len).map(|_| FUZZ_CHARS[rng.below(FUZZ_CHARS.len())]).collect();
+                vec![egui::Event::Text(text)]
+            }
+        };
+        let frame = FrameEvents::new(time, events);
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.push(frame.clone());
+        }
+        frame
+    }
+
+    /// Save the frames generated so far as a recording.
+    pub fn save(&self, path: &str) -> Result<(), ReplayError> {
+        let frames = self.frames.lock().map(|frames| frames.clone()).unwrap_or_default();
+        ReplayFile::new(frames).save(path)
+    }
+
+    /// Save the generated frames to [`FuzzSource::crash_file_name`] when the
+    /// app panics. The previous panic hook is still called afterwards.
+    pub fn install_panic_hook(&self) {
+        let frames = self.frames.clone();
+        let path = self.crash_file_name();
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // The lock is never held while calling into the app, but don't
+            // risk a deadlock in the panic hook.
+            if let Ok(frames) = frames.try_lock() {
+                if !frames.is_empty() {
+                    match ReplayFile::new(frames.clone()).save(&path) {
+                        Ok(()) => log::error!("Saved the fuzzed UI events which caused a panic to {}", path),
+                        Err(err) => log::error!("Failed to save the fuzzed UI events to {}: {}", path, err),
+                    }
+                }
+            }
+            previous_hook(info);
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(seed: u64, screen_rect: egui::Rect) -> Vec<FrameEvents> {
+        let mut source = FuzzSource::new(seed);
+        (0..200)
+            .map(|i| source.next_frame(NanoTimestamp::from(i), screen_rect))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_generates_same_frames() {
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+
+        assert_eq!(generate(7, screen_rect), generate(7, screen_rect));
+        assert_ne!(generate(7, screen_rect), generate(8, screen_rect));
+    }
+
+    #[test]
+    fn pointer_events_are_inside_the_screen() {
+        let screen_rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(300.0, 200.0));
+
+        for frame in generate(1, screen_rect) {
+            assert!(!frame.events.is_empty());
+            for event in frame.events {
+                match event {
+                    egui::Event::PointerMoved(pos) | egui::Event::PointerButton { pos, .. } => {
+                        assert!(screen_rect.contains(pos), "{:?} outside of {:?}", pos, screen_rect)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Heatmaps of where users interact, for UX analysis.
+//!
+//! [`InteractionHeatmap`] bins the pointer positions of one or more recordings
+//! into square cells: where the pointer buttons were pressed, and how long the
+//! pointer hovered over each cell. It keeps the pointer trajectories too. It's
+//! shown scaled down in the replay window, or painted over the app itself with
+//! [`crate::replay_events::ReplayManager::set_heatmap_overlay`]. Only the root
+//! viewport is included.
+
+use std::collections::HashMap;
+
+use egui::emath::RectTransform;
+use egui::{Color32, Pos2, Rect, Stroke};
+
+use crate::replay_events::FrameEvents;
+use crate::replay_file::ReplayFile;
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+pub const DEFAULT_CELL_SIZE: f32 = 16.0;
+
+// Longest time the pointer counts as hovering over a cell, so that leaving
+// the pointer idle doesn't outweigh everything else.
+const MAX_DWELL: NanoDelta = NanoDelta::from_secs_safe(1);
+
+/// Clicks, hover times and pointer trajectories, see the module docs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InteractionHeatmap {
+    cell_size: f32,
+    // Presses of pointer buttons, and seconds hovered, in each cell.
+    clicks: HashMap<(i32, i32), u32>,
+    hover: HashMap<(i32, i32), f32>,
+    trajectories: Vec<Vec<Pos2>>,
+    num_recordings: usize,
+    // Bounding box of all positions.
+    bounds: Rect,
+}
+
+impl Default for InteractionHeatmap {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl InteractionHeatmap {
+    /// An empty heatmap with cells of the given size, in points.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            clicks: HashMap::new(),
+            hover: HashMap::new(),
+            trajectories: Vec::new(),
+            num_recordings: 0,
+            bounds: Rect::NOTHING,
+        }
+    }
+
+    pub fn from_recording(recording: &ReplayFile) -> Self {
+        let mut heatmap = Self::default();
+        heatmap.add_frames(&recording.frames);
+        heatmap
+    }
+
+    /// Add the interactions of another recording, to aggregate many.
+    pub fn add_frames(&mut self, frames: &[FrameEvents]) {
+        self.num_recordings += 1;
+        let mut trajectory = Vec::new();
+        // Where the pointer is, and since when.
+        let mut hovering: Option<(Pos2, NanoTimestamp)> = None;
+        for frame in frames {
+            if frame.viewport_id != egui::ViewportId::ROOT {
+                continue;
+            }
+            for event in frame.events.iter() {
+                match event {
+                    egui::Event::PointerMoved(pos) => {
+                        self.add_dwell(hovering.take(), frame.time);
+                        hovering = Some((*pos, frame.time));
+                        trajectory.push(*pos);
+                        self.bounds.extend_with(*pos);
+                    }
+                    egui::Event::PointerButton { pos, pressed: true, .. } => {
+                        *self.clicks.entry(self.cell(*pos)).or_default() += 1;
+                        self.bounds.extend_with(*pos);
+                    }
+                    egui::Event::PointerGone => {
+                        self.add_dwell(hovering.take(), frame.time);
+                        self.add_trajectory(std::mem::take(&mut trajectory));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Some(last) = frames.last() {
+            self.add_dwell(hovering, last.time);
+        }
+        self.add_trajectory(trajectory);
+    }
+
+    fn add_dwell(&mut self, hovering: Option<(Pos2, NanoTimestamp)>, until: NanoTimestamp) {
+        if let Some((pos, since)) = hovering {
+            let dwell = (until - since).min(MAX_DWELL);
+            *self.hover.entry(self.cell(pos)).or_default() += dwell.as_nanos() as f32 / 1e9;
+        }
+    }
+
+    fn add_trajectory(&mut self, trajectory: Vec<Pos2>) {
+        if trajectory.len() > 1 {
+            self.trajectories.push(trajectory);
+        }
+    }
+
+    fn cell(&self, pos: Pos2) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_rect(&self, (x, y): (i32, i32)) -> Rect {
+        let min = Pos2::new(x as f32 * self.cell_size, y as f32 * self.cell_size);
+        Rect::from_min_size(min, egui::Vec2::splat(self.cell_size))
+    }
+
+    pub fn num_recordings(&self) -> usize {
+        self.num_recordings
+    }
+
+    pub fn num_clicks(&self) -> u32 {
+        self.clicks.values().sum()
+    }
+
+    /// Number of presses of pointer buttons in the cell of `pos`.
+    pub fn clicks_at(&self, pos: Pos2) -> u32 {
+        self.clicks.get(&self.cell(pos)).copied().unwrap_or_default()
+    }
+
+    /// Seconds the pointer hovered over the cell of `pos`.
+    pub fn hover_at(&self, pos: Pos2) -> f32 {
+        self.hover.get(&self.cell(pos)).copied().unwrap_or_default()
+    }
+
+    /// The `n` cells with the most clicks, most clicked first.
+    pub fn most_clicked(&self, n: usize) -> Vec<(Rect, u32)> {
+        let mut cells: Vec<_> = self.clicks.iter().map(|(cell, clicks)| (*cell, *clicks)).collect();
+        cells.sort_by_key(|(cell, clicks)| (std::cmp::Reverse(*clicks), *cell));
+        cells
+            .into_iter()
+            .take(n)
+            .map(|(cell, clicks)| (self.cell_rect(cell), clicks))
+            .collect()
+    }
+
+    /// The paths of the pointer, split where it left the window.
+    pub fn trajectories(&self) -> &[Vec<Pos2>] {
+        &self.trajectories
+    }
+
+    /// Paint the hover times as colored cells, from blue to red, the
+    /// trajectories as lines and the clicks as dots growing with their count.
+    pub fn paint(&self, painter: &egui::Painter, to_screen: RectTransform) {
+        let max_hover = self.hover.values().copied().fold(0.0, f32::max);
+        for (cell, seconds) in self.hover.iter() {
+            let heat = if max_hover > 0.0 { seconds / max_hover } else { 0.0 };
+            let color = Color32::from_rgb((255.0 * heat) as u8, 0, (255.0 * (1.0 - heat)) as u8);
+            let rect = to_screen.transform_rect(self.cell_rect(*cell));
+            painter.rect_filled(rect, 0.0, color.gamma_multiply(0.2 + 0.5 * heat));
+        }
+        let stroke = Stroke::new(1.0, Color32::from_white_alpha(96));
+        for trajectory in self.trajectories.iter() {
+            let points = trajectory.iter().map(|pos| to_screen.transform_pos(*pos)).collect();
+            painter.add(egui::Shape::line(points, stroke));
+        }
+        let max_clicks = self.clicks.values().copied().max().unwrap_or_default();
+        for (cell, clicks) in self.clicks.iter() {
+            let center = to_screen.transform_pos(self.cell_rect(*cell).center());
+            let radius = 2.0 + 6.0 * (*clicks as f32 / max_clicks as f32).sqrt();
+            painter.circle_filled(center, radius, Color32::from_rgb(255, 200, 0));
+        }
+    }
+
+    /// Show a summary, and the heatmap scaled to the available width.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let hover: f32 = self.hover.values().sum();
+        ui.label(format!(
+            "{} clicks, {:.1} s of hovering in {} recordings",
+            self.num_clicks(),
+            hover,
+            self.num_recordings
+        ));
+        // Positions are relative to the top left corner of the window.
+        let content = Rect::from_min_max(Pos2::ZERO, self.bounds.max);
+        if !(content.width() > 0.0 && content.height() > 0.0) {
+            return;
+        }
+        let width = ui.available_width();
+        let size = egui::vec2(width, width * content.height() / content.width());
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        painter.rect_filled(response.rect, 0.0, ui.visuals().extreme_bg_color);
+        self.paint(&painter, RectTransform::from_to(content, response.rect));
+    }
+
+    /// Paint the heatmap over the app, at the recorded positions.
+    pub fn paint_overlay(&self, ctx: &egui::Context) {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("replay_heatmap_overlay"),
+        ));
+        self.paint(&painter, RectTransform::identity(ctx.screen_rect()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time_ms: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time_ms * 1_000_000), events)
+    }
+
+    fn click(pos: Pos2) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed: true,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn interactions_are_binned_into_cells() {
+        let a = egui::pos2(10.0, 10.0);
+        let b = egui::pos2(40.0, 10.0);
+        let frames = vec![
+            frame(0, vec![egui::Event::PointerMoved(a)]),
+            frame(500, vec![egui::Event::PointerMoved(b), click(b)]),
+            frame(3000, vec![egui::Event::PointerGone]),
+            frame(4000, vec![egui::Event::PointerMoved(a), click(a)]),
+        ];
+        let mut heatmap = InteractionHeatmap::default();
+        heatmap.add_frames(&frames);
+
+        assert_eq!(heatmap.hover_at(a), 0.5);
+        // Capped, and the pointer left.
+        assert_eq!(heatmap.hover_at(b), 1.0);
+        assert_eq!(heatmap.clicks_at(egui::pos2(47.0, 1.0)), 1);
+        assert_eq!(heatmap.trajectories(), &[vec![a, b]]);
+
+        heatmap.add_frames(&frames);
+        assert_eq!(heatmap.num_recordings(), 2);
+        assert_eq!(heatmap.num_clicks(), 4);
+        assert_eq!(heatmap.hover_at(b), 2.0);
+    }
+}
@@ -0,0 +1,117 @@
+//! Import of recordings from serialized `egui::RawInput` dumps.
+//!
+//! Dumps are either a JSON array of `RawInput` objects, or one `RawInput`
+//! object per line. Each input with events or input state changes becomes a
+//! frame, timed by `RawInput::time`.
+
+use std::path::Path;
+
+use crate::replay_events::{FrameEvents, RecordedFile};
+use crate::replay_file::{ReplayError, ReplayFile};
+use crate::timestamp::{NanoTimestamp, NANOS_PER_SECOND};
+
+/// Time between inputs without a time, assuming 60 frames per second.
+const DEFAULT_FRAME_NANOS: i64 = NANOS_PER_SECOND / 60;
+
+/// Convert a sequence of raw inputs into frames. Hovered files and focus are
+/// only stored when they change, like when recording.
+pub fn frames_from_raw_inputs(inputs: &[egui::RawInput]) -> Vec<FrameEvents> {
+    let mut frames = Vec::new();
+    let mut time = NanoTimestamp::zero();
+    let mut hovered_files: Vec<RecordedFile> = Vec::new();
+    let mut focused = None;
+    for input in inputs {
+        time = match input.time {
+            Some(secs) => NanoTimestamp::from((secs * NANOS_PER_SECOND as f64) as i64),
+            None => NanoTimestamp::from(time.as_nanos() + DEFAULT_FRAME_NANOS),
+        };
+
+        let mut frame = FrameEvents::new(time, input.events.clone())
+            .with_viewport_id(input.viewport_id)
+            .with_modifiers(input.modifiers);
+        let current_hovered_files: Vec<RecordedFile> =
+            input.hovered_files.iter().map(RecordedFile::from_hovered).collect();
+        if current_hovered_files != hovered_files {
+            hovered_files = current_hovered_files.clone();
+            frame.hovered_files = Some(current_hovered_files);
+        }
+        frame.dropped_files = input
+            .dropped_files
+            .iter()
+            .map(|file| RecordedFile::from_dropped(file, Some(usize::MAX)))
+            .collect();
+        if focused != Some(input.focused) {
+            focused = Some(input.focused);
+            frame.focused = focused;
+        }
+
+        if !frame.events.is_empty() || frame.has_input_state() {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+/// Load a `RawInput` dump and convert it into a recording.
+pub fn import_raw_input_dump(path: impl AsRef<Path>) -> Result<ReplayFile, ReplayError> {
+    let bytes = std::fs::read(path)?;
+    let is_array = bytes.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'[');
+    let inputs: Vec<egui::RawInput> = if is_array {
+        serde_json::from_slice(&bytes)?
+    } else {
+        serde_json::Deserializer::from_slice(&bytes)
+            .into_iter()
+            .collect::<Result<_, _>>()?
+    };
+    Ok(ReplayFile::new(frames_from_raw_inputs(&inputs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_input(time: Option<f64>, events: Vec<egui::Event>) -> egui::RawInput {
+        egui::RawInput {
+            time,
+            events,
+            focused: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn raw_inputs_become_frames() {
+        let inputs = vec![
+            raw_input(Some(1.0), vec![egui::Event::Text("a".to_string())]),
+            raw_input(Some(1.5), vec![]),
+            raw_input(None, vec![egui::Event::Text("b".to_string())]),
+        ];
+
+        let frames = frames_from_raw_inputs(&inputs);
+
+        let times: Vec<i64> = frames.iter().map(|frame| frame.time.as_nanos()).collect();
+        assert_eq!(times, vec![1_000_000_000, 1_500_000_000 + DEFAULT_FRAME_NANOS]);
+        assert_eq!(frames[0].focused, Some(true));
+        assert_eq!(frames[1].focused, None);
+    }
+
+    #[test]
+    fn json_lines_dump_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dump.jsonl");
+        let inputs = [
+            raw_input(Some(1.0), vec![egui::Event::Copy]),
+            raw_input(Some(2.0), vec![egui::Event::Cut]),
+        ];
+        let lines: Vec<String> = inputs
+            .iter()
+            .map(|input| serde_json::to_string(input).unwrap())
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let file = import_raw_input_dump(&path).unwrap();
+
+        assert_eq!(file.num_frames(), 2);
+        assert_eq!(file.frames[1].events, vec![egui::Event::Cut]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Indexed binary recordings, replayed lazily from a memory map.
+//!
+//! `.ibin` files store every frame encoded on its own, after a header with the
+//! rest of the recording and a table with the offset and the time of every
+//! frame:
+//!
+//! ```text
+//! magic | format version | header length | header | number of frames | (offset, time) of each frame | frames
+//! ```
+//!
+//! [`IndexedReplayFile::open`] maps the file into memory and decodes only the
+//! header and the table, so opening is instant even for recordings of hundreds
+//! of MB. Frames are decoded on demand by [`IndexedReplayFile::frame`], and the
+//! OS pages the file in and out as needed, so memory stays flat during replay,
+//! see [`crate::replay_events::ReplayManager::start_lazy_replay`].
+//! [`IndexedCodec`] saves and loads these files like the other formats.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::codec::{ReplayCodec, BINARY_FORMAT_VERSION};
+use crate::replay_events::FrameEvents;
+use crate::replay_file::{ReplayError, ReplayFile};
+use crate::timestamp::NanoTimestamp;
+
+const INDEXED_MAGIC: &[u8; 8] = b"EGRPIDX\0";
+// Size of the offset and time of a frame in the table.
+const ENTRY_SIZE: usize = 16;
+
+/// `.ibin` files, see the module docs. The frames are encoded like in binary
+/// recordings, so the format version is the binary one.
+pub struct IndexedCodec;
+
+impl ReplayCodec for IndexedCodec {
+    fn extensions(&self) -> &[&str] {
+        &["ibin"]
+    }
+
+    fn encode(&self, file: &ReplayFile, writer: &mut dyn Write) -> Result<(), ReplayError> {
+        let config = bincode::config::standard();
+        let header = bincode::serde::encode_to_vec(without_frames(file), config)?;
+        let frames = file
+            .frames
+            .iter()
+            .map(|frame| bincode::serde::encode_to_vec(frame, config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        writer.write_all(INDEXED_MAGIC)?;
+        writer.write_all(&BINARY_FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(header.len() as u64).to_le_bytes())?;
+        writer.write_all(&header)?;
+        writer.write_all(&(frames.len() as u64).to_le_bytes())?;
+        let table_len = frames.len() * ENTRY_SIZE;
+        let mut offset = (INDEXED_MAGIC.len() + 4 + 8 + header.len() + 8 + table_len) as u64;
+        for (frame, bytes) in file.frames.iter().zip(frames.iter()) {
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&frame.time.as_nanos().to_le_bytes())?;
+            offset += bytes.len() as u64;
+        }
+        for bytes in frames.iter() {
+            writer.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<ReplayFile, ReplayError> {
+        let layout = Layout::parse(bytes)?;
+        let frames = (0..layout.num_frames)
+            .map(|index| layout.frame(bytes, index))
+            .collect::<Result<_, _>>()?;
+        Ok(ReplayFile {
+            frames,
+            ..layout.header
+        })
+    }
+}
+
+// The recording without its frames, stored in the header.
+fn without_frames(file: &ReplayFile) -> ReplayFile {
+    ReplayFile {
+        rng_seeds: file.rng_seeds.clone(),
+        state_snapshot: file.state_snapshot.clone(),
+        app_identity: file.app_identity.clone(),
+        state_fingerprint: file.state_fingerprint.clone(),
+        screen_rect: file.screen_rect,
+        frames: Vec::new(),
+        markers: file.markers.clone(),
+        annotations: file.annotations.clone(),
+        gaps: file.gaps.clone(),
+        assertions: file.assertions.clone(),
+        logs: file.logs.clone(),
+        audio: file.audio.clone(),
+    }
+}
+
+fn truncated() -> ReplayError {
+    ReplayError::Codec("Truncated indexed recording".to_string())
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, ReplayError> {
+    let end = pos.checked_add(8).ok_or_else(truncated)?;
+    let bytes = bytes.get(pos..end).ok_or_else(truncated)?;
+    Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| truncated())?))
+}
+
+// The decoded header and the position of the table of an indexed file.
+struct Layout {
+    header: ReplayFile,
+    num_frames: usize,
+    table_start: usize,
+}
+
+impl Layout {
+    fn parse(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let Some(body) = bytes.strip_prefix(INDEXED_MAGIC.as_slice()) else {
+            return Err(ReplayError::Codec("Not an indexed recording".to_string()));
+        };
+        let version = body.get(..4).ok_or_else(truncated)?;
+        let version = u32::from_le_bytes(version.try_into().map_err(|_| truncated())?);
+        if version != BINARY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let header_start = INDEXED_MAGIC.len() + 4 + 8;
+        let header_len = read_u64(bytes, header_start - 8)? as usize;
+        let header_end = header_start.checked_add(header_len).ok_or_else(truncated)?;
+        let header = bytes.get(header_start..header_end).ok_or_else(truncated)?;
+        let (header, _): (ReplayFile, usize) = bincode::serde::decode_from_slice(header, bincode::config::standard())?;
+        let num_frames = read_u64(bytes, header_end)? as usize;
+        let table_start = header_end + 8;
+        let table_len = num_frames.checked_mul(ENTRY_SIZE).ok_or_else(truncated)?;
+        let table_end = table_start.checked_add(table_len).ok_or_else(truncated)?;
+        if table_end > bytes.len() {
+            return Err(truncated());
+        }
+        Ok(Self {
+            header,
+            num_frames,
+            table_start,
+        })
+    }
+
+    fn offset(&self, bytes: &[u8], index: usize) -> Result<usize, ReplayError> {
+        Ok(read_u64(bytes, self.table_start + index * ENTRY_SIZE)? as usize)
+    }
+
+    fn time(&self, bytes: &[u8], index: usize) -> Result<NanoTimestamp, ReplayError> {
+        let nanos = read_u64(bytes, self.table_start + index * ENTRY_SIZE + 8)?;
+        Ok(NanoTimestamp::from_nanos(nanos as i64))
+    }
+
+    fn frame(&self, bytes: &[u8], index: usize) -> Result<FrameEvents, ReplayError> {
+        let start = self.offset(bytes, index)?;
+        let end = match index + 1 < self.num_frames {
+            true => self.offset(bytes, index + 1)?,
+            false => bytes.len(),
+        };
+        let frame = bytes.get(start..end).ok_or_else(truncated)?;
+        let (frame, _): (FrameEvents, usize) = bincode::serde::decode_from_slice(frame, bincode::config::standard())?;
+        Ok(frame)
+    }
+}
+
+/// An `.ibin` recording mapped into memory, see the module docs.
+pub struct IndexedReplayFile {
+    mmap: Mmap,
+    layout: Layout,
+}
+
+impl IndexedReplayFile {
+    /// Map the recording at `path` and decode its header and table.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let file = File::open(path.as_ref())?;
+        // SAFETY: The mapping is only read. Recordings aren't modified after
+        // they were saved, if one is truncated meanwhile reads may fault.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let layout = Layout::parse(&mmap)?;
+        Ok(Self { mmap, layout })
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.layout.num_frames
+    }
+
+    /// The recording without its frames, i.e. its markers, assertions etc.
+    pub fn header(&self) -> &ReplayFile {
+        &self.layout.header
+    }
+
+    /// Time of the frame at `index`, read from the table.
+    pub fn frame_time(&self, index: usize) -> Result<NanoTimestamp, ReplayError> {
+        if index >= self.num_frames() {
+            return Err(ReplayError::Codec(format!("No frame {} in the recording", index)));
+        }
+        self.layout.time(&self.mmap, index)
+    }
+
+    /// Decode the frame at `index`.
+    pub fn frame(&self, index: usize) -> Result<FrameEvents, ReplayError> {
+        if index >= self.num_frames() {
+            return Err(ReplayError::Codec(format!("No frame {} in the recording", index)));
+        }
+        self.layout.frame(&self.mmap, index)
+    }
+
+    /// The recording with frames which only have their time, to schedule a
+    /// lazy replay. The frames are decoded as they are replayed.
+    pub fn skeleton(&self) -> Result<ReplayFile, ReplayError> {
+        let frames = (0..self.num_frames())
+            .map(|index| Ok(FrameEvents::new(self.frame_time(index)?, Vec::new())))
+            .collect::<Result<_, ReplayError>>()?;
+        Ok(ReplayFile {
+            frames,
+            ..without_frames(self.header())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_file() -> ReplayFile {
+        let mut file = ReplayFile::new(vec![
+            FrameEvents::new(NanoTimestamp::from(1_000), vec![egui::Event::Text("a".to_string())]),
+            FrameEvents::new(NanoTimestamp::from(2_000), vec![egui::Event::Copy, egui::Event::Cut]),
+        ]);
+        file.app_identity = Some("app 1.0".to_string());
+        file
+    }
+
+    #[test]
+    fn indexed_files_round_trip() {
+        let file = sample_file();
+        let mut bytes = Vec::new();
+        IndexedCodec.encode(&file, &mut bytes).unwrap();
+
+        assert_eq!(IndexedCodec.decode(&bytes).unwrap(), file);
+        assert!(IndexedCodec.decode(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn frames_are_decoded_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("indexed.ibin");
+        let file = sample_file();
+        file.save(&path).unwrap();
+
+        let indexed = IndexedReplayFile::open(&path).unwrap();
+        let skeleton = indexed.skeleton().unwrap();
+        let second = indexed.frame(1).unwrap();
+
+        assert_eq!(skeleton.app_identity, file.app_identity);
+        let times: Vec<_> = skeleton.frames.iter().map(|frame| frame.time).collect();
+        assert_eq!(times, vec![NanoTimestamp::from(1_000), NanoTimestamp::from(2_000)]);
+        assert!(skeleton.frames.iter().all(|frame| frame.events.is_empty()));
+        assert_eq!(second, file.frames[1]);
+    }
+}
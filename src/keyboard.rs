@@ -0,0 +1,237 @@
+//! Keyboard layout independent recordings.
+//!
+//! egui sends an [`egui::Event::Key`] for every key press, followed by an
+//! [`egui::Event::Text`] if the press typed text. Both depend on the keyboard
+//! layout of the recording machine, so replaying them on a machine with
+//! another layout can type the wrong text, or type it twice in apps which
+//! handle both. [`KeyboardMode`] picks which of the two is authoritative,
+//! both when recording and when replaying, see
+//! [`crate::replay_events::ReplayManager::with_keyboard_mode`].
+
+use std::collections::HashSet;
+
+use crate::replay_events::FrameEvents;
+
+/// Which events of typed text are kept, see the module docs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum KeyboardMode {
+    /// Keep key and text events as recorded.
+    #[default]
+    AsRecorded,
+    /// Drop the key events which typed text, including their repeats and
+    /// releases, and replay the typed text. Shortcuts are kept.
+    TextAuthoritative,
+    /// Drop the text typed by key presses, and type the text of the keys
+    /// again when replaying, see [`key_text`].
+    KeyAuthoritative,
+}
+
+impl KeyboardMode {
+    /// Normalize the frames of a recording before it is saved.
+    pub fn normalize_recording(&self, frames: &mut [FrameEvents]) {
+        match self {
+            KeyboardMode::AsRecorded => {}
+            KeyboardMode::TextAuthoritative => drop_typing_keys(frames),
+            KeyboardMode::KeyAuthoritative => drop_typed_text(frames),
+        }
+    }
+
+    /// Normalize the frames of a recording before it is replayed, so that
+    /// recordings made in any mode replay the same way.
+    pub fn normalize_replay(&self, frames: &mut [FrameEvents]) {
+        match self {
+            KeyboardMode::AsRecorded => {}
+            KeyboardMode::TextAuthoritative => {
+                // Key authoritative recordings have no text to keep.
+                type_key_text(frames);
+                drop_typing_keys(frames);
+            }
+            KeyboardMode::KeyAuthoritative => {
+                drop_typed_text(frames);
+                type_key_text(frames);
+            }
+        }
+    }
+}
+
+/// Text typed by the key with the given modifiers, independent of the
+/// keyboard layout: letters, digits, space and the punctuation keys of egui.
+/// Shift only changes letters, egui has separate keys for e.g. `:` and `?`.
+/// `None` for other keys, and for shortcuts.
+pub fn key_text(key: egui::Key, modifiers: egui::Modifiers) -> Option<String> {
+    use egui::Key;
+
+    if is_shortcut(modifiers) {
+        return None;
+    }
+    let text = match key {
+        Key::Space => " ",
+        Key::Colon => ":",
+        Key::Comma => ",",
+        Key::Backslash => "\\",
+        Key::Slash => "/",
+        Key::Pipe => "|",
+        Key::Questionmark => "?",
+        Key::Exclamationmark => "!",
+        Key::OpenBracket => "[",
+        Key::CloseBracket => "]",
+        Key::OpenCurlyBracket => "{",
+        Key::CloseCurlyBracket => "}",
+        Key::Backtick => "`",
+        Key::Minus => "-",
+        Key::Period => ".",
+        Key::Plus => "+",
+        Key::Equals => "=",
+        Key::Semicolon => ";",
+        Key::Quote => "'",
+        _ => {
+            // The names of letters and digits are their text.
+            let name = key.name();
+            let is_char = name.len() == 1 && name.chars().all(|c| c.is_ascii_alphanumeric());
+            return match (is_char, modifiers.shift) {
+                (false, _) => None,
+                (true, true) => Some(name.to_ascii_uppercase()),
+                (true, false) => Some(name.to_ascii_lowercase()),
+            };
+        }
+    };
+    Some(text.to_string())
+}
+
+// Whether the modifiers make a key press a shortcut rather than typing.
+fn is_shortcut(modifiers: egui::Modifiers) -> bool {
+    modifiers.ctrl || modifiers.command || modifiers.mac_cmd
+}
+
+// The key pressed by the event, if it may type text.
+fn typing_key(event: &egui::Event) -> Option<egui::Key> {
+    match event {
+        egui::Event::Key {
+            key,
+            pressed: true,
+            modifiers,
+            ..
+        } if !is_shortcut(*modifiers) => Some(*key),
+        _ => None,
+    }
+}
+
+// Whether the event at `index` is text typed by the key press before it.
+fn is_typed_text(events: &[egui::Event], index: usize) -> bool {
+    let is_text = matches!(events[index], egui::Event::Text(_));
+    is_text && index > 0 && typing_key(&events[index - 1]).is_some()
+}
+
+// Drop the key presses followed by the text they typed, and their releases.
+fn drop_typing_keys(frames: &mut [FrameEvents]) {
+    let mut typing_keys = HashSet::new();
+    for frame in frames.iter_mut() {
+        let events = std::mem::take(&mut frame.events);
+        for (index, event) in events.iter().enumerate() {
+            let typed_text = index + 1 < events.len() && is_typed_text(&events, index + 1);
+            if let egui::Event::Key { key, pressed, .. } = event {
+                let id = (frame.viewport_id, *key);
+                let is_dropped = match pressed {
+                    true if typed_text => {
+                        typing_keys.insert(id);
+                        true
+                    }
+                    true => false,
+                    false => typing_keys.remove(&id),
+                };
+                if is_dropped {
+                    continue;
+                }
+            }
+            frame.events.push(event.clone());
+        }
+    }
+}
+
+// Drop the text typed by key presses, keeping e.g. pasted text.
+fn drop_typed_text(frames: &mut [FrameEvents]) {
+    for frame in frames.iter_mut() {
+        let events = std::mem::take(&mut frame.events);
+        for (index, event) in events.iter().enumerate() {
+            if !is_typed_text(&events, index) {
+                frame.events.push(event.clone());
+            }
+        }
+    }
+}
+
+// Type the text of the key presses which aren't followed by text.
+fn type_key_text(frames: &mut [FrameEvents]) {
+    for frame in frames.iter_mut() {
+        let events = std::mem::take(&mut frame.events);
+        for (index, event) in events.iter().enumerate() {
+            frame.events.push(event.clone());
+            let has_text = index + 1 < events.len() && is_typed_text(&events, index + 1);
+            let text = match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if !has_text => key_text(*key, *modifiers),
+                _ => None,
+            };
+            frame.events.extend(text.map(egui::Event::Text));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    fn key(key: egui::Key, pressed: bool, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    // Typing `Z` on a German layout, as `Y` with a US layout, and copying.
+    fn recorded_frames() -> Vec<FrameEvents> {
+        let shift = egui::Modifiers::SHIFT;
+        vec![
+            FrameEvents::new(
+                NanoTimestamp::from(1),
+                vec![key(egui::Key::Z, true, shift), egui::Event::Text("Z".to_string())],
+            ),
+            FrameEvents::new(NanoTimestamp::from(2), vec![key(egui::Key::Z, false, shift)]),
+            FrameEvents::new(
+                NanoTimestamp::from(3),
+                vec![key(egui::Key::C, true, egui::Modifiers::COMMAND), egui::Event::Copy],
+            ),
+        ]
+    }
+
+    #[test]
+    fn text_authoritative_drops_typing_keys() {
+        let mut frames = recorded_frames();
+        KeyboardMode::TextAuthoritative.normalize_recording(&mut frames);
+
+        assert_eq!(frames[0].events, vec![egui::Event::Text("Z".to_string())]);
+        assert!(frames[1].events.is_empty());
+        assert_eq!(frames[2].events, recorded_frames()[2].events);
+    }
+
+    #[test]
+    fn key_authoritative_types_the_text_of_keys() {
+        let mut frames = recorded_frames();
+        frames[0].events[1] = egui::Event::Text("Y".to_string());
+        KeyboardMode::KeyAuthoritative.normalize_recording(&mut frames);
+        assert_eq!(frames[0].events.len(), 1);
+
+        KeyboardMode::KeyAuthoritative.normalize_replay(&mut frames);
+        assert_eq!(frames, recorded_frames());
+        KeyboardMode::TextAuthoritative.normalize_replay(&mut frames);
+        assert_eq!(frames[0].events, vec![egui::Event::Text("Z".to_string())]);
+    }
+}
@@ -1,5 +1,61 @@
+pub mod analytics;
 pub mod app;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod assertions;
+pub mod audio;
+pub mod background;
+pub mod breakpoint;
+pub mod browser;
+pub mod chain;
+pub mod chapters;
 pub mod clock;
+pub mod coalesce;
+pub mod codec;
+pub mod compare;
+pub mod crash;
+pub mod cursor;
+pub mod diff;
+#[cfg(not(feature = "replay"))]
+pub mod disabled;
+pub mod divergence;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod event;
+pub mod event_types;
+#[cfg(feature = "gif")]
+pub mod export;
+pub mod flight;
+pub mod fuzz;
+pub mod heatmap;
+pub mod import;
+pub mod indexed;
+pub mod keyboard;
+pub mod logs;
+pub mod metrics;
+#[cfg(feature = "replay")]
+pub mod minimize;
 pub mod modal;
+pub mod output;
+pub mod plugin;
+pub mod recent;
+pub mod remote;
 pub mod replay_events;
+pub mod replay_file;
+pub mod report;
+#[cfg(feature = "rng")]
+pub mod rng;
+pub mod script;
+#[cfg(feature = "accesskit")]
+pub mod semantic;
+pub mod settings;
+pub mod simplify;
+pub mod snapshot;
+pub mod spill;
+pub mod stats;
+pub mod stream;
 pub mod timestamp;
+pub mod validate;
+pub mod wrap;
+
+pub use wrap::wrap_app;
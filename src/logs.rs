@@ -0,0 +1,232 @@
+//! Capture of application log lines during recording and replay.
+//!
+//! [`LogCapture::install`] installs a `log` logger which forwards all records
+//! to the application's own logger, and captures them while recording or
+//! replaying. The captured lines are saved with the recording, ordered by
+//! time like the frames, and compared with the lines logged during replay to
+//! help correlate UI actions with the behavior of the application. Records of
+//! this crate are not captured.
+
+use std::sync::{Arc, Mutex};
+
+use bincode::{Decode, Encode};
+
+use crate::clock::{Clock, MonotonicClock};
+use crate::timestamp::NanoTimestamp;
+
+/// A log line logged by the application.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct LogRecord {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub time: NanoTimestamp,
+    /// Level as in `log::Level`, e.g. `WARN`.
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    pub fn level(&self) -> Option<log::Level> {
+        self.level.parse().ok()
+    }
+
+    // Whether both records logged the same, regardless of when.
+    fn same_line(&self, other: &LogRecord) -> bool {
+        self.level == other.level && self.target == other.target && self.message == other.message
+    }
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{} {}] {}", self.level, self.target, self.message)
+    }
+}
+
+/// A log line which was logged differently during replay than during
+/// recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogDifference {
+    /// Index of the line among the captured lines.
+    pub index: usize,
+    /// Line logged during recording, `None` if fewer lines were logged.
+    pub recorded: Option<LogRecord>,
+    /// Line logged during replay, `None` if fewer lines were logged.
+    pub replayed: Option<LogRecord>,
+}
+
+/// Compare the lines logged during recording and replay in order, ignoring
+/// the times they were logged at.
+pub fn diff_logs(recorded: &[LogRecord], replayed: &[LogRecord]) -> Vec<LogDifference> {
+    (0..recorded.len().max(replayed.len()))
+        .filter_map(|index| {
+            let (recorded, replayed) = (recorded.get(index), replayed.get(index));
+            match (recorded, replayed) {
+                (Some(recorded), Some(replayed)) if recorded.same_line(replayed) => None,
+                _ => Some(LogDifference {
+                    index,
+                    recorded: recorded.cloned(),
+                    replayed: replayed.cloned(),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct CaptureState {
+    is_capturing: bool,
+    records: Vec<LogRecord>,
+}
+
+/// Handle to the captured log lines, see the module docs.
+#[derive(Clone, Default)]
+pub struct LogCapture {
+    state: Arc<Mutex<CaptureState>>,
+}
+
+impl LogCapture {
+    /// Install the capturing logger, forwarding records to `inner`, e.g. an
+    /// `env_logger::Logger`. Fails if a logger was already installed.
+    pub fn install(
+        inner: Option<Box<dyn log::Log>>,
+        max_level: log::LevelFilter,
+    ) -> Result<LogCapture, log::SetLoggerError> {
+        let capture = LogCapture::default();
+        log::set_boxed_logger(Box::new(LogBridge {
+            inner,
+            capture: capture.clone(),
+        }))?;
+        log::set_max_level(max_level);
+        Ok(capture)
+    }
+
+    /// Start capturing, dropping the lines captured so far.
+    pub fn start(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.is_capturing = true;
+            state.records.clear();
+        }
+    }
+
+    /// Stop capturing, returning the captured lines.
+    pub fn stop(&self) -> Vec<LogRecord> {
+        match self.state.lock() {
+            Ok(mut state) => {
+                state.is_capturing = false;
+                std::mem::take(&mut state.records)
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.state.lock().is_ok_and(|state| state.is_capturing)
+    }
+
+    fn capture(&self, record: &log::Record) {
+        if record.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return;
+        }
+        if let Ok(mut state) = self.state.lock() {
+            if state.is_capturing {
+                state.records.push(LogRecord {
+                    time: MonotonicClock::shared().now(),
+                    level: record.level().as_str().to_string(),
+                    target: record.target().to_string(),
+                    message: record.args().to_string(),
+                });
+            }
+        }
+    }
+}
+
+// The installed logger.
+struct LogBridge {
+    inner: Option<Box<dyn log::Log>>,
+    capture: LogCapture,
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.capture.capture(record);
+        if let Some(inner) = &self.inner {
+            if inner.enabled(record.metadata()) {
+                inner.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(message: &str) -> LogRecord {
+        LogRecord {
+            time: NanoTimestamp::from(0),
+            level: "INFO".to_string(),
+            target: "app".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn captures_only_while_capturing() {
+        let capture = LogCapture::default();
+        let log = |message: &str| {
+            capture.capture(
+                &log::Record::builder()
+                    .level(log::Level::Warn)
+                    .target("app")
+                    .args(format_args!("{}", message))
+                    .build(),
+            )
+        };
+
+        log("before");
+        capture.start();
+        log("during");
+        let records = capture.stop();
+        log("after");
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "during");
+        assert_eq!(records[0].level(), Some(log::Level::Warn));
+        assert!(capture.stop().is_empty());
+    }
+
+    #[test]
+    fn diff_ignores_times() {
+        let mut replayed = vec![record("a"), record("c"), record("d")];
+        replayed[0].time = NanoTimestamp::from(5);
+
+        let differences = diff_logs(&[record("a"), record("b")], &replayed);
+
+        assert_eq!(
+            differences,
+            vec![
+                LogDifference {
+                    index: 1,
+                    recorded: Some(record("b")),
+                    replayed: Some(record("c")),
+                },
+                LogDifference {
+                    index: 2,
+                    recorded: None,
+                    replayed: Some(record("d")),
+                },
+            ]
+        );
+    }
+}
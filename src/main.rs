@@ -1,10 +1,11 @@
-use egui_replay::app::ReplayApp;
+use std::cell::Cell;
+use std::process::ExitCode;
+use std::rc::Rc;
 
-fn make_app(_cc: &eframe::CreationContext<'_>) -> ReplayApp {
-    ReplayApp::new()
-}
+use egui_replay::app::ReplayApp;
+use egui_replay::report::ReplayStatus;
 
-fn main() -> eframe::Result {
+fn main() -> ExitCode {
     env_logger::init();
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -12,9 +13,17 @@ fn main() -> eframe::Result {
             .with_min_inner_size([512.0, 512.0]),
         ..Default::default()
     };
-    eframe::run_native(
+    // Fail if the replay on startup failed, e.g. in CI.
+    let replay_status = Rc::new(Cell::new(None));
+    let app_replay_status = replay_status.clone();
+    let result = eframe::run_native(
         "Replay demo",
         native_options,
-        Box::new(|cc| Ok(Box::new(make_app(cc)))),
-    )
+        Box::new(move |cc| Ok(Box::new(ReplayApp::new(cc).with_replay_status(app_replay_status)))),
+    );
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        return ExitCode::FAILURE;
+    }
+    replay_status.get().map_or(ExitCode::SUCCESS, ReplayStatus::exit_code)
 }
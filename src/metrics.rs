@@ -0,0 +1,356 @@
+//! Size of recordings, estimated while recording and measured once saved.
+//!
+//! Long sessions can produce large files. With
+//! [`crate::replay_events::ReplayManager::with_recording_metrics`], the
+//! manager estimates the size of the recording in every registered format
+//! while recording, shows it in the HUD, and reports the saved size, the
+//! compression ratio and the events dropped by
+//! [`crate::simplify::PointerSimplification`] once saved, so that the
+//! recording settings can be tuned.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::codec::registered_codecs;
+use crate::replay_events::FrameEvents;
+use crate::replay_file::ReplayFile;
+use crate::timestamp::NanoDelta;
+
+// Frames are encoded in batches of this many frames, to keep recording cheap.
+const ESTIMATE_BATCH: usize = 64;
+
+/// Size of a recording, see the module docs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordingMetrics {
+    pub num_frames: usize,
+    pub num_events: usize,
+    /// Time between the first and the last frame.
+    pub duration: NanoDelta,
+    /// Approximate memory used by the frames, see
+    /// [`FrameEvents::estimated_size`].
+    pub memory_size: usize,
+    /// Pointer moves dropped by the pointer simplification.
+    pub events_dropped: usize,
+    /// Estimated size of the recording in each format, keyed by extension.
+    pub estimated_sizes: BTreeMap<String, u64>,
+    /// Size of the saved file, once it was saved.
+    pub saved_size: Option<u64>,
+}
+
+impl RecordingMetrics {
+    /// Bytes per minute of recording for a file of the given size, `None` for
+    /// recordings shorter than a millisecond.
+    pub fn bytes_per_minute(&self, size: u64) -> Option<f64> {
+        let millis = self.duration.as_millis();
+        if millis <= 0 {
+            return None;
+        }
+        Some(size as f64 * 60_000.0 / millis as f64)
+    }
+
+    /// Memory used by the frames per byte of the saved file.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match self.saved_size {
+            Some(saved_size) if saved_size > 0 => Some(self.memory_size as f64 / saved_size as f64),
+            _ => None,
+        }
+    }
+
+    /// Show the metrics as a two column grid.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("recording_metrics").num_columns(2).show(ui, |ui| {
+            ui.label("Frames");
+            ui.label(self.num_frames.to_string());
+            ui.end_row();
+            ui.label("Events");
+            ui.label(self.num_events.to_string());
+            ui.end_row();
+            ui.label("Events dropped");
+            ui.label(self.events_dropped.to_string());
+            ui.end_row();
+            ui.label("Memory");
+            ui.label(format_bytes(self.memory_size as u64));
+            ui.end_row();
+            for (extension, size) in self.estimated_sizes.iter() {
+                ui.label(format!("Estimated .{}", extension));
+                ui.label(self.format_size(*size));
+                ui.end_row();
+            }
+            if let Some(saved_size) = self.saved_size {
+                ui.label("Saved");
+                ui.label(self.format_size(saved_size));
+                ui.end_row();
+            }
+            if let Some(ratio) = self.compression_ratio() {
+                ui.label("Compression");
+                ui.label(format!("{:.1}×", ratio));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The size, followed by the size per minute of recording if known.
+    pub fn format_size(&self, size: u64) -> String {
+        match self.bytes_per_minute(size) {
+            Some(per_minute) => format!("{} ({}/min)", format_bytes(size), format_bytes(per_minute as u64)),
+            None => format_bytes(size),
+        }
+    }
+}
+
+/// Format a number of bytes with a binary unit, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Estimates the size of a recording in every registered format while it
+/// is recorded, by encoding its frames in batches.
+pub struct SizeEstimator {
+    // Size of an empty recording and of the encoded frames, per extension.
+    empty_sizes: BTreeMap<String, u64>,
+    frame_sizes: BTreeMap<String, u64>,
+    num_encoded: usize,
+    batch: Vec<FrameEvents>,
+}
+
+impl Default for SizeEstimator {
+    fn default() -> Self {
+        let empty_sizes = encoded_sizes(&ReplayFile::default());
+        let frame_sizes = empty_sizes.keys().map(|extension| (extension.clone(), 0)).collect();
+        Self {
+            empty_sizes,
+            frame_sizes,
+            num_encoded: 0,
+            batch: Vec::new(),
+        }
+    }
+}
+
+impl SizeEstimator {
+    pub fn push(&mut self, frame: &FrameEvents) {
+        self.batch.push(frame.clone());
+        if self.batch.len() >= ESTIMATE_BATCH {
+            self.encode_batch();
+        }
+    }
+
+    /// Estimated size per extension. Frames not encoded yet are estimated
+    /// from the average size of the encoded ones.
+    pub fn sizes(&self) -> BTreeMap<String, u64> {
+        self.empty_sizes
+            .iter()
+            .map(|(extension, empty_size)| {
+                let frames_size = self.frame_sizes.get(extension).copied().unwrap_or_default();
+                let pending_size = match self.num_encoded {
+                    0 => 0,
+                    num_encoded => frames_size * self.batch.len() as u64 / num_encoded as u64,
+                };
+                (extension.clone(), empty_size + frames_size + pending_size)
+            })
+            .collect()
+    }
+
+    /// Encode the remaining frames, for an exact estimate of the frames.
+    pub fn flush(&mut self) {
+        if !self.batch.is_empty() {
+            self.encode_batch();
+        }
+    }
+
+    fn encode_batch(&mut self) {
+        let batch = ReplayFile::new(std::mem::take(&mut self.batch));
+        self.num_encoded += batch.frames.len();
+        for (extension, size) in encoded_sizes(&batch) {
+            let empty_size = self.empty_sizes.get(&extension).copied().unwrap_or_default();
+            *self.frame_sizes.entry(extension).or_default() += size.saturating_sub(empty_size);
+        }
+    }
+}
+
+/// Metrics of the recording in progress and of the last saved one, kept by
+/// the manager, see
+/// [`crate::replay_events::ReplayManager::recording_metrics`].
+#[derive(Default)]
+pub struct MetricsTracker {
+    // Whether to estimate the size of recordings.
+    enabled: bool,
+    // Estimate of the current recording or chapter, and the events dropped
+    // from it.
+    estimator: Option<SizeEstimator>,
+    dropped_events: usize,
+    // Metrics of the last saved recording, with its path.
+    last_saved: Option<(PathBuf, RecordingMetrics)>,
+}
+
+impl MetricsTracker {
+    /// Estimate the size of recordings only if `enabled`, the other metrics
+    /// are always tracked.
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Start tracking a new recording or chapter.
+    pub fn start(&mut self) {
+        self.estimator = self.enabled.then(SizeEstimator::default);
+        self.dropped_events = 0;
+    }
+
+    pub fn push(&mut self, frame: &FrameEvents) {
+        if let Some(estimator) = &mut self.estimator {
+            estimator.push(frame);
+        }
+    }
+
+    /// Count events dropped from the current recording, e.g. by the pointer
+    /// simplification.
+    pub fn add_dropped_events(&mut self, num_events: usize) {
+        self.dropped_events += num_events;
+    }
+
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events
+    }
+
+    /// Estimated size of the current recording per extension, empty if sizes
+    /// are not estimated.
+    pub fn estimated_sizes(&self) -> BTreeMap<String, u64> {
+        self.estimator.as_ref().map(SizeEstimator::sizes).unwrap_or_default()
+    }
+
+    /// Keep the metrics of `file`, which is saved to `path`. The saved size
+    /// is measured by [`MetricsTracker::measure_saved`] once it is written.
+    pub fn saving(&mut self, file: &ReplayFile, path: impl Into<PathBuf>) {
+        let estimated_sizes = match &mut self.estimator {
+            Some(estimator) => {
+                estimator.flush();
+                estimator.sizes()
+            }
+            None => BTreeMap::new(),
+        };
+        let duration = match (file.frames.first(), file.frames.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => NanoDelta::zero(),
+        };
+        let metrics = RecordingMetrics {
+            num_frames: file.num_frames(),
+            num_events: file.num_events(),
+            duration,
+            memory_size: file.frames.iter().map(FrameEvents::estimated_size).sum(),
+            events_dropped: self.dropped_events,
+            estimated_sizes,
+            saved_size: None,
+        };
+        self.last_saved = Some((path.into(), metrics));
+    }
+
+    /// Measure and log the size of the file written to `path`, if it is the
+    /// last saved recording, to tune the recording settings.
+    pub fn measure_saved(&mut self, path: &Path) {
+        let Some((saved_path, metrics)) = &mut self.last_saved else {
+            return;
+        };
+        if saved_path.as_path() != path {
+            return;
+        }
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return;
+        };
+        metrics.saved_size = Some(metadata.len());
+        log::info!(
+            "Saved {} to {}, {} events dropped, compression ratio {:.1}",
+            metrics.format_size(metadata.len()),
+            path.display(),
+            metrics.events_dropped,
+            metrics.compression_ratio().unwrap_or_default()
+        );
+    }
+
+    /// Metrics of the last saved recording, with its path.
+    pub fn last_saved(&self) -> Option<&(PathBuf, RecordingMetrics)> {
+        self.last_saved.as_ref()
+    }
+}
+
+// Size of the file encoded by every registered codec, keyed by extension.
+fn encoded_sizes(file: &ReplayFile) -> BTreeMap<String, u64> {
+    let codecs = registered_codecs();
+    let mut sizes = BTreeMap::new();
+    for extension in codecs.extensions() {
+        let Ok(codec) = codecs.codec_for_path(format!("recording.{}", extension).as_ref()) else {
+            continue;
+        };
+        let mut counter = ByteCounter(0);
+        if codec.encode(file, &mut counter).is_ok() {
+            sizes.insert(extension.to_string(), counter.0);
+        }
+    }
+    sizes
+}
+
+// Counts the written bytes instead of storing them.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn estimates_match_the_encoded_sizes() {
+        let frames: Vec<_> = (0..100)
+            .map(|i| FrameEvents::new(NanoTimestamp::from(i), vec![egui::Event::Text(i.to_string())]))
+            .collect();
+        let mut estimator = SizeEstimator::default();
+        for frame in frames.iter() {
+            estimator.push(frame);
+        }
+        estimator.flush();
+
+        // Only the separators between the batches differ, e.g. in JSON.
+        let sizes = encoded_sizes(&ReplayFile::new(frames));
+        for (extension, estimate) in estimator.sizes() {
+            let size = sizes[&extension];
+            assert!(estimate.abs_diff(size) * 100 <= size, "{}", extension);
+        }
+    }
+
+    #[test]
+    fn sizes_per_minute() {
+        let metrics = RecordingMetrics {
+            duration: NanoDelta::from(30_000_000_000),
+            memory_size: 4096,
+            saved_size: Some(1024),
+            ..Default::default()
+        };
+
+        assert_eq!(metrics.bytes_per_minute(1024), Some(2048.0));
+        assert_eq!(metrics.compression_ratio(), Some(4.0));
+        assert_eq!(metrics.format_size(1024), "1.0 KiB (2.0 KiB/min)");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+    }
+}
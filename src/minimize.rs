@@ -0,0 +1,232 @@
+//! Minimization of recordings which make the app fail.
+//!
+//! A [`Minimizer`] replays recordings headless, i.e. by running the UI of the
+//! app in an `egui::Context` without a window. Given a recording which makes
+//! the app panic or fail an assertion, it removes frames by delta debugging
+//! and keeps the smallest recording which still fails the same way.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use egui::Context;
+
+use crate::replay_events::ReplayManager;
+use crate::replay_file::ReplayFile;
+use crate::report::ReplayReport;
+use crate::timestamp::{NanoTimestamp, NANOS_PER_SECOND};
+
+/// Time between headless frames, as if running at 60 frames per second.
+const HEADLESS_FRAME_NANOS: i64 = NANOS_PER_SECOND / 60;
+/// Frames run after the last replayed frame, until the report is emitted.
+const HEADLESS_EXTRA_FRAMES: usize = 3;
+
+/// Why a headless replay failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayFailure {
+    /// The app panicked with the given message.
+    Panic(String),
+    /// The replay ran to its end, but did not succeed, e.g. an assertion
+    /// failed.
+    Failed(Box<ReplayReport>),
+}
+
+impl ReplayFailure {
+    /// Whether both failures have the same cause: panics with the same
+    /// message, or unsuccessful replays.
+    pub fn is_same_failure(&self, other: &ReplayFailure) -> bool {
+        match (self, other) {
+            (ReplayFailure::Panic(message), ReplayFailure::Panic(other_message)) => message == other_message,
+            (ReplayFailure::Failed(_), ReplayFailure::Failed(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+// All indices of the chunks, except those of the skipped chunk.
+fn without_chunk(chunks: &[Vec<usize>], skipped: usize) -> Vec<usize> {
+    chunks
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != skipped)
+        .flat_map(|(_, chunk)| chunk.iter().copied())
+        .collect()
+}
+
+/// Replays and minimizes recordings headless, see the module docs.
+pub struct Minimizer<F: FnMut(&Context)> {
+    app: F,
+    screen_rect: egui::Rect,
+}
+
+impl<F: FnMut(&Context)> Minimizer<F> {
+    /// `app` builds the UI of one frame, like `eframe::App::update`.
+    pub fn new(app: F) -> Self {
+        Self {
+            app,
+            screen_rect: egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1280.0, 720.0)),
+        }
+    }
+
+    /// Size of the headless screen, which should match the size of the window
+    /// the recording was made in.
+    pub fn with_screen_size(mut self, size: egui::Vec2) -> Self {
+        self.screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, size);
+        self
+    }
+
+    /// Replay the recording headless in a fresh context, returning the report
+    /// of the replay if it succeeded.
+    pub fn replay(&mut self, replay_file: &ReplayFile) -> Result<ReplayReport, ReplayFailure> {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_overlay(false);
+        manager.start_replay_file(replay_file.clone());
+        let app = &mut self.app;
+        let screen_rect = self.screen_rect;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            for i in 0..replay_file.num_frames() + HEADLESS_EXTRA_FRAMES {
+                let now = NanoTimestamp::from_nanos(i as i64 * HEADLESS_FRAME_NANOS);
+                let mut raw_input = egui::RawInput {
+                    screen_rect: Some(screen_rect),
+                    time: Some(now.as_nanos() as f64 / NANOS_PER_SECOND as f64),
+                    focused: true,
+                    ..Default::default()
+                };
+                manager.on_raw_input_update(now, &ctx, &mut raw_input);
+                let _ = ctx.run(raw_input, |ctx| {
+                    app(ctx);
+                    manager.on_frame_end(ctx);
+                });
+                if let Some(report) = manager.last_replay_report() {
+                    return report.clone();
+                }
+            }
+            unreachable!("The replay report is emitted after the last frame")
+        }));
+        match result {
+            Ok(report) if report.is_success() => Ok(report),
+            Ok(report) => Err(ReplayFailure::Failed(Box::new(report))),
+            Err(payload) => Err(ReplayFailure::Panic(panic_message(payload.as_ref()))),
+        }
+    }
+
+    /// Find a smallest subset of the frames of the recording which still
+    /// makes the app fail the same way. Returns `None` if the recording does
+    /// not make the app fail. Markers and assertions are kept.
+    pub fn minimize(&mut self, replay_file: &ReplayFile) -> Option<ReplayFile> {
+        let failure = self.replay(replay_file).err()?;
+        log::info!(
+            "Minimizing {} frames failing with {:?}",
+            replay_file.num_frames(),
+            failure
+        );
+        let with_frames = |indices: &[usize]| ReplayFile {
+            frames: indices.iter().map(|&i| replay_file.frames[i].clone()).collect(),
+            ..replay_file.clone()
+        };
+
+        // Delta debugging: try to keep only one of `n` chunks, then to remove
+        // one of them, and refine the chunks when neither fails.
+        let mut indices: Vec<usize> = (0..replay_file.num_frames()).collect();
+        let mut n = 2;
+        while indices.len() >= 2 {
+            let chunk_size = (indices.len() + n - 1) / n;
+            let chunks: Vec<Vec<usize>> = indices.chunks(chunk_size).map(<[usize]>::to_vec).collect();
+            let num_chunks = chunks.len();
+            let complements = (0..num_chunks).map(|skipped| without_chunk(&chunks, skipped));
+            let mut candidates = chunks.iter().cloned().chain(complements);
+            let reduced = candidates.position(|candidate| {
+                !candidate.is_empty()
+                    && self
+                        .replay(&with_frames(&candidate))
+                        .is_err_and(|candidate_failure| candidate_failure.is_same_failure(&failure))
+            });
+            match reduced {
+                Some(position) => {
+                    let (is_chunk, chunk) = (position < num_chunks, position % num_chunks);
+                    indices = if is_chunk {
+                        chunks[chunk].clone()
+                    } else {
+                        without_chunk(&chunks, chunk)
+                    };
+                    log::info!("Reduced the failing recording to {} frames", indices.len());
+                    n = if is_chunk { 2 } else { (n - 1).max(2) };
+                }
+                None if n >= indices.len() => break,
+                None => n = (n * 2).min(indices.len()),
+            }
+        }
+        Some(with_frames(&indices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+
+    fn text_frames(texts: &[&str]) -> ReplayFile {
+        ReplayFile::new(
+            texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| {
+                    FrameEvents::new(NanoTimestamp::from(i as i64), vec![egui::Event::Text(text.to_string())])
+                })
+                .collect(),
+        )
+    }
+
+    fn typed_texts(replay_file: &ReplayFile) -> Vec<egui::Event> {
+        replay_file
+            .frames
+            .iter()
+            .flat_map(|frame| frame.events.clone())
+            .collect()
+    }
+
+    #[test]
+    fn minimizes_to_the_failing_frames() {
+        // The typed text is kept in the context, which is fresh for every
+        // replay.
+        let mut minimizer = Minimizer::new(|ctx: &Context| {
+            let events = ctx.input(|input| input.events.clone());
+            let typed = ctx.data_mut(|data| {
+                let typed = data.get_temp_mut_or_default::<String>(egui::Id::NULL);
+                for event in events {
+                    if let egui::Event::Text(text) = event {
+                        typed.push_str(&text);
+                    }
+                }
+                typed.clone()
+            });
+            if typed.contains('x') && typed.contains('y') {
+                panic!("typed x and y");
+            }
+        });
+        let replay_file = text_frames(&["a", "x", "b", "c", "d", "y", "e", "f"]);
+
+        let minimized = minimizer.minimize(&replay_file).unwrap();
+
+        assert_eq!(
+            typed_texts(&minimized),
+            vec![egui::Event::Text("x".to_string()), egui::Event::Text("y".to_string())]
+        );
+    }
+
+    #[test]
+    fn successful_replay_is_not_minimized() {
+        let mut minimizer = Minimizer::new(|_: &Context| {});
+
+        assert!(minimizer.replay(&text_frames(&["a", "b"])).is_ok());
+        assert_eq!(minimizer.minimize(&text_frames(&["a", "b"])), None);
+    }
+}
@@ -655,18 +655,11 @@ impl Modal {
                 if let Some(title) = modal_data.title {
                     self.title(ui, title)
                 }
-                self.frame(ui, |ui| {
-                    if modal_data.body.is_none() {
-                        if let Some(icon) = modal_data.icon {
-                            self.icon(ui, icon)
-                        }
-                    } else if modal_data.icon.is_none() {
-                        if let Some(body) = modal_data.body {
-                            self.body(ui, body)
-                        }
-                    } else if modal_data.icon.is_some() && modal_data.icon.is_some() {
-                        self.body_and_icon(ui, modal_data.body.unwrap(), modal_data.icon.unwrap())
-                    }
+                self.frame(ui, |ui| match (modal_data.body, modal_data.icon) {
+                    (None, Some(icon)) => self.icon(ui, icon),
+                    (Some(body), None) => self.body(ui, body),
+                    (Some(body), Some(icon)) => self.body_and_icon(ui, body, icon),
+                    (None, None) => {}
                 });
                 self.buttons(ui, |ui| {
                     ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
@@ -0,0 +1,266 @@
+//! Where recordings are saved, and how they are named.
+//!
+//! File names are built from a template with the placeholders:
+//! - `{timestamp}`: time the recording was saved, safe for file names on all
+//!   platforms, e.g. `2024-05-01T12-30-45.000000000Z`.
+//! - `{app}`: name of the application, the executable name by default.
+//! - `{git_sha}`: git commit of the application, if given.
+//!
+//! The extension is added to the file name, depending on the format.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use crate::codec::registered_codecs;
+use crate::replay_file::PARTIAL_EXTENSION;
+use crate::timestamp::NanoTimestamp;
+
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "egui_replay_{timestamp}";
+
+// Characters which are invalid in file names on some platform.
+const INVALID_FILE_NAME_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if INVALID_FILE_NAME_CHARS.contains(&c) || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn executable_name() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// Output directory and file name template of recordings, see the module
+/// docs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordingOutput {
+    dir: PathBuf,
+    template: String,
+    app_name: Option<String>,
+    git_sha: Option<String>,
+}
+
+impl Default for RecordingOutput {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("."),
+            template: DEFAULT_FILE_NAME_TEMPLATE.to_string(),
+            app_name: None,
+            git_sha: None,
+        }
+    }
+}
+
+impl RecordingOutput {
+    /// Save recordings into the directory, which is created if needed.
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// File name template, without the extension.
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Git commit of the application, e.g. `env!("GIT_SHA")` set by the
+    /// application's build script.
+    pub fn with_git_sha(mut self, git_sha: impl Into<String>) -> Self {
+        self.git_sha = Some(git_sha.into());
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The literal start of the file names, before the first placeholder.
+    /// Used to tell recordings apart from other files in the directory.
+    pub fn file_name_prefix(&self) -> &str {
+        let end = self.template.find('{').unwrap_or(self.template.len());
+        &self.template[..end]
+    }
+
+    /// Path of a recording saved at `now` with the given extension.
+    pub fn file_path(&self, now: NanoTimestamp, extension: &str) -> PathBuf {
+        let app_name = self
+            .app_name
+            .clone()
+            .or_else(executable_name)
+            .unwrap_or_else(|| "app".to_string());
+        let file_name = self
+            .template
+            .replace("{timestamp}", &now.as_file_name())
+            .replace("{app}", &sanitize(&app_name))
+            .replace("{git_sha}", &sanitize(self.git_sha.as_deref().unwrap_or("unknown")));
+        self.dir.join(format!("{}.{}", file_name, extension))
+    }
+
+    /// Create the output directory if it does not exist.
+    pub fn create_dir(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)
+    }
+
+    /// All recordings in the output directory, i.e. files starting with the
+    /// file name prefix, sorted by name. Recordings still being written are
+    /// skipped.
+    pub fn recordings(&self) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let prefix = self.file_name_prefix();
+        let mut recordings: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .map_or(true, |extension| extension != PARTIAL_EXTENSION)
+                    && path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(prefix))
+            })
+            .collect();
+        recordings.sort();
+        recordings
+    }
+
+    /// The most recent of the recordings in the output directory, see
+    /// [`sort_newest_first`].
+    pub fn latest_recording(&self) -> Option<PathBuf> {
+        let mut recordings = self.recordings();
+        sort_newest_first(&mut recordings);
+        recordings.into_iter().next()
+    }
+}
+
+// Length of the timestamps of `NanoTimestamp::as_file_name`.
+const FILE_NAME_TIMESTAMP_LEN: usize = 30;
+
+/// The most recent recording in `dir`, i.e. of the files with the extension
+/// of a registered codec, see [`sort_newest_first`].
+pub fn latest_recording_in(dir: impl AsRef<Path>) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let codecs = registered_codecs();
+    let mut recordings: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && codecs.codec_for_path(path).is_ok())
+        .collect();
+    sort_newest_first(&mut recordings);
+    recordings.into_iter().next()
+}
+
+/// Sort recordings newest first, by [`recording_time`]. Recordings of the
+/// same time, e.g. the chapters of a recording or a recording saved in
+/// several formats, are sorted by name, so the first chapter comes first.
+/// Recordings of unknown time come last.
+pub fn sort_newest_first(recordings: &mut [PathBuf]) {
+    recordings.sort_by_cached_key(|path| (Reverse(recording_time(path)), path.clone()));
+}
+
+/// When a recording was saved: the timestamp in its file name, see the
+/// `{timestamp}` placeholder, or else the time the file was modified.
+pub fn recording_time(path: &Path) -> Option<NanoTimestamp> {
+    let name = path.file_name()?.to_str()?;
+    let embedded = (0..name.len())
+        .filter(|start| name.as_bytes().get(start + 10) == Some(&b'T'))
+        .find_map(|start| {
+            let timestamp = name.get(start..start + FILE_NAME_TIMESTAMP_LEN)?;
+            NanoTimestamp::from_file_name(timestamp).ok()
+        });
+    embedded.or_else(|| {
+        let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+        NanoTimestamp::try_from(modified).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_placeholders_are_replaced() {
+        let output = RecordingOutput::default()
+            .with_dir("recordings")
+            .with_template("{app}_{git_sha}_{timestamp}")
+            .with_app_name("my:app")
+            .with_git_sha("abc123");
+
+        let path = output.file_path(NanoTimestamp::from_secs_safe(0), "bin");
+
+        assert_eq!(
+            path,
+            Path::new("recordings").join("my-app_abc123_1970-01-01T00-00-00.000000000Z.bin")
+        );
+        assert_eq!(output.file_name_prefix(), "");
+    }
+
+    #[test]
+    fn partial_recordings_are_not_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        output.create_dir().unwrap();
+        let path = output.file_path(NanoTimestamp::from_secs_safe(0), "bin");
+        std::fs::write(&path, "").unwrap();
+        std::fs::write(crate::replay_file::partial_path(&path), "").unwrap();
+
+        let recordings = output.recordings();
+
+        assert_eq!(recordings, vec![path]);
+    }
+
+    #[test]
+    fn latest_recording_is_the_newest_by_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "egui_replay_2025-01-01T00-00-00.000000000Z.json",
+            "egui_replay_2025-01-01T00-00-00.000000000Z.bin",
+            "egui_replay_2024-12-31T00-00-00.000000000Z.bin",
+            "egui_replay_2026-01-01T00-00-00.000000000Z.bin.partial",
+            "zz_notes.txt",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let latest = latest_recording_in(dir.path());
+        let mut recordings = RecordingOutput::default().with_dir(dir.path()).recordings();
+        sort_newest_first(&mut recordings);
+
+        let newest = dir.path().join("egui_replay_2025-01-01T00-00-00.000000000Z.bin");
+        let oldest = dir.path().join("egui_replay_2024-12-31T00-00-00.000000000Z.bin");
+        assert_eq!(latest, Some(newest.clone()));
+        assert_eq!(recordings.len(), 3);
+        assert_eq!(recordings[0], newest);
+        assert_eq!(recordings[2], oldest);
+    }
+
+    #[test]
+    fn default_names_keep_the_prefix() {
+        let output = RecordingOutput::default();
+
+        let path = output.file_path(NanoTimestamp::from_secs_safe(0), "json");
+
+        assert_eq!(output.file_name_prefix(), "egui_replay_");
+        assert_eq!(
+            path,
+            Path::new(".").join("egui_replay_1970-01-01T00-00-00.000000000Z.json")
+        );
+    }
+}
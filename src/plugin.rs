@@ -0,0 +1,100 @@
+//! Integration through the pass callbacks of the egui context.
+//!
+//! Instead of calling [`ReplayManager::on_raw_input_update`] from
+//! `eframe::App::raw_input_hook` and the other hooks from the update, apps
+//! can hand the manager over with [`ReplayManager::install`]. The manager is
+//! then driven by [`egui::Context::on_begin_pass`] and
+//! [`egui::Context::on_end_pass`], and reached with [`with_installed`].
+//!
+//! The callbacks run after egui processed the input of the pass, so the
+//! manager sees and filters the events, but can't change the pointer state
+//! derived from them. Recording works for all events, while replayed pointer
+//! events reach [`egui::InputState::events`] without moving the pointer, so
+//! replays of clicks and drags still need the raw input hook.
+//!
+//! The manager isn't `Send`, it is kept for the UI thread which installed it.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use egui::Context;
+
+use crate::clock::{Clock, MonotonicClock};
+use crate::replay_events::ReplayManager;
+
+const DEBUG_NAME: &str = "egui_replay";
+
+thread_local! {
+    static INSTALLED: RefCell<Option<ReplayManager>> = const { RefCell::new(None) };
+}
+
+/// Drive `manager` from the pass callbacks of `ctx`, replacing the manager
+/// installed before, if any.
+pub fn install(ctx: &Context, manager: ReplayManager) {
+    INSTALLED.with(|installed| *installed.borrow_mut() = Some(manager));
+
+    let registered_id = egui::Id::new(DEBUG_NAME);
+    if ctx.data(|data| data.get_temp::<bool>(registered_id)).unwrap_or(false) {
+        return;
+    }
+    ctx.data_mut(|data| data.insert_temp(registered_id, true));
+    ctx.on_begin_pass(DEBUG_NAME, Arc::new(begin_pass));
+    ctx.on_end_pass(DEBUG_NAME, Arc::new(end_pass));
+}
+
+/// Remove the installed manager, e.g. to save its state.
+pub fn uninstall() -> Option<ReplayManager> {
+    INSTALLED.with(|installed| installed.borrow_mut().take())
+}
+
+/// Call `f` with the installed manager. Returns `None` if no manager is
+/// installed on this thread, or when called from within a callback of the
+/// manager.
+pub fn with_installed<R>(f: impl FnOnce(&mut ReplayManager) -> R) -> Option<R> {
+    INSTALLED.with(|installed| {
+        let mut installed = installed.try_borrow_mut().ok()?;
+        installed.as_mut().map(f)
+    })
+}
+
+fn begin_pass(ctx: &Context) {
+    with_installed(|manager| {
+        let mut raw_input = ctx.input(|input| input.raw.clone());
+        manager.on_raw_input_update(MonotonicClock::shared().now(), ctx, &mut raw_input);
+        ctx.input_mut(|input| input.events = raw_input.events);
+        manager.on_frame_update(ctx);
+    });
+}
+
+fn end_pass(ctx: &Context) {
+    with_installed(|manager| {
+        manager.show_hud(ctx);
+        manager.on_frame_end(ctx);
+    });
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installed_manager_records_from_the_callbacks() {
+        let ctx = Context::default();
+        ReplayManager::new().install(&ctx);
+        let raw_input = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: egui::Key::F1,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            }],
+            ..Default::default()
+        };
+
+        let _ = ctx.run(raw_input, |_| {});
+
+        assert_eq!(with_installed(|manager| manager.is_recording()), Some(true));
+        assert!(uninstall().is_some());
+    }
+}
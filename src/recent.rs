@@ -0,0 +1,105 @@
+//! Recently replayed recordings, persisted across sessions in the eframe
+//! storage.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Key of the recent recordings in the eframe storage.
+pub const RECENT_RECORDINGS_KEY: &str = "egui_replay_recent_recordings";
+pub const DEFAULT_MAX_RECENT_RECORDINGS: usize = 10;
+
+/// The last replayed recordings, most recent first.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecentRecordings {
+    paths: VecDeque<String>,
+    max_len: usize,
+}
+
+impl Default for RecentRecordings {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RECENT_RECORDINGS)
+    }
+}
+
+impl RecentRecordings {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            paths: VecDeque::new(),
+            max_len,
+        }
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.paths.iter().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Keep at most `max_len` recordings, dropping the oldest ones.
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+        self.paths.truncate(max_len);
+    }
+
+    /// Move the recording to the front, adding it if it's not in the list.
+    pub fn push(&mut self, path: &str) {
+        self.paths.retain(|recent| recent != path);
+        self.paths.push_front(path.to_string());
+        self.paths.truncate(self.max_len);
+    }
+
+    /// Drop recordings which no longer exist.
+    pub fn retain_existing(&mut self) {
+        self.paths.retain(|path| Path::new(path).is_file());
+    }
+
+    /// Restore the recordings saved by [`RecentRecordings::save`], keeping
+    /// the current maximum length.
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(saved) = eframe::get_value::<RecentRecordings>(storage, RECENT_RECORDINGS_KEY) {
+            self.paths = saved.paths;
+            self.paths.truncate(self.max_len);
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, RECENT_RECORDINGS_KEY, self);
+    }
+
+    /// Show the recordings as buttons, returning the clicked one.
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<String> {
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Recent:");
+            for path in &self.paths {
+                let name = Path::new(path)
+                    .file_name()
+                    .map_or_else(|| path.clone(), |name| name.to_string_lossy().to_string());
+                if ui.small_button(name).on_hover_text(path).clicked() {
+                    clicked = Some(path.clone());
+                }
+            }
+        });
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_recent_first_without_duplicates() {
+        let mut recent = RecentRecordings::new(3);
+        for path in ["a", "b", "c", "a", "d"] {
+            recent.push(path);
+        }
+
+        assert_eq!(recent.paths().collect::<Vec<_>>(), vec!["d", "a", "c"]);
+
+        recent.set_max_len(1);
+        assert_eq!(recent.paths().collect::<Vec<_>>(), vec!["d"]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Remote control of recording and replay over TCP.
+//!
+//! Clients send one JSON command per line and receive one JSON response per
+//! line, e.g.
+//! ```text
+//! > {"command": "load", "path": "./ui_events_2024.bin"}
+//! < {"ok":true}
+//! > {"command": "play"}
+//! < {"ok":true}
+//! > {"command": "status"}
+//! < {"ok":true,"status":{"recording":false,"replaying":true,"frames":120,"replay_index":42}}
+//! ```
+//!
+//! Commands are executed by [`crate::replay_events::ReplayManager`] on the UI
+//! thread, during the next frame.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+/// How long a connection waits for the UI thread to execute a command.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    StartRecording,
+    StopRecording,
+    /// Load a recording to be replayed by `play`.
+    Load {
+        path: String,
+    },
+    Play,
+    Status,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RemoteStatus {
+    pub recording: bool,
+    pub replaying: bool,
+    /// Number of recorded or loaded frames.
+    pub frames: usize,
+    pub replay_index: usize,
+    /// Whether the last finished replay succeeded, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_replay_success: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RemoteResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// File written by `stop_recording`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RemoteStatus>,
+}
+
+impl RemoteResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn error(error: impl ToString) -> Self {
+        Self {
+            ok: false,
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// A command received from a client, with the channel to send the response
+/// to.
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    responder: Sender<RemoteResponse>,
+}
+
+impl RemoteRequest {
+    pub fn respond(self, response: RemoteResponse) {
+        // The client may have disconnected in the meantime.
+        let _ = self.responder.send(response);
+    }
+}
+
+/// TCP server accepting remote control commands, see the module docs.
+pub struct RemoteControl {
+    requests: Receiver<RemoteRequest>,
+    local_addr: SocketAddr,
+}
+
+impl RemoteControl {
+    /// Listen for clients on the given address. The context is repainted when
+    /// a command arrives, so that it is executed even if the app is idle.
+    pub fn bind(addr: impl ToSocketAddrs, ctx: egui::Context) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, requests) = channel();
+        std::thread::Builder::new()
+            .name("egui_replay_remote".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let sender = sender.clone();
+                            let ctx = ctx.clone();
+                            std::thread::spawn(move || {
+                                if let Err(err) = serve_client(stream, &sender, &ctx) {
+                                    log::warn!("Remote control connection failed: {}", err);
+                                }
+                            });
+                        }
+                        Err(err) => log::warn!("Failed to accept remote control connection: {}", err),
+                    }
+                }
+            })?;
+        log::info!("Remote control listening on {}", local_addr);
+        Ok(Self { requests, local_addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The next command waiting to be executed, if any.
+    pub fn try_recv(&self) -> Option<RemoteRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+fn serve_client(stream: TcpStream, requests: &Sender<RemoteRequest>, ctx: &egui::Context) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<RemoteCommand>(&line) {
+            Ok(command) => {
+                let (responder, response) = channel();
+                if requests.send(RemoteRequest { command, responder }).is_err() {
+                    // The app was closed.
+                    return Ok(());
+                }
+                ctx.request_repaint();
+                response
+                    .recv_timeout(RESPONSE_TIMEOUT)
+                    .unwrap_or_else(|_| RemoteResponse::error("The app did not respond"))
+            }
+            Err(err) => RemoteResponse::error(format!("Invalid command: {}", err)),
+        };
+        let mut json = serde_json::to_string(&response)?;
+        json.push('\n');
+        writer.write_all(json.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_from_json() {
+        let load: RemoteCommand = serde_json::from_str(r#"{"command": "load", "path": "a.bin"}"#).unwrap();
+        let play: RemoteCommand = serde_json::from_str(r#"{"command": "play"}"#).unwrap();
+
+        assert_eq!(
+            load,
+            RemoteCommand::Load {
+                path: "a.bin".to_string()
+            }
+        );
+        assert_eq!(play, RemoteCommand::Play);
+        assert!(serde_json::from_str::<RemoteCommand>(r#"{"command": "jump"}"#).is_err());
+    }
+
+    #[test]
+    fn commands_are_forwarded_over_tcp() {
+        let remote = RemoteControl::bind("127.0.0.1:0", egui::Context::default()).unwrap();
+        let mut client = TcpStream::connect(remote.local_addr()).unwrap();
+
+        client.write_all(b"{\"command\": \"status\"}\n").unwrap();
+        let request = loop {
+            if let Some(request) = remote.try_recv() {
+                break request;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(request.command, RemoteCommand::Status);
+        request.respond(RemoteResponse::ok());
+
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        assert_eq!(line, "{\"ok\":true}\n");
+    }
+}
@@ -1,104 +1,631 @@
+// Without the `replay` feature, the helpers of the manager are unused.
+#![cfg_attr(not(feature = "replay"), allow(dead_code, unused_imports))]
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use bincode::{Decode, Encode};
 use egui::{Color32, Context};
-use crate::timestamp::NanoTimestamp;
+use crate::timestamp::{NanoDelta, NanoTimestamp};
 
+use crate::assertions::{Assertion, AssertionKind, AssertionReport, AssertionRunner};
+use crate::audio::AudioTrack;
+use crate::background::{BackgroundIo, IoOutcome};
+use crate::breakpoint::Breakpoint;
+use crate::browser::{BrowserAction, RecordingBrowser};
+use crate::chain::{ChainState, RESTART_MARKER};
+use crate::chapters::{load_chapters, load_chapters_lenient, ChapterLength, ChapterRecorder};
+use crate::clock::{FrameScheduler, FrozenClock};
+use crate::coalesce::{coalesce_scroll_events, ScrollCoalescing};
+#[cfg(feature = "encryption")]
+use crate::codec::register_codec;
+use crate::compare::FrameComparison;
+use crate::crash::{crash_file_path, CrashAutosave};
+use crate::cursor::SyntheticCursor;
+use crate::divergence::{hash_pass, DivergenceReport};
+#[cfg(feature = "encryption")]
+use crate::encryption::{EncryptedCodec, RecordingSecret};
+use crate::event_types::EventTypes;
+#[cfg(feature = "gif")]
+use crate::export::GifExporter;
+use crate::flight::FlightRecorder;
+use crate::fuzz::FuzzSource;
+use crate::heatmap::InteractionHeatmap;
+use crate::indexed::IndexedReplayFile;
+use crate::keyboard::KeyboardMode;
+use crate::logs::{diff_logs, LogCapture};
+use crate::metrics::{format_bytes, MetricsTracker, RecordingMetrics};
 use crate::modal::{Modal, ModalStyle};
+use crate::output::{sort_newest_first, RecordingOutput};
+use crate::recent::RecentRecordings;
+use crate::remote::{RemoteCommand, RemoteControl, RemoteResponse, RemoteStatus};
+use crate::replay_file::{convert_replay, LoadDiagnostics, ReplayError, ReplayFile};
+use crate::report::{write_reports, ReplayReport, ReplayStatus};
+#[cfg(feature = "accesskit")]
+use crate::semantic::SemanticRecorder;
+use crate::settings::{Hotkeys, ReplaySettings};
+use crate::simplify::{simplify_pointer_path, PointerSimplification};
+use crate::snapshot::ReplayStateSnapshot;
+use crate::spill::SpillFile;
+use crate::stats::ReplayStats;
+use crate::stream::{StreamReceiver, StreamSender};
+use crate::validate::repair_key_releases;
 
 // A batch of events recorded/replayed in a single frame.
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
 pub struct FrameEvents {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
     #[bincode(with_serde)]
     pub time: NanoTimestamp,
+    // Stored as `ReplayEvent`s in recordings, see `crate::event`.
+    #[serde(with = "crate::event::serde_events")]
     #[bincode(with_serde)]
     pub events: Vec<egui::Event>,
+    // Hash of the shapes rendered by this frame, if frame hashing was enabled.
+    #[serde(default)]
+    pub frame_hash: Option<u64>,
+    // Viewport that received the events.
+    #[serde(default = "root_viewport_id")]
+    #[bincode(with_serde)]
+    pub viewport_id: egui::ViewportId,
+    // Files hovered over the window, only set when they changed since the
+    // previous recorded frame.
+    #[serde(default)]
+    pub hovered_files: Option<Vec<RecordedFile>>,
+    // Files dropped onto the window in this frame.
+    #[serde(default)]
+    pub dropped_files: Vec<RecordedFile>,
+    // State of the modifier keys during the frame.
+    #[serde(default)]
+    #[bincode(with_serde)]
+    pub modifiers: egui::Modifiers,
+    // Whether the window is focused, only set when it changed since the
+    // previous recorded frame.
+    #[serde(default)]
+    pub focused: Option<bool>,
+    // Payloads attached by the application while the frame was recorded, see
+    // `ReplayManager::attach_data`.
+    #[serde(default)]
+    pub user_data: BTreeMap<String, Vec<u8>>,
+    // Widget under the pointer when the frame was recorded, see
+    // `ReplayManager::with_widget_anchors`.
+    #[serde(default)]
+    #[bincode(with_serde)]
+    pub pointer_anchor: Option<PointerAnchor>,
+}
+
+/// Widget which the pointer events of a frame were recorded on. During
+/// replay, pointer positions within the recorded rectangle of the widget are
+/// moved to its current rectangle, so that the replay survives layout
+/// changes.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PointerAnchor {
+    pub widget_id: egui::Id,
+    /// Rectangle of the widget during recording.
+    pub rect: egui::Rect,
 }
 
-const UI_EVENTS_FILE_PREFIX: &str = "egui_replay";
+impl PointerAnchor {
+    /// Move the pointer positions within the recorded rectangle to the same
+    /// relative position within `rect`.
+    pub fn reanchor(&self, events: &mut [egui::Event], rect: egui::Rect) {
+        let transform = egui::emath::RectTransform::from_to(self.rect, rect);
+        for event in events.iter_mut() {
+            let pos = match event {
+                egui::Event::PointerMoved(pos) => pos,
+                egui::Event::PointerButton { pos, .. } => pos,
+                _ => continue,
+            };
+            if self.rect.contains(*pos) {
+                *pos = transform.transform_pos(*pos);
+            }
+        }
+    }
+}
 
-fn get_first_ui_events_file() -> Option<String> {
-    std::fs::read_dir("./")
-        .ok()?
-        .filter_map(Result::ok)
-        .filter_map(|entry| {
-            let path = entry.path();
-            let file_name = path.file_name()?.to_str()?;
+// A file hovered over or dropped onto the window. The contents of dropped
+// files can be embedded, so that the drop can be replayed on another machine.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct RecordedFile {
+    pub path: Option<String>,
+    pub name: String,
+    pub mime: String,
+    pub bytes: Option<Vec<u8>>,
+}
 
-            if path.is_file() && file_name.starts_with(UI_EVENTS_FILE_PREFIX) {
-                Some(path.to_string_lossy().to_string())
-            } else {
-                None
+impl RecordedFile {
+    pub(crate) fn from_hovered(file: &egui::HoveredFile) -> Self {
+        Self {
+            path: file.path.as_ref().map(|path| path.to_string_lossy().into_owned()),
+            mime: file.mime.clone(),
+            ..Default::default()
+        }
+    }
+
+    // Embed the contents if the file is at most `max_embedded_size` bytes.
+    pub(crate) fn from_dropped(file: &egui::DroppedFile, max_embedded_size: Option<usize>) -> Self {
+        let bytes = max_embedded_size.and_then(|max_size| match (&file.bytes, &file.path) {
+            (Some(bytes), _) => (bytes.len() <= max_size).then(|| bytes.to_vec()),
+            (None, Some(path)) => {
+                let size = std::fs::metadata(path).ok()?.len();
+                if size <= max_size as u64 {
+                    std::fs::read(path).ok()
+                } else {
+                    None
+                }
             }
-        })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .min()
+            (None, None) => None,
+        });
+        Self {
+            path: file.path.as_ref().map(|path| path.to_string_lossy().into_owned()),
+            name: file.name.clone(),
+            mime: file.mime.clone(),
+            bytes,
+        }
+    }
+
+    pub fn to_hovered(&self) -> egui::HoveredFile {
+        egui::HoveredFile {
+            path: self.path.as_ref().map(std::path::PathBuf::from),
+            mime: self.mime.clone(),
+        }
+    }
+
+    pub fn to_dropped(&self) -> egui::DroppedFile {
+        egui::DroppedFile {
+            path: self.path.as_ref().map(std::path::PathBuf::from),
+            name: self.name.clone(),
+            mime: self.mime.clone(),
+            bytes: self.bytes.as_ref().map(|bytes| Arc::from(bytes.as_slice())),
+            ..Default::default()
+        }
+    }
 }
 
-fn event_logfile(now: NanoTimestamp, use_bincode: bool) -> String {
-    format!(
-        "./{}_{}.{}",
-        UI_EVENTS_FILE_PREFIX,
-        now.as_rfc3339(),
-        if use_bincode { "bin" } else { "json" }
-    )
+pub(crate) fn root_viewport_id() -> egui::ViewportId {
+    egui::ViewportId::ROOT
 }
 
-fn load_replay(file_name: &str) -> Result<Vec<FrameEvents>, std::io::Error> {
-    let mut file = std::fs::File::open(file_name)?;
-    let events = if file_name.ends_with(".bin") {
-        bincode::decode_from_std_read(&mut file, bincode::config::standard()).map_err(std::io::Error::other)?
-    } else if file_name.ends_with(".json") {
-        serde_json::from_reader(file)?
-    } else {
-        return Err(std::io::Error::other("Unknown file extension"));
-    };
-    Ok(events)
+impl FrameEvents {
+    /// Events of the root viewport.
+    pub fn new(time: NanoTimestamp, events: Vec<egui::Event>) -> Self {
+        Self {
+            time,
+            events,
+            frame_hash: None,
+            viewport_id: egui::ViewportId::ROOT,
+            hovered_files: None,
+            dropped_files: Vec::new(),
+            modifiers: egui::Modifiers::NONE,
+            focused: None,
+            user_data: BTreeMap::new(),
+            pointer_anchor: None,
+        }
+    }
+
+    // Whether the frame carries input state other than events, or data of
+    // the application, which cannot be merged with other frames.
+    pub(crate) fn has_input_state(&self) -> bool {
+        self.hovered_files.is_some()
+            || !self.dropped_files.is_empty()
+            || self.focused.is_some()
+            || !self.user_data.is_empty()
+            || self.pointer_anchor.is_some()
+    }
+
+    pub fn with_viewport_id(mut self, viewport_id: egui::ViewportId) -> Self {
+        self.viewport_id = viewport_id;
+        self
+    }
+
+    pub fn with_modifiers(mut self, modifiers: egui::Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Approximate number of bytes the frame occupies in memory.
+    pub fn estimated_size(&self) -> usize {
+        let text_size: usize = self
+            .events
+            .iter()
+            .map(|event| match event {
+                egui::Event::Text(text) | egui::Event::Paste(text) => text.len(),
+                egui::Event::Ime(egui::ImeEvent::Preedit(text) | egui::ImeEvent::Commit(text)) => text.len(),
+                _ => 0,
+            })
+            .sum();
+        let files_size: usize = self
+            .hovered_files
+            .iter()
+            .flatten()
+            .chain(self.dropped_files.iter())
+            .map(|file| std::mem::size_of::<RecordedFile>() + file.bytes.as_ref().map_or(0, Vec::len))
+            .sum();
+        let user_data_size: usize = self.user_data.iter().map(|(key, data)| key.len() + data.len()).sum();
+        std::mem::size_of::<Self>()
+            + self.events.len() * std::mem::size_of::<egui::Event>()
+            + text_size
+            + files_size
+            + user_data_size
+    }
+}
+
+// A named point in time within a recording, used to navigate long recordings
+// and to replay them partially.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct Marker {
+    pub name: String,
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub time: NanoTimestamp,
+}
+
+/// A note typed during recording, on the annotation hotkey (F3) or with
+/// [`ReplayManager::add_annotation`], documenting what the user was doing at
+/// its time.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct Annotation {
+    pub text: String,
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub time: NanoTimestamp,
+}
+
+/// A pause of the recording, see [`ReplayManager::pause_recording`]. Nothing
+/// was recorded for `duration` from `time` on.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct Gap {
+    #[serde(with = "crate::timestamp::serde::rfc3339")]
+    #[bincode(with_serde)]
+    pub time: NanoTimestamp,
+    #[bincode(with_serde)]
+    pub duration: NanoDelta,
 }
 
-fn save_replay(file_name: &str, frame_events: &Vec<FrameEvents>) {
-    let mut file = std::fs::File::create(file_name).unwrap();
-    let num_frames: usize = frame_events.len();
-    let num_events: usize = frame_events.iter().map(|frame| frame.events.len()).sum();
-    if file_name.ends_with(".bin") {
-        bincode::encode_into_std_write(frame_events, &mut file, bincode::config::standard()).unwrap();
-    } else if file_name.ends_with(".json") {
-        serde_json::to_writer(file, &frame_events).unwrap();
+// Swap the extension of a recording for the other supported format.
+fn converted_file_name(file_name: &str) -> Option<String> {
+    if let Some(stem) = file_name.strip_suffix(".bin") {
+        Some(format!("{}.json", stem))
     } else {
-        // This should never happen.
-        panic!("Unknown file extension: {}", file_name);
+        file_name
+            .strip_suffix(".json")
+            .map(|stem| format!("{}.bin", stem))
+    }
+}
+
+/// How fast recorded frames are injected during replay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReplaySpeed {
+    /// Inject one recorded frame per rendered frame, repainting continuously.
+    #[default]
+    FastForward,
+    /// Inject each recorded frame once as much time passed since the start of
+    /// the replay as it did during recording.
+    RealTime,
+}
+
+/// Empty frames rendered between replayed frames, for UI logic which needs
+/// frames to elapse between events, e.g. tooltips, double-click detection or
+/// animations. See [`ReplayManager::with_idle_frames`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdleFrames {
+    /// Replay the recorded frames in consecutive rendered frames.
+    #[default]
+    Off,
+    /// Render the given number of empty frames after each replayed frame.
+    Fixed(usize),
+    /// Render one empty frame per `interval` recorded between two frames, at
+    /// most `max`, e.g. one per 16 ms to approximate the recorded frame rate.
+    FromTimestamps { interval: NanoDelta, max: usize },
+    /// Render empty frames until the given time passed since the previous
+    /// replayed frame.
+    Duration(NanoDelta),
+}
+
+// Empty frames rendered before the next replayed frame, see `IdleFrames`.
+#[derive(Default)]
+struct IdleSchedule {
+    idle_frames: IdleFrames,
+    // Empty frames left to render.
+    frames_left: usize,
+    // Time until which empty frames are rendered.
+    until: Option<NanoTimestamp>,
+}
+
+impl IdleSchedule {
+    // Schedule the empty frames rendered before the next replayed frame, which
+    // was recorded `recorded_gap` after the one just replayed.
+    fn schedule(&mut self, now: NanoTimestamp, recorded_gap: NanoDelta) {
+        match self.idle_frames {
+            IdleFrames::Off => {}
+            IdleFrames::Fixed(frames) => self.frames_left = frames,
+            IdleFrames::FromTimestamps { interval, max } => {
+                let frames = match interval.as_nanos() {
+                    0 => 0,
+                    interval => (recorded_gap.as_nanos() / interval).max(0) as usize,
+                };
+                self.frames_left = frames.min(max);
+            }
+            IdleFrames::Duration(duration) => self.until = Some(now + duration),
+        }
+    }
+
+    // Whether an empty frame is rendered instead of the next replayed frame.
+    fn is_idling(&mut self, now: NanoTimestamp) -> bool {
+        if self.frames_left > 0 {
+            self.frames_left -= 1;
+            return true;
+        }
+        match self.until {
+            Some(until) if now < until => true,
+            _ => {
+                self.until = None;
+                false
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.frames_left = 0;
+        self.until = None;
     }
-    log::info!("Saved {} frames, {} events, to {}", num_frames, num_events, file_name);
 }
 
+/// Bound of the flight recorder buffer, see
+/// [`ReplayManager::with_flight_recorder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlightRecorderLimit {
+    /// Keep the last given number of frames.
+    Frames(usize),
+    /// Keep the frames recorded within the given time before the last one.
+    Duration(NanoDelta),
+}
+
+// Fraction of a recording limit above which the HUD warns that the recording
+// is about to stop.
+const RECORDING_LIMIT_WARNING: f32 = 0.9;
+
+// How often running background saves and loads are polled.
+const IO_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+// Ids of the areas of the HUD, the progress badge and the modal. They differ
+// between recording and replay, so they are left out of the frame hashes.
+const HUD_ID: &str = "replay_hud";
+const PROGRESS_BADGE_ID: &str = "replay_progress_badge";
+const MODAL_ID: &str = "replay_modal";
+
+/// Environment variable with the path of a recording to replay on startup,
+/// see [`ReplayManager::with_auto_replay_from_env`].
+pub const AUTO_REPLAY_ENV_VAR: &str = "EGUI_REPLAY_FILE";
+/// Environment variable with the path of the report written by
+/// [`ReplayManager::with_auto_replay_from_env`].
+pub const REPORT_ENV_VAR: &str = "EGUI_REPLAY_REPORT";
+
+/// Callback of [`ReplayManager::on_replay_progress`].
+pub type ReplayProgressCallback = Box<dyn FnMut(usize, usize, NanoTimestamp)>;
+/// Callback of [`ReplayManager::on_replay_started`], given the recorded RNG
+/// seeds by name.
+pub type ReplayStartedCallback = Box<dyn FnMut(&BTreeMap<String, u64>)>;
+/// Callback of [`ReplayManager::on_replay_audio`].
+pub type ReplayAudioCallback = Box<dyn FnMut(&AudioTrack, NanoDelta)>;
+/// Rewrites replayed events, see [`ReplayManager::set_replay_transform`].
+pub type ReplayTransform = Box<dyn FnMut(&mut Vec<egui::Event>)>;
+
 // UI event recording. Useful for debugging to replay UI events.
 // While replaying it displays a modal window that blocks other user
 // interaction.
+#[cfg(feature = "replay")]
 pub struct ReplayManager {
     is_window_open: bool,
     is_replaying: bool,
     is_recording: bool,
 
-    // List of events being recorded/replayed.
-    frame_events: Vec<FrameEvents>,
+    // Recording being recorded/replayed.
+    recording: ReplayFile,
+    // Time and text of the annotation being typed after its hotkey was
+    // pressed.
+    // Events are not recorded while it is open.
+    annotation_draft: Option<(NanoTimestamp, String)>,
     // Index of the next frame to replay.
     replay_index: usize,
+    // Index of the frame at which the replay stops early, if any.
+    replay_end_index: Option<usize>,
+    replay_speed: ReplaySpeed,
+    // Name of the marker to stop the replay at, as entered in the modal.
+    replay_until_marker_name: String,
+    replay_assertions: AssertionRunner,
+    // Last text copied to the clipboard during replay.
+    last_copied_text: Option<String>,
     // Input file name for replay.
     replay_file: String,
+    // File currently loaded into frame_events, and the error if loading failed.
+    loaded_replay_file: Option<String>,
+    load_error: Option<String>,
+    failed_save: Option<FailedSave>,
+    // Whether to skip events which fail to load instead of failing the load.
+    lenient_loading: bool,
+    load_diagnostics: LoadDiagnostics,
+    background_io: BackgroundIo,
+    // Indexed recording whose frames are decoded as they are replayed, and
+    // the index of the frame decoded into `recording`, see
+    // `start_lazy_replay`.
+    replay_lazy: Option<IndexedReplayFile>,
+    replay_lazy_index: Option<usize>,
     // Whether to lookup the latest input file.
     should_lookup_replay: bool,
+    recording_browser: RecordingBrowser,
+    recent_recordings: RecentRecordings,
+    // Whether the settings tab of the modal is shown instead of the replay
+    // tab.
+    show_settings: bool,
+    hotkeys: Hotkeys,
 
     // Recording settings.
     record_use_bincode: bool,
-    record_apply_postprocessing: bool,
-    simplify_pointer_events: bool,
+    // Whether to save a copy of recordings in the other format, and the files
+    // the last recording was saved to.
+    record_dual_output: bool,
+    last_saved_files: Vec<String>,
+    // Whether recordings are saved encrypted, as `.enc` files.
+    #[cfg(feature = "encryption")]
+    record_encrypted: bool,
+    record_output: RecordingOutput,
+    record_preserve_frame_boundaries: bool,
+    pointer_simplification: PointerSimplification,
+    // Which of key and text events are kept, when recording and replaying.
+    keyboard_mode: KeyboardMode,
+    scroll_coalescing: ScrollCoalescing,
+    recorded_event_types: EventTypes,
+    record_widget_anchors: bool,
+    #[cfg(feature = "accesskit")]
+    semantic_recorder: Option<SemanticRecorder>,
+    // Widgets registered with `register_anchor` since the last input, with
+    // their rectangles.
+    anchor_widgets: Vec<(egui::Id, egui::Rect)>,
+    simplify_touch_events: bool,
 
     // Internal recording state.
-    record_is_pointer_moving: bool,
+    // Pointer moves since the last other event.
+    record_pointer_moves: usize,
+    // Touches for which a move event was already recorded.
+    record_moving_touches: BTreeSet<(egui::TouchDeviceId, egui::TouchId)>,
+    // Hovered files of the last recorded change.
+    record_hovered_files: Vec<RecordedFile>,
+    // Maximum size of dropped files whose contents are embedded.
+    embed_dropped_files_max_size: Option<usize>,
+    // Focus state of the last recorded change.
+    record_focused: Option<bool>,
+    // Memory used by the recorded frames above which they are spilled to disk.
+    record_memory_cap: Option<usize>,
+    record_memory_usage: usize,
+    // Limits after which the recording is stopped and saved.
+    record_max_duration: Option<NanoDelta>,
+    record_max_frames: Option<usize>,
+    record_chapter_length: Option<ChapterLength>,
+    // Chapters of the current recording, if it is saved in chapters.
+    record_chapters: Option<ChapterRecorder>,
+    // Path of the first part of a chained recording, which the whole
+    // recording is saved over.
+    record_chained_path: Option<String>,
+    // Frames of the current recording spilled to disk, preceding the ones in
+    // memory.
+    record_spill: Option<SpillFile>,
+    // Size of the current and of the last saved recording.
+    record_metrics: MetricsTracker,
+    // Mirror of the recorded frames saved when the app panics.
+    crash_autosave: Option<CrashAutosave>,
+    log_capture: Option<LogCapture>,
+    // Frames recorded continuously while not recording explicitly, if
+    // enabled.
+    flight_recorder: Option<FlightRecorder>,
+    // Hovered files injected into every replayed frame.
+    replay_hovered_files: Vec<egui::HoveredFile>,
+    // Focus state injected into every replayed frame.
+    replay_focused: bool,
+    // Whether to report the window as focused during replay, regardless of
+    // the recorded and real focus state.
+    replay_force_focused: bool,
+
+    // Frame hashing settings and state.
+    capture_frame_hashes: bool,
+    // Index of the recorded frame that the next frame output belongs to.
+    pending_hash_frame: Option<usize>,
+    // Index and recorded hash of the frame injected during this pass.
+    pending_expected_hash: Option<(usize, Option<u64>)>,
+    divergence_report: DivergenceReport,
+    comparison: Option<FrameComparison>,
+    heatmap_overlay: Option<InteractionHeatmap>,
+
+    // Times at which the first and the last frame of the replay were injected.
+    replay_started_at: Option<NanoTimestamp>,
+    // Paces real time replays, with the clock moved to the time of each pass.
+    replay_clock: FrozenClock,
+    replay_scheduler: Option<FrameScheduler<FrozenClock>>,
+    replay_last_frame_at: Option<NanoTimestamp>,
+    replay_idle: IdleSchedule,
+    // Report of the finished replay, completed and emitted on the next frame
+    // once the results of the last replayed frame are known.
+    pending_replay_report: Option<ReplayReport>,
+    last_replay_report: Option<ReplayReport>,
+    on_replay_finished: Option<Box<dyn FnMut(ReplayReport)>>,
+    on_replay_progress: Option<ReplayProgressCallback>,
+    on_replay_started: Option<ReplayStartedCallback>,
+    on_replay_audio: Option<ReplayAudioCallback>,
+    on_replay_restart: Option<Box<dyn FnMut(usize)>>,
+    // Rewrites the events of each replayed frame before they are injected.
+    replay_transform: Option<ReplayTransform>,
+    replay_breakpoints: Vec<Breakpoint>,
+    // Whether the replay is paused, by a breakpoint or `pause_replay`, and
+    // the number of frames to replay before pausing again.
+    replay_paused: bool,
+    replay_steps: usize,
+    // Whether the schedule starts over at the next frame, after a pause.
+    replay_reschedule: bool,
+    // Index of the frame and name of the breakpoint which paused the replay
+    // last.
+    replay_break: Option<(usize, String)>,
+    // Seeds registered by the application, saved with each recording.
+    rng_seeds: BTreeMap<String, u64>,
+    replays_started: u64,
+    // Whether to save or restore the application state in the next call of
+    // `sync_state`.
+    record_snapshot_pending: bool,
+    replay_restore_pending: bool,
+    app_identity: Option<String>,
+    state_fingerprint: Option<String>,
+    // Differences between the loaded recording and the current session,
+    // shown for confirmation before replaying from the modal.
+    replay_mismatches: Vec<String>,
+    // Recording to replay on the first frame.
+    auto_replay_file: Option<String>,
+    // Outcome of the replay on startup, if requested.
+    auto_replay_status: Option<ReplayStatus>,
+    // Machine-readable report of all replays, rewritten after each replay.
+    report_file: Option<String>,
+    finished_replays: Vec<ReplayReport>,
+    // Time of the last input pass, and of the start of the recording.
+    last_input_time: Option<NanoTimestamp>,
+    last_input_viewport_id: egui::ViewportId,
+    // Index of the frame recorded in the current pass, if any.
+    record_current_frame: Option<usize>,
+    // Data attached to the frame replayed in the current pass.
+    replay_user_data: BTreeMap<String, Vec<u8>>,
+    record_started_at: Option<NanoTimestamp>,
+    // Time at which the recording was paused, if it is.
+    record_paused_at: Option<NanoTimestamp>,
+    // Synthetic cursor painted at the injected pointer positions, if enabled.
+    synthetic_cursor: Option<SyntheticCursor>,
+    // GIF to export the replay to, and the exporter of the running replay.
+    #[cfg(feature = "gif")]
+    gif_export_path: Option<String>,
+    #[cfg(feature = "gif")]
+    gif_exporter: Option<GifExporter>,
+    // Recorded time of the frame whose screenshot was requested.
+    #[cfg(feature = "gif")]
+    pending_screenshot_time: Option<NanoTimestamp>,
+    remote_control: Option<RemoteControl>,
+    // Live streaming of the recorded frames to, or from another app.
+    stream_sender: Option<StreamSender>,
+    stream_receiver: Option<StreamReceiver>,
+    // Received frames waiting for their viewport to be updated.
+    stream_queue: VecDeque<FrameEvents>,
+    // Generates random frames to inject when not replaying or recording.
+    fuzz_source: Option<FuzzSource>,
+    // Whether to show the modal with its overlay during replay.
+    show_overlay: bool,
+    // Whether to show a progress badge when replaying without the modal.
+    show_progress_badge: bool,
+    // Number of frames to run after a completed replay before closing the
+    // app, if enabled.
+    exit_on_completion_settle_frames: Option<usize>,
+    // Frames left to run before closing the app.
+    replay_settle_frames_left: Option<usize>,
 }
 
-fn is_f1_key(event: &egui::Event) -> bool {
+fn is_key(event: &egui::Event, expected_key: egui::Key) -> bool {
     if let egui::Event::Key { key, .. } = event {
-        *key == egui::Key::F1
+        *key == expected_key
     } else {
         false
     }
@@ -116,25 +643,111 @@ fn is_pointer_moved(event: &egui::Event) -> bool {
     matches!(event, egui::Event::PointerMoved { .. })
 }
 
+/// Short name of the event variant, used for statistics and reporting.
+pub fn event_kind(event: &egui::Event) -> &'static str {
+    match event {
+        egui::Event::Copy => "Copy",
+        egui::Event::Cut => "Cut",
+        egui::Event::Paste(..) => "Paste",
+        egui::Event::Text(..) => "Text",
+        egui::Event::Key { .. } => "Key",
+        egui::Event::PointerMoved(..) => "PointerMoved",
+        egui::Event::MouseMoved(..) => "MouseMoved",
+        egui::Event::PointerButton { .. } => "PointerButton",
+        egui::Event::PointerGone => "PointerGone",
+        egui::Event::Zoom(..) => "Zoom",
+        egui::Event::Ime(..) => "Ime",
+        egui::Event::Touch { .. } => "Touch",
+        egui::Event::MouseWheel { .. } => "MouseWheel",
+        egui::Event::WindowFocused(..) => "WindowFocused",
+        _ => "Other",
+    }
+}
+
+fn is_touch(event: &egui::Event) -> bool {
+    matches!(event, egui::Event::Touch { .. })
+}
+
+fn is_ime(event: &egui::Event) -> bool {
+    matches!(event, egui::Event::Ime(..))
+}
+
+// The topmost of the widgets, in registration order, containing the position
+// of the first pointer event.
+fn find_pointer_anchor(events: &[egui::Event], widgets: &[(egui::Id, egui::Rect)]) -> Option<PointerAnchor> {
+    let pos = events.iter().find_map(|event| match event {
+        egui::Event::PointerMoved(pos) | egui::Event::PointerButton { pos, .. } => Some(*pos),
+        _ => None,
+    })?;
+    widgets
+        .iter()
+        .rev()
+        .find(|(_, rect)| rect.contains(pos))
+        .map(|(widget_id, rect)| PointerAnchor {
+            widget_id: *widget_id,
+            rect: *rect,
+        })
+}
+
+// Kind of events that can be merged together by the postprocessing.
+#[derive(Clone, Copy, PartialEq)]
+enum EventGroupKind {
+    PointerMoved,
+    // Touch and IME events are only grouped within the frame they were
+    // recorded in, given by its index. Gesture detection depends on the touch
+    // phases arriving in separate frames, and text edits depend on the IME
+    // composition (enable, preedit, commit) arriving in separate frames.
+    FrameLocal(usize),
+    Other,
+}
+
+// A recording which failed to save, kept so that saving can be retried with
+// another path.
+struct FailedSave {
+    file_name: String,
+    error: String,
+    recording: ReplayFile,
+    // Path to retry with, as entered in the modal.
+    retry_path: String,
+}
+
 // Merge all events into a single frame if possible. For merges, the first
 // timestamp is used. PointerMoved events are kept in separate frames, otherwise
-// replay cannot work.
+// replay cannot work. Touch and IME events are never merged across frames.
+// Events of different viewports or with different modifiers are never merged,
+// and frames with hovered or dropped files or focus changes are kept as they
+// are. The order of events is always preserved.
 fn apply_event_postprocessing(frames: Vec<FrameEvents>) -> Vec<FrameEvents> {
     let mut merged_frames = Vec::new();
-    let mut current_group: Option<(bool, FrameEvents)> = None;
+    let mut current_group: Option<((egui::ViewportId, egui::Modifiers, EventGroupKind), FrameEvents)> = None;
 
     // Add the first frame. This is a special pointer initial event.
     merged_frames.push(frames[0].clone());
 
     // Skip the first frame.
-    for frame in frames.into_iter().skip(1) {
+    for (frame_index, frame) in frames.into_iter().enumerate().skip(1) {
+        if frame.has_input_state() {
+            if let Some((_, finished_group)) = current_group.take() {
+                merged_frames.push(finished_group);
+            }
+            merged_frames.push(frame);
+            continue;
+        }
+
         // Process each event in each frame in order.
         for event in frame.events.into_iter() {
-            let event_is_pointer = is_pointer_moved(&event);
+            let group_kind = if is_pointer_moved(&event) {
+                EventGroupKind::PointerMoved
+            } else if is_touch(&event) || is_ime(&event) {
+                EventGroupKind::FrameLocal(frame_index)
+            } else {
+                EventGroupKind::Other
+            };
+            let group_key = (frame.viewport_id, frame.modifiers, group_kind);
             match current_group.as_mut() {
                 // If the current group exists and the current event type
                 // matches the group’s type, just accumulate the event.
-                Some((group_type, group)) if *group_type == event_is_pointer => {
+                Some((group_type, group)) if *group_type == group_key => {
                     group.events.push(event);
                 }
                 // Otherwise flush the current group and start a new one.
@@ -143,23 +756,21 @@ fn apply_event_postprocessing(frames: Vec<FrameEvents>) -> Vec<FrameEvents> {
                         merged_frames.push(finished_group);
                     }
                     current_group = Some((
-                        event_is_pointer,
-                        FrameEvents {
-                            // Use the current frame's timestamp for the new group.
-                            // This is the first event in the new group.
-                            time: frame.time,
-                            events: vec![event],
-                        },
+                        group_key,
+                        // Use the current frame's timestamp for the new group.
+                        // This is the first event in the new group.
+                        FrameEvents::new(frame.time, vec![event])
+                            .with_viewport_id(frame.viewport_id)
+                            .with_modifiers(frame.modifiers),
                     ));
                 }
                 // No active group, so start one with the current event.
                 None => {
                     current_group = Some((
-                        event_is_pointer,
-                        FrameEvents {
-                            time: frame.time,
-                            events: vec![event],
-                        },
+                        group_key,
+                        FrameEvents::new(frame.time, vec![event])
+                            .with_viewport_id(frame.viewport_id)
+                            .with_modifiers(frame.modifiers),
                     ));
                 }
             }
@@ -174,232 +785,3773 @@ fn apply_event_postprocessing(frames: Vec<FrameEvents>) -> Vec<FrameEvents> {
     merged_frames
 }
 
+#[cfg(not(feature = "replay"))]
+pub use crate::disabled::ReplayManager;
+
+#[cfg(feature = "replay")]
 impl Default for ReplayManager {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "replay")]
 impl ReplayManager {
     pub fn new() -> Self {
         Self {
             is_window_open: false,
             is_replaying: false,
             is_recording: false,
-            frame_events: Vec::new(),
+            recording: ReplayFile::default(),
+            annotation_draft: None,
             replay_index: 0,
+            replay_end_index: None,
+            replay_speed: ReplaySpeed::default(),
+            replay_until_marker_name: "".to_string(),
+            replay_assertions: AssertionRunner::default(),
+            last_copied_text: None,
             replay_file: "".to_string(),
+            loaded_replay_file: None,
+            load_error: None,
+            failed_save: None,
+            lenient_loading: false,
+            load_diagnostics: LoadDiagnostics::default(),
+            background_io: BackgroundIo::default(),
+            replay_lazy: None,
+            replay_lazy_index: None,
             should_lookup_replay: true,
+            recording_browser: RecordingBrowser::default(),
+            recent_recordings: RecentRecordings::default(),
+            show_settings: false,
+            hotkeys: Hotkeys::default(),
 
             // Recording settings.
             record_use_bincode: true,
-            record_apply_postprocessing: true,
-            simplify_pointer_events: true,
+            record_dual_output: false,
+            last_saved_files: Vec::new(),
+            #[cfg(feature = "encryption")]
+            record_encrypted: false,
+            record_output: RecordingOutput::default(),
+            record_preserve_frame_boundaries: false,
+            pointer_simplification: PointerSimplification::default(),
+            keyboard_mode: KeyboardMode::default(),
+            scroll_coalescing: ScrollCoalescing::default(),
+            recorded_event_types: EventTypes::default(),
+            record_widget_anchors: false,
+            #[cfg(feature = "accesskit")]
+            semantic_recorder: None,
+            anchor_widgets: Vec::new(),
+            simplify_touch_events: false,
 
             // Recording state.
-            record_is_pointer_moving: false,
+            record_pointer_moves: 0,
+            record_moving_touches: BTreeSet::new(),
+            record_hovered_files: Vec::new(),
+            embed_dropped_files_max_size: None,
+            record_focused: None,
+            record_memory_cap: None,
+            record_max_duration: None,
+            record_max_frames: None,
+            record_chapter_length: None,
+            record_chapters: None,
+            record_chained_path: None,
+            record_memory_usage: 0,
+            record_spill: None,
+            record_metrics: MetricsTracker::default(),
+            crash_autosave: None,
+            log_capture: None,
+            flight_recorder: None,
+            replay_hovered_files: Vec::new(),
+            replay_focused: true,
+            replay_force_focused: false,
+
+            capture_frame_hashes: false,
+            pending_hash_frame: None,
+            pending_expected_hash: None,
+            divergence_report: DivergenceReport::default(),
+            comparison: None,
+            heatmap_overlay: None,
+
+            replay_started_at: None,
+            replay_clock: FrozenClock::new(NanoTimestamp::zero()),
+            replay_scheduler: None,
+            replay_last_frame_at: None,
+            replay_idle: IdleSchedule::default(),
+            pending_replay_report: None,
+            last_replay_report: None,
+            on_replay_finished: None,
+            on_replay_progress: None,
+            on_replay_started: None,
+            on_replay_audio: None,
+            on_replay_restart: None,
+            replay_transform: None,
+            replay_breakpoints: Vec::new(),
+            replay_paused: false,
+            replay_steps: 0,
+            replay_reschedule: false,
+            replay_break: None,
+            rng_seeds: BTreeMap::new(),
+            replays_started: 0,
+            record_snapshot_pending: false,
+            replay_restore_pending: false,
+            app_identity: None,
+            state_fingerprint: None,
+            replay_mismatches: Vec::new(),
+            auto_replay_file: None,
+            auto_replay_status: None,
+            report_file: None,
+            finished_replays: Vec::new(),
+            last_input_time: None,
+            last_input_viewport_id: egui::ViewportId::ROOT,
+            record_current_frame: None,
+            replay_user_data: BTreeMap::new(),
+            record_started_at: None,
+            record_paused_at: None,
+            synthetic_cursor: None,
+            #[cfg(feature = "gif")]
+            gif_export_path: None,
+            #[cfg(feature = "gif")]
+            gif_exporter: None,
+            #[cfg(feature = "gif")]
+            pending_screenshot_time: None,
+            remote_control: None,
+            stream_sender: None,
+            stream_receiver: None,
+            stream_queue: VecDeque::new(),
+            fuzz_source: None,
+            show_overlay: true,
+            show_progress_badge: false,
+            exit_on_completion_settle_frames: None,
+            replay_settle_frames_left: None,
         }
     }
 
-    pub fn open_window(&mut self) {
-        self.is_window_open = true;
-        self.is_replaying = false;
-        self.is_recording = false;
-        self.frame_events.clear();
-        self.replay_index = 0;
-        self.should_lookup_replay = true;
+    /// Hash the rendered shapes of each frame while recording, and compare
+    /// them while replaying. The shapes are hashed by
+    /// [`ReplayManager::on_frame_end`].
+    pub fn with_frame_hashes(mut self, enabled: bool) -> Self {
+        self.capture_frame_hashes = enabled;
+        self
     }
 
-    pub fn close_window(&mut self) {
-        self.is_window_open = false;
-        self.is_replaying = false;
-        self.is_recording = false;
-        self.frame_events.clear();
-        self.replay_index = 0;
+    /// Compare each replayed frame with the recording, and show the
+    /// comparison in a window once the replay finishes or pauses, see
+    /// [`crate::compare`]. Enables frame hashes. With `screenshots`, the
+    /// frames right before and at the first difference are captured too.
+    pub fn with_comparison(mut self, screenshots: bool) -> Self {
+        self.capture_frame_hashes = true;
+        self.comparison = Some(FrameComparison::new(screenshots));
+        self
     }
 
-    pub fn is_replaying(&self) -> bool {
-        self.is_replaying
+    /// The comparison of the last replay with its recording, if enabled.
+    pub fn comparison(&self) -> Option<&FrameComparison> {
+        self.comparison.as_ref()
     }
 
-    pub fn is_recording(&self) -> bool {
-        self.is_recording
+    /// Paint the heatmap over the app, e.g. one aggregated from many
+    /// recordings to see where users interact with it. `None` removes it.
+    pub fn set_heatmap_overlay(&mut self, heatmap: Option<InteractionHeatmap>) {
+        self.heatmap_overlay = heatmap;
     }
 
-    pub fn num_recorded_frames(&self) -> usize {
-        self.frame_events.len()
+    /// Anchor recorded pointer events to the widget under the pointer, among
+    /// the widgets registered with [`ReplayManager::register_anchor`]. During
+    /// replay, the pointer positions are moved along with the widget, so that
+    /// replays survive layout changes, e.g. after a hot reload.
+    pub fn with_widget_anchors(mut self, enabled: bool) -> Self {
+        self.record_widget_anchors = enabled;
+        self
     }
 
-    pub fn num_recorded_events(&self) -> usize {
-        self.frame_events.iter().map(|frame| frame.events.len()).sum()
+    /// Record clicks on widgets and typing as accessibility actions, see
+    /// [`crate::semantic`]. The widgets are read from the accessibility tree
+    /// by [`ReplayManager::on_frame_end`].
+    #[cfg(feature = "accesskit")]
+    pub fn with_semantic_recording(mut self, enabled: bool) -> Self {
+        self.semantic_recorder = enabled.then(SemanticRecorder::default);
+        self
     }
 
-    pub fn on_frame_update(&mut self, ctx: &Context) {
-        if !self.is_window_open {
-            return;
+    /// Register a widget which pointer events can be anchored to, see
+    /// [`ReplayManager::with_widget_anchors`]. Call every frame for each
+    /// widget, e.g. with the responses of buttons and sliders. Widgets
+    /// registered later are considered on top of earlier ones.
+    pub fn register_anchor(&mut self, response: &egui::Response) {
+        if self.record_widget_anchors && self.is_recording {
+            self.anchor_widgets.push((response.id, response.rect));
         }
+    }
 
-        // Lookup for the latest input file if not set.
-        if self.should_lookup_replay {
-            self.replay_file = get_first_ui_events_file().unwrap_or(self.replay_file.clone());
-            self.should_lookup_replay = false;
-        }
+    /// Record a frame for every rendered frame, including frames without
+    /// input, and save the frames without merging them. Each recorded frame is
+    /// then replayed in its own rendered frame, as during recording, for apps
+    /// sensitive to how events are batched, e.g. drag thresholds. Recordings
+    /// grow with the number of rendered frames.
+    pub fn with_preserve_frame_boundaries(mut self, enabled: bool) -> Self {
+        self.record_preserve_frame_boundaries = enabled;
+        self
+    }
 
-        let modal = Modal::new(ctx, "replay_modal")
-            // Modal should not consume events when replaying.
-            // Otherwise it will block the input events from being processed.
-            .with_consume_events(!self.is_replaying)
-            .with_style(&ModalStyle {
-                overlay_color: Color32::from_rgba_premultiplied(0, 0, 0, 50),
-                ..Default::default()
-            });
+    /// How pointer moves are simplified while recording. By default, only
+    /// the start of each movement and the position of button events are
+    /// recorded.
+    pub fn with_pointer_simplification(mut self, simplification: PointerSimplification) -> Self {
+        self.pointer_simplification = simplification;
+        self
+    }
 
-        modal.show(|ui| {
-            modal.title(ui, "Replay UI events");
+    /// Whether key or text events are authoritative for typed text, so that
+    /// recordings replay the same on other keyboard layouts. Applied when
+    /// recordings are saved and again when they are replayed, also to
+    /// recordings made in another mode. Keeps both by default.
+    pub fn with_keyboard_mode(mut self, mode: KeyboardMode) -> Self {
+        self.keyboard_mode = mode;
+        self
+    }
 
-            modal.frame(ui, |ui| {
-                if self.is_replaying {
-                    ui.label(format!(
-                        "Frame {} / {}",
-                        self.replay_index + 1,
-                        self.num_recorded_frames()
-                    ));
-                    ui.spinner();
-                } else {
-                    ui.label("Select input file [latest file is pre-filled]:");
-                    ui.add(
-                        egui::TextEdit::singleline(&mut self.replay_file)
-                            .hint_text("No input file found")
-                            .interactive(true)
-                            .desired_width(ui.available_width()),
-                    );
-                }
-            });
+    /// How consecutive scroll and zoom events are merged when recordings are
+    /// saved. By default, the events within a frame are merged.
+    pub fn with_scroll_coalescing(mut self, coalescing: ScrollCoalescing) -> Self {
+        self.scroll_coalescing = coalescing;
+        self
+    }
 
-            modal.buttons(ui, |ui| {
-                if self.is_replaying {
-                    return;
-                }
+    /// Record only the given types of events, e.g. only keys and text for a
+    /// minimal recording of a text field problem. Everything is recorded by
+    /// default.
+    pub fn with_recorded_event_types(mut self, types: EventTypes) -> Self {
+        self.recorded_event_types = types;
+        self
+    }
 
-                if modal.button(ui, "Start replay").clicked() {
-                    let ui_events = load_replay(&self.replay_file);
-                    match ui_events {
-                        Ok(ui_events) => {
-                            let num_frames = ui_events.len();
-                            let num_events = ui_events.iter().map(|frame| frame.events.len()).sum::<usize>();
-                            log::info!(
-                                "Loaded {} frames, {} events, from {}",
-                                num_frames,
-                                num_events,
-                                &self.replay_file
-                            );
-                            self.is_replaying = true;
-                            self.frame_events = ui_events;
-                            self.replay_index = 0;
-                        }
-                        Err(err) => {
-                            log::error!("Failed to parse UI events: {}", err);
-                        }
-                    }
-                }
-                if modal.button(ui, "Close").clicked() {
-                    self.close_window();
-                }
-            });
-        });
+    /// Record only the first move event of each touch, like pointer moves are
+    /// simplified by default. The start and end of each touch are always recorded. This
+    /// shrinks recordings, but gestures like pinch-zoom replay less precisely.
+    pub fn with_simplify_touch_events(mut self, enabled: bool) -> Self {
+        self.simplify_touch_events = enabled;
+        self
+    }
 
-        modal.open();
+    /// Embed the contents of dropped files of at most `max_size` bytes into
+    /// the recording, so that file drops can be replayed on another machine.
+    pub fn with_embedded_dropped_files(mut self, max_size: usize) -> Self {
+        self.embed_dropped_files_max_size = Some(max_size);
+        self
     }
 
-    pub fn on_raw_input_update(&mut self, now: NanoTimestamp, _ctx: &Context, raw_input: &mut egui::RawInput) {
-        if self.is_replaying && self.replay_index < self.num_recorded_frames() {
-            // Replay the events for the current frame index.
-            log::info!(
-                "Replaying frame {} / {}",
-                self.replay_index + 1,
-                self.num_recorded_frames()
-            );
-            raw_input.events = std::mem::take(&mut self.frame_events[self.replay_index].events);
-            self.replay_index += 1;
-            if self.replay_index >= self.num_recorded_frames() {
-                self.close_window();
-            }
+    /// Spill the recorded frames to a temporary file once they use more than
+    /// `max_bytes` of memory. They are read back when the recording is saved.
+    pub fn with_memory_cap(mut self, max_bytes: usize) -> Self {
+        self.record_memory_cap = Some(max_bytes);
+        self
+    }
 
-            for event in raw_input.events.iter() {
-                log::debug!("Replay event: {:?}", event);
-            }
-            return;
-        }
+    /// Stop and save the recording once it is `max_duration` long, not
+    /// counting pauses, so that forgotten recordings don't grow unbounded.
+    pub fn with_max_recording_duration(mut self, max_duration: NanoDelta) -> Self {
+        self.record_max_duration = Some(max_duration);
+        self
+    }
 
-        let mut event_batch = Vec::new();
-        for (i, event) in raw_input.events.iter().enumerate() {
-            // Start / stop recording events on F1 key.
-            if is_f1_key(event) && is_key_pressed(event) {
-                self.is_recording = !self.is_recording;
-                if self.is_recording {
-                    log::info!("Starting UI event recording");
-                    self.frame_events.clear();
-                    self.frame_events.push(FrameEvents {
-                        time: now,
-                        events: vec![egui::Event::PointerMoved(egui::Pos2::new(0.0, 0.0))],
-                    });
-                } else {
-                    log::info!("Stopping UI event recording");
-                    let file_name = event_logfile(now, self.record_use_bincode);
-                    if self.record_apply_postprocessing {
-                        self.frame_events = apply_event_postprocessing(std::mem::take(&mut self.frame_events));
-                    }
-                    save_replay(&file_name, &self.frame_events);
-                }
-            }
+    /// Stop and save the recording once it has `max_frames` frames.
+    pub fn with_max_recording_frames(mut self, max_frames: usize) -> Self {
+        self.record_max_frames = Some(max_frames);
+        self
+    }
 
-            if self.is_recording {
-                if let egui::Event::PointerButton { pos, .. } = event {
-                    if self.simplify_pointer_events {
-                        // This is needed because the simplification in should_
-                        // record_event does not capture the last pointer moved event,
-                        // so the last recorded position can be off.
-                        log::debug!("Recording (fake) UI event: {:?} {:?}", i, event);
-                        event_batch.push(egui::Event::PointerMoved(*pos));
-                    }
-                }
+    /// Save long recordings in chapters of the given length, see
+    /// [`crate::chapters`]. Loading a chapter loads the chapters following it
+    /// as well, so that they are replayed in sequence.
+    pub fn with_chapters(mut self, length: ChapterLength) -> Self {
+        self.record_chapter_length = Some(length);
+        self
+    }
 
-                if self.should_record_event(event) {
-                    log::debug!("Recording UI event: {:?} {:?}", i, event);
-                    event_batch.push(event.clone());
-                }
-            }
+    /// Continuously record the UI events into a bounded buffer while not
+    /// recording explicitly. The buffer is saved with
+    /// [`ReplayManager::save_flight_recorder`] or by pressing its hotkey, F5
+    /// by default, e.g. right after observing a bug.
+    pub fn with_flight_recorder(mut self, limit: FlightRecorderLimit) -> Self {
+        self.flight_recorder = Some(FlightRecorder::new(limit));
+        self
+    }
+
+    /// Directory and file name template of saved recordings. By default they
+    /// are saved as `./egui_replay_{timestamp}.bin`.
+    pub fn with_recording_output(mut self, output: RecordingOutput) -> Self {
+        self.record_output = output;
+        self
+    }
+
+    /// Number of recently replayed recordings offered in the modal.
+    pub fn with_max_recent_recordings(mut self, max_len: usize) -> Self {
+        self.recent_recordings.set_max_len(max_len);
+        self
+    }
+
+    /// Drive the manager from the pass callbacks of `ctx`, for apps which
+    /// can't call the hooks themselves, see [`crate::plugin`].
+    pub fn install(self, ctx: &Context) {
+        crate::plugin::install(ctx, self);
+    }
+
+    /// Restore the recently replayed recordings, e.g. from
+    /// `eframe::CreationContext::storage`.
+    pub fn load_state(&mut self, storage: &dyn eframe::Storage) {
+        self.recent_recordings.load(storage);
+        if let Some(settings) = ReplaySettings::load(storage) {
+            self.apply_settings(settings);
         }
+    }
 
-        if !event_batch.is_empty() {
-            self.frame_events.push(FrameEvents {
-                time: now,
-                events: event_batch,
-            });
+    /// A manager with the settings and the recently replayed recordings
+    /// restored from the eframe storage, call from the app creator passed to
+    /// `eframe::run_native`. Builder methods called afterwards override the
+    /// restored settings.
+    pub fn from_storage(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut manager = Self::new();
+        if let Some(storage) = cc.storage {
+            manager.load_state(storage);
         }
+        manager
     }
 
-    fn should_record_event(&mut self, event: &egui::Event) -> bool {
-        if matches!(event, egui::Event::MouseMoved { .. }) {
-            return false;
+    /// Save the recently replayed recordings and the settings, call from
+    /// `eframe::App::save`.
+    pub fn save_state(&self, storage: &mut dyn eframe::Storage) {
+        self.recent_recordings.save(storage);
+        self.settings().save(storage);
+    }
+
+    /// The options editable in the settings tab of the modal.
+    pub fn settings(&self) -> ReplaySettings {
+        ReplaySettings {
+            use_bincode: self.record_use_bincode,
+            dual_output: self.record_dual_output,
+            preserve_frame_boundaries: self.record_preserve_frame_boundaries,
+            pointer_simplification: self.pointer_simplification,
+            keyboard_mode: self.keyboard_mode,
+            scroll_coalescing: self.scroll_coalescing,
+            event_types: self.recorded_event_types,
+            output_dir: self.record_output.dir().to_path_buf(),
+            hotkeys: self.hotkeys,
+            replay_speed: self.replay_speed,
         }
-        if is_f1_key(event) {
-            return false;
+    }
+
+    /// Apply the options of the settings tab, e.g. restored from storage.
+    pub fn apply_settings(&mut self, settings: ReplaySettings) {
+        self.record_use_bincode = settings.use_bincode;
+        self.record_dual_output = settings.dual_output;
+        self.record_preserve_frame_boundaries = settings.preserve_frame_boundaries;
+        self.pointer_simplification = settings.pointer_simplification;
+        self.keyboard_mode = settings.keyboard_mode;
+        self.scroll_coalescing = settings.scroll_coalescing;
+        self.recorded_event_types = settings.event_types;
+        if settings.output_dir != self.record_output.dir() {
+            self.record_output = self.record_output.clone().with_dir(settings.output_dir);
+            self.should_lookup_replay = true;
         }
-        if self.simplify_pointer_events {
-            // Record only pointer start and end events.
-            if is_pointer_moved(event) {
-                if self.record_is_pointer_moving {
-                    return false;
-                } else {
-                    self.record_is_pointer_moving = true;
-                    return true;
-                }
-            } else {
-                self.record_is_pointer_moving = false;
+        self.hotkeys = settings.hotkeys;
+        self.replay_speed = settings.replay_speed;
+    }
+
+    /// Start with the given settings instead of the defaults.
+    pub fn with_settings(mut self, settings: ReplaySettings) -> Self {
+        self.apply_settings(settings);
+        self
+    }
+
+    /// Save the frames recorded so far when the app panics while recording,
+    /// with a `_crash` suffix. The recorded frames are additionally kept in
+    /// memory for the panic hook, including those spilled to disk.
+    pub fn with_crash_autosave(mut self, enabled: bool) -> Self {
+        self.crash_autosave = enabled.then(|| {
+            let autosave = CrashAutosave::default();
+            autosave.install_panic_hook();
+            autosave
+        });
+        self
+    }
+
+    /// Save the log lines captured during recording with the recording, and
+    /// compare them with the lines logged during replay, see
+    /// [`crate::logs`].
+    pub fn with_log_capture(mut self, capture: LogCapture) -> Self {
+        self.log_capture = Some(capture);
+        self
+    }
+
+    /// Report the window as focused during replay, regardless of the recorded
+    /// and the real window state. Recorded focus loss events are dropped.
+    pub fn with_force_focused(mut self, enabled: bool) -> Self {
+        self.replay_force_focused = enabled;
+        self
+    }
+
+    /// Replay the given recording starting with the first frame. The app is
+    /// closed if the recording cannot be loaded, or if the replay diverges,
+    /// fails an assertion or is aborted. The host should then exit with the
+    /// code of [`ReplayManager::auto_replay_status`].
+    pub fn with_auto_replay(mut self, path: impl Into<String>) -> Self {
+        self.auto_replay_file = Some(path.into());
+        self.auto_replay_status = Some(ReplayStatus::Pending);
+        self
+    }
+
+    /// Outcome of the replay requested by [`ReplayManager::with_auto_replay`],
+    /// if any.
+    pub fn auto_replay_status(&self) -> Option<ReplayStatus> {
+        self.auto_replay_status
+    }
+
+    /// Like [`ReplayManager::with_auto_replay`], with the recording given by
+    /// the [`AUTO_REPLAY_ENV_VAR`] environment variable, and the report file
+    /// by the [`REPORT_ENV_VAR`] environment variable. Does nothing if the
+    /// variables are not set.
+    pub fn with_auto_replay_from_env(mut self) -> Self {
+        if let Ok(path) = std::env::var(REPORT_ENV_VAR) {
+            if !path.is_empty() {
+                self = self.with_report_file(path);
             }
         }
+        match std::env::var(AUTO_REPLAY_ENV_VAR) {
+            Ok(path) if !path.is_empty() => self.with_auto_replay(path),
+            _ => self,
+        }
+    }
 
-        true
+    /// Write the reports of all finished replays to the given file, as JUnit
+    /// XML for `.xml` files or as JSON for `.json` files, e.g. for CI
+    /// dashboards. The file is rewritten after each replay.
+    pub fn with_report_file(mut self, path: impl Into<String>) -> Self {
+        self.report_file = Some(path.into());
+        self
+    }
+
+    /// Skip events which fail to load, e.g. events recorded with a newer
+    /// version of egui, instead of failing to load the whole recording. The
+    /// skipped events are listed in [`ReplayManager::load_diagnostics`].
+    pub fn with_lenient_loading(mut self, enabled: bool) -> Self {
+        self.lenient_loading = enabled;
+        self
+    }
+
+    /// Save recordings, and load the recordings selected in the window, on a
+    /// background thread, so that large files don't freeze the UI. Saving
+    /// returns before the file is written, failures are reported by
+    /// [`ReplayManager::poll_io`]. Saves still running when the manager is
+    /// dropped are waited for.
+    pub fn with_background_io(mut self, enabled: bool) -> Self {
+        self.background_io = BackgroundIo::new(enabled);
+        self
+    }
+
+    /// Save recordings in both formats: a `.bin` file for replay, and a
+    /// `.json` copy for inspection and diffs in code review.
+    pub fn with_dual_output(mut self, enabled: bool) -> Self {
+        self.record_dual_output = enabled;
+        self
+    }
+
+    /// Save recordings encrypted with the key or passphrase, as `.enc`
+    /// files, see [`crate::encryption`]. Registers an [`EncryptedCodec`] to
+    /// also replay them, dual output doesn't save a plaintext copy.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(mut self, secret: RecordingSecret) -> Self {
+        register_codec(EncryptedCodec::new(secret));
+        self.record_encrypted = true;
+        self
+    }
+
+    /// Files the last recording was saved to, the recording and its copy
+    /// with [`ReplayManager::with_dual_output`].
+    pub fn last_saved_files(&self) -> &[String] {
+        &self.last_saved_files
+    }
+
+    /// Estimate the size of recordings in every registered format while
+    /// recording, and show it in the HUD, see [`crate::metrics`]. Recording
+    /// encodes the frames in batches to estimate their size.
+    pub fn with_recording_metrics(mut self, enabled: bool) -> Self {
+        self.record_metrics = MetricsTracker::new(enabled);
+        self
+    }
+
+    /// Size of the current recording, or of the last saved one when not
+    /// recording. Sizes are only estimated with
+    /// [`ReplayManager::with_recording_metrics`].
+    pub fn recording_metrics(&self) -> Option<RecordingMetrics> {
+        if !self.is_recording {
+            return self.record_metrics.last_saved().map(|(_, metrics)| metrics.clone());
+        }
+        Some(RecordingMetrics {
+            num_frames: self.num_recorded_frames(),
+            num_events: self.num_recorded_events(),
+            duration: self.recorded_duration(self.last_input_time.unwrap_or_default()),
+            memory_size: self.record_memory_usage,
+            events_dropped: self.record_metrics.dropped_events(),
+            estimated_sizes: self.record_metrics.estimated_sizes(),
+            saved_size: None,
+        })
+    }
+
+    /// Whether a background save or load is running, see
+    /// [`ReplayManager::with_background_io`].
+    pub fn is_io_pending(&self) -> bool {
+        self.background_io.is_pending()
+    }
+
+    /// Take the oldest finished background save or load. Tasks are collected
+    /// by [`ReplayManager::on_frame_update`].
+    pub fn poll_io(&mut self) -> Option<IoOutcome> {
+        self.background_io.poll()
+    }
+
+    /// Problems found while loading the last recording.
+    pub fn load_diagnostics(&self) -> &LoadDiagnostics {
+        &self.load_diagnostics
+    }
+
+    // Load a recording, leniently if enabled.
+    fn load_replay_file(&mut self, path: impl AsRef<Path>) -> Result<ReplayFile, ReplayError> {
+        self.load_diagnostics = LoadDiagnostics::default();
+        if !self.lenient_loading {
+            return load_chapters(path);
+        }
+        let (replay_file, diagnostics) = load_chapters_lenient(path)?;
+        self.load_diagnostics = diagnostics;
+        Ok(replay_file)
+    }
+
+    /// Set how fast recorded frames are injected during replay.
+    pub fn with_replay_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.replay_speed = speed;
+        self
+    }
+
+    /// Render empty frames between replayed frames, see [`IdleFrames`].
+    pub fn with_idle_frames(mut self, idle_frames: IdleFrames) -> Self {
+        self.replay_idle = IdleSchedule {
+            idle_frames,
+            ..Default::default()
+        };
+        self
+    }
+
+    /// Show the modal with its overlay during replay. When disabled, the
+    /// replay runs without any visible chrome, e.g. for screenshot
+    /// comparisons and demo videos.
+    pub fn with_overlay(mut self, enabled: bool) -> Self {
+        self.show_overlay = enabled;
+        self
+    }
+
+    /// Paint a cursor at the replayed pointer position, and a ripple at each
+    /// replayed click, so that viewers can follow the replay. The painted
+    /// shapes are part of the frame, so do not combine this with
+    /// [`ReplayManager::with_frame_hashes`].
+    pub fn with_synthetic_cursor(mut self, enabled: bool) -> Self {
+        self.synthetic_cursor = enabled.then(SyntheticCursor::default);
+        self
+    }
+
+    /// Export each replay to an animated GIF at the given path, from
+    /// screenshots taken after every replayed frame. Combine with
+    /// [`ReplayManager::with_overlay`] to keep the modal out of the GIF.
+    #[cfg(feature = "gif")]
+    pub fn with_gif_export(mut self, path: impl Into<String>) -> Self {
+        self.gif_export_path = Some(path.into());
+        self
+    }
+
+    // Pass the screenshots taken during the replay to the GIF exporter.
+    #[cfg(feature = "gif")]
+    fn export_screenshots(&mut self, raw_input: &egui::RawInput) {
+        for event in raw_input.events.iter() {
+            let egui::Event::Screenshot { image, .. } = event else {
+                continue;
+            };
+            let (Some(exporter), Some(time)) = (self.gif_exporter.as_mut(), self.pending_screenshot_time.take()) else {
+                continue;
+            };
+            if let Err(err) = exporter.add_frame(image, time) {
+                log::error!("Failed to export replay frame: {}", err);
+                self.gif_exporter = None;
+            }
+        }
+    }
+
+    #[cfg(feature = "gif")]
+    fn finish_gif_export(&mut self) {
+        if let Some(exporter) = self.gif_exporter.take() {
+            match exporter.finish() {
+                Ok(()) => log::info!("Exported replay to GIF"),
+                Err(err) => log::error!("Failed to export replay: {}", err),
+            }
+        }
+    }
+
+    /// Execute the commands received by the remote control server.
+    pub fn with_remote_control(mut self, remote_control: RemoteControl) -> Self {
+        self.remote_control = Some(remote_control);
+        self
+    }
+
+    /// Stream every captured frame to another app, which mirrors the
+    /// interactions in real time. Frames are captured even when not
+    /// recording.
+    pub fn with_stream_sender(mut self, sender: StreamSender) -> Self {
+        self.stream_sender = Some(sender);
+        self
+    }
+
+    /// Inject the frames streamed by another app as soon as they arrive.
+    pub fn with_stream_receiver(mut self, receiver: StreamReceiver) -> Self {
+        self.stream_receiver = Some(receiver);
+        self
+    }
+
+    /// Inject random frames generated by the fuzz source whenever no replay
+    /// is running, to hunt for crashes. When the app panics, the generated
+    /// frames are saved as a recording that reproduces the crash.
+    pub fn with_fuzzing(mut self, source: FuzzSource) -> Self {
+        log::info!("Fuzzing UI events with seed {}", source.seed());
+        source.install_panic_hook();
+        self.fuzz_source = Some(source);
+        self
+    }
+
+    fn handle_remote_commands(&mut self, now: NanoTimestamp) {
+        while let Some(request) = self.remote_control.as_ref().and_then(RemoteControl::try_recv) {
+            log::info!("Remote command: {:?}", request.command);
+            let response = match &request.command {
+                RemoteCommand::StartRecording if self.is_replaying => RemoteResponse::error("A replay is running"),
+                RemoteCommand::StartRecording => {
+                    self.start_recording(now);
+                    RemoteResponse::ok()
+                }
+                RemoteCommand::StopRecording => match self.stop_recording(now) {
+                    Ok(file) => RemoteResponse {
+                        file: Some(file),
+                        ..RemoteResponse::ok()
+                    },
+                    Err(err) => RemoteResponse::error(err),
+                },
+                RemoteCommand::Load { .. } if self.is_recording || self.is_replaying => {
+                    RemoteResponse::error("Cannot load while recording or replaying")
+                }
+                RemoteCommand::Load { path } => {
+                    self.replay_file = path.clone();
+                    self.load_selected_file();
+                    match &self.load_error {
+                        Some(err) => RemoteResponse::error(err),
+                        None => RemoteResponse::ok(),
+                    }
+                }
+                RemoteCommand::Play if self.is_recording || self.recording.frames.is_empty() => {
+                    RemoteResponse::error("No UI events loaded")
+                }
+                RemoteCommand::Play => {
+                    self.start_loaded_replay();
+                    RemoteResponse::ok()
+                }
+                RemoteCommand::Status => RemoteResponse {
+                    status: Some(RemoteStatus {
+                        recording: self.is_recording,
+                        replaying: self.is_replaying,
+                        frames: self.num_recorded_frames(),
+                        replay_index: self.replay_index,
+                        last_replay_success: self.last_replay_report.as_ref().map(ReplayReport::is_success),
+                    }),
+                    ..RemoteResponse::ok()
+                },
+            };
+            request.respond(response);
+        }
+    }
+
+    /// Show a small badge with the replay progress in the corner while
+    /// replaying without the modal.
+    pub fn with_progress_badge(mut self, enabled: bool) -> Self {
+        self.show_progress_badge = enabled;
+        self
+    }
+
+    /// Close the app once a replay ran to its end, after running
+    /// `settle_frames` more frames (e.g. to let animations finish). The report
+    /// is passed to the [`ReplayManager::on_replay_finished`] callback before.
+    pub fn with_exit_on_completion(mut self, settle_frames: usize) -> Self {
+        self.exit_on_completion_settle_frames = Some(settle_frames);
+        self
+    }
+
+    /// Frames whose rendering differed from the recording during the last
+    /// replay.
+    pub fn divergence_report(&self) -> &DivergenceReport {
+        &self.divergence_report
+    }
+
+    /// Call the given callback with the report of each replay once it
+    /// finished or was aborted.
+    pub fn on_replay_finished(&mut self, callback: Box<dyn FnMut(ReplayReport)>) {
+        self.on_replay_finished = Some(callback);
+    }
+
+    /// Call the given callback after each replayed frame with the number of
+    /// replayed frames, the total number of frames and the recorded time of
+    /// the replayed frame. Useful to show the progress outside of the modal.
+    pub fn on_replay_progress(&mut self, callback: ReplayProgressCallback) {
+        self.on_replay_progress = Some(callback);
+    }
+
+    /// Call the given callback when a replay starts, with the seeds of the
+    /// random number generators registered during recording, so that the
+    /// application can reseed them. See [`ReplayManager::register_rng_seed`].
+    pub fn on_replay_started(&mut self, callback: ReplayStartedCallback) {
+        self.on_replay_started = Some(callback);
+    }
+
+    /// Call the given callback when a replay of a recording with audio starts,
+    /// with the audio track and the position in the audio at the first
+    /// replayed frame. The position is negative if the audio started later,
+    /// so its playback should be delayed. The audio only stays in sync with
+    /// [`ReplaySpeed::RealTime`] replays.
+    pub fn on_replay_audio(&mut self, callback: ReplayAudioCallback) {
+        self.on_replay_audio = Some(callback);
+    }
+
+    /// Call the given callback when the replay pauses at a restart of the
+    /// recorded app, with the index of the first frame after the restart. The
+    /// harness then restarts the app, which continues the replay with
+    /// [`ReplayManager::resume_chained_replay`]. Without a restart, the replay
+    /// can be resumed in the same process, see [`crate::chain`].
+    pub fn on_replay_restart(&mut self, callback: Box<dyn FnMut(usize)>) {
+        self.on_replay_restart = Some(callback);
+    }
+
+    /// Rewrite the events of each replayed frame right before they are
+    /// injected, e.g. to offset pointer positions, remap a key or drop some
+    /// text, so that old recordings keep working after small UI changes. The
+    /// recording itself is not changed.
+    pub fn set_replay_transform(&mut self, transform: ReplayTransform) {
+        self.replay_transform = Some(transform);
+    }
+
+    /// Inject the recorded events unchanged again.
+    pub fn clear_replay_transform(&mut self) {
+        self.replay_transform = None;
+    }
+
+    /// Pause replays right before a frame matching the breakpoint would be
+    /// replayed. Step through the following frames with
+    /// [`ReplayManager::step_replay`], or continue with
+    /// [`ReplayManager::resume_replay`], also from the modal.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.replay_breakpoints.push(breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.replay_breakpoints
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.replay_breakpoints.clear();
+    }
+
+    /// Pause the running replay before its next frame. The real input
+    /// reaches the application while the replay is paused, e.g. to inspect
+    /// it.
+    pub fn pause_replay(&mut self) {
+        if self.is_replaying {
+            self.replay_paused = true;
+            self.replay_steps = 0;
+        }
+    }
+
+    pub fn is_replay_paused(&self) -> bool {
+        self.is_replaying && self.replay_paused
+    }
+
+    /// Replay the next frame of the paused replay, and pause again.
+    pub fn step_replay(&mut self) {
+        if self.is_replay_paused() {
+            self.replay_steps += 1;
+            self.replay_reschedule = true;
+        }
+    }
+
+    /// Continue the paused replay, with the remaining frames as far apart as
+    /// they were recorded from now on.
+    pub fn resume_replay(&mut self) {
+        if self.is_replay_paused() {
+            self.replay_paused = false;
+            self.replay_steps = 0;
+            self.replay_reschedule = true;
+        }
+    }
+
+    // Pause the replay before the first frame after a restart of the app, and
+    // ask the harness to restart it.
+    fn pause_for_restart(&mut self) {
+        let index = self.replay_index;
+        log::info!("Paused the replay for a restart of the app before frame {}", index + 1);
+        self.replay_break = Some((index, RESTART_MARKER.to_string()));
+        self.replay_paused = true;
+        self.replay_steps = 0;
+        if let Some(path) = &self.loaded_replay_file {
+            let state = ChainState::Replay {
+                path: PathBuf::from(path),
+                frame_index: index,
+            };
+            if let Err(err) = state.save(self.record_output.dir()) {
+                log::error!("Failed to save the chained replay state: {}", err);
+            }
+        }
+        if let Some(callback) = self.on_replay_restart.as_mut() {
+            callback(index);
+        }
+    }
+
+    // Whether the app was restarted right before the frame at `index`.
+    fn is_restart_frame(&self, index: usize) -> bool {
+        self.recording
+            .markers
+            .iter()
+            .any(|marker| marker.name == RESTART_MARKER && self.marker_frame_index(marker) == index)
+    }
+
+    /// Continue the replay paused for a restart in the previous process, if
+    /// any, e.g. on startup. Returns whether a replay was continued.
+    pub fn resume_chained_replay(&mut self) -> Result<bool, ReplayError> {
+        let dir = self.record_output.dir().to_path_buf();
+        let Some(ChainState::Replay { path, frame_index }) = ChainState::load(&dir)? else {
+            return Ok(false);
+        };
+        ChainState::clear(&dir)?;
+        self.start_replay_from_file(&path)?;
+        if !self.is_replaying || frame_index >= self.num_recorded_frames() {
+            return Ok(false);
+        }
+        log::info!(
+            "Continuing the replay of {} at frame {}",
+            path.display(),
+            frame_index + 1
+        );
+        self.replay_index = frame_index;
+        // Don't pause at the same restart again.
+        self.replay_break = Some((frame_index, RESTART_MARKER.to_string()));
+        self.replay_reschedule = true;
+        Ok(true)
+    }
+
+    /// Index of the frame and name of the breakpoint which paused the
+    /// replay last.
+    pub fn hit_breakpoint(&self) -> Option<(usize, &str)> {
+        self.replay_break.as_ref().map(|(index, name)| (*index, name.as_str()))
+    }
+
+    /// Attach narration to the recording in progress, e.g. once an audio
+    /// recorder was started. Does nothing when not recording.
+    pub fn attach_audio(&mut self, track: AudioTrack) {
+        if !self.is_recording {
+            return;
+        }
+        log::info!("Attaching audio starting at {}", track.start.as_rfc3339());
+        self.recording.audio = Some(track);
+    }
+
+    /// Register the seed of a random number generator of the application. The
+    /// seeds are saved with each recording and are available when it is
+    /// replayed, so that the application behaves identically.
+    pub fn register_rng_seed(&mut self, name: impl Into<String>, seed: u64) {
+        let name = name.into();
+        if self.is_recording {
+            self.recording.rng_seeds.insert(name.clone(), seed);
+        }
+        self.rng_seeds.insert(name, seed);
+    }
+
+    /// Seed registered under `name` when the recording being replayed was
+    /// recorded.
+    pub fn replay_rng_seed(&self, name: &str) -> Option<u64> {
+        if !self.is_replaying {
+            return None;
+        }
+        self.recording.rng_seeds.get(name).copied()
+    }
+
+    /// Number of replays started so far, to detect the start of a replay.
+    pub fn replays_started(&self) -> u64 {
+        self.replays_started
+    }
+
+    /// Save the application state when a recording starts, and restore the
+    /// saved state when it is replayed, so that the replay starts from the
+    /// state the recording was made in. Call at the start of every frame,
+    /// before the input is handled.
+    pub fn sync_state(&mut self, state: &mut dyn ReplayStateSnapshot) {
+        if std::mem::take(&mut self.record_snapshot_pending) && self.is_recording {
+            self.recording.state_snapshot = Some(state.save());
+        }
+        if std::mem::take(&mut self.replay_restore_pending) && self.is_replaying {
+            if let Some(snapshot) = &self.recording.state_snapshot {
+                log::info!("Restoring the application state of the recording");
+                state.restore(snapshot);
+            }
+        }
+    }
+
+    /// Identity of the application, e.g. its name and version, stored in
+    /// recordings. Replaying a recording of another application from the
+    /// modal asks for confirmation first.
+    pub fn with_app_identity(mut self, identity: impl Into<String>) -> Self {
+        self.app_identity = Some(identity.into());
+        self
+    }
+
+    /// Set the fingerprint of the current application state, e.g. a hash of
+    /// the open document. It is stored in recordings when they start, and
+    /// replaying a recording made in a different state from the modal asks
+    /// for confirmation first.
+    pub fn set_state_fingerprint(&mut self, fingerprint: impl Into<String>) {
+        self.state_fingerprint = Some(fingerprint.into());
+    }
+
+    /// Differences between the identity and state fingerprint stored in the
+    /// loaded recording and those of the current session.
+    pub fn session_mismatches(&self) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if let (Some(recorded), Some(current)) = (&self.recording.app_identity, &self.app_identity) {
+            if recorded != current {
+                mismatches.push(format!("Recorded by {}, but this is {}", recorded, current));
+            }
+        }
+        if let (Some(recorded), Some(current)) = (&self.recording.state_fingerprint, &self.state_fingerprint) {
+            if recorded != current {
+                mismatches.push(format!(
+                    "Recorded in state {}, but the current state is {}",
+                    recorded, current
+                ));
+            }
+        }
+        mismatches
+    }
+
+    /// The recording being recorded, replayed or loaded in the modal.
+    pub fn recording(&self) -> &ReplayFile {
+        &self.recording
+    }
+
+    /// Report of the last finished replay.
+    pub fn last_replay_report(&self) -> Option<&ReplayReport> {
+        self.last_replay_report.as_ref()
+    }
+
+    // Stop the replay. The report is completed once the results of the last
+    // replayed frame are known, see `emit_replay_report`.
+    fn finish_replay(&mut self, aborted: bool) {
+        if !self.is_replaying {
+            return;
+        }
+        self.is_replaying = false;
+        let replayed_logs = self.log_capture.as_ref().map(LogCapture::stop).unwrap_or_default();
+        let log_differences = if self.recording.logs.is_empty() {
+            Vec::new()
+        } else {
+            diff_logs(&self.recording.logs, &replayed_logs)
+        };
+        self.pending_replay_report = Some(ReplayReport {
+            recording: self.loaded_replay_file.clone(),
+            start_time: self.replay_started_at,
+            end_time: self.replay_last_frame_at,
+            frames_played: self.replay_index,
+            num_frames: self.num_recorded_frames(),
+            aborted,
+            log_differences,
+            ..Default::default()
+        });
+    }
+
+    fn emit_replay_report(&mut self) {
+        let Some(mut report) = self.pending_replay_report.take() else {
+            return;
+        };
+        report.assertions = self.replay_assertions.report().clone();
+        report.divergence = self.divergence_report.clone();
+        log::info!(
+            "Replay {} after {} / {} frames, {} assertions failed, diverged: {}",
+            if report.aborted { "aborted" } else { "finished" },
+            report.frames_played,
+            report.num_frames,
+            report.assertions.num_failed(),
+            report.divergence.has_diverged()
+        );
+        if let Some(difference) = report.log_differences.first() {
+            log::warn!(
+                "{} log lines differ from the recording, first at line {}",
+                report.log_differences.len(),
+                difference.index + 1
+            );
+        }
+        #[cfg(feature = "gif")]
+        self.finish_gif_export();
+        if let Some(callback) = self.on_replay_finished.as_mut() {
+            callback(report.clone());
+        }
+        if let Some(path) = &self.report_file {
+            self.finished_replays.push(report.clone());
+            if let Err(err) = write_reports(path, &self.finished_replays) {
+                log::error!("Failed to write the replay report to {}: {}", path, err);
+            }
+        }
+        if !report.aborted {
+            self.replay_settle_frames_left = self.exit_on_completion_settle_frames;
+        }
+        if let Some(status) = &mut self.auto_replay_status {
+            if !report.is_success() {
+                log::error!("Replay did not succeed, closing the app");
+                *status = ReplayStatus::Failed;
+                self.replay_settle_frames_left = Some(0);
+            } else if *status == ReplayStatus::Pending {
+                *status = ReplayStatus::Succeeded;
+            }
+        }
+        self.last_replay_report = Some(report);
+    }
+
+    /// Start replaying the given frames, without going through the modal.
+    pub fn start_replay(&mut self, frames: Vec<FrameEvents>) {
+        self.start_replay_file(ReplayFile::new(frames));
+    }
+
+    /// Load a recording and start replaying it, without going through the
+    /// modal. Markers and assertions of the recording are used as well. Fails
+    /// with [`ReplayError::EmptyRecording`] if the recording has no frames.
+    pub fn start_replay_from_file(&mut self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension == "ibin") {
+            let file = IndexedReplayFile::open(path)?;
+            if file.num_frames() == 0 {
+                return Err(ReplayError::EmptyRecording);
+            }
+            self.replay_file = path.display().to_string();
+            self.loaded_replay_file = Some(self.replay_file.clone());
+            self.start_lazy_replay(file);
+            return Ok(());
+        }
+        let replay_file = self.load_replay_file(path)?;
+        if replay_file.frames.is_empty() {
+            return Err(ReplayError::EmptyRecording);
+        }
+        self.replay_file = path.display().to_string();
+        self.loaded_replay_file = Some(self.replay_file.clone());
+        self.start_replay_file(replay_file);
+        Ok(())
+    }
+
+    /// Like [`ReplayManager::start_replay_from_file`], loading the recording
+    /// on a background thread. The replay starts in the frame the load
+    /// finishes, failures are reported by [`ReplayManager::poll_io`].
+    pub fn start_replay_from_file_in_background(&mut self, path: impl AsRef<Path>) {
+        self.replay_file = path.as_ref().display().to_string();
+        self.loaded_replay_file = Some(self.replay_file.clone());
+        self.background_io.load(path.as_ref(), self.lenient_loading, true);
+    }
+
+    /// Start replaying a recording, including its markers and assertions,
+    /// without going through the modal.
+    pub fn start_replay_file(&mut self, replay_file: ReplayFile) {
+        if replay_file.frames.is_empty() {
+            log::error!("No UI events to replay");
+            return;
+        }
+        self.recording = replay_file;
+        self.replay_lazy = None;
+        for mismatch in self.session_mismatches() {
+            log::warn!("{}", mismatch);
+        }
+        self.start_loaded_replay();
+    }
+
+    /// Start replaying an indexed recording, decoding each frame only when it
+    /// is replayed, so that the replay starts at once and memory stays flat
+    /// for large recordings. `.ibin` files passed to
+    /// [`ReplayManager::start_replay_from_file`] are replayed like this.
+    pub fn start_lazy_replay(&mut self, file: IndexedReplayFile) {
+        let skeleton = match file.skeleton() {
+            Ok(skeleton) => skeleton,
+            Err(err) => {
+                log::error!("Failed to read the indexed recording: {}", err);
+                return;
+            }
+        };
+        self.start_replay_file(skeleton);
+        if self.is_replaying {
+            self.replay_lazy = Some(file);
+            self.replay_lazy_index = None;
+        }
+    }
+
+    /// Replay the current recording again from its first frame, e.g. to loop
+    /// a replay once it finished, without loading it again.
+    pub fn restart_replay(&mut self) {
+        if self.recording.frames.is_empty() {
+            log::error!("No UI events to replay");
+            return;
+        }
+        self.start_loaded_replay();
+    }
+
+    // Start replaying the recording loaded in the modal, until the entered
+    // marker if any.
+    fn start_modal_replay(&mut self) {
+        self.start_loaded_replay();
+        if !self.replay_until_marker_name.is_empty() {
+            let name = self.replay_until_marker_name.clone();
+            self.replay_until_marker(&name);
+        }
+    }
+
+    // Schedule the frames of `self.recording` as far apart as they were
+    // recorded, with the pauses of the recording skipped.
+    fn frame_scheduler(&self) -> FrameScheduler<FrozenClock> {
+        let first_time = self.recording.start_time().unwrap_or_default();
+        let targets = self
+            .recording
+            .frames
+            .iter()
+            .map(|frame| frame.time - self.recording.paused_between(first_time, frame.time))
+            .collect();
+        FrameScheduler::new(self.replay_clock.clone(), targets)
+    }
+
+    // Start replaying `self.recording` from its first frame.
+    fn start_loaded_replay(&mut self) {
+        self.keyboard_mode.normalize_replay(&mut self.recording.frames);
+        // Keys left pressed would stay stuck in the app after the replay,
+        // e.g. if their releases were filtered out while recording.
+        let num_repaired = repair_key_releases(&mut self.recording);
+        if num_repaired > 0 {
+            log::warn!("Added {} missing key releases to the replay", num_repaired);
+        }
+        if let Some(path) = &self.loaded_replay_file {
+            self.recent_recordings.push(path);
+        }
+        self.finish_replay(true);
+        self.emit_replay_report();
+        if self.is_recording {
+            log::warn!("Discarding the UI event recording to start a replay");
+            self.discard_recording();
+        }
+        self.is_replaying = true;
+        if let Some(capture) = &self.log_capture {
+            capture.start();
+        }
+        self.replays_started += 1;
+        self.replay_restore_pending = true;
+        if let Some(callback) = self.on_replay_started.as_mut() {
+            callback(&self.recording.rng_seeds);
+        }
+        if let (Some(callback), Some(track), Some(start_time)) = (
+            self.on_replay_audio.as_mut(),
+            &self.recording.audio,
+            self.recording.start_time(),
+        ) {
+            callback(track, start_time - track.start);
+        }
+        self.replay_index = 0;
+        self.replay_end_index = None;
+        self.replay_paused = false;
+        self.replay_steps = 0;
+        self.replay_reschedule = false;
+        self.replay_break = None;
+        self.divergence_report.clear();
+        if let Some(comparison) = &mut self.comparison {
+            comparison.clear();
+        }
+        self.replay_hovered_files.clear();
+        self.replay_focused = true;
+        self.replay_assertions.restart();
+        self.last_copied_text = None;
+        self.replay_started_at = None;
+        self.replay_scheduler = Some(self.frame_scheduler());
+        self.replay_last_frame_at = None;
+        self.replay_idle.clear();
+        self.pending_replay_report = None;
+        self.replay_settle_frames_left = None;
+        if let Some(cursor) = &mut self.synthetic_cursor {
+            cursor.reset();
+        }
+        #[cfg(feature = "gif")]
+        if let Some(path) = &self.gif_export_path {
+            self.pending_screenshot_time = None;
+            self.gif_exporter = match GifExporter::create(path) {
+                Ok(exporter) => Some(exporter),
+                Err(err) => {
+                    log::error!("Failed to export replay to {}: {}", path, err);
+                    None
+                }
+            };
+        }
+    }
+
+    // Count down the frames after a completed replay and close the app once
+    // none are left.
+    fn settle_before_exit(&mut self, ctx: &Context) {
+        match self.replay_settle_frames_left {
+            Some(0) => {
+                log::info!("Replay completed, closing the app");
+                self.replay_settle_frames_left = None;
+                ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, egui::ViewportCommand::Close);
+            }
+            Some(frames_left) => {
+                self.replay_settle_frames_left = Some(frames_left - 1);
+                ctx.request_repaint();
+            }
+            None => {}
+        }
+    }
+
+    pub fn open_window(&mut self) {
+        self.is_window_open = true;
+        self.is_replaying = false;
+        self.discard_recording();
+        self.recording = ReplayFile::default();
+        self.replay_lazy = None;
+        self.replay_index = 0;
+        self.replay_end_index = None;
+        self.loaded_replay_file = None;
+        self.should_lookup_replay = true;
+    }
+
+    pub fn close_window(&mut self) {
+        self.finish_replay(true);
+        self.is_window_open = false;
+        self.is_replaying = false;
+        self.discard_recording();
+        self.recording = ReplayFile::default();
+        self.replay_lazy = None;
+        self.replay_index = 0;
+        self.replay_end_index = None;
+        self.loaded_replay_file = None;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.is_replaying
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    pub fn is_recording_paused(&self) -> bool {
+        self.is_recording && self.record_paused_at.is_some()
+    }
+
+    /// Stop capturing UI events until [`ReplayManager::resume_recording`],
+    /// keeping the frames recorded so far. Does nothing when not recording.
+    pub fn pause_recording(&mut self, now: NanoTimestamp) {
+        if self.is_recording && self.record_paused_at.is_none() {
+            log::info!("Pausing UI event recording");
+            self.record_paused_at = Some(now);
+        }
+    }
+
+    /// Continue a paused recording. The pause is stored as a [`Gap`], which
+    /// is skipped when replaying in real time.
+    pub fn resume_recording(&mut self, now: NanoTimestamp) {
+        let Some(paused_at) = self.record_paused_at.take() else {
+            return;
+        };
+        let gap = Gap {
+            time: paused_at,
+            duration: now - paused_at,
+        };
+        log::info!("Resuming UI event recording after {:?}", gap);
+        self.recording.gaps.push(gap);
+        // The pointer moved while paused.
+        self.record_pointer_moves = 0;
+    }
+
+    pub fn num_recorded_frames(&self) -> usize {
+        self.recording.frames.len() + self.record_spill.as_ref().map_or(0, SpillFile::num_frames)
+    }
+
+    pub fn num_recorded_events(&self) -> usize {
+        self.recording.num_events() + self.record_spill.as_ref().map_or(0, SpillFile::num_events)
+    }
+
+    /// Start recording UI events, as if the record key was pressed.
+    pub fn start_recording(&mut self, now: NanoTimestamp) {
+        self.start_recording_in(now, egui::ViewportId::ROOT);
+    }
+
+    fn start_recording_in(&mut self, now: NanoTimestamp, viewport_id: egui::ViewportId) {
+        log::info!("Starting UI event recording");
+        self.is_recording = true;
+        self.replay_lazy = None;
+        self.recording = ReplayFile {
+            rng_seeds: self.rng_seeds.clone(),
+            app_identity: self.app_identity.clone(),
+            state_fingerprint: self.state_fingerprint.clone(),
+            ..Default::default()
+        };
+        self.record_snapshot_pending = true;
+        self.record_pointer_moves = 0;
+        #[cfg(feature = "accesskit")]
+        if let Some(recorder) = &mut self.semantic_recorder {
+            recorder.reset();
+        }
+        self.record_moving_touches.clear();
+        self.record_hovered_files.clear();
+        self.record_focused = None;
+        self.record_started_at = Some(now);
+        self.record_paused_at = None;
+        self.record_chapters = self.record_chapter_length.map(|length| {
+            let path = self.record_output.file_path(now, self.recording_extension());
+            ChapterRecorder::new(length, path, now)
+        });
+        self.record_chained_path = None;
+        self.record_memory_usage = 0;
+        self.record_spill = None;
+        self.record_metrics.start();
+        let first_frame = FrameEvents::new(now, vec![egui::Event::PointerMoved(egui::Pos2::new(0.0, 0.0))])
+            .with_viewport_id(viewport_id);
+        if let Some(autosave) = &self.crash_autosave {
+            let path = self.record_output.file_path(now, self.recording_extension());
+            autosave.start(crash_file_path(&path), self.rng_seeds.clone());
+            autosave.push(first_frame.clone());
+        }
+        self.recording.frames.push(first_frame);
+        if let Some(capture) = &self.log_capture {
+            capture.start();
+        }
+        self.pending_hash_frame = Some(0);
+        self.record_current_frame = Some(0);
+    }
+
+    /// Stop recording UI events and save them to a new file, returning its
+    /// name.
+    pub fn stop_recording(&mut self, now: NanoTimestamp) -> Result<String, ReplayError> {
+        if !self.is_recording {
+            return Err(ReplayError::NotRecording);
+        }
+        log::info!("Stopping UI event recording");
+        self.is_recording = false;
+        self.annotation_draft = None;
+        self.record_paused_at = None;
+        self.stop_crash_autosave();
+        let file_name = match (self.record_chapters.take(), self.record_chained_path.take()) {
+            (Some(chapters), _) => self.chapter_file_name(&chapters),
+            (None, Some(path)) => Ok(path),
+            (None, None) => self.recording_file_name(now),
+        };
+        let file_name = match file_name {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                // E.g. the output directory can't be created, keep the
+                // recording to save it elsewhere.
+                self.restore_spilled_frames();
+                let path = self.record_output.file_path(now, self.recording_extension());
+                self.keep_failed_save(&path.to_string_lossy(), self.recording.clone(), &err);
+                return Err(err);
+            }
+        };
+        self.save_recording(&file_name)?;
+        Ok(file_name)
+    }
+
+    /// Save the recording in progress right before the app restarts, with a
+    /// [`RESTART_MARKER`] at the restart, so that the relaunched process
+    /// continues it with [`ReplayManager::resume_chained_recording`]. Returns
+    /// the name of the saved file. With background IO, wait until
+    /// [`ReplayManager::is_io_pending`] is false before exiting.
+    pub fn restart_recording(&mut self, now: NanoTimestamp) -> Result<String, ReplayError> {
+        if !self.is_recording {
+            return Err(ReplayError::NotRecording);
+        }
+        self.resume_recording(now);
+        self.add_marker(RESTART_MARKER, now);
+        let file_name = self.stop_recording(now)?;
+        let state = ChainState::Recording {
+            path: PathBuf::from(&file_name),
+        };
+        state.save(self.record_output.dir())?;
+        Ok(file_name)
+    }
+
+    /// Continue the recording saved by [`ReplayManager::restart_recording`]
+    /// in the previous process, if any, e.g. on startup. The frames recorded
+    /// from now on are appended to it, with the restart stored as a [`Gap`],
+    /// and the whole recording is saved over the first part. Returns whether
+    /// a recording was continued.
+    pub fn resume_chained_recording(&mut self, now: NanoTimestamp) -> Result<bool, ReplayError> {
+        let dir = self.record_output.dir().to_path_buf();
+        let Some(ChainState::Recording { path }) = ChainState::load(&dir)? else {
+            return Ok(false);
+        };
+        ChainState::clear(&dir)?;
+        let previous = self.load_replay_file(&path)?;
+        log::info!("Continuing the UI event recording of {}", path.display());
+        self.start_recording(now);
+        let restarted_at = previous
+            .markers
+            .iter()
+            .rev()
+            .find(|marker| marker.name == RESTART_MARKER)
+            .map_or(now, |marker| marker.time);
+        let first_frame = std::mem::take(&mut self.recording.frames);
+        self.recording = previous;
+        self.recording.gaps.push(Gap {
+            time: restarted_at,
+            duration: now - restarted_at,
+        });
+        self.recording.frames.extend(first_frame);
+        self.record_started_at = self.recording.start_time();
+        self.record_chained_path = Some(path.to_string_lossy().to_string());
+        Ok(true)
+    }
+
+    // Keep a recording which failed to save, to retry from the modal.
+    fn keep_failed_save(&mut self, file_name: &str, recording: ReplayFile, err: &ReplayError) {
+        log::error!("Failed to save UI events to {}: {}", file_name, err);
+        self.failed_save = Some(FailedSave {
+            file_name: file_name.to_string(),
+            error: err.to_string(),
+            recording,
+            retry_path: file_name.to_string(),
+        });
+    }
+
+    /// Why the last recording failed to save, if it did. The recording is
+    /// kept until it is saved with [`ReplayManager::retry_save`] or discarded
+    /// with [`ReplayManager::discard_failed_save`]. The error is shown in the
+    /// HUD and in the modal, which offers to retry with another path.
+    pub fn save_error(&self) -> Option<&str> {
+        self.failed_save.as_ref().map(|failed| failed.error.as_str())
+    }
+
+    /// Save the recording which failed to save to `file_name`, e.g. on
+    /// another disk.
+    pub fn retry_save(&mut self, file_name: &str) -> Result<(), ReplayError> {
+        let Some(failed) = self.failed_save.take() else {
+            return Err(ReplayError::NotRecording);
+        };
+        if let Err(err) = failed.recording.save(file_name) {
+            self.keep_failed_save(file_name, failed.recording, &err);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Drop the recording which failed to save.
+    pub fn discard_failed_save(&mut self) {
+        self.failed_save = None;
+    }
+
+    // Save the frames recorded so far, e.g. when recording stops.
+    fn save_recording(&mut self, file_name: &str) -> Result<(), ReplayError> {
+        self.pending_hash_frame = None;
+        self.restore_spilled_frames();
+        if let Some(capture) = &self.log_capture {
+            self.recording.logs = capture.stop();
+        }
+        self.record_memory_usage = 0;
+        if let PointerSimplification::Tolerance(tolerance) = self.pointer_simplification {
+            let num_events = self.recording.num_events();
+            self.recording.frames = simplify_pointer_path(std::mem::take(&mut self.recording.frames), tolerance);
+            let num_dropped = num_events - self.recording.num_events();
+            self.record_metrics.add_dropped_events(num_dropped);
+        }
+        let num_merged = coalesce_scroll_events(&mut self.recording.frames, self.scroll_coalescing);
+        self.record_metrics.add_dropped_events(num_merged);
+        // Merging frames would detach the hashes from the events that
+        // produced them.
+        if !self.record_preserve_frame_boundaries && !self.capture_frame_hashes {
+            self.recording.frames = apply_event_postprocessing(std::mem::take(&mut self.recording.frames));
+        }
+        self.keyboard_mode.normalize_recording(&mut self.recording.frames);
+        // E.g. the hotkey stopping the recording is still held down.
+        let num_repaired = repair_key_releases(&mut self.recording);
+        if num_repaired > 0 {
+            log::info!("Added {} missing key releases to the recording", num_repaired);
+        }
+        let copy_name = converted_file_name(file_name).filter(|_| self.record_dual_output);
+        let mut saved_files = vec![file_name.to_string()];
+        saved_files.extend(copy_name.clone());
+        if self.background_io.is_enabled() {
+            // Shared with the save threads, to retry if they fail.
+            let recording = Arc::new(self.recording.clone());
+            if let Some(copy_name) = &copy_name {
+                self.background_io.save(recording.clone(), copy_name);
+            }
+            self.background_io.save(recording, file_name);
+            self.record_metrics.saving(&self.recording, file_name);
+            self.last_saved_files = saved_files;
+            return Ok(());
+        }
+        if let Err(err) = self.recording.save(file_name) {
+            self.keep_failed_save(file_name, self.recording.clone(), &err);
+            return Err(err);
+        }
+        if let Some(copy_name) = &copy_name {
+            // The recording itself is saved, the copy is only for convenience.
+            if let Err(err) = self.recording.save(copy_name) {
+                log::error!("Failed to save a copy of the UI events to {}: {}", copy_name, err);
+            }
+        }
+        self.last_saved_files = saved_files;
+        self.record_metrics.saving(&self.recording, file_name);
+        self.record_metrics.measure_saved(Path::new(file_name));
+        Ok(())
+    }
+
+    // Path of the current chapter, creating the output directory if needed.
+    fn chapter_file_name(&self, chapters: &ChapterRecorder) -> Result<String, ReplayError> {
+        self.record_output.create_dir()?;
+        Ok(chapters.current_path().to_string_lossy().to_string())
+    }
+
+    // Whether the current chapter reached its length.
+    fn is_chapter_full(&self, now: NanoTimestamp) -> bool {
+        let num_frames = self.num_recorded_frames();
+        self.record_chapters
+            .as_ref()
+            .is_some_and(|chapters| chapters.is_full(num_frames, now))
+    }
+
+    // Save the current chapter and continue recording into the next one,
+    // returning the file name of the saved chapter.
+    fn save_chapter(&mut self, now: NanoTimestamp) -> Result<String, ReplayError> {
+        let Some(chapters) = &self.record_chapters else {
+            return Err(ReplayError::NotRecording);
+        };
+        let file_name = self.chapter_file_name(chapters)?;
+        let num_frames = self.num_recorded_frames();
+        self.save_recording(&file_name)?;
+        self.recording = ReplayFile {
+            rng_seeds: self.rng_seeds.clone(),
+            app_identity: self.app_identity.clone(),
+            state_fingerprint: self.state_fingerprint.clone(),
+            screen_rect: self.recording.screen_rect,
+            ..Default::default()
+        };
+        self.record_metrics.start();
+        self.record_current_frame = None;
+        if let Some(capture) = &self.log_capture {
+            capture.start();
+        }
+        if let Some(chapters) = &mut self.record_chapters {
+            chapters.next(num_frames, now);
+        }
+        Ok(file_name)
+    }
+
+    /// Attach a payload to the frame being recorded, e.g. a hash of the state
+    /// of the application. During replay, the payload is exposed by
+    /// [`ReplayManager::replayed_data`] in the same frame, to check that the
+    /// application reached the same state. Call after
+    /// [`ReplayManager::on_raw_input_update`], e.g. from `eframe::App::update`.
+    /// Does nothing while not recording.
+    pub fn attach_data(&mut self, key: impl Into<String>, data: impl Into<Vec<u8>>) {
+        if !self.is_recording {
+            return;
+        }
+        // Frames without events are not recorded, so record one for the data.
+        let index = match self.record_current_frame {
+            Some(index) => index,
+            None => {
+                let now = self.last_input_time.unwrap_or_default();
+                let frame = FrameEvents::new(now, Vec::new()).with_viewport_id(self.last_input_viewport_id);
+                self.push_recorded_frame(now, frame);
+                self.recording.frames.len() - 1
+            }
+        };
+        let frame = &mut self.recording.frames[index];
+        let data = data.into();
+        self.record_memory_usage += data.len();
+        frame.user_data.insert(key.into(), data);
+    }
+
+    /// Payload attached with [`ReplayManager::attach_data`] to the frame
+    /// replayed in the current pass, if any.
+    pub fn replayed_data(&self, key: &str) -> Option<&[u8]> {
+        self.replay_user_data.get(key).map(Vec::as_slice)
+    }
+
+    /// Approximate memory used by the frames of the current recording, not
+    /// counting the frames spilled to disk.
+    pub fn recording_memory_usage(&self) -> usize {
+        self.record_memory_usage
+    }
+
+    fn stop_crash_autosave(&self) {
+        if let Some(autosave) = &self.crash_autosave {
+            autosave.stop();
+        }
+    }
+
+    // Stop recording without saving, dropping the frames spilled to disk. The
+    // frames in memory are replaced by the caller.
+    fn discard_recording(&mut self) {
+        self.is_recording = false;
+        self.stop_crash_autosave();
+        self.record_spill = None;
+        self.record_memory_usage = 0;
+    }
+
+    fn push_recorded_frame(&mut self, now: NanoTimestamp, frame: FrameEvents) {
+        if self.record_memory_cap.is_some_and(|cap| self.record_memory_usage > cap) {
+            self.spill_recorded_frames(now);
+        }
+        self.record_memory_usage += frame.estimated_size();
+        self.record_metrics.push(&frame);
+        if let Some(autosave) = &self.crash_autosave {
+            autosave.push(frame.clone());
+        }
+        self.recording.frames.push(frame);
+        self.pending_hash_frame = Some(self.recording.frames.len() - 1);
+        self.record_current_frame = self.pending_hash_frame;
+    }
+
+    // Move the frames in memory to the spill file. They are kept in memory if
+    // spilling fails.
+    fn spill_recorded_frames(&mut self, now: NanoTimestamp) {
+        let spill = match &mut self.record_spill {
+            Some(spill) => spill,
+            None => match SpillFile::create(now) {
+                Ok(spill) => self.record_spill.insert(spill),
+                Err(err) => {
+                    log::error!("Failed to create spill file: {}", err);
+                    return;
+                }
+            },
+        };
+        match spill.append(&self.recording.frames) {
+            Ok(()) => {
+                log::info!("Spilled {} frames to disk", self.recording.frames.len());
+                self.recording.frames.clear();
+                self.record_memory_usage = 0;
+            }
+            Err(err) => log::error!("Failed to spill UI events: {}", err),
+        }
+    }
+
+    // Move the spilled frames back in front of the frames in memory.
+    fn restore_spilled_frames(&mut self) {
+        let Some(mut spill) = self.record_spill.take() else {
+            return;
+        };
+        match spill.read_all() {
+            Ok(mut frames) => {
+                frames.append(&mut self.recording.frames);
+                self.recording.frames = frames;
+            }
+            Err(err) => log::error!("Failed to read spilled UI events: {}", err),
+        }
+    }
+
+    /// Statistics of the recorded or loaded frames.
+    pub fn stats(&self) -> ReplayStats {
+        ReplayStats::from_frames(&self.recording.frames)
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.recording.markers
+    }
+
+    /// Save the frames of the flight recorder buffer to a new recording file,
+    /// returning its name. The buffer is emptied.
+    pub fn save_flight_recorder(&mut self, now: NanoTimestamp) -> Result<String, ReplayError> {
+        let mut frames = match &mut self.flight_recorder {
+            Some(flight_recorder) => flight_recorder.take_frames(),
+            None => Vec::new(),
+        };
+        if let PointerSimplification::Tolerance(tolerance) = self.pointer_simplification {
+            frames = simplify_pointer_path(frames, tolerance);
+        }
+        self.keyboard_mode.normalize_recording(&mut frames);
+        coalesce_scroll_events(&mut frames, self.scroll_coalescing);
+        if !self.record_preserve_frame_boundaries && !frames.is_empty() {
+            frames = apply_event_postprocessing(frames);
+        }
+        let file_name = self.recording_file_name(now)?;
+        let mut replay_file = ReplayFile {
+            rng_seeds: self.rng_seeds.clone(),
+            ..ReplayFile::new(frames)
+        };
+        // Keys held down when the buffer is saved have no release yet.
+        repair_key_releases(&mut replay_file);
+        if self.background_io.is_enabled() {
+            self.background_io.save(Arc::new(replay_file), &file_name);
+        } else if let Err(err) = replay_file.save(&file_name) {
+            self.keep_failed_save(&file_name, replay_file, &err);
+            return Err(err);
+        }
+        Ok(file_name)
+    }
+
+    fn recording_extension(&self) -> &'static str {
+        #[cfg(feature = "encryption")]
+        if self.record_encrypted {
+            return "enc";
+        }
+        if self.record_use_bincode {
+            "bin"
+        } else {
+            "json"
+        }
+    }
+
+    // Path of a new recording file, creating the output directory if needed.
+    fn recording_file_name(&self, now: NanoTimestamp) -> Result<String, ReplayError> {
+        self.record_output.create_dir()?;
+        let path = self.record_output.file_path(now, self.recording_extension());
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    // Frames recorded so far, including saved chapters.
+    fn num_total_recorded_frames(&self) -> usize {
+        let saved_frames = self.record_chapters.as_ref().map_or(0, ChapterRecorder::saved_frames);
+        saved_frames + self.num_recorded_frames()
+    }
+
+    // Time recorded so far, not counting pauses.
+    fn recorded_duration(&self, now: NanoTimestamp) -> NanoDelta {
+        let Some(started_at) = self.record_started_at else {
+            return NanoDelta::zero();
+        };
+        let paused = self.recording.paused_between(started_at, now);
+        let end = self.record_paused_at.unwrap_or(now);
+        end - paused - started_at
+    }
+
+    // How close the recording is to its limits, 1.0 or more once one of them
+    // is reached.
+    fn recording_limit_progress(&self, now: NanoTimestamp) -> Option<f32> {
+        let duration = self.record_max_duration.map(|max_duration| {
+            let duration = self.recorded_duration(now);
+            duration.as_nanos() as f32 / max_duration.as_nanos().max(1) as f32
+        });
+        let frames = self
+            .record_max_frames
+            .map(|max_frames| self.num_total_recorded_frames() as f32 / max_frames.max(1) as f32);
+        match (duration, frames) {
+            (Some(duration), Some(frames)) => Some(duration.max(frames)),
+            (duration, frames) => duration.or(frames),
+        }
+    }
+
+    // Warning shown in the HUD when the recording is about to be stopped.
+    fn recording_limit_warning(&self, now: NanoTimestamp) -> Option<String> {
+        if !self.is_recording || self.recording_limit_progress(now)? < RECORDING_LIMIT_WARNING {
+            return None;
+        }
+        let mut remaining = Vec::new();
+        if let Some(max_duration) = self.record_max_duration {
+            let secs = (max_duration.as_millis() - self.recorded_duration(now).as_millis()).max(0) / 1000;
+            remaining.push(format!("{} s", secs));
+        }
+        if let Some(max_frames) = self.record_max_frames {
+            let frames_left = max_frames.saturating_sub(self.num_total_recorded_frames());
+            remaining.push(format!("{} frames", frames_left));
+        }
+        Some(format!("Stops in {}", remaining.join(" / ")))
+    }
+
+    // Whether UI events are captured, either explicitly, by the flight
+    // recorder or for streaming.
+    fn is_capturing(&self) -> bool {
+        (self.is_recording && self.record_paused_at.is_none())
+            || self.flight_recorder.is_some()
+            || self.stream_sender.is_some()
+    }
+
+    /// Add a named marker at the current time of the recording. Does nothing
+    /// when not recording.
+    pub fn add_marker(&mut self, name: impl Into<String>, now: NanoTimestamp) {
+        if !self.is_recording {
+            return;
+        }
+        let marker = Marker {
+            name: name.into(),
+            time: now,
+        };
+        log::info!("Adding marker {:?}", marker);
+        self.recording.markers.push(marker);
+    }
+
+    /// Add an annotation at the current time of the recording, shown in the
+    /// timeline of the replay. Does nothing when not recording.
+    pub fn add_annotation(&mut self, text: impl Into<String>, now: NanoTimestamp) {
+        if !self.is_recording {
+            return;
+        }
+        let annotation = Annotation {
+            text: text.into(),
+            time: now,
+        };
+        log::info!("Adding annotation {:?}", annotation);
+        self.recording.annotations.push(annotation);
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.recording.annotations
+    }
+
+    // Show the input box of the annotation opened by its hotkey. Enter adds the
+    // annotation, Escape discards it.
+    fn show_annotation_editor(&mut self, ctx: &Context) {
+        let Some((time, text)) = &mut self.annotation_draft else {
+            return;
+        };
+        let time = *time;
+        let mut submitted = false;
+        egui::Window::new("Annotation")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                let response = ui.add(egui::TextEdit::singleline(text).hint_text("What are you doing?"));
+                submitted = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                response.request_focus();
+                ui.small("Enter to add, Escape to discard");
+            });
+        if submitted {
+            if let Some((_, text)) = self.annotation_draft.take() {
+                if !text.trim().is_empty() {
+                    self.add_annotation(text, time);
+                }
+            }
+        } else if ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+            self.annotation_draft = None;
+        }
+    }
+
+    /// Add an assertion at the current time of the recording, checked when the
+    /// recording is replayed. Does nothing when not recording.
+    pub fn add_assertion(&mut self, kind: AssertionKind, now: NanoTimestamp) {
+        if !self.is_recording {
+            return;
+        }
+        let assertion = Assertion { time: now, kind };
+        log::info!("Adding assertion {:?}", assertion);
+        self.recording.assertions.push(assertion);
+    }
+
+    /// Register a check for [`AssertionKind::Custom`] assertions with the
+    /// given name.
+    pub fn register_assertion_check(&mut self, name: impl Into<String>, check: impl Fn(&Context) -> bool + 'static) {
+        self.replay_assertions.register_check(name, Box::new(check));
+    }
+
+    /// Results of the assertions checked during the last replay.
+    pub fn assertion_report(&self) -> &AssertionReport {
+        self.replay_assertions.report()
+    }
+
+    /// Stop the current replay once all frames up to the named marker were
+    /// replayed. Returns false if no replay is running or the marker does not
+    /// exist.
+    pub fn replay_until_marker(&mut self, name: &str) -> bool {
+        if !self.is_replaying {
+            log::warn!("Cannot replay until marker {}, no replay is running", name);
+            return false;
+        }
+        let Some(marker) = self.recording.markers.iter().find(|marker| marker.name == name) else {
+            log::warn!("Marker {} not found in the replay", name);
+            return false;
+        };
+        self.replay_end_index = Some(self.marker_frame_index(marker));
+        true
+    }
+
+    // Start loading the selected input file on a background thread, see
+    // `update_background_io`.
+    fn start_loading_selected_file(&mut self) {
+        self.loaded_replay_file = Some(self.replay_file.clone());
+        self.replay_mismatches.clear();
+        self.recording = ReplayFile::default();
+        self.replay_lazy = None;
+        self.load_error = None;
+        self.load_diagnostics = LoadDiagnostics::default();
+        self.background_io.load(&self.replay_file, self.lenient_loading, false);
+    }
+
+    // Collect the finished background saves and loads, and keep polling the
+    // running ones.
+    fn update_background_io(&mut self, ctx: &Context) {
+        for (outcome, recording) in self.background_io.finished_saves() {
+            match (&outcome, recording) {
+                (IoOutcome::Saved(path), _) => self.record_metrics.measure_saved(path),
+                (IoOutcome::Failed(path, err), Some(recording)) => {
+                    self.keep_failed_save(&path.to_string_lossy(), recording, err);
+                }
+                _ => {}
+            }
+            self.background_io.push_outcome(outcome);
+        }
+        if let Some(load) = self.background_io.finished_load() {
+            let outcome = match load.result {
+                Ok((replay_file, diagnostics)) => {
+                    self.load_diagnostics = diagnostics;
+                    if load.starts_replay {
+                        self.start_replay_file(replay_file);
+                    } else {
+                        self.recording = replay_file;
+                        self.replay_lazy = None;
+                    }
+                    IoOutcome::Loaded(load.path)
+                }
+                Err(err) => {
+                    log::error!("Failed to load UI events from {}: {}", load.path.display(), err);
+                    self.load_error = Some(err.to_string());
+                    IoOutcome::Failed(load.path, err)
+                }
+            };
+            self.background_io.push_outcome(outcome);
+        }
+        if self.is_io_pending() {
+            ctx.request_repaint_after(IO_POLL_INTERVAL);
+        }
+    }
+
+    // The error of the recording which failed to save, with the path to
+    // retry saving it to.
+    fn show_failed_save(&mut self, ui: &mut egui::Ui) {
+        let Some(failed) = &mut self.failed_save else {
+            return;
+        };
+        ui.colored_label(
+            Color32::RED,
+            format!("Failed to save UI events to {}: {}", failed.file_name, failed.error),
+        );
+        ui.add(
+            egui::TextEdit::singleline(&mut failed.retry_path)
+                .hint_text("Save to")
+                .desired_width(ui.available_width()),
+        );
+        let retry_path = failed.retry_path.clone();
+        ui.horizontal(|ui| {
+            if ui.button("Retry").clicked() {
+                // A failed retry is kept and shown again.
+                let _ = self.retry_save(&retry_path);
+            }
+            if ui.button("Discard").clicked() {
+                self.discard_failed_save();
+            }
+        });
+        ui.separator();
+    }
+
+    // Load the selected input file, so it can be inspected before replaying.
+    fn load_selected_file(&mut self) {
+        self.loaded_replay_file = Some(self.replay_file.clone());
+        self.replay_mismatches.clear();
+        self.replay_lazy = None;
+        match self.load_replay_file(self.replay_file.clone()) {
+            Ok(replay_file) => {
+                self.recording = replay_file;
+                self.load_error = None;
+            }
+            Err(err) => {
+                self.recording = ReplayFile::default();
+                self.load_error = Some(err.to_string());
+            }
+        }
+    }
+
+    // Number of frames that were recorded before or at the marker.
+    fn marker_frame_index(&self, marker: &Marker) -> usize {
+        self.time_frame_index(marker.time)
+    }
+
+    // Number of frames that were recorded before or at the given time.
+    fn time_frame_index(&self, time: NanoTimestamp) -> usize {
+        self.recording.frames.partition_point(|frame| frame.time <= time)
+    }
+
+    /// Show a compact status display in the top right corner while recording
+    /// or replaying, with the frame counters and the elapsed time.
+    pub fn show_hud(&self, ctx: &Context) {
+        let (status, color, frames, started_at) = if self.is_recording_paused() {
+            (
+                "❚❚ PAUSED",
+                Color32::YELLOW,
+                format!("{} frames", self.num_recorded_frames()),
+                self.record_started_at,
+            )
+        } else if self.is_recording {
+            (
+                "● REC",
+                Color32::RED,
+                format!("{} frames", self.num_recorded_frames()),
+                self.record_started_at,
+            )
+        } else if self.is_replay_paused() {
+            (
+                "❚❚ BREAK",
+                Color32::YELLOW,
+                format!("{} / {}", self.replay_index, self.num_recorded_frames()),
+                self.replay_started_at,
+            )
+        } else if self.is_replaying {
+            (
+                "▶ PLAY",
+                Color32::GREEN,
+                format!("{} / {}", self.replay_index, self.num_recorded_frames()),
+                self.replay_started_at,
+            )
+        } else if self.failed_save.is_some() {
+            ("⚠ NOT SAVED", Color32::RED, String::new(), None)
+        } else {
+            return;
+        };
+        let shows_time = self.is_recording || self.is_replaying;
+        let elapsed = match (started_at, self.last_input_time) {
+            (Some(started_at), Some(now)) => (now - started_at).as_millis() as f64 / 1000.0,
+            _ => 0.0,
+        };
+        let limit_warning = self.last_input_time.and_then(|now| self.recording_limit_warning(now));
+        let estimated_sizes = if self.is_recording {
+            let sizes = self.record_metrics.estimated_sizes();
+            sizes
+                .iter()
+                .map(|(extension, size)| format!(".{} ~{}", extension, format_bytes(*size)))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        egui::Area::new(egui::Id::new(HUD_ID))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(color, egui::RichText::new(status).strong());
+                        ui.small(frames);
+                        if shows_time {
+                            ui.small(format!("{:02}:{:04.1}", (elapsed / 60.0) as u64, elapsed % 60.0));
+                        }
+                    });
+                    if let Some(failed) = &self.failed_save {
+                        ui.colored_label(Color32::RED, format!("Failed to save {}", failed.file_name));
+                        ui.small("Open the replay window to retry");
+                    }
+                    if !estimated_sizes.is_empty() {
+                        ui.small(estimated_sizes.join("  "));
+                    }
+                    if let Some(warning) = limit_warning {
+                        ui.colored_label(Color32::YELLOW, format!("⚠ {}", warning));
+                    }
+                });
+            });
+        // Keep the elapsed time running without input.
+        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+    }
+
+    // Draw the replay progress in the bottom right corner.
+    fn show_progress_badge(&self, ctx: &Context) {
+        egui::Area::new(egui::Id::new(PROGRESS_BADGE_ID))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.small(format!("Replay {} / {}", self.replay_index, self.num_recorded_frames()));
+                });
+            });
+    }
+
+    // List the log lines of the recording, with their time since the first
+    // frame.
+    fn show_recorded_logs(&self, ui: &mut egui::Ui) {
+        let start_time = self.recording.start_time().unwrap_or_default();
+        egui::CollapsingHeader::new(format!("Recorded logs ({})", self.recording.logs.len())).show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                for record in self.recording.logs.iter() {
+                    let color = match record.level() {
+                        Some(log::Level::Error) => Color32::RED,
+                        Some(log::Level::Warn) => Color32::YELLOW,
+                        _ => ui.visuals().text_color(),
+                    };
+                    let millis = (record.time - start_time).as_millis();
+                    ui.colored_label(color, format!("{:>8} ms {}", millis, record));
+                }
+            });
+        });
+    }
+
+    // Step and resume buttons while the replay is paused, and the
+    // breakpoints. The replay can't be paused from here, as the real input is
+    // replaced while replaying.
+    fn show_replay_controls(&mut self, ui: &mut egui::Ui) {
+        if self.replay_paused {
+            match self.hit_breakpoint().filter(|(index, _)| *index == self.replay_index) {
+                Some((index, RESTART_MARKER)) => {
+                    ui.label(format!("Paused for a restart of the app before frame {}", index + 1));
+                }
+                Some((index, name)) => {
+                    ui.label(format!("Paused at breakpoint {} before frame {}", name, index + 1));
+                }
+                None => {}
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Step").clicked() {
+                    self.step_replay();
+                }
+                if ui.button("Resume").clicked() {
+                    self.resume_replay();
+                }
+            });
+        } else {
+            ui.spinner();
+        }
+        if !self.replay_breakpoints.is_empty() {
+            egui::CollapsingHeader::new("Breakpoints").show(ui, |ui| {
+                for breakpoint in self.replay_breakpoints.iter_mut() {
+                    ui.checkbox(&mut breakpoint.enabled, &breakpoint.name);
+                }
+            });
+        }
+    }
+
+    // Draw a replay progress bar with the markers and annotations as ticks.
+    fn show_timeline(&self, ui: &mut egui::Ui) {
+        let num_frames = self.num_recorded_frames().max(1) as f32;
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 12.0), egui::Sense::hover());
+        let painter = ui.painter();
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let mut progress_rect = rect;
+        progress_rect.set_right(rect.left() + rect.width() * self.replay_index as f32 / num_frames);
+        painter.rect_filled(progress_rect, 2.0, ui.visuals().selection.bg_fill);
+        for marker in self.recording.markers.iter() {
+            let x = rect.left() + rect.width() * self.marker_frame_index(marker) as f32 / num_frames;
+            painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, Color32::YELLOW));
+        }
+        for annotation in self.recording.annotations.iter() {
+            let x = rect.left() + rect.width() * self.time_frame_index(annotation.time) as f32 / num_frames;
+            painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, Color32::LIGHT_BLUE));
+        }
+        let mut hover_text = Vec::new();
+        if !self.recording.markers.is_empty() {
+            let names: Vec<&str> = self
+                .recording
+                .markers
+                .iter()
+                .map(|marker| marker.name.as_str())
+                .collect();
+            hover_text.push(format!("Markers: {}", names.join(", ")));
+        }
+        let start_time = self.recording.start_time().unwrap_or_default();
+        for annotation in self.recording.annotations.iter() {
+            let millis = (annotation.time - start_time).as_millis();
+            hover_text.push(format!("{} ms: {}", millis, annotation.text));
+        }
+        if !hover_text.is_empty() {
+            response.on_hover_text(hover_text.join("\n"));
+        }
+    }
+
+    pub fn on_frame_update(&mut self, ctx: &Context) {
+        self.update_background_io(ctx);
+        self.show_annotation_editor(ctx);
+        // Shown once the replay finishes or pauses, its window isn't part of
+        // the frame hashes.
+        let between_frames = !self.is_replaying || self.replay_paused;
+        if let Some(comparison) = self.comparison.as_mut().filter(|_| between_frames) {
+            comparison.show(ctx);
+        }
+        if let Some(heatmap) = &self.heatmap_overlay {
+            heatmap.paint_overlay(ctx);
+        }
+        if self.is_replaying && (!self.show_overlay || !self.is_window_open) {
+            if self.show_progress_badge {
+                self.show_progress_badge(ctx);
+            }
+            return;
+        }
+        if !self.is_window_open {
+            return;
+        }
+
+        // Lookup for the latest input file if not set.
+        if self.should_lookup_replay {
+            if let Some(path) = self.record_output.latest_recording() {
+                self.replay_file = path.to_string_lossy().to_string();
+            }
+            self.recording_browser.refresh(&self.record_output);
+            self.recent_recordings.retain_existing();
+            self.should_lookup_replay = false;
+        }
+
+        if !self.is_replaying && self.loaded_replay_file.as_ref() != Some(&self.replay_file) {
+            if self.background_io.is_enabled() {
+                self.start_loading_selected_file();
+            } else {
+                self.load_selected_file();
+            }
+        }
+
+        let modal = Modal::new(ctx, MODAL_ID)
+            // Modal should not consume events when replaying.
+            // Otherwise it will block the input events from being processed.
+            .with_consume_events(!self.is_replaying)
+            .with_style(&ModalStyle {
+                overlay_color: Color32::from_rgba_premultiplied(0, 0, 0, 50),
+                ..Default::default()
+            });
+
+        modal.show(|ui| {
+            modal.title(ui, "Replay UI events");
+
+            modal.frame(ui, |ui| {
+                if self.is_replaying {
+                    ui.label(format!(
+                        "Frame {} / {}",
+                        self.replay_index + 1,
+                        self.num_recorded_frames()
+                    ));
+                    self.show_timeline(ui);
+                    self.show_replay_controls(ui);
+                } else {
+                    self.background_io.show_progress(ui);
+                    self.show_failed_save(ui);
+                    if !self.last_saved_files.is_empty() {
+                        ui.label(format!("Saved UI events to {}", self.last_saved_files.join(" and ")));
+                    }
+                    if let Some((path, metrics)) = self.record_metrics.last_saved() {
+                        egui::CollapsingHeader::new(format!("Last saved recording: {}", path.display()))
+                            .show(ui, |ui| metrics.show(ui));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.show_settings, false, "Replay");
+                        ui.selectable_value(&mut self.show_settings, true, "Settings");
+                    });
+                    ui.separator();
+                    if self.show_settings {
+                        let mut settings = self.settings();
+                        if settings.show(ui) {
+                            self.apply_settings(settings);
+                        }
+                        return;
+                    }
+                    if !self.recent_recordings.is_empty() {
+                        if let Some(path) = self.recent_recordings.show(ui) {
+                            self.replay_file = path;
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Recordings in {}:", self.record_output.dir().display()));
+                        if ui.small_button("⟳").on_hover_text("Refresh").clicked() {
+                            self.recording_browser.refresh(&self.record_output);
+                        }
+                    });
+                    match self.recording_browser.show(ui, &self.replay_file) {
+                        Some(BrowserAction::Select(path)) => {
+                            self.replay_file = path.to_string_lossy().to_string();
+                        }
+                        Some(BrowserAction::Delete(path)) => match self.recording_browser.delete(&path) {
+                            Ok(()) => {
+                                log::info!("Deleted {}", path.display());
+                                if path.to_string_lossy() == self.replay_file {
+                                    self.replay_file.clear();
+                                }
+                            }
+                            Err(err) => log::error!("Failed to delete {}: {}", path.display(), err),
+                        },
+                        None => {}
+                    }
+                    ui.horizontal(|ui| {
+                        #[cfg(feature = "rfd")]
+                        if ui.button("Browse…").clicked() {
+                            if let Some(path) = crate::browser::pick_recording(self.record_output.dir()) {
+                                self.replay_file = path.to_string_lossy().to_string();
+                            }
+                        }
+                        egui::ComboBox::from_id_salt("replay_recordings")
+                            .selected_text("Newest")
+                            .show_ui(ui, |ui| {
+                                let mut paths: Vec<PathBuf> = self
+                                    .recording_browser
+                                    .entries()
+                                    .iter()
+                                    .map(|entry| entry.path.clone())
+                                    .collect();
+                                sort_newest_first(&mut paths);
+                                for path in paths {
+                                    let label = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                                    let path = path.to_string_lossy().to_string();
+                                    ui.selectable_value(&mut self.replay_file, path, label);
+                                }
+                            });
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.replay_file)
+                                .hint_text("No input file found")
+                                .interactive(true)
+                                .desired_width(ui.available_width()),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.replay_speed, ReplaySpeed::FastForward, "Fast-forward");
+                        ui.radio_value(&mut self.replay_speed, ReplaySpeed::RealTime, "Real time");
+                    });
+                    ui.label("Replay until marker [optional]:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.replay_until_marker_name)
+                            .hint_text("Replay all frames")
+                            .desired_width(ui.available_width()),
+                    );
+                    if !self.replay_mismatches.is_empty() {
+                        ui.colored_label(
+                            Color32::YELLOW,
+                            "The recording may not match this session, replay anyway?",
+                        );
+                        for mismatch in self.replay_mismatches.iter() {
+                            ui.colored_label(Color32::YELLOW, mismatch);
+                        }
+                    }
+                    match &self.load_error {
+                        Some(err) => {
+                            ui.colored_label(Color32::RED, format!("Failed to load UI events: {}", err));
+                        }
+                        None => {
+                            if !self.load_diagnostics.is_empty() {
+                                ui.colored_label(
+                                    Color32::YELLOW,
+                                    format!(
+                                        "Skipped {} events which could not be loaded",
+                                        self.load_diagnostics.num_skipped()
+                                    ),
+                                );
+                            }
+                            self.stats().show(ui);
+                            egui::CollapsingHeader::new("Heatmap").show(ui, |ui| {
+                                InteractionHeatmap::from_recording(&self.recording).show(ui);
+                            });
+                            if !self.recording.logs.is_empty() {
+                                self.show_recorded_logs(ui);
+                            }
+                        }
+                    }
+                }
+            });
+
+            modal.buttons(ui, |ui| {
+                if self.is_replaying {
+                    return;
+                }
+                if self.show_settings {
+                    if modal.button(ui, "Close").clicked() {
+                        self.close_window();
+                    }
+                    return;
+                }
+
+                if !self.replay_mismatches.is_empty() {
+                    if modal.caution_button(ui, "Replay anyway").clicked() {
+                        self.replay_mismatches.clear();
+                        self.start_modal_replay();
+                    }
+                    if modal.button(ui, "Cancel").clicked() {
+                        self.replay_mismatches.clear();
+                    }
+                    return;
+                }
+                if modal.button(ui, "Start replay").clicked() {
+                    if self.background_io.is_loading() {
+                        log::warn!("Still loading UI events from {}", &self.replay_file);
+                    } else if self.recording.frames.is_empty() {
+                        log::error!("No UI events loaded from {}", &self.replay_file);
+                    } else {
+                        self.replay_mismatches = self.session_mismatches();
+                        if self.replay_mismatches.is_empty() {
+                            self.start_modal_replay();
+                        }
+                    }
+                }
+                if modal.button(ui, "Close").clicked() {
+                    self.close_window();
+                }
+                if let Some(converted_file) = converted_file_name(&self.replay_file) {
+                    if ui.button("Convert format").clicked() {
+                        match convert_replay(&self.replay_file, &converted_file) {
+                            Ok(()) => {
+                                log::info!("Converted {} to {}", &self.replay_file, &converted_file);
+                                self.replay_file = converted_file;
+                                self.recording_browser.refresh(&self.record_output);
+                            }
+                            Err(err) => {
+                                log::error!("Failed to convert UI events: {}", err);
+                            }
+                        }
+                    }
+                }
+            });
+        });
+
+        modal.open();
+    }
+
+    /// Call from the raw input hook of every viewport. Events are recorded and
+    /// injected per viewport. Take `now` from
+    /// [`crate::clock::MonotonicClock::shared`], so that adjustments of the
+    /// system clock don't distort the timing of recordings.
+    pub fn on_raw_input_update(&mut self, now: NanoTimestamp, ctx: &Context, raw_input: &mut egui::RawInput) {
+        self.pending_hash_frame = None;
+        self.pending_expected_hash = None;
+        self.last_input_time = Some(now);
+        self.last_input_viewport_id = raw_input.viewport_id;
+        self.record_current_frame = None;
+        self.replay_user_data.clear();
+        // The events of this frame target the widgets of the previous one.
+        let anchor_widgets = std::mem::take(&mut self.anchor_widgets);
+        self.handle_remote_commands(now);
+        #[cfg(feature = "gif")]
+        self.export_screenshots(raw_input);
+        if let Some(comparison) = &mut self.comparison {
+            comparison.receive_screenshots(&raw_input.events);
+        }
+        self.replay_assertions.check_due(ctx, self.last_copied_text.as_deref());
+        self.emit_replay_report();
+        self.settle_before_exit(ctx);
+
+        if let Some(path) = self.auto_replay_file.take() {
+            log::info!("Replaying {} on startup", path);
+            if let Err(err) = self.start_replay_from_file(&path) {
+                log::error!("Failed to load UI events from {}, closing the app: {}", path, err);
+                self.auto_replay_status = Some(ReplayStatus::Failed);
+                ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, egui::ViewportCommand::Close);
+            }
+        }
+
+        if self.is_replaying && self.replay_index < self.num_recorded_frames() {
+            if let Some(file) = &self.replay_lazy {
+                if self.replay_lazy_index != Some(self.replay_index) {
+                    match file.frame(self.replay_index) {
+                        Ok(mut frame) => {
+                            self.keyboard_mode.normalize_replay(std::slice::from_mut(&mut frame));
+                            self.recording.frames[self.replay_index] = frame;
+                        }
+                        Err(err) => {
+                            log::error!("Failed to decode frame {}: {}", self.replay_index + 1, err);
+                            self.finish_replay(true);
+                            return;
+                        }
+                    }
+                    self.replay_lazy_index = Some(self.replay_index);
+                }
+            }
+            let frame_viewport_id = self.recording.frames[self.replay_index].viewport_id;
+            if frame_viewport_id != raw_input.viewport_id {
+                // Block the real input, and wake up the viewport which the next
+                // frame belongs to.
+                raw_input.events.clear();
+                ctx.request_repaint_of(frame_viewport_id);
+                return;
+            }
+
+            let broke_here = matches!(&self.replay_break, Some((index, _)) if *index == self.replay_index);
+            if !self.replay_paused && !broke_here && self.is_restart_frame(self.replay_index) {
+                self.pause_for_restart();
+            }
+            if !self.replay_paused && !broke_here {
+                let frame = &self.recording.frames[self.replay_index];
+                let hit = self
+                    .replay_breakpoints
+                    .iter()
+                    .find(|breakpoint| breakpoint.matches(self.replay_index, frame));
+                if let Some(breakpoint) = hit {
+                    log::info!(
+                        "Paused the replay at breakpoint {} before frame {}",
+                        breakpoint.name,
+                        self.replay_index + 1
+                    );
+                    self.replay_break = Some((self.replay_index, breakpoint.name.clone()));
+                    self.replay_paused = true;
+                    self.replay_steps = 0;
+                }
+            }
+            if self.replay_paused {
+                if self.replay_steps == 0 {
+                    // The real input reaches the application while paused.
+                    return;
+                }
+                self.replay_steps -= 1;
+            }
+            if std::mem::take(&mut self.replay_reschedule) {
+                // Continue as if the frame was recorded now.
+                self.replay_idle.clear();
+                self.replay_clock.move_to(now);
+                if let Some(scheduler) = &mut self.replay_scheduler {
+                    scheduler.start_at(self.replay_index);
+                }
+            }
+
+            if self.replay_idle.is_idling(now) {
+                // Block the real input, and keep rendering empty frames.
+                raw_input.events.clear();
+                ctx.request_repaint();
+                return;
+            }
+
+            self.replay_clock.move_to(now);
+            if self.replay_speed == ReplaySpeed::RealTime {
+                let until_due = match &self.replay_scheduler {
+                    Some(scheduler) => scheduler.until_due(self.replay_index),
+                    None => NanoDelta::zero(),
+                };
+                if until_due > NanoDelta::zero() {
+                    // Block the real input while waiting for the frame.
+                    raw_input.events.clear();
+                    ctx.request_repaint_after(until_due.try_into().unwrap_or_default());
+                    return;
+                }
+            }
+
+            // Replay the events for the current frame index.
+            log::info!(
+                "Replaying frame {} / {}",
+                self.replay_index + 1,
+                self.num_recorded_frames()
+            );
+            if self.replay_started_at.is_none() {
+                self.replay_started_at = Some(now);
+                if let Some(scheduler) = &mut self.replay_scheduler {
+                    scheduler.start_at(self.replay_index);
+                }
+            }
+            self.replay_last_frame_at = Some(now);
+            let frame_time = self.recording.frames[self.replay_index].time;
+            self.pending_expected_hash = Some((self.replay_index, self.recording.frames[self.replay_index].frame_hash));
+            // The recording is kept intact, so that it can be replayed again.
+            // Lazily replayed frames are decoded again instead.
+            let mut frame = match self.replay_lazy {
+                Some(_) => {
+                    self.replay_lazy_index = None;
+                    let skeleton = FrameEvents::new(frame_time, Vec::new());
+                    std::mem::replace(&mut self.recording.frames[self.replay_index], skeleton)
+                }
+                None => self.recording.frames[self.replay_index].clone(),
+            };
+            self.replay_user_data = std::mem::take(&mut frame.user_data);
+            if let Some(anchor) = frame.pointer_anchor {
+                match ctx.read_response(anchor.widget_id) {
+                    Some(response) => anchor.reanchor(&mut frame.events, response.rect),
+                    None => log::debug!("Anchor widget {:?} not found", anchor.widget_id),
+                }
+            }
+            self.inject_frame(frame, raw_input);
+            self.replay_index += 1;
+            if let Some(next_frame) = self.recording.frames.get(self.replay_index) {
+                let paused = self.recording.paused_between(frame_time, next_frame.time);
+                let recorded_gap = next_frame.time - paused - frame_time;
+                self.replay_idle.schedule(now, recorded_gap);
+            }
+            if let Some(comparison) = &self.comparison {
+                comparison.request_screenshot(ctx, self.replay_index - 1);
+            }
+            #[cfg(feature = "gif")]
+            if self.gif_exporter.is_some() {
+                self.pending_screenshot_time = Some(frame_time);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+            }
+            if let Some(callback) = self.on_replay_progress.as_mut() {
+                callback(self.replay_index, self.recording.frames.len(), frame_time);
+            }
+            let end_index = self.replay_end_index.unwrap_or(self.num_recorded_frames());
+            // Assertions recorded after the last frame are checked too.
+            let is_last_frame = self.replay_index >= self.num_recorded_frames();
+            let due_until = (!is_last_frame).then_some(frame_time);
+            let assertions = &self.recording.assertions;
+            self.replay_assertions.schedule_until(assertions, due_until);
+            if self.replay_index >= end_index {
+                self.finish_replay(false);
+                // Unlike closing the window, this keeps the recording, to
+                // replay it again with `restart_replay`.
+                self.is_window_open = false;
+            }
+            // Flush the frames as fast as possible, egui would otherwise idle
+            // without real input. The report is emitted on the frame after
+            // the last one.
+            ctx.request_repaint();
+
+            for event in raw_input.events.iter() {
+                log::debug!("Replay event: {:?}", event);
+            }
+            if let Some(cursor) = &mut self.synthetic_cursor {
+                cursor.track(&raw_input.events, now);
+            }
+            return;
+        }
+
+        if !self.is_recording {
+            if let Some(receiver) = &self.stream_receiver {
+                self.stream_queue.extend(std::iter::from_fn(|| receiver.try_recv()));
+            }
+            if let Some(frame) = self.stream_queue.pop_front() {
+                if frame.viewport_id != raw_input.viewport_id {
+                    // Block the real input, and wake up the viewport which the
+                    // frame belongs to.
+                    raw_input.events.clear();
+                    ctx.request_repaint_of(frame.viewport_id);
+                    self.stream_queue.push_front(frame);
+                    return;
+                }
+                self.inject_frame(frame, raw_input);
+                if !self.stream_queue.is_empty() {
+                    ctx.request_repaint();
+                }
+                return;
+            }
+        }
+
+        if !self.is_recording && raw_input.viewport_id == egui::ViewportId::ROOT {
+            if let Some(source) = &mut self.fuzz_source {
+                let screen_rect = raw_input.screen_rect.unwrap_or_else(|| ctx.screen_rect());
+                let frame = source.next_frame(now, screen_rect);
+                self.inject_frame(frame, raw_input);
+                ctx.request_repaint();
+                return;
+            }
+        }
+
+        if self.is_recording && self.recording_limit_progress(now).is_some_and(|p| p >= 1.0) {
+            log::warn!("Recording limit reached, stopping the recording");
+            match self.stop_recording(now) {
+                Ok(file_name) => log::info!("Saved UI events to {}", file_name),
+                Err(err) => log::error!("Failed to save UI events: {}", err),
+            }
+        }
+
+        if self.is_recording && self.is_chapter_full(now) {
+            match self.save_chapter(now) {
+                Ok(file_name) => log::info!("Saved chapter to {}", file_name),
+                Err(err) => log::error!("Failed to save chapter: {}", err),
+            }
+        }
+
+        // Recording runs every frame, keep it cheap when nothing is recorded
+        // or logged.
+        let log_events = log::log_enabled!(log::Level::Debug);
+        let mut event_batch = if self.is_capturing() {
+            Vec::with_capacity(raw_input.events.len())
+        } else {
+            Vec::new()
+        };
+        for (i, event) in raw_input.events.iter().enumerate() {
+            if is_key_pressed(event) {
+                self.handle_hotkey(event, now, ctx, raw_input.viewport_id);
+            }
+
+            // Typing into the annotation is not part of the recording.
+            if self.is_capturing() && self.annotation_draft.is_none() {
+                if let egui::Event::PointerButton { pos, .. } = event {
+                    let records_buttons = self.recorded_event_types.contains(EventTypes::POINTER_BUTTONS);
+                    if self.pointer_simplification.drops_moves() && records_buttons {
+                        // This is needed because the simplification in should_
+                        // record_event does not capture the last pointer moved event,
+                        // so the last recorded position can be off.
+                        if log_events {
+                            log::debug!("Recording (fake) UI event: {:?} {:?}", i, event);
+                        }
+                        event_batch.push(egui::Event::PointerMoved(*pos));
+                    }
+                }
+
+                if self.should_record_event(event) {
+                    if log_events {
+                        log::debug!("Recording UI event: {:?} {:?}", i, event);
+                    }
+                    event_batch.push(event.clone());
+                }
+            }
+        }
+
+        #[cfg(feature = "accesskit")]
+        if let Some(recorder) = self.semantic_recorder.as_mut().filter(|_| self.is_recording) {
+            // The accessibility tree is only built while AccessKit is enabled.
+            ctx.enable_accesskit();
+            event_batch = recorder.translate(event_batch);
+        }
+
+        let mut hovered_files = None;
+        let mut dropped_files = Vec::new();
+        let mut focused = None;
+        if self.is_capturing() && self.recorded_event_types.contains(EventTypes::FILES) {
+            let current_hovered_files: Vec<RecordedFile> =
+                raw_input.hovered_files.iter().map(RecordedFile::from_hovered).collect();
+            if current_hovered_files != self.record_hovered_files {
+                self.record_hovered_files = current_hovered_files.clone();
+                hovered_files = Some(current_hovered_files);
+            }
+            dropped_files = raw_input
+                .dropped_files
+                .iter()
+                .map(|file| RecordedFile::from_dropped(file, self.embed_dropped_files_max_size))
+                .collect();
+        }
+        if self.is_capturing()
+            && self.recorded_event_types.contains(EventTypes::FOCUS)
+            && self.record_focused != Some(raw_input.focused)
+        {
+            self.record_focused = Some(raw_input.focused);
+            focused = Some(raw_input.focused);
+        }
+
+        if self.is_recording && raw_input.viewport_id == egui::ViewportId::ROOT {
+            if let Some(screen_rect) = raw_input.screen_rect {
+                let recorded = self.recording.screen_rect.get_or_insert(screen_rect);
+                *recorded = recorded.union(screen_rect);
+            }
+        }
+
+        let has_input =
+            !event_batch.is_empty() || hovered_files.is_some() || !dropped_files.is_empty() || focused.is_some();
+        if has_input || (self.record_preserve_frame_boundaries && self.is_capturing()) {
+            let mut frame = FrameEvents::new(now, event_batch)
+                .with_viewport_id(raw_input.viewport_id)
+                .with_modifiers(raw_input.modifiers);
+            frame.hovered_files = hovered_files;
+            frame.dropped_files = dropped_files;
+            frame.focused = focused;
+            if self.record_widget_anchors {
+                frame.pointer_anchor = find_pointer_anchor(&frame.events, &anchor_widgets);
+            }
+            if let Some(sender) = &self.stream_sender {
+                // Frames which are only streamed don't need to be cloned.
+                let is_kept = if self.is_recording {
+                    self.record_paused_at.is_none()
+                } else {
+                    self.flight_recorder.is_some()
+                };
+                if !is_kept {
+                    sender.send(frame);
+                    return;
+                }
+                sender.send(frame.clone());
+            }
+            if self.is_recording {
+                // Paused recordings only capture frames for streaming.
+                if self.record_paused_at.is_none() {
+                    self.push_recorded_frame(now, frame);
+                }
+            } else if let Some(flight_recorder) = &mut self.flight_recorder {
+                flight_recorder.push(frame);
+            }
+        }
+    }
+
+    // Act on a pressed key if it is one of the hotkeys.
+    fn handle_hotkey(&mut self, event: &egui::Event, now: NanoTimestamp, ctx: &Context, viewport_id: egui::ViewportId) {
+        // Start / stop recording events on the record key.
+        if is_key(event, self.hotkeys.record) {
+            if self.is_recording {
+                if let Err(err) = self.stop_recording(now) {
+                    log::error!("Failed to save UI events: {}", err);
+                }
+            } else {
+                self.start_recording_in(now, viewport_id);
+            }
+        }
+
+        // Add a marker on the marker key.
+        if self.is_recording && is_key(event, self.hotkeys.marker) {
+            let name = format!("marker_{}", self.recording.markers.len() + 1);
+            self.add_marker(name, now);
+        }
+
+        // Type an annotation on the annotation key.
+        if self.is_recording && is_key(event, self.hotkeys.annotation) {
+            self.annotation_draft.get_or_insert_with(|| (now, String::new()));
+        }
+
+        // Assert that the focused widget exists on the assertion key.
+        if self.is_recording && is_key(event, self.hotkeys.assertion) {
+            match ctx.memory(|mem| mem.focused()) {
+                Some(id) => self.add_assertion(AssertionKind::WidgetExists { id }, now),
+                None => log::warn!("No focused widget to add an assertion for"),
+            }
+        }
+
+        // Save the flight recorder buffer on the flight recorder key.
+        if self.flight_recorder.is_some() && !self.is_recording && is_key(event, self.hotkeys.flight_recorder) {
+            match self.save_flight_recorder(now) {
+                Ok(file_name) => log::info!("Saved flight recorder to {}", file_name),
+                Err(err) => log::error!("Failed to save flight recorder: {}", err),
+            }
+        }
+    }
+
+    // Replace the real input by the recorded input of the frame.
+    fn inject_frame(&mut self, frame: FrameEvents, raw_input: &mut egui::RawInput) {
+        raw_input.events = frame.events;
+        if let Some(hovered_files) = &frame.hovered_files {
+            self.replay_hovered_files = hovered_files.iter().map(RecordedFile::to_hovered).collect();
+        }
+        raw_input.hovered_files = self.replay_hovered_files.clone();
+        raw_input.dropped_files = frame.dropped_files.iter().map(RecordedFile::to_dropped).collect();
+        raw_input.modifiers = frame.modifiers;
+        if let Some(focused) = frame.focused {
+            self.replay_focused = focused;
+        }
+        if self.replay_force_focused {
+            raw_input.focused = true;
+            raw_input
+                .events
+                .retain(|event| !matches!(event, egui::Event::WindowFocused(false)));
+        } else {
+            raw_input.focused = self.replay_focused;
+        }
+        if let Some(transform) = &mut self.replay_transform {
+            transform(&mut raw_input.events);
+        }
+    }
+
+    /// Call at the end of each frame's update, after the UI was built, e.g.
+    /// also from an [`egui::Context::on_end_pass`] callback. This is the only
+    /// hook at the end of a frame: it captures or verifies the frame hashes,
+    /// see [`ReplayManager::with_frame_hashes`], and reads the widgets of
+    /// semantic recordings.
+    pub fn on_frame_end(&mut self, ctx: &Context) {
+        if self.capture_frame_hashes {
+            self.hash_frame(ctx);
+        }
+        #[cfg(feature = "accesskit")]
+        if let Some(recorder) = &mut self.semantic_recorder {
+            recorder.update_from_pass(ctx);
+        }
+        if let Some(cursor) = &mut self.synthetic_cursor {
+            cursor.paint(ctx, self.last_input_time.unwrap_or_default(), self.is_replaying);
+        }
+
+        // The last frame is replayed after the replay was already closed, its
+        // output is still needed for the pending assertions.
+        if !self.is_replaying && !self.replay_assertions.has_due() {
+            return;
+        }
+        let copied_text = ctx.output(|output| {
+            output.commands.iter().rev().find_map(|command| match command {
+                egui::OutputCommand::CopyText(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        if copied_text.is_some() {
+            self.last_copied_text = copied_text;
+        }
+    }
+
+    // Store the hash of the frame just recorded, or compare it with the
+    // recorded one if the frame was just replayed.
+    fn hash_frame(&mut self, ctx: &Context) {
+        let recorded = self.pending_hash_frame.take().filter(|_| self.is_recording);
+        let replayed = self.pending_expected_hash.take();
+        if recorded.is_none() && replayed.is_none() {
+            return;
+        }
+        let modal_id = egui::Id::new(MODAL_ID);
+        let skipped = [
+            egui::Id::new(HUD_ID),
+            egui::Id::new(PROGRESS_BADGE_ID),
+            modal_id,
+            modal_id.with("window"),
+            egui::Id::new(crate::compare::WINDOW_ID),
+        ];
+        let hash = hash_pass(ctx, &skipped);
+        if let Some(index) = recorded {
+            self.recording.frames[index].frame_hash = Some(hash);
+        }
+        let Some((index, Some(expected))) = replayed else {
+            return;
+        };
+        if expected != hash && !self.divergence_report.has_diverged() {
+            log::warn!(
+                "Replay diverged at frame {}: expected hash {:x}, got {:x}",
+                index + 1,
+                expected,
+                hash
+            );
+        }
+        self.divergence_report.compare(index, expected, hash);
+        if let Some(comparison) = &mut self.comparison {
+            comparison.compare(index, expected, hash);
+        }
+    }
+
+    fn should_record_event(&mut self, event: &egui::Event) -> bool {
+        if matches!(event, egui::Event::MouseMoved { .. }) {
+            return false;
+        }
+        if self.hotkeys.all().iter().any(|key| is_key(event, *key)) {
+            return false;
+        }
+        if !self.recorded_event_types.records(event) {
+            return false;
+        }
+        if is_pointer_moved(event) {
+            self.record_pointer_moves += 1;
+            let is_recorded = self.pointer_simplification.records_move(self.record_pointer_moves - 1);
+            if !is_recorded && self.is_recording {
+                self.record_metrics.add_dropped_events(1);
+            }
+            return is_recorded;
+        }
+        self.record_pointer_moves = 0;
+        if let egui::Event::Touch {
+            device_id, id, phase, ..
+        } = event
+        {
+            let touch = (*device_id, *id);
+            match phase {
+                egui::TouchPhase::Move if self.simplify_touch_events => {
+                    // Record only the first move of each touch.
+                    return self.record_moving_touches.insert(touch);
+                }
+                egui::TouchPhase::Move => {}
+                egui::TouchPhase::Start | egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                    self.record_moving_touches.remove(&touch);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn frame(time: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time), events)
+    }
+
+    fn text(text: &str) -> egui::Event {
+        egui::Event::Text(text.to_string())
+    }
+
+    fn preedit(text: &str) -> egui::Event {
+        egui::Event::Ime(egui::ImeEvent::Preedit(text.to_string()))
+    }
+
+    // Run the input pass of a focused frame at `now` with the given real
+    // events, and return the input passed on to the app.
+    fn input_pass(manager: &mut ReplayManager, ctx: &Context, now: i64, events: Vec<egui::Event>) -> egui::RawInput {
+        let mut raw_input = egui::RawInput {
+            events,
+            focused: true,
+            ..Default::default()
+        };
+        manager.on_raw_input_update(NanoTimestamp::from(now), ctx, &mut raw_input);
+        raw_input
+    }
+
+    // Run a whole frame, with `ui` between the input pass and the end of the
+    // frame.
+    fn run_frame(
+        manager: &mut ReplayManager,
+        ctx: &Context,
+        now: i64,
+        events: Vec<egui::Event>,
+        mut ui: impl FnMut(&mut ReplayManager, &Context),
+    ) {
+        let raw_input = input_pass(manager, ctx, now, events);
+        let _ = ctx.run(raw_input, |ctx| {
+            ui(manager, ctx);
+            manager.on_frame_end(ctx);
+        });
+    }
+
+    #[test]
+    fn postprocessing_merges_other_events() {
+        let frames = vec![
+            frame(0, vec![egui::Event::PointerMoved(egui::Pos2::ZERO)]),
+            frame(1, vec![text("a")]),
+            frame(2, vec![text("b")]),
+        ];
+
+        let merged = apply_event_postprocessing(frames);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1], frame(1, vec![text("a"), text("b")]));
+    }
+
+    #[test]
+    fn postprocessing_keeps_ime_frames_separate() {
+        let frames = vec![
+            frame(0, vec![egui::Event::PointerMoved(egui::Pos2::ZERO)]),
+            frame(1, vec![preedit("n")]),
+            frame(2, vec![preedit("ni")]),
+            frame(3, vec![egui::Event::Ime(egui::ImeEvent::Commit("你".to_string())), text("x")]),
+        ];
+
+        let merged = apply_event_postprocessing(frames);
+
+        assert_eq!(
+            merged[1..],
+            [
+                frame(1, vec![preedit("n")]),
+                frame(2, vec![preedit("ni")]),
+                frame(3, vec![egui::Event::Ime(egui::ImeEvent::Commit("你".to_string()))]),
+                frame(3, vec![text("x")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn programmatic_replay_injects_frames_and_reports() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+
+        manager.start_replay(vec![frame(0, vec![text("a")]), frame(1, vec![text("b")])]);
+        let injected: Vec<_> = (0..3)
+            .map(|_| input_pass(&mut manager, &ctx, 0, Vec::new()).events)
+            .collect();
+
+        assert_eq!(injected, vec![vec![text("a")], vec![text("b")], vec![]]);
+        assert!(!manager.is_replaying());
+        let report = manager.last_replay_report().unwrap();
+        assert_eq!(report.frames_played, 2);
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn replays_ignore_the_spilled_frames_of_a_discarded_recording() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_memory_cap(0);
+        manager.start_recording(NanoTimestamp::from(0));
+        for now in 1..4 {
+            input_pass(&mut manager, &ctx, now, vec![text("recorded")]);
+        }
+
+        manager.start_replay(vec![frame(0, vec![text("a")]), frame(1, vec![text("b")])]);
+        assert_eq!(manager.num_recorded_frames(), 2);
+        let injected: Vec<_> = (4..7)
+            .map(|now| input_pass(&mut manager, &ctx, now, Vec::new()).events)
+            .collect();
+
+        assert_eq!(injected, vec![vec![text("a")], vec![text("b")], vec![]]);
+        assert!(!manager.is_replaying());
+    }
+
+    #[test]
+    fn real_time_replay_waits_for_frame_time() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_replay_speed(ReplaySpeed::RealTime);
+        manager.start_replay(vec![frame(0, vec![text("a")]), frame(1_000, vec![text("b")])]);
+        let mut replay_at = |now: i64| input_pass(&mut manager, &ctx, now, Vec::new()).events;
+
+        assert_eq!(replay_at(5_000), vec![text("a")]);
+        assert_eq!(replay_at(5_500), vec![]);
+        assert_eq!(replay_at(6_000), vec![text("b")]);
+    }
+
+    #[test]
+    fn background_replay_starts_once_loaded() {
+        let ctx = Context::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("background_replay.json");
+        ReplayFile::new(vec![frame(0, vec![text("a")])]).save(&path).unwrap();
+        let mut manager = ReplayManager::new().with_background_io(true);
+
+        manager.start_replay_from_file_in_background(&path);
+        while manager.is_io_pending() {
+            manager.on_frame_update(&ctx);
+            std::thread::yield_now();
+        }
+
+        assert!(matches!(manager.poll_io(), Some(IoOutcome::Loaded(loaded)) if loaded == path));
+        assert!(manager.is_replaying());
+        assert_eq!(manager.recording().frames, vec![frame(0, vec![text("a")])]);
+    }
+
+    #[test]
+    fn replays_can_be_restarted() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+        let replay = |manager: &mut ReplayManager| -> Vec<Vec<egui::Event>> {
+            (0..3)
+                .map(|now| input_pass(manager, &ctx, now, Vec::new()).events)
+                .collect()
+        };
+
+        manager.start_replay(vec![frame(0, vec![text("a")]), frame(1, vec![text("b")])]);
+        let first = replay(&mut manager);
+        manager.restart_replay();
+        let second = replay(&mut manager);
+
+        assert_eq!(first, vec![vec![text("a")], vec![text("b")], vec![]]);
+        assert_eq!(second, first);
+        assert_eq!(manager.recording().frames[0].events, vec![text("a")]);
+    }
+
+    #[test]
+    fn indexed_recordings_are_replayed_lazily() {
+        let ctx = Context::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lazy.ibin");
+        ReplayFile::new(vec![frame(0, vec![text("a")]), frame(1, vec![text("b")])])
+            .save(&path)
+            .unwrap();
+        let mut manager = ReplayManager::new();
+
+        manager.start_replay_from_file(&path).unwrap();
+        let events: Vec<_> = (0..3)
+            .map(|now| input_pass(&mut manager, &ctx, now, Vec::new()).events)
+            .collect();
+
+        assert_eq!(events, vec![vec![text("a")], vec![text("b")], vec![]]);
+        // Replayed frames aren't kept in memory.
+        assert!(manager.recording().frames.iter().all(|frame| frame.events.is_empty()));
+    }
+
+    #[test]
+    fn empty_recordings_are_not_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.json");
+        ReplayFile::default().save(&path).unwrap();
+        let mut manager = ReplayManager::new();
+
+        let result = manager.start_replay_from_file(&path);
+
+        assert!(matches!(result, Err(ReplayError::EmptyRecording)));
+        assert!(!manager.is_replaying());
+    }
+
+    #[test]
+    fn replays_on_startup_report_their_status() {
+        let ctx = Context::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("startup.json");
+        ReplayFile::new(vec![frame(0, vec![text("a")])]).save(&path).unwrap();
+        let mut replayed = ReplayManager::new().with_auto_replay(path.to_string_lossy());
+        let mut missing = ReplayManager::new().with_auto_replay(dir.path().join("missing.json").to_string_lossy());
+
+        assert_eq!(replayed.auto_replay_status(), Some(ReplayStatus::Pending));
+        for now in 0..3 {
+            input_pass(&mut replayed, &ctx, now, Vec::new());
+        }
+        input_pass(&mut missing, &ctx, 0, Vec::new());
+
+        assert_eq!(replayed.auto_replay_status(), Some(ReplayStatus::Succeeded));
+        assert_eq!(missing.auto_replay_status(), Some(ReplayStatus::Failed));
+        assert_eq!(ReplayManager::new().auto_replay_status(), None);
+    }
+
+    #[test]
+    fn replayed_events_are_transformed_before_injection() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+        manager.set_replay_transform(Box::new(|events: &mut Vec<egui::Event>| {
+            events.retain(|event| *event != text("b"));
+            for event in events.iter_mut() {
+                if let egui::Event::PointerMoved(pos) = event {
+                    *pos += egui::vec2(10.0, 0.0);
+                }
+            }
+        }));
+        manager.start_replay(vec![
+            frame(0, vec![egui::Event::PointerMoved(egui::Pos2::ZERO), text("a")]),
+            frame(1, vec![text("b")]),
+        ]);
+        let events: Vec<_> = (0..2)
+            .map(|now| input_pass(&mut manager, &ctx, now, Vec::new()).events)
+            .collect();
+
+        let moved = egui::Event::PointerMoved(egui::pos2(10.0, 0.0));
+        assert_eq!(events, vec![vec![moved, text("a")], vec![]]);
+        assert_eq!(manager.recording().frames[1].events, vec![text("b")]);
+    }
+
+    #[test]
+    fn breakpoints_pause_the_replay() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+        manager.add_breakpoint(Breakpoint::on_event("Text b", |event| *event == text("b")));
+        manager.start_replay(vec![
+            frame(0, vec![text("a")]),
+            frame(1, vec![text("b")]),
+            frame(2, vec![text("c")]),
+        ]);
+        let replay = |manager: &mut ReplayManager, now: i64| input_pass(manager, &ctx, now, vec![text("real")]).events;
+
+        assert_eq!(replay(&mut manager, 0), vec![text("a")]);
+        assert_eq!(replay(&mut manager, 1), vec![text("real")]);
+        assert!(manager.is_replay_paused());
+        assert_eq!(manager.hit_breakpoint(), Some((1, "Text b")));
+
+        manager.step_replay();
+        assert_eq!(replay(&mut manager, 2), vec![text("b")]);
+        assert_eq!(replay(&mut manager, 3), vec![text("real")]);
+        manager.resume_replay();
+        assert_eq!(replay(&mut manager, 4), vec![text("c")]);
+        assert!(!manager.is_replay_paused());
+    }
+
+    #[test]
+    fn frame_hashes_are_captured_at_the_end_of_the_frame() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_frame_hashes(true);
+        let run = |manager: &mut ReplayManager, now: i64, events: Vec<egui::Event>, label: &str| {
+            run_frame(manager, &ctx, now, events, |manager, ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| ui.label(label));
+                manager.show_hud(ctx);
+            });
+        };
+
+        manager.start_recording(NanoTimestamp::from(0));
+        run(&mut manager, 1, vec![text("a")], "same");
+        run(&mut manager, 2, vec![text("b")], "recorded");
+        let recorded = manager.recording().frames.clone();
+        manager.start_replay(recorded.clone());
+        for (index, now) in (10..).take(recorded.len()).enumerate() {
+            let is_last = index + 1 == recorded.len();
+            run(&mut manager, now, Vec::new(), if is_last { "replayed" } else { "same" });
+        }
+
+        assert_eq!(recorded.iter().filter(|frame| frame.frame_hash.is_some()).count(), 2);
+        let report = manager.divergence_report();
+        assert_eq!(report.frames_compared, 2);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.first_divergence().unwrap().frame_index, recorded.len() - 1);
+    }
+
+    #[test]
+    fn replayed_frames_are_compared_with_the_recording() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_comparison(false);
+        let run = |manager: &mut ReplayManager, now: i64, events: Vec<egui::Event>, label: &str| {
+            run_frame(manager, &ctx, now, events, |manager, ctx| {
+                manager.on_frame_update(ctx);
+                egui::CentralPanel::default().show(ctx, |ui| ui.label(label));
+            });
+        };
+
+        manager.start_recording(NanoTimestamp::from(0));
+        for (now, label) in [(1, "a"), (2, "b"), (3, "c")] {
+            run(&mut manager, now, vec![text(label)], label);
+        }
+        let recorded = manager.recording().frames.clone();
+        manager.start_replay(recorded);
+        // The first frame was recorded when the recording started.
+        for (now, label) in [(10, ""), (11, "a"), (12, "changed"), (13, "c")] {
+            run(&mut manager, now, Vec::new(), label);
+        }
+
+        let comparison = manager.comparison().unwrap();
+        assert_eq!(comparison.frames().len(), 3);
+        assert_eq!(comparison.first_difference().map(|frame| frame.frame_index), Some(2));
+        // The comparison window shown with the last frame isn't hashed.
+        assert!(comparison.frames()[2].matches());
+    }
+
+    #[test]
+    fn idle_frames_are_rendered_between_replayed_frames() {
+        let ctx = Context::default();
+        let idle_frames = IdleFrames::FromTimestamps {
+            interval: NanoDelta::from(10),
+            max: 5,
+        };
+        let mut manager = ReplayManager::new().with_idle_frames(idle_frames);
+        manager.start_replay(vec![
+            frame(0, vec![text("a")]),
+            frame(25, vec![text("b")]),
+            frame(1_000, vec![text("c")]),
+        ]);
+        let replayed: Vec<Vec<egui::Event>> = (0..10)
+            .map(|now| input_pass(&mut manager, &ctx, now, Vec::new()).events)
+            .collect();
+
+        let mut expected = vec![vec![text("a")], vec![], vec![], vec![text("b")]];
+        // The idle frames are capped by `max`.
+        expected.extend(std::iter::repeat(vec![]).take(5));
+        expected.push(vec![text("c")]);
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn pointer_events_follow_their_anchor_widget() {
+        let button = egui::Rect::from_min_size(egui::pos2(10.0, 10.0), egui::vec2(20.0, 10.0));
+        let widgets = [
+            (egui::Id::new("panel"), egui::Rect::EVERYTHING),
+            (egui::Id::new("button"), button),
+        ];
+        let mut events = vec![
+            egui::Event::PointerMoved(egui::pos2(15.0, 12.0)),
+            egui::Event::PointerMoved(egui::pos2(100.0, 100.0)),
+        ];
+
+        let anchor = find_pointer_anchor(&events, &widgets).unwrap();
+        assert_eq!(anchor.widget_id, egui::Id::new("button"));
+        anchor.reanchor(&mut events, button.translate(egui::vec2(50.0, 0.0)));
+
+        assert_eq!(
+            events,
+            vec![
+                egui::Event::PointerMoved(egui::pos2(65.0, 12.0)),
+                egui::Event::PointerMoved(egui::pos2(100.0, 100.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn attached_data_is_replayed_with_its_frame() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+
+        manager.start_recording(NanoTimestamp::from(0));
+        input_pass(&mut manager, &ctx, 1, Vec::new());
+        manager.attach_data("state", [1]);
+        // Nothing changed, the data gets a frame of its own.
+        input_pass(&mut manager, &ctx, 2, Vec::new());
+        manager.attach_data("state", [2]);
+        let recording = manager.recording().clone();
+        manager.start_replay_file(recording);
+        let mut replayed = Vec::new();
+        for time in 3..6 {
+            input_pass(&mut manager, &ctx, time, Vec::new());
+            replayed.push(manager.replayed_data("state").map(<[u8]>::to_vec));
+        }
+
+        assert_eq!(replayed, vec![None, Some(vec![1]), Some(vec![2])]);
+    }
+
+    #[test]
+    fn mismatched_identity_is_reported() {
+        let mut manager = ReplayManager::new().with_app_identity("app 1.0");
+        manager.set_state_fingerprint("abc");
+        manager.start_replay_file(ReplayFile {
+            app_identity: Some("app 0.9".to_string()),
+            state_fingerprint: Some("abc".to_string()),
+            ..ReplayFile::new(vec![frame(0, vec![])])
+        });
+
+        assert_eq!(
+            manager.session_mismatches(),
+            vec!["Recorded by app 0.9, but this is app 1.0".to_string()]
+        );
+    }
+
+    #[test]
+    fn preserved_frame_boundaries_record_frames_without_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(RecordingOutput::default().with_dir(dir.path()))
+            .with_flight_recorder(FlightRecorderLimit::Frames(10))
+            .with_preserve_frame_boundaries(true);
+
+        for (time, events) in [(0, vec![text("a")]), (1, vec![]), (2, vec![text("b")])] {
+            input_pass(&mut manager, &ctx, time, events);
+        }
+
+        let file_name = manager.save_flight_recorder(NanoTimestamp::from(3)).unwrap();
+        let frames: Vec<Vec<egui::Event>> = ReplayFile::load(&file_name)
+            .unwrap()
+            .frames
+            .into_iter()
+            .map(|frame| frame.events)
+            .collect();
+        assert_eq!(frames, vec![vec![text("a")], vec![], vec![text("b")]]);
+    }
+
+    #[test]
+    fn typing_an_annotation_is_not_recorded() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+        manager.start_recording(NanoTimestamp::from(0));
+        let key = |key: egui::Key| egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        };
+
+        let frames = [
+            (1, vec![text("a"), key(egui::Key::F3)]),
+            (2, vec![text("note")]),
+            (3, vec![key(egui::Key::Enter)]),
+        ];
+        for (time, events) in frames {
+            run_frame(&mut manager, &ctx, time, events, |manager, ctx| {
+                manager.on_frame_update(ctx);
+            });
+        }
+
+        assert_eq!(manager.num_recorded_events(), 2);
+        assert_eq!(
+            manager.annotations(),
+            &[Annotation {
+                text: "note".to_string(),
+                time: NanoTimestamp::from(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn paused_recording_keeps_frames_and_records_the_gap() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new();
+        manager.start_recording(NanoTimestamp::from(0));
+
+        for (time, event) in [(1, "a"), (2, "b"), (5, "c")] {
+            match time {
+                2 => manager.pause_recording(NanoTimestamp::from(time)),
+                5 => manager.resume_recording(NanoTimestamp::from(time)),
+                _ => {}
+            }
+            input_pass(&mut manager, &ctx, time, vec![text(event)]);
+        }
+
+        let recording = manager.recording();
+        let texts: Vec<&egui::Event> = recording
+            .frames
+            .iter()
+            .flat_map(|frame| frame.events.iter())
+            .filter(|event| matches!(event, egui::Event::Text(_)))
+            .collect();
+        assert_eq!(texts, vec![&text("a"), &text("c")]);
+        assert_eq!(
+            recording.gaps,
+            vec![Gap {
+                time: NanoTimestamp::from(2),
+                duration: NanoDelta::from(3),
+            }]
+        );
+        assert_eq!(
+            recording.paused_between(NanoTimestamp::from(1), NanoTimestamp::from(5)),
+            NanoDelta::from(3)
+        );
+    }
+
+    #[test]
+    fn recording_stops_at_its_frame_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(output.clone())
+            .with_max_recording_frames(3);
+        manager.start_recording(NanoTimestamp::from(0));
+
+        for (time, event) in [(1, "a"), (2, "b"), (3, "c")] {
+            input_pass(&mut manager, &ctx, time, vec![text(event)]);
+            if time == 2 {
+                let warning = manager.recording_limit_warning(NanoTimestamp::from(time));
+                assert_eq!(warning.as_deref(), Some("Stops in 0 frames"));
+            }
+        }
+
+        assert!(!manager.is_recording());
+        assert_eq!(output.recordings().len(), 1);
+    }
+
+    #[test]
+    fn recording_metrics_report_the_saved_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(RecordingOutput::default().with_dir(dir.path()))
+            .with_recording_metrics(true);
+        manager.start_recording(NanoTimestamp::from(0));
+
+        let moves = (0..3).map(|i| egui::Event::PointerMoved(egui::pos2(i as f32, 0.0)));
+        input_pass(&mut manager, &ctx, 1, moves.chain([text("a")]).collect());
+        let live = manager.recording_metrics().unwrap();
+        let file_name = manager.stop_recording(NanoTimestamp::from(2)).unwrap();
+        let saved = manager.recording_metrics().unwrap();
+        let file_size = std::fs::metadata(&file_name).unwrap().len();
+
+        assert!(live.estimated_sizes.contains_key("bin"));
+        assert_eq!(live.events_dropped, 2);
+        assert_eq!(saved.events_dropped, 2);
+        assert_eq!(saved.saved_size, Some(file_size));
+        assert!(saved.compression_ratio().is_some());
+    }
+
+    #[test]
+    fn dual_output_saves_both_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(RecordingOutput::default().with_dir(dir.path()))
+            .with_dual_output(true);
+        manager.start_recording(NanoTimestamp::from(0));
+        input_pass(&mut manager, &ctx, 1, vec![text("a")]);
+
+        let file_name = manager.stop_recording(NanoTimestamp::from(2)).unwrap();
+        let saved_files = manager.last_saved_files().to_vec();
+        let binary = ReplayFile::load(&saved_files[0]).unwrap();
+        let json = ReplayFile::load(&saved_files[1]).unwrap();
+
+        assert_eq!(saved_files, vec![file_name.clone(), file_name.replace(".bin", ".json")]);
+        assert_eq!(binary, json);
+    }
+
+    #[test]
+    fn chained_recordings_continue_after_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_recording_output(output.clone());
+        manager.start_recording(NanoTimestamp::from(0));
+        input_pass(&mut manager, &ctx, 1, vec![text("a")]);
+        let file_name = manager.restart_recording(NanoTimestamp::from(2)).unwrap();
+
+        let mut relaunched = ReplayManager::new().with_recording_output(output.clone());
+        assert!(relaunched.resume_chained_recording(NanoTimestamp::from(10)).unwrap());
+        input_pass(&mut relaunched, &ctx, 11, vec![text("b")]);
+        assert_eq!(relaunched.stop_recording(NanoTimestamp::from(12)).unwrap(), file_name);
+        let recording = ReplayFile::load(&file_name).unwrap();
+        assert_eq!(recording.markers[0].name, RESTART_MARKER);
+        let paused = recording.paused_between(NanoTimestamp::from(0), NanoTimestamp::from(11));
+        assert_eq!(paused, NanoDelta::from(8));
+
+        let restarted_at = std::rc::Rc::new(std::cell::Cell::new(None));
+        let mut manager = ReplayManager::new().with_recording_output(output.clone());
+        let on_restart = restarted_at.clone();
+        manager.on_replay_restart(Box::new(move |index| on_restart.set(Some(index))));
+        manager.start_replay_from_file(&file_name).unwrap();
+        let replayed: Vec<egui::Event> = (0..recording.frames.len() as i64)
+            .flat_map(|now| input_pass(&mut manager, &ctx, now, Vec::new()).events)
+            .collect();
+        assert!(manager.is_replay_paused());
+        assert!(replayed.contains(&text("a")) && !replayed.contains(&text("b")));
+        let index = restarted_at.get().unwrap();
+        assert_eq!(manager.hit_breakpoint(), Some((index, RESTART_MARKER)));
+
+        let mut relaunched = ReplayManager::new().with_recording_output(output);
+        assert!(relaunched.resume_chained_replay().unwrap());
+        let replayed: Vec<egui::Event> = (0..recording.frames.len() as i64)
+            .flat_map(|now| input_pass(&mut relaunched, &ctx, now, Vec::new()).events)
+            .collect();
+
+        assert!(replayed.contains(&text("b")) && !replayed.contains(&text("a")));
+        assert!(!relaunched.resume_chained_replay().unwrap());
+    }
+
+    #[test]
+    fn only_the_selected_event_types_are_recorded() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_recorded_event_types(EventTypes::TEXT);
+        manager.start_recording(NanoTimestamp::from(0));
+        let events = vec![
+            egui::Event::PointerMoved(egui::Pos2::ZERO),
+            text("a"),
+            egui::Event::Zoom(2.0),
+        ];
+        input_pass(&mut manager, &ctx, 1, events);
+
+        // The first frame was recorded when the recording started.
+        let frames = &manager.recording().frames;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].events, vec![text("a")]);
+        assert_eq!(frames[1].focused, None);
+    }
+
+    #[test]
+    fn only_the_first_move_of_a_touch_is_recorded() {
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new().with_simplify_touch_events(true);
+        manager.start_recording(NanoTimestamp::from(0));
+        let touch = |phase: egui::TouchPhase, x: f32| egui::Event::Touch {
+            device_id: egui::TouchDeviceId(0),
+            id: egui::TouchId(0),
+            phase,
+            pos: egui::pos2(x, 0.0),
+            force: None,
+        };
+
+        let phases = [
+            egui::TouchPhase::Start,
+            egui::TouchPhase::Move,
+            egui::TouchPhase::Move,
+            egui::TouchPhase::End,
+        ];
+        for (time, phase) in phases.into_iter().enumerate() {
+            input_pass(&mut manager, &ctx, time as i64 + 1, vec![touch(phase, time as f32)]);
+        }
+
+        let touches: Vec<&egui::Event> = manager
+            .recording()
+            .frames
+            .iter()
+            .flat_map(|frame| frame.events.iter())
+            .filter(|event| matches!(event, egui::Event::Touch { .. }))
+            .collect();
+        assert_eq!(
+            touches,
+            vec![
+                &touch(egui::TouchPhase::Start, 0.0),
+                &touch(egui::TouchPhase::Move, 1.0),
+                &touch(egui::TouchPhase::End, 3.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_saves_can_be_retried_elsewhere() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("blocker");
+        let retry_path = dir.path().join("retry.bin");
+        std::fs::write(&blocker, "not a directory").unwrap();
+        let ctx = Context::default();
+        let mut manager =
+            ReplayManager::new().with_recording_output(RecordingOutput::default().with_dir(blocker.join("out")));
+        manager.start_recording(NanoTimestamp::from(0));
+        input_pass(&mut manager, &ctx, 1, vec![text("a")]);
+
+        assert!(manager.stop_recording(NanoTimestamp::from(2)).is_err());
+        assert!(manager.save_error().is_some());
+        manager.retry_save(&retry_path.to_string_lossy()).unwrap();
+        let saved = ReplayFile::load(&retry_path).unwrap();
+
+        assert!(manager.save_error().is_none());
+        assert!(saved.frames.iter().any(|frame| frame.events.contains(&text("a"))));
+    }
+
+    #[test]
+    fn failed_background_saves_can_be_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        let retry_path = dir.path().join("retry.bin");
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(output.clone())
+            .with_background_io(true);
+        // The recording can't replace a directory.
+        std::fs::create_dir_all(output.file_path(NanoTimestamp::from(2), "bin")).unwrap();
+        manager.start_recording(NanoTimestamp::from(0));
+        input_pass(&mut manager, &ctx, 1, vec![text("a")]);
+
+        manager.stop_recording(NanoTimestamp::from(2)).unwrap();
+        while manager.is_io_pending() {
+            manager.on_frame_update(&ctx);
+            std::thread::yield_now();
+        }
+        assert!(matches!(manager.poll_io(), Some(IoOutcome::Failed(..))));
+        assert!(manager.save_error().is_some());
+        manager.retry_save(&retry_path.to_string_lossy()).unwrap();
+        let saved = ReplayFile::load(&retry_path).unwrap();
+
+        assert!(manager.save_error().is_none());
+        assert!(saved.frames.iter().any(|frame| frame.events.contains(&text("a"))));
+    }
+
+    #[test]
+    fn long_recordings_are_saved_in_chapters() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = RecordingOutput::default().with_dir(dir.path());
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(output.clone())
+            .with_chapters(ChapterLength::Frames(2));
+        manager.start_recording(NanoTimestamp::from(0));
+
+        for (time, event) in [(1, "a"), (2, "b"), (3, "c")] {
+            input_pass(&mut manager, &ctx, time, vec![text(event)]);
+        }
+        let last_chapter = manager.stop_recording(NanoTimestamp::from(4)).unwrap();
+
+        let chapters = output.recordings();
+        let loaded = load_chapters(&chapters[0]).unwrap();
+
+        assert_eq!(chapters.len(), 2);
+        assert!(last_chapter.ends_with("_part002.bin"));
+        let texts: Vec<&egui::Event> = loaded
+            .frames
+            .iter()
+            .flat_map(|frame| frame.events.iter())
+            .filter(|event| matches!(event, egui::Event::Text(_)))
+            .collect();
+        assert_eq!(texts, vec![&text("a"), &text("b"), &text("c")]);
+    }
+
+    #[test]
+    fn hotkeys_follow_the_settings() {
+        let ctx = Context::default();
+        let mut settings = ReplaySettings::default();
+        settings.hotkeys.record = egui::Key::F9;
+        let mut manager = ReplayManager::new().with_settings(settings.clone());
+        let f9 = egui::Event::Key {
+            key: egui::Key::F9,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        };
+
+        input_pass(&mut manager, &ctx, 0, vec![f9]);
+
+        assert!(manager.is_recording());
+        assert_eq!(manager.settings(), settings);
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn settings_survive_restarts() {
+        let mut storage = MemoryStorage::default();
+        let settings = ReplaySettings {
+            use_bincode: false,
+            output_dir: PathBuf::from("recordings"),
+            replay_speed: ReplaySpeed::RealTime,
+            hotkeys: Hotkeys {
+                marker: egui::Key::F10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let manager = ReplayManager::new().with_settings(settings.clone());
+        manager.save_state(&mut storage);
+
+        let mut restarted = ReplayManager::new();
+        restarted.load_state(&storage);
+
+        assert_eq!(restarted.settings(), settings);
+    }
+
+    #[test]
+    fn flight_recorder_keeps_last_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::default();
+        let mut manager = ReplayManager::new()
+            .with_recording_output(RecordingOutput::default().with_dir(dir.path()))
+            .with_flight_recorder(FlightRecorderLimit::Frames(2));
+
+        for (time, event) in [(0, "a"), (1, "b"), (2, "c")] {
+            input_pass(&mut manager, &ctx, time, vec![text(event)]);
+        }
+
+        let file_name = manager.save_flight_recorder(NanoTimestamp::from(3)).unwrap();
+        let frames: Vec<Vec<egui::Event>> = ReplayFile::load(&file_name)
+            .unwrap()
+            .frames
+            .into_iter()
+            .map(|frame| frame.events)
+            .collect();
+        assert!(!manager.is_recording());
+        assert_eq!(frames, vec![vec![text("b")], vec![text("c")]]);
     }
 }
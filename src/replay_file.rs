@@ -0,0 +1,572 @@
+//! Loading and saving of recordings.
+//!
+//! The format is chosen by the file extension, see [`crate::codec`] for the
+//! supported formats.
+//!
+//! Recordings are saved atomically: they are written to a `.partial` file
+//! next to the recording, which is renamed once it was written completely. An
+//! interrupted save leaves the previous recording, if any, intact, and
+//! `.partial` files are never listed as recordings.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use crate::assertions::Assertion;
+use crate::audio::AudioTrack;
+use crate::codec::registered_codecs;
+use crate::logs::LogRecord;
+use crate::replay_events::{Annotation, FrameEvents, Gap, Marker};
+use crate::script::{render_script, script_steps, ScriptFormat};
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// Error type for loading and saving recordings.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to decode binary recording: {0}")]
+    BincodeDecode(#[from] bincode::error::DecodeError),
+    #[error("Failed to encode binary recording: {0}")]
+    BincodeEncode(#[from] bincode::error::EncodeError),
+    #[error("Failed to process JSON recording: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("Failed to encode MessagePack recording: {0}")]
+    MsgPackEncode(#[from] rmp_serde::encode::Error),
+    #[cfg(feature = "msgpack")]
+    #[error("Failed to decode MessagePack recording: {0}")]
+    MsgPackDecode(#[from] rmp_serde::decode::Error),
+    #[cfg(feature = "cbor")]
+    #[error("Failed to encode CBOR recording: {0}")]
+    CborEncode(#[from] ciborium::ser::Error<std::io::Error>),
+    #[cfg(feature = "cbor")]
+    #[error("Failed to decode CBOR recording: {0}")]
+    CborDecode(#[from] ciborium::de::Error<std::io::Error>),
+    #[error("Unknown file extension: {0}")]
+    UnknownExtension(String),
+    #[error("Unsupported recording format version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("No recording in progress")]
+    NotRecording,
+    #[error("No UI events in the recording")]
+    EmptyRecording,
+    #[error("Replay is disabled, enable the `replay` feature")]
+    Disabled,
+    #[error("Failed to process recording: {0}")]
+    Codec(String),
+    #[cfg(feature = "encryption")]
+    #[error("Failed to decrypt recording, wrong key or corrupted file")]
+    Decryption,
+}
+
+/// An event skipped by [`ReplayFile::load_lenient`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkippedEvent {
+    /// Index of the frame the event belonged to.
+    pub frame_index: usize,
+    pub reason: String,
+}
+
+/// Problems found by [`ReplayFile::load_lenient`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadDiagnostics {
+    pub skipped_events: Vec<SkippedEvent>,
+}
+
+impl LoadDiagnostics {
+    pub fn num_skipped(&self) -> usize {
+        self.skipped_events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skipped_events.is_empty()
+    }
+}
+
+/// A recording of UI events, as stored on disk.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, Encode, Decode)]
+pub struct ReplayFile {
+    /// Seeds of the random number generators of the application, by name,
+    /// see [`crate::replay_events::ReplayManager::register_rng_seed`].
+    #[serde(default)]
+    pub rng_seeds: BTreeMap<String, u64>,
+    /// State of the application when the recording started, see
+    /// [`crate::snapshot::ReplayStateSnapshot`].
+    #[serde(default)]
+    pub state_snapshot: Option<Vec<u8>>,
+    /// Identity of the application which made the recording, e.g. its name
+    /// and version.
+    #[serde(default)]
+    pub app_identity: Option<String>,
+    /// Fingerprint of the application state when the recording started.
+    #[serde(default)]
+    pub state_fingerprint: Option<String>,
+    /// Bounding rectangle of the screen of the root viewport during recording.
+    #[serde(default)]
+    #[bincode(with_serde)]
+    pub screen_rect: Option<egui::Rect>,
+    pub frames: Vec<FrameEvents>,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+    /// Notes typed during recording.
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Pauses of the recording.
+    #[serde(default)]
+    pub gaps: Vec<Gap>,
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+    /// Log lines of the application captured during recording.
+    #[serde(default)]
+    pub logs: Vec<LogRecord>,
+    /// Narration of the recording, see [`crate::audio`].
+    #[serde(default)]
+    pub audio: Option<AudioTrack>,
+}
+
+impl ReplayFile {
+    pub fn new(frames: Vec<FrameEvents>) -> Self {
+        Self {
+            frames,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn num_events(&self) -> usize {
+        self.frames.iter().map(|frame| frame.events.len()).sum()
+    }
+
+    /// Time of the first frame, if any.
+    pub fn start_time(&self) -> Option<NanoTimestamp> {
+        self.frames.first().map(|frame| frame.time)
+    }
+
+    /// Time of the last frame, if any.
+    pub fn end_time(&self) -> Option<NanoTimestamp> {
+        self.frames.last().map(|frame| frame.time)
+    }
+
+    /// Total duration of the pauses of the recording which started within
+    /// `from..to`.
+    pub fn paused_between(&self, from: NanoTimestamp, to: NanoTimestamp) -> NanoDelta {
+        let nanos = self
+            .gaps
+            .iter()
+            .filter(|gap| gap.time >= from && gap.time < to)
+            .map(|gap| gap.duration.as_nanos())
+            .sum();
+        NanoDelta::from_nanos(nanos)
+    }
+
+    /// Copy of the frames within the given index range. Out of bounds indices
+    /// are clamped, timestamps are kept as recorded. Markers within the time
+    /// span of the selected frames are kept, as are annotations, pauses and
+    /// assertions. The state snapshot and fingerprint are only kept if the
+    /// slice starts at the first frame.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let len = self.frames.len();
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        }
+        .min(len);
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(end);
+        let frames = self.frames[start..end].to_vec();
+        let (first_time, last_time) = match (frames.first(), frames.last()) {
+            (Some(first), Some(last)) => (first.time, last.time),
+            _ => return Self::new(frames),
+        };
+        let in_range = |time: NanoTimestamp| time >= first_time && time <= last_time;
+        Self {
+            rng_seeds: self.rng_seeds.clone(),
+            state_snapshot: self.state_snapshot.clone().filter(|_| start == 0),
+            app_identity: self.app_identity.clone(),
+            state_fingerprint: self.state_fingerprint.clone().filter(|_| start == 0),
+            screen_rect: self.screen_rect,
+            frames,
+            markers: self.markers.iter().filter(|m| in_range(m.time)).cloned().collect(),
+            annotations: self.annotations.iter().filter(|a| in_range(a.time)).cloned().collect(),
+            gaps: self.gaps.iter().filter(|g| in_range(g.time)).cloned().collect(),
+            assertions: self.assertions.iter().filter(|a| in_range(a.time)).cloned().collect(),
+            logs: self.logs.iter().filter(|l| in_range(l.time)).cloned().collect(),
+            audio: self.audio.clone(),
+        }
+    }
+
+    /// Append another recording. Its timestamps are shifted so that it starts
+    /// at the time this recording ends. Only the audio of this recording is
+    /// kept, or of the other one if this one has none.
+    pub fn concat(self, other: ReplayFile) -> Self {
+        let other = match self.end_time() {
+            Some(end_time) => other.retime(end_time),
+            None => other,
+        };
+        self.append(other)
+    }
+
+    /// Append a recording which continues this one, e.g. its next chapter,
+    /// keeping the timestamps as recorded. Only the audio of this recording
+    /// is kept, or of the other one if this one has none.
+    pub fn append(mut self, other: ReplayFile) -> Self {
+        self.frames.extend(other.frames);
+        self.markers.extend(other.markers);
+        self.annotations.extend(other.annotations);
+        self.gaps.extend(other.gaps);
+        self.assertions.extend(other.assertions);
+        self.logs.extend(other.logs);
+        self.audio = self.audio.or(other.audio);
+        self
+    }
+
+    /// Shift all timestamps so that the first frame starts at `start`. The
+    /// time between frames is preserved.
+    pub fn retime(mut self, start: NanoTimestamp) -> Self {
+        if let Some(start_time) = self.start_time() {
+            let offset = start - start_time;
+            for frame in self.frames.iter_mut() {
+                frame.time = frame.time + offset;
+            }
+            for marker in self.markers.iter_mut() {
+                marker.time = marker.time + offset;
+            }
+            for annotation in self.annotations.iter_mut() {
+                annotation.time = annotation.time + offset;
+            }
+            for gap in self.gaps.iter_mut() {
+                gap.time = gap.time + offset;
+            }
+            for assertion in self.assertions.iter_mut() {
+                assertion.time = assertion.time + offset;
+            }
+            for log in self.logs.iter_mut() {
+                log.time = log.time + offset;
+            }
+            if let Some(audio) = &mut self.audio {
+                audio.start = audio.start + offset;
+            }
+        }
+        self
+    }
+
+    /// Load a recording, choosing the format by the file extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let path = path.as_ref();
+        let codec = registered_codecs().codec_for_path(path)?;
+        let bytes = std::fs::read(path)?;
+        let file = codec.decode(&bytes)?;
+        log::info!(
+            "Loaded {} frames, {} events, from {}",
+            file.num_frames(),
+            file.num_events(),
+            path.display()
+        );
+        Ok(file)
+    }
+
+    /// Like [`ReplayFile::load`], but events which fail to load, e.g. events
+    /// unknown to this version of egui, are skipped and reported in the
+    /// diagnostics instead of failing the whole load. Unknown event variants
+    /// can only be skipped in JSON recordings, other formats only skip events
+    /// which are known but cannot be converted, e.g. keys of a newer egui.
+    pub fn load_lenient(path: impl AsRef<Path>) -> Result<(Self, LoadDiagnostics), ReplayError> {
+        let path = path.as_ref();
+        let codec = registered_codecs().codec_for_path(path)?;
+        let bytes = std::fs::read(path)?;
+        let (file, diagnostics) = codec.decode_lenient(&bytes)?;
+        for skipped in diagnostics.skipped_events.iter() {
+            log::warn!(
+                "Skipped event of frame {} in {}: {}",
+                skipped.frame_index + 1,
+                path.display(),
+                skipped.reason
+            );
+        }
+        log::info!(
+            "Loaded {} frames, {} events, from {}",
+            file.num_frames(),
+            file.num_events(),
+            path.display()
+        );
+        Ok((file, diagnostics))
+    }
+
+    /// Save the recording, choosing the format by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let path = path.as_ref();
+        let codec = registered_codecs().codec_for_path(path)?;
+        write_atomically(path, |writer| codec.encode(self, writer))?;
+        log::info!(
+            "Saved {} frames, {} events, to {}",
+            self.num_frames(),
+            self.num_events(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Save the recording as human-readable automation steps, to be turned
+    /// into a scripted test.
+    pub fn export_script(&self, path: impl AsRef<Path>, format: ScriptFormat) -> Result<(), ReplayError> {
+        let script = render_script(&script_steps(&self.frames), format);
+        std::fs::write(path, script)?;
+        Ok(())
+    }
+}
+
+/// Extension appended to recordings while they are written, see the module
+/// docs.
+pub const PARTIAL_EXTENSION: &str = "partial";
+
+/// Path of the file `path` is written to before it is renamed, see the module
+/// docs.
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(PARTIAL_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// Write a file with `write` into its partial file, and rename it to `path`
+/// once written and synced to disk. The partial file is removed on failure.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), ReplayError>,
+) -> Result<(), ReplayError> {
+    let partial = partial_path(path);
+    let result = (|| -> Result<(), ReplayError> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&partial)?);
+        write(&mut writer)?;
+        let file = writer.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        // The partial file may not exist, e.g. if it could not be created.
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Convert a recording between formats. The input and output formats are
+/// chosen by the file extensions, all frames are preserved.
+pub fn convert_replay(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>) -> Result<(), ReplayError> {
+    ReplayFile::load(input_path)?.save(output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_file() -> ReplayFile {
+        ReplayFile::new(vec![
+            FrameEvents {
+                frame_hash: Some(42),
+                user_data: [("state".to_string(), vec![1, 2, 3])].into_iter().collect(),
+                ..FrameEvents::new(
+                    NanoTimestamp::from(1_000),
+                    vec![egui::Event::PointerMoved(egui::Pos2::new(1.0, 2.0))],
+                )
+            },
+            FrameEvents::new(NanoTimestamp::from(2_000), vec![egui::Event::Text("hello".to_string())])
+                .with_viewport_id(egui::ViewportId::from_hash_of("second_viewport")),
+        ])
+    }
+
+    fn sample_file_with_marker() -> ReplayFile {
+        let mut file = sample_file();
+        file.markers.push(Marker {
+            name: "after_hello".to_string(),
+            time: NanoTimestamp::from(2_000),
+        });
+        file.rng_seeds.insert("world".to_string(), 7);
+        file.annotations.push(Annotation {
+            text: "typed hello".to_string(),
+            time: NanoTimestamp::from(1_500),
+        });
+        file.audio = Some(AudioTrack::file("narration.ogg", NanoTimestamp::from(500)));
+        file
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("round_trip.bin");
+        let file = sample_file_with_marker();
+
+        file.save(&path).unwrap();
+        let loaded = ReplayFile::load(&path).unwrap();
+
+        assert_eq!(loaded, file);
+    }
+
+    #[test]
+    fn failed_saves_keep_the_previous_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("atomic.json");
+        let file = sample_file_with_marker();
+        file.save(&path).unwrap();
+
+        let result = write_atomically(&path, |writer| {
+            writer.write_all(b"[")?;
+            Err(ReplayError::Codec("interrupted".to_string()))
+        });
+        let loaded = ReplayFile::load(&path).unwrap();
+        let partial_exists = partial_path(&path).exists();
+
+        assert!(result.is_err());
+        assert!(!partial_exists);
+        assert_eq!(loaded, file);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("round_trip.json");
+        let file = sample_file_with_marker();
+
+        file.save(&path).unwrap();
+        let loaded = ReplayFile::load(&path).unwrap();
+
+        assert_eq!(loaded, file);
+    }
+
+    #[test]
+    fn slice_clamps_range() {
+        let file = sample_file();
+
+        assert_eq!(file.slice(1..).frames, file.frames[1..].to_vec());
+        assert_eq!(file.slice(..=0).frames, file.frames[..1].to_vec());
+        assert_eq!(file.slice(1..10).num_frames(), 1);
+        assert_eq!(file.slice(5..10).num_frames(), 0);
+    }
+
+    #[test]
+    fn retime_preserves_frame_intervals() {
+        let file = sample_file().retime(NanoTimestamp::from(10_000));
+
+        assert_eq!(file.start_time(), Some(NanoTimestamp::from(10_000)));
+        assert_eq!(file.end_time(), Some(NanoTimestamp::from(11_000)));
+    }
+
+    #[test]
+    fn concat_continues_at_end_time() {
+        let file = sample_file().concat(sample_file());
+        let times: Vec<i64> = file.frames.iter().map(|frame| frame.time.as_nanos()).collect();
+
+        assert_eq!(times, vec![1_000, 2_000, 2_000, 3_000]);
+    }
+
+    #[test]
+    fn slice_keeps_markers_in_range() {
+        let file = sample_file_with_marker();
+
+        assert_eq!(file.slice(1..).markers, file.markers);
+        assert!(file.slice(..1).markers.is_empty());
+    }
+
+    // JSON of the frame as written before events were stored as
+    // `ReplayEvent`s.
+    fn with_egui_events(frame: &FrameEvents) -> serde_json::Value {
+        let mut json = serde_json::to_value(frame).unwrap();
+        json["events"] = serde_json::to_value(&frame.events).unwrap();
+        json
+    }
+
+    // The sample without the data added after `ReplayEvent`s were introduced.
+    fn sample_file_with_egui_events() -> ReplayFile {
+        let mut file = sample_file_with_marker();
+        file.rng_seeds.clear();
+        file.annotations.clear();
+        file.audio = None;
+        for frame in file.frames.iter_mut() {
+            frame.user_data.clear();
+        }
+        file
+    }
+
+    #[test]
+    fn json_with_egui_events_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("egui_events.json");
+        let file = sample_file_with_egui_events();
+        let json = serde_json::json!({
+            "frames": file.frames.iter().map(with_egui_events).collect::<Vec<_>>(),
+            "markers": file.markers,
+        });
+        std::fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+
+        let loaded = ReplayFile::load(&path).unwrap();
+
+        assert_eq!(loaded, file);
+    }
+
+    #[test]
+    fn lenient_load_skips_unknown_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unknown_events.json");
+        let json = serde_json::json!({
+            "frames": [{
+                "time": 1_000,
+                "events": [
+                    {"text": {"text": "a"}},
+                    {"swipe": {"direction": "left"}},
+                    {"key": {"key": "NoSuchKey", "physical_key": null, "pressed": true, "repeat": false,
+                             "modifiers": {"alt": false, "ctrl": false, "shift": false, "mac_cmd": false, "command": false}}},
+                ],
+            }],
+        });
+        std::fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+
+        let strict = ReplayFile::load(&path);
+        let (file, diagnostics) = ReplayFile::load_lenient(&path).unwrap();
+
+        assert!(strict.is_err());
+        assert_eq!(file.frames[0].events, vec![egui::Event::Text("a".to_string())]);
+        assert_eq!(diagnostics.num_skipped(), 2);
+        assert_eq!(diagnostics.skipped_events[1].reason, "Unknown key: NoSuchKey");
+    }
+
+    #[test]
+    fn legacy_json_array_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.json");
+        let frames = sample_file_with_egui_events().frames;
+        let json: Vec<serde_json::Value> = frames.iter().map(with_egui_events).collect();
+        std::fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+
+        let loaded = ReplayFile::load(&path).unwrap();
+
+        assert_eq!(loaded.frames, frames);
+        assert!(loaded.markers.is_empty());
+    }
+
+    #[test]
+    fn unknown_extension_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = sample_file().save(dir.path().join("file.txt"));
+        assert!(matches!(result, Err(ReplayError::UnknownExtension(_))));
+    }
+
+    #[test]
+    fn legacy_binary_example_loads() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/egui_replay_example.bin");
+        let file = ReplayFile::load(path).unwrap();
+        assert!(file.num_frames() > 0);
+    }
+}
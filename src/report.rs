@@ -0,0 +1,329 @@
+//! Summary of a finished replay, and its machine-readable forms for CI: a
+//! JUnit XML file or a JSON file with one entry per replayed recording.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::assertions::AssertionReport;
+use crate::divergence::DivergenceReport;
+use crate::logs::LogDifference;
+use crate::replay_file::ReplayError;
+use crate::timestamp::{NanoDelta, NanoTimestamp, NANOS_PER_SECOND};
+
+/// Result of a replay, produced once the replay finished or was aborted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayReport {
+    /// Path of the replayed recording, if it was loaded from a file.
+    pub recording: Option<String>,
+    /// Time at which the first frame was replayed, if any.
+    pub start_time: Option<NanoTimestamp>,
+    /// Time at which the last frame was replayed, if any.
+    pub end_time: Option<NanoTimestamp>,
+    pub frames_played: usize,
+    /// Number of frames of the replayed recording.
+    pub num_frames: usize,
+    /// Whether the replay was stopped before reaching its end.
+    pub aborted: bool,
+    pub assertions: AssertionReport,
+    pub divergence: DivergenceReport,
+    /// Log lines logged differently than during recording, if the recording
+    /// has captured log lines. They don't make the replay fail, since logs
+    /// often contain e.g. times.
+    pub log_differences: Vec<LogDifference>,
+}
+
+/// Outcome of the replay on startup requested by
+/// [`crate::replay_events::ReplayManager::with_auto_replay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayStatus {
+    /// The recording is not replayed yet, or still replaying.
+    Pending,
+    /// All replays succeeded, see [`ReplayReport::is_success`].
+    Succeeded,
+    /// The recording could not be loaded, or a replay did not succeed. The
+    /// app is closed.
+    Failed,
+}
+
+impl ReplayStatus {
+    /// Exit code of the process, non-zero if the replay failed, e.g. for CI.
+    pub fn exit_code(self) -> std::process::ExitCode {
+        match self {
+            ReplayStatus::Failed => std::process::ExitCode::FAILURE,
+            ReplayStatus::Pending | ReplayStatus::Succeeded => std::process::ExitCode::SUCCESS,
+        }
+    }
+}
+
+impl ReplayReport {
+    /// Wall clock time the replay took.
+    pub fn duration(&self) -> Option<NanoDelta> {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => Some(end - start),
+            _ => None,
+        }
+    }
+
+    /// The replay ran to its end, all assertions passed and the rendering did
+    /// not diverge from the recording.
+    pub fn is_success(&self) -> bool {
+        !self.aborted && !self.assertions.has_failures() && !self.divergence.has_diverged()
+    }
+
+    /// Name of the replayed recording in reports.
+    fn recording_name(&self) -> &str {
+        self.recording.as_deref().unwrap_or("replay")
+    }
+
+    fn status(&self) -> &'static str {
+        if self.aborted {
+            "aborted"
+        } else if self.is_success() {
+            "passed"
+        } else {
+            "failed"
+        }
+    }
+
+    // One line per reason of failure.
+    fn failure_messages(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        if self.aborted {
+            messages.push(format!(
+                "Replay aborted after {} / {} frames",
+                self.frames_played, self.num_frames
+            ));
+        }
+        if let Some(divergence) = self.divergence.first_divergence() {
+            messages.push(format!("Rendering diverged at frame {}", divergence.frame_index + 1));
+        }
+        for failure in self.assertions.failures.iter() {
+            messages.push(format!(
+                "Assertion at {} ms failed: {}",
+                failure.assertion.time.as_millis(),
+                failure.message
+            ));
+        }
+        messages
+    }
+}
+
+/// Machine-readable report formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    JUnit,
+    Json,
+}
+
+impl ReportFormat {
+    /// `.xml` files are JUnit reports, `.json` files JSON reports.
+    pub fn from_path(path: &Path) -> Result<Self, ReplayError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("xml") => Ok(Self::JUnit),
+            Some("json") => Ok(Self::Json),
+            _ => Err(ReplayError::UnknownExtension(path.display().to_string())),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JsonAssertionFailure<'a> {
+    time_ns: i64,
+    message: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct JsonReplayReport<'a> {
+    recording: &'a str,
+    status: &'static str,
+    frames_played: usize,
+    num_frames: usize,
+    duration_ms: Option<i64>,
+    /// Index of the first frame whose rendering diverged.
+    divergence_frame: Option<usize>,
+    assertions_passed: usize,
+    assertion_failures: Vec<JsonAssertionFailure<'a>>,
+    /// Number of log lines logged differently than during recording.
+    log_differences: usize,
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render the reports of the replayed recordings.
+pub fn render_reports(reports: &[ReplayReport], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::JUnit => render_junit(reports),
+        ReportFormat::Json => {
+            let entries: Vec<JsonReplayReport> = reports
+                .iter()
+                .map(|report| JsonReplayReport {
+                    recording: report.recording_name(),
+                    status: report.status(),
+                    frames_played: report.frames_played,
+                    num_frames: report.num_frames,
+                    duration_ms: report.duration().map(|duration| duration.as_millis()),
+                    divergence_frame: report
+                        .divergence
+                        .first_divergence()
+                        .map(|divergence| divergence.frame_index),
+                    assertions_passed: report.assertions.passed,
+                    assertion_failures: report
+                        .assertions
+                        .failures
+                        .iter()
+                        .map(|failure| JsonAssertionFailure {
+                            time_ns: failure.assertion.time.as_nanos(),
+                            message: &failure.message,
+                        })
+                        .collect(),
+                    log_differences: report.log_differences.len(),
+                })
+                .collect();
+            // Serializing plain structs into a string cannot fail.
+            serde_json::to_string_pretty(&entries).unwrap_or_default()
+        }
+    }
+}
+
+fn render_junit(reports: &[ReplayReport]) -> String {
+    let num_failures = reports.iter().filter(|report| !report.is_success()).count();
+    let mut xml = String::new();
+    // Writing into a String cannot fail.
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        xml,
+        r#"<testsuite name="egui_replay" tests="{}" failures="{}">"#,
+        reports.len(),
+        num_failures
+    );
+    for report in reports {
+        let seconds = report
+            .duration()
+            .map_or(0.0, |duration| duration.as_nanos() as f64 / NANOS_PER_SECOND as f64);
+        let _ = write!(
+            xml,
+            r#"  <testcase classname="egui_replay" name="{}" time="{:.3}""#,
+            escape_xml(report.recording_name()),
+            seconds
+        );
+        if report.is_success() {
+            let _ = writeln!(xml, "/>");
+            continue;
+        }
+        let messages = report.failure_messages();
+        let _ = writeln!(xml, ">");
+        let _ = writeln!(
+            xml,
+            r#"    <failure message="{}" type="{}">{}</failure>"#,
+            escape_xml(messages.first().map_or("", String::as_str)),
+            report.status(),
+            escape_xml(&messages.join("\n"))
+        );
+        let _ = writeln!(xml, "  </testcase>");
+    }
+    let _ = writeln!(xml, "</testsuite>");
+    xml
+}
+
+/// Write the reports to a file, choosing the format by the file extension.
+pub fn write_reports(path: impl AsRef<Path>, reports: &[ReplayReport]) -> Result<(), ReplayError> {
+    let path = path.as_ref();
+    let format = ReportFormat::from_path(path)?;
+    std::fs::write(path, render_reports(reports, format))?;
+    log::info!("Wrote the replay report to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertions::{Assertion, AssertionKind};
+
+    #[test]
+    fn success_requires_completed_replay() {
+        let report = ReplayReport {
+            start_time: Some(NanoTimestamp::from(1_000)),
+            end_time: Some(NanoTimestamp::from(3_000)),
+            frames_played: 2,
+            num_frames: 2,
+            ..Default::default()
+        };
+
+        assert!(report.is_success());
+        assert_eq!(report.duration(), Some(NanoDelta::from(2_000)));
+        let aborted = ReplayReport {
+            aborted: true,
+            ..report
+        };
+        assert!(!aborted.is_success());
+    }
+
+    #[test]
+    fn divergence_fails_replay() {
+        let mut report = ReplayReport::default();
+
+        report.divergence.compare(0, 1, 2);
+
+        assert!(!report.is_success());
+    }
+
+    fn failed_report() -> ReplayReport {
+        let mut report = ReplayReport {
+            recording: Some("failed.bin".to_string()),
+            frames_played: 3,
+            num_frames: 3,
+            ..Default::default()
+        };
+        report.divergence.compare(1, 1, 2);
+        let assertion = Assertion {
+            time: NanoTimestamp::from(5),
+            kind: AssertionKind::ClipboardContains {
+                text: "<b>".to_string(),
+            },
+        };
+        report
+            .assertions
+            .record(&assertion, Err("Clipboard is empty".to_string()));
+        report
+    }
+
+    #[test]
+    fn json_report_lists_failures() {
+        let reports = vec![failed_report()];
+
+        let json: serde_json::Value = serde_json::from_str(&render_reports(&reports, ReportFormat::Json)).unwrap();
+
+        assert_eq!(json[0]["recording"], "failed.bin");
+        assert_eq!(json[0]["status"], "failed");
+        assert_eq!(json[0]["divergence_frame"], 1);
+        assert_eq!(json[0]["assertion_failures"][0]["message"], "Clipboard is empty");
+    }
+
+    #[test]
+    fn junit_report_escapes_names() {
+        let passed = ReplayReport {
+            recording: Some("<ok>.bin".to_string()),
+            ..Default::default()
+        };
+        let reports = vec![passed, failed_report()];
+
+        let xml = render_reports(&reports, ReportFormat::JUnit);
+
+        assert!(xml.contains(r#"<testsuite name="egui_replay" tests="2" failures="1">"#));
+        assert!(xml.contains(r#"name="&lt;ok&gt;.bin" time="0.000"/>"#));
+        assert!(xml.contains(r#"<failure message="Rendering diverged at frame 2" type="failed">"#));
+    }
+}
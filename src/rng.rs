@@ -0,0 +1,107 @@
+//! A random number generator which is reseeded when a recording is replayed.
+//!
+//! [`ReplayRng`] registers its seed with the [`ReplayManager`], so that the
+//! seed is saved with each recording. When a replay starts, it is reseeded
+//! with the seed it had during recording, so the application draws the same
+//! numbers as during recording.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use crate::replay_events::ReplayManager;
+
+/// A named, seedable random number generator, see the module docs.
+pub struct ReplayRng {
+    name: String,
+    seed: u64,
+    rng: StdRng,
+    // Value of `ReplayManager::replays_started` at the last sync.
+    replays_started: u64,
+}
+
+impl ReplayRng {
+    pub fn new(name: impl Into<String>, seed: u64) -> Self {
+        Self {
+            name: name.into(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            replays_started: 0,
+        }
+    }
+
+    /// A generator with a random seed.
+    pub fn from_entropy(name: impl Into<String>) -> Self {
+        Self::new(name, rand::random())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart the sequence of random numbers from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Register the seed with the manager, and reseed with the recorded seed
+    /// when a replay started since the last call. Call at the start of every
+    /// frame, before drawing random numbers.
+    pub fn sync(&mut self, manager: &mut ReplayManager) {
+        if manager.replays_started() != self.replays_started && manager.is_replaying() {
+            self.replays_started = manager.replays_started();
+            match manager.replay_rng_seed(&self.name) {
+                Some(seed) => self.reseed(seed),
+                None => log::warn!("No seed of {} was recorded, replaying with the current one", self.name),
+            }
+        }
+        manager.register_rng_seed(self.name.clone(), self.seed);
+    }
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+    use crate::replay_file::ReplayFile;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn reseeds_with_the_recorded_seed_on_replay() {
+        let mut manager = ReplayManager::new();
+        let mut rng = ReplayRng::new("world", 1);
+        rng.sync(&mut manager);
+        let recorded = (rng.next_u64(), rng.next_u64());
+
+        let mut replay_file = ReplayFile::new(vec![FrameEvents::new(NanoTimestamp::from(0), vec![])]);
+        replay_file.rng_seeds.insert("world".to_string(), 1);
+        let mut rng = ReplayRng::new("world", 2);
+        manager.start_replay_file(replay_file);
+        rng.sync(&mut manager);
+
+        assert_eq!(rng.seed(), 1);
+        assert_eq!((rng.next_u64(), rng.next_u64()), recorded);
+    }
+}
@@ -0,0 +1,349 @@
+//! Conversion of recordings into human-readable automation scripts.
+//!
+//! The events of a recording are condensed into steps like "click at (x, y)",
+//! "type 'hello'" or "press Ctrl+S", which are rendered either as Rust code for
+//! an `egui_kittest` harness or as YAML for other automation tools.
+
+use std::fmt::Write;
+
+use crate::replay_events::FrameEvents;
+use crate::timestamp::{NanoDelta, NanoTimestamp};
+
+/// Pointer movement below which a press and release form a click, in points.
+const CLICK_MAX_DISTANCE: f32 = 4.0;
+/// Time without input above which a wait step is added.
+const MIN_WAIT: NanoDelta = NanoDelta::from_millis_safe(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptFormat {
+    /// Rust code driving an `egui_kittest::Harness` named `harness`.
+    Rust,
+    /// A YAML list of steps.
+    Yaml,
+}
+
+/// A single automation step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptStep {
+    Click {
+        pos: egui::Pos2,
+        button: egui::PointerButton,
+    },
+    Drag {
+        from: egui::Pos2,
+        to: egui::Pos2,
+        button: egui::PointerButton,
+    },
+    Scroll {
+        delta: egui::Vec2,
+    },
+    Type {
+        text: String,
+    },
+    Press {
+        key: egui::Key,
+        modifiers: egui::Modifiers,
+    },
+    Wait {
+        millis: i64,
+    },
+}
+
+// Keys that produce text are covered by the `Text` events, unless pressed as
+// a shortcut.
+fn is_text_key(key: egui::Key, modifiers: egui::Modifiers) -> bool {
+    let is_character_key = key.name().chars().count() == 1;
+    let is_shortcut = modifiers.ctrl || modifiers.alt || modifiers.command || modifiers.mac_cmd;
+    (is_character_key || key == egui::Key::Space) && !is_shortcut
+}
+
+/// Condense the events of the frames into automation steps.
+pub fn script_steps(frames: &[FrameEvents]) -> Vec<ScriptStep> {
+    let mut steps = Vec::new();
+    let mut pressed_at: Option<(egui::Pos2, egui::PointerButton)> = None;
+    let mut last_time: Option<NanoTimestamp> = None;
+    for frame in frames {
+        if let Some(last_time) = last_time {
+            let idle = frame.time - last_time;
+            if idle >= MIN_WAIT && !frame.events.is_empty() {
+                steps.push(ScriptStep::Wait {
+                    millis: idle.as_millis(),
+                });
+            }
+        }
+        if !frame.events.is_empty() {
+            last_time = Some(frame.time);
+        }
+
+        for event in frame.events.iter() {
+            match event {
+                egui::Event::PointerButton {
+                    pos,
+                    button,
+                    pressed: true,
+                    ..
+                } => pressed_at = Some((*pos, *button)),
+                egui::Event::PointerButton {
+                    pos, pressed: false, ..
+                } => {
+                    if let Some((from, button)) = pressed_at.take() {
+                        if from.distance(*pos) <= CLICK_MAX_DISTANCE {
+                            steps.push(ScriptStep::Click { pos: from, button });
+                        } else {
+                            steps.push(ScriptStep::Drag { from, to: *pos, button });
+                        }
+                    }
+                }
+                egui::Event::MouseWheel { delta, .. } => match steps.last_mut() {
+                    Some(ScriptStep::Scroll { delta: total }) => *total += *delta,
+                    _ => steps.push(ScriptStep::Scroll { delta: *delta }),
+                },
+                egui::Event::Text(text) => match steps.last_mut() {
+                    Some(ScriptStep::Type { text: typed }) => typed.push_str(text),
+                    _ => steps.push(ScriptStep::Type { text: text.clone() }),
+                },
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat: false,
+                    modifiers,
+                    ..
+                } if !is_text_key(*key, *modifiers) => steps.push(ScriptStep::Press {
+                    key: *key,
+                    modifiers: *modifiers,
+                }),
+                _ => {}
+            }
+        }
+    }
+    steps
+}
+
+fn modifiers_name(modifiers: egui::Modifiers) -> String {
+    let mut name = String::new();
+    for (enabled, modifier) in [
+        (modifiers.ctrl, "Ctrl+"),
+        (modifiers.alt, "Alt+"),
+        (modifiers.shift, "Shift+"),
+        (modifiers.mac_cmd, "Cmd+"),
+    ] {
+        if enabled {
+            name.push_str(modifier);
+        }
+    }
+    name
+}
+
+fn rust_modifiers(modifiers: egui::Modifiers) -> String {
+    format!(
+        "egui::Modifiers {{ alt: {}, ctrl: {}, shift: {}, mac_cmd: {}, command: {} }}",
+        modifiers.alt, modifiers.ctrl, modifiers.shift, modifiers.mac_cmd, modifiers.command
+    )
+}
+
+fn rust_pointer_button(pos: egui::Pos2, button: egui::PointerButton, pressed: bool) -> String {
+    format!(
+        "harness.event(egui::Event::PointerButton {{ pos: egui::pos2({:.1}, {:.1}), button: egui::PointerButton::{:?}, \
+         pressed: {}, modifiers: egui::Modifiers::NONE }});",
+        pos.x, pos.y, button, pressed
+    )
+}
+
+fn rust_pointer_moved(pos: egui::Pos2) -> String {
+    format!(
+        "harness.event(egui::Event::PointerMoved(egui::pos2({:.1}, {:.1})));",
+        pos.x, pos.y
+    )
+}
+
+/// Render the steps as a script in the given format.
+pub fn render_script(steps: &[ScriptStep], format: ScriptFormat) -> String {
+    let mut script = String::new();
+    for step in steps {
+        // Writing into a String cannot fail.
+        let _ = match format {
+            ScriptFormat::Rust => write_rust_step(&mut script, step),
+            ScriptFormat::Yaml => write_yaml_step(&mut script, step),
+        };
+    }
+    script
+}
+
+fn write_rust_step(script: &mut String, step: &ScriptStep) -> std::fmt::Result {
+    match step {
+        ScriptStep::Click { pos, button } => {
+            writeln!(script, "// Click at ({:.1}, {:.1})", pos.x, pos.y)?;
+            writeln!(script, "{}", rust_pointer_moved(*pos))?;
+            writeln!(script, "{}", rust_pointer_button(*pos, *button, true))?;
+            writeln!(script, "{}", rust_pointer_button(*pos, *button, false))?;
+        }
+        ScriptStep::Drag { from, to, button } => {
+            writeln!(
+                script,
+                "// Drag from ({:.1}, {:.1}) to ({:.1}, {:.1})",
+                from.x, from.y, to.x, to.y
+            )?;
+            writeln!(script, "{}", rust_pointer_moved(*from))?;
+            writeln!(script, "{}", rust_pointer_button(*from, *button, true))?;
+            writeln!(script, "harness.run();")?;
+            writeln!(script, "{}", rust_pointer_moved(*to))?;
+            writeln!(script, "{}", rust_pointer_button(*to, *button, false))?;
+        }
+        ScriptStep::Scroll { delta } => {
+            writeln!(script, "// Scroll by ({:.1}, {:.1})", delta.x, delta.y)?;
+            writeln!(
+                script,
+                "harness.event(egui::Event::MouseWheel {{ unit: egui::MouseWheelUnit::Point, \
+                 delta: egui::vec2({:.1}, {:.1}), modifiers: egui::Modifiers::NONE }});",
+                delta.x, delta.y
+            )?;
+        }
+        ScriptStep::Type { text } => {
+            writeln!(script, "// Type {:?}", text)?;
+            writeln!(script, "harness.event(egui::Event::Text({:?}.to_owned()));", text)?;
+        }
+        ScriptStep::Press { key, modifiers } => {
+            writeln!(script, "// Press {}{}", modifiers_name(*modifiers), key.name())?;
+            writeln!(
+                script,
+                "harness.key_press_modifiers({}, egui::Key::{:?});",
+                rust_modifiers(*modifiers),
+                key
+            )?;
+        }
+        ScriptStep::Wait { millis } => {
+            writeln!(script, "// Idle for {} ms", millis)?;
+            return Ok(());
+        }
+    }
+    writeln!(script, "harness.run();")
+}
+
+fn write_yaml_step(script: &mut String, step: &ScriptStep) -> std::fmt::Result {
+    match step {
+        ScriptStep::Click { pos, button } => writeln!(
+            script,
+            "- click: {{ x: {:.1}, y: {:.1}, button: {:?} }}",
+            pos.x, pos.y, button
+        ),
+        ScriptStep::Drag { from, to, button } => writeln!(
+            script,
+            "- drag: {{ from: [{:.1}, {:.1}], to: [{:.1}, {:.1}], button: {:?} }}",
+            from.x, from.y, to.x, to.y, button
+        ),
+        ScriptStep::Scroll { delta } => writeln!(script, "- scroll: {{ x: {:.1}, y: {:.1} }}", delta.x, delta.y),
+        // JSON strings are valid YAML strings.
+        ScriptStep::Type { text } => writeln!(script, "- type: {}", serde_json::Value::from(text.as_str())),
+        ScriptStep::Press { key, modifiers } => {
+            writeln!(script, "- press: \"{}{}\"", modifiers_name(*modifiers), key.name())
+        }
+        ScriptStep::Wait { millis } => writeln!(script, "- wait_ms: {}", millis),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(pos: egui::Pos2, pressed: bool) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    fn key(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    fn frame(time: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time), events)
+    }
+
+    #[test]
+    fn events_are_condensed_into_steps() {
+        let pos = egui::pos2(10.0, 20.0);
+        let frames = vec![
+            frame(0, vec![button(pos, true), button(pos, false)]),
+            frame(
+                1,
+                vec![
+                    key(egui::Key::H, egui::Modifiers::NONE),
+                    egui::Event::Text("h".to_string()),
+                ],
+            ),
+            frame(2, vec![egui::Event::Text("i".to_string())]),
+            frame(3, vec![key(egui::Key::S, egui::Modifiers::CTRL)]),
+        ];
+
+        let steps = script_steps(&frames);
+
+        assert_eq!(
+            steps,
+            vec![
+                ScriptStep::Click {
+                    pos,
+                    button: egui::PointerButton::Primary
+                },
+                ScriptStep::Type { text: "hi".to_string() },
+                ScriptStep::Press {
+                    key: egui::Key::S,
+                    modifiers: egui::Modifiers::CTRL
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_between_frames_become_waits() {
+        let pos = egui::pos2(10.0, 20.0);
+        let frames = vec![
+            frame(0, vec![button(pos, true), button(pos, false)]),
+            frame(600_000_000, vec![egui::Event::Text("a".to_string())]),
+        ];
+
+        let steps = script_steps(&frames);
+
+        assert_eq!(
+            steps,
+            vec![
+                ScriptStep::Click {
+                    pos,
+                    button: egui::PointerButton::Primary
+                },
+                ScriptStep::Wait { millis: 600 },
+                ScriptStep::Type { text: "a".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_script() {
+        let steps = vec![
+            ScriptStep::Drag {
+                from: egui::pos2(0.0, 0.0),
+                to: egui::pos2(50.0, 0.0),
+                button: egui::PointerButton::Primary,
+            },
+            ScriptStep::Wait { millis: 600 },
+            ScriptStep::Type {
+                text: "say \"hi\"".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            render_script(&steps, ScriptFormat::Yaml),
+            "- drag: { from: [0.0, 0.0], to: [50.0, 0.0], button: Primary }\n\
+             - wait_ms: 600\n\
+             - type: \"say \\\"hi\\\"\"\n"
+        );
+    }
+}
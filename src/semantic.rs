@@ -0,0 +1,211 @@
+//! Semantic recording of interactions as accessibility actions.
+//!
+//! With [`crate::replay_events::ReplayManager::with_semantic_recording`],
+//! clicks on widgets are recorded as AccessKit actions on the clicked widget
+//! instead of pointer events, and typing is preceded by an action focusing
+//! the widget typed into. Replays of such recordings don't depend on the
+//! layout or the theme of the UI, and drive the app the same way assistive
+//! technology does, so they double as accessibility tests.
+//!
+//! Widgets are found in the accessibility tree of the previous frame, read at
+//! its end by [`crate::replay_events::ReplayManager::on_frame_end`], so that
+//! it works in every integration, including eframe apps. Interactions with
+//! parts of the UI outside of the tree are recorded as raw events.
+
+use std::collections::HashSet;
+
+use egui::accesskit::{self, Action, NodeId};
+
+use crate::event::ReplayAccessAction;
+
+/// Translation of raw events into accessibility actions, see the module docs.
+#[derive(Default)]
+pub struct SemanticRecorder {
+    // Nodes which can be clicked, with their bounds, in tree order.
+    clickable: Vec<(NodeId, egui::Rect)>,
+    focusable: HashSet<NodeId>,
+    focus: Option<NodeId>,
+    // Focus of the last recorded focus action.
+    recorded_focus: Option<NodeId>,
+    // Node which the primary button was pressed on, and the events held back
+    // since, until it is known whether the press is a click.
+    pressed_on: Option<NodeId>,
+    held_back: Vec<egui::Event>,
+}
+
+impl SemanticRecorder {
+    /// Update the widgets from the accessibility tree built in the current
+    /// pass of `ctx`, at the end of the frame. The tree is only built while
+    /// AccessKit is enabled.
+    pub fn update_from_pass(&mut self, ctx: &egui::Context) {
+        let focus = ctx
+            .memory(|memory| memory.focused())
+            .unwrap_or_else(egui::accesskit_root_id);
+        ctx.viewport(|viewport| {
+            if let Some(state) = &viewport.this_pass.accesskit_state {
+                let nodes = state.nodes.iter().map(|(id, node)| (NodeId(id.value()), node));
+                self.update_nodes(nodes, NodeId(focus.value()));
+            }
+        });
+    }
+
+    fn update_nodes<'a>(&mut self, nodes: impl IntoIterator<Item = (NodeId, &'a accesskit::Node)>, focus: NodeId) {
+        self.clickable.clear();
+        self.focusable.clear();
+        for (id, node) in nodes {
+            if let Some(bounds) = node.bounds().filter(|_| node.supports_action(Action::Click)) {
+                let rect = egui::Rect::from_min_max(
+                    egui::pos2(bounds.x0 as f32, bounds.y0 as f32),
+                    egui::pos2(bounds.x1 as f32, bounds.y1 as f32),
+                );
+                self.clickable.push((id, rect));
+            }
+            if node.supports_action(Action::Focus) {
+                self.focusable.insert(id);
+            }
+        }
+        self.focus = Some(focus);
+    }
+
+    /// Forget the state of the previous recording.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Replace the clicks on clickable widgets by click actions, and focus
+    /// the focused widget before typing into it. Events after a press on a
+    /// widget are held back until its release.
+    pub fn translate(&mut self, events: Vec<egui::Event>) -> Vec<egui::Event> {
+        let mut translated = Vec::new();
+        for event in events {
+            match &event {
+                egui::Event::PointerButton {
+                    pos,
+                    button: egui::PointerButton::Primary,
+                    pressed: true,
+                    ..
+                } if self.pressed_on.is_none() => {
+                    if let Some(node) = self.clickable_at(*pos) {
+                        self.pressed_on = Some(node);
+                        self.held_back.push(event);
+                        continue;
+                    }
+                }
+                egui::Event::PointerButton {
+                    pos,
+                    button: egui::PointerButton::Primary,
+                    pressed: false,
+                    ..
+                } => {
+                    if let Some(node) = self.pressed_on.take() {
+                        if self.clickable_at(*pos) == Some(node) {
+                            self.held_back.clear();
+                            translated.push(access_action(ReplayAccessAction::Click, node));
+                        } else {
+                            // E.g. a drag, which has no action.
+                            translated.append(&mut self.held_back);
+                            translated.push(event);
+                        }
+                        continue;
+                    }
+                }
+                egui::Event::Text(_) | egui::Event::Paste(_) | egui::Event::Key { .. } => {
+                    let focus = self.focus.filter(|focus| self.focusable.contains(focus));
+                    if let Some(focus) = focus.filter(|focus| self.recorded_focus != Some(*focus)) {
+                        self.recorded_focus = Some(focus);
+                        translated.push(access_action(ReplayAccessAction::Focus, focus));
+                    }
+                }
+                _ => {}
+            }
+            if self.pressed_on.is_some() {
+                self.held_back.push(event);
+            } else {
+                translated.push(event);
+            }
+        }
+        translated
+    }
+
+    // The topmost clickable widget at `pos`, i.e. the last one in tree order.
+    fn clickable_at(&self, pos: egui::Pos2) -> Option<NodeId> {
+        self.clickable
+            .iter()
+            .rev()
+            .find(|(_, rect)| rect.contains(pos))
+            .map(|(id, _)| *id)
+    }
+}
+
+fn access_action(action: ReplayAccessAction, target: NodeId) -> egui::Event {
+    egui::Event::AccessKitActionRequest(action.into_request(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A node 10 points high at `y`.
+    fn node(role: accesskit::Role, y: f64, action: Action) -> accesskit::Node {
+        let mut node = accesskit::Node::new(role);
+        node.set_bounds(accesskit::Rect::new(0.0, y, 10.0, y + 10.0));
+        node.add_action(action);
+        node
+    }
+
+    fn button(pressed: bool, pos: egui::Pos2) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn clicks_and_typing_become_actions() {
+        let (button_id, text_id) = (NodeId(1), NodeId(2));
+        let mut recorder = SemanticRecorder::default();
+        recorder.update_nodes(
+            [
+                (button_id, &node(accesskit::Role::Button, 0.0, Action::Click)),
+                (text_id, &node(accesskit::Role::TextInput, 20.0, Action::Focus)),
+            ],
+            text_id,
+        );
+
+        let translated = recorder.translate(vec![
+            button(true, egui::pos2(5.0, 5.0)),
+            egui::Event::PointerMoved(egui::pos2(6.0, 6.0)),
+            button(false, egui::pos2(6.0, 6.0)),
+            egui::Event::Text("a".to_string()),
+            egui::Event::Text("b".to_string()),
+        ]);
+
+        assert_eq!(
+            translated,
+            vec![
+                access_action(ReplayAccessAction::Click, button_id),
+                access_action(ReplayAccessAction::Focus, text_id),
+                egui::Event::Text("a".to_string()),
+                egui::Event::Text("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn drags_are_kept_as_raw_events() {
+        let mut recorder = SemanticRecorder::default();
+        recorder.update_nodes(
+            [(NodeId(1), &node(accesskit::Role::Button, 0.0, Action::Click))],
+            NodeId(0),
+        );
+        let events = vec![
+            button(true, egui::pos2(5.0, 5.0)),
+            egui::Event::PointerMoved(egui::pos2(50.0, 5.0)),
+            button(false, egui::pos2(50.0, 5.0)),
+        ];
+
+        assert_eq!(recorder.translate(events.clone()), events);
+    }
+}
@@ -0,0 +1,272 @@
+//! User-editable recording and replay options.
+//!
+//! [`ReplaySettings`] are edited in the settings tab of the replay modal and
+//! persisted in the eframe storage, so that users can change how recordings
+//! are made without recompiling the application.
+
+use std::path::PathBuf;
+
+use egui::Key;
+
+use crate::coalesce::ScrollCoalescing;
+use crate::event_types::EventTypes;
+use crate::keyboard::KeyboardMode;
+use crate::replay_events::ReplaySpeed;
+use crate::simplify::PointerSimplification;
+use crate::timestamp::NanoDelta;
+
+/// Key of the settings in the eframe storage.
+pub const SETTINGS_KEY: &str = "egui_replay_settings";
+
+/// Keys handled by the manager. They are never recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    /// Start and stop recording.
+    pub record: Key,
+    /// Add a marker.
+    pub marker: Key,
+    /// Type an annotation.
+    pub annotation: Key,
+    /// Assert that the focused widget exists.
+    pub assertion: Key,
+    /// Save the flight recorder buffer.
+    pub flight_recorder: Key,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            record: Key::F1,
+            marker: Key::F2,
+            annotation: Key::F3,
+            assertion: Key::F4,
+            flight_recorder: Key::F5,
+        }
+    }
+}
+
+impl Hotkeys {
+    pub fn all(&self) -> [Key; 5] {
+        [
+            self.record,
+            self.marker,
+            self.annotation,
+            self.assertion,
+            self.flight_recorder,
+        ]
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        for (label, key) in [
+            ("Start / stop recording", &mut self.record),
+            ("Add marker", &mut self.marker),
+            ("Add annotation", &mut self.annotation),
+            ("Add assertion", &mut self.assertion),
+            ("Save flight recorder", &mut self.flight_recorder),
+        ] {
+            ui.label(label);
+            egui::ComboBox::from_id_salt(label)
+                .selected_text(key.name())
+                .show_ui(ui, |ui| {
+                    for option in Key::ALL {
+                        changed |= ui.selectable_value(key, *option, option.name()).changed();
+                    }
+                });
+            ui.end_row();
+        }
+        changed
+    }
+}
+
+/// Recording and replay options, see the module docs.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ReplaySettings {
+    /// Save recordings as `.bin` files, or as `.json` otherwise.
+    pub use_bincode: bool,
+    /// See [`crate::replay_events::ReplayManager::with_dual_output`].
+    pub dual_output: bool,
+    /// See [`crate::replay_events::ReplayManager::with_preserve_frame_boundaries`].
+    pub preserve_frame_boundaries: bool,
+    pub pointer_simplification: PointerSimplification,
+    /// See [`crate::replay_events::ReplayManager::with_keyboard_mode`].
+    pub keyboard_mode: KeyboardMode,
+    pub scroll_coalescing: ScrollCoalescing,
+    /// See [`crate::replay_events::ReplayManager::with_recorded_event_types`].
+    pub event_types: EventTypes,
+    /// Directory the recordings are saved to.
+    pub output_dir: PathBuf,
+    pub hotkeys: Hotkeys,
+    pub replay_speed: ReplaySpeed,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            use_bincode: true,
+            dual_output: false,
+            preserve_frame_boundaries: false,
+            pointer_simplification: PointerSimplification::default(),
+            keyboard_mode: KeyboardMode::default(),
+            scroll_coalescing: ScrollCoalescing::default(),
+            event_types: EventTypes::default(),
+            output_dir: PathBuf::from("."),
+            hotkeys: Hotkeys::default(),
+            replay_speed: ReplaySpeed::default(),
+        }
+    }
+}
+
+impl ReplaySettings {
+    /// Restore the settings saved by [`ReplaySettings::save`].
+    pub fn load(storage: &dyn eframe::Storage) -> Option<Self> {
+        eframe::get_value(storage, SETTINGS_KEY)
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, SETTINGS_KEY, self);
+    }
+
+    /// Show the settings as a form, returning whether any of them changed.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::Grid::new("replay_settings").num_columns(2).show(ui, |ui| {
+            ui.label("Format");
+            ui.horizontal(|ui| {
+                changed |= ui.radio_value(&mut self.use_bincode, true, "Binary (.bin)").changed();
+                changed |= ui.radio_value(&mut self.use_bincode, false, "JSON (.json)").changed();
+                changed |= ui.checkbox(&mut self.dual_output, "Save both").changed();
+            });
+            ui.end_row();
+
+            ui.label("Output directory");
+            let mut output_dir = self.output_dir.to_string_lossy().to_string();
+            if ui.text_edit_singleline(&mut output_dir).changed() {
+                self.output_dir = PathBuf::from(output_dir);
+                changed = true;
+            }
+            ui.end_row();
+
+            ui.label("Frame boundaries");
+            changed |= ui
+                .checkbox(&mut self.preserve_frame_boundaries, "Preserve, don't merge frames")
+                .changed();
+            ui.end_row();
+
+            ui.label("Recorded events");
+            changed |= self.event_types.show(ui);
+            ui.end_row();
+
+            ui.label("Pointer moves");
+            changed |= show_pointer_simplification(ui, &mut self.pointer_simplification);
+            ui.end_row();
+
+            ui.label("Scroll and zoom");
+            changed |= show_scroll_coalescing(ui, &mut self.scroll_coalescing);
+            ui.end_row();
+
+            ui.label("Typed text");
+            ui.horizontal(|ui| {
+                let options = [
+                    (KeyboardMode::AsRecorded, "Keys and text"),
+                    (KeyboardMode::TextAuthoritative, "Text only"),
+                    (KeyboardMode::KeyAuthoritative, "Keys only"),
+                ];
+                for (option, label) in options {
+                    changed |= ui.radio_value(&mut self.keyboard_mode, option, label).changed();
+                }
+            });
+            ui.end_row();
+
+            ui.label("Replay speed");
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .radio_value(&mut self.replay_speed, ReplaySpeed::FastForward, "Fast-forward")
+                    .changed();
+                changed |= ui
+                    .radio_value(&mut self.replay_speed, ReplaySpeed::RealTime, "Real time")
+                    .changed();
+            });
+            ui.end_row();
+
+            changed |= self.hotkeys.show(ui);
+        });
+        changed
+    }
+}
+
+fn show_pointer_simplification(ui: &mut egui::Ui, simplification: &mut PointerSimplification) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let options = [
+            (PointerSimplification::Off, "All"),
+            (PointerSimplification::StartEnd, "Start and end"),
+            (PointerSimplification::EveryNth(4), "Every n-th"),
+            (PointerSimplification::Tolerance(2.0), "Simplified path"),
+        ];
+        for (option, label) in options {
+            let selected = std::mem::discriminant(simplification) == std::mem::discriminant(&option);
+            if ui.radio(selected, label).clicked() && !selected {
+                *simplification = option;
+                changed = true;
+            }
+        }
+        match simplification {
+            PointerSimplification::EveryNth(n) => {
+                changed |= ui.add(egui::DragValue::new(n).range(1..=100)).changed();
+            }
+            PointerSimplification::Tolerance(tolerance) => {
+                changed |= ui
+                    .add(egui::DragValue::new(tolerance).range(0.0..=50.0).suffix(" pt"))
+                    .changed();
+            }
+            PointerSimplification::Off | PointerSimplification::StartEnd => {}
+        }
+    });
+    changed
+}
+
+fn show_scroll_coalescing(ui: &mut egui::Ui, coalescing: &mut ScrollCoalescing) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let window = NanoDelta::from_millis_safe(50);
+        let options = [
+            (ScrollCoalescing::Off, "All events"),
+            (ScrollCoalescing::Frame, "Merge per frame"),
+            (ScrollCoalescing::Window(window), "Merge within"),
+        ];
+        for (option, label) in options {
+            let selected = std::mem::discriminant(coalescing) == std::mem::discriminant(&option);
+            if ui.radio(selected, label).clicked() && !selected {
+                *coalescing = option;
+                changed = true;
+            }
+        }
+        if let ScrollCoalescing::Window(window) = coalescing {
+            let mut millis = window.as_millis();
+            if ui
+                .add(egui::DragValue::new(&mut millis).range(1..=1000).suffix(" ms"))
+                .changed()
+            {
+                *window = NanoDelta::from_millis_safe(millis);
+                changed = true;
+            }
+        }
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_settings_use_defaults() {
+        let settings: ReplaySettings = serde_json::from_str(r#"{"use_bincode": false}"#).unwrap();
+
+        assert!(!settings.use_bincode);
+        assert_eq!(settings.hotkeys, Hotkeys::default());
+    }
+}
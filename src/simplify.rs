@@ -0,0 +1,187 @@
+//! Simplification of recorded pointer paths.
+//!
+//! Most pointer moves only matter for hover effects, so by default only the
+//! start of each movement is recorded. Drags of e.g. sliders or drawing tools
+//! need the path in between, so [`PointerSimplification`] trades the size of
+//! recordings for the accuracy of the replayed path.
+
+use std::collections::HashSet;
+
+use crate::replay_events::FrameEvents;
+
+/// How pointer moves are simplified while recording, see
+/// [`crate::replay_events::ReplayManager::with_pointer_simplification`].
+/// A movement is a run of pointer moves without other events in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PointerSimplification {
+    /// Record every pointer move.
+    Off,
+    /// Record only the first move of each movement, and the position of
+    /// button presses and releases. Drags replay as straight lines.
+    #[default]
+    StartEnd,
+    /// Record every n-th move of each movement, and the position of button
+    /// presses and releases.
+    EveryNth(usize),
+    /// Record every pointer move, and drop the moves within the given number
+    /// of points of the simplified path when the recording is saved, see
+    /// [`simplify_pointer_path`].
+    Tolerance(f32),
+}
+
+impl PointerSimplification {
+    /// Whether the move with the given index within its movement is recorded.
+    pub fn records_move(&self, index: usize) -> bool {
+        match self {
+            PointerSimplification::StartEnd => index == 0,
+            PointerSimplification::EveryNth(n) => index % (*n).max(1) == 0,
+            PointerSimplification::Off | PointerSimplification::Tolerance(_) => true,
+        }
+    }
+
+    /// Whether moves are dropped while recording, so that the pointer
+    /// position of button events has to be recorded as a move.
+    pub fn drops_moves(&self) -> bool {
+        matches!(
+            self,
+            PointerSimplification::StartEnd | PointerSimplification::EveryNth(_)
+        )
+    }
+}
+
+/// Drop the pointer moves within `tolerance` points of the path through the
+/// remaining moves, using the Douglas–Peucker algorithm. The first and last
+/// move of each movement are always kept. Frames are kept even if all their
+/// events were dropped.
+pub fn simplify_pointer_path(frames: Vec<FrameEvents>, tolerance: f32) -> Vec<FrameEvents> {
+    // Frame index, event index and position of the moves of each movement.
+    let mut movements: Vec<Vec<(usize, usize, egui::Pos2)>> = Vec::new();
+    let mut movement = Vec::new();
+    let mut viewport_id = None;
+    for (frame_index, frame) in frames.iter().enumerate() {
+        if viewport_id != Some(frame.viewport_id) {
+            movements.push(std::mem::take(&mut movement));
+            viewport_id = Some(frame.viewport_id);
+        }
+        for (event_index, event) in frame.events.iter().enumerate() {
+            match event {
+                egui::Event::PointerMoved(pos) => movement.push((frame_index, event_index, *pos)),
+                _ => movements.push(std::mem::take(&mut movement)),
+            }
+        }
+    }
+    movements.push(movement);
+
+    let mut dropped = HashSet::new();
+    for movement in movements.iter() {
+        let positions: Vec<egui::Pos2> = movement.iter().map(|(_, _, pos)| *pos).collect();
+        let keep = douglas_peucker(&positions, tolerance);
+        for (&(frame_index, event_index, _), keep) in movement.iter().zip(keep) {
+            if !keep {
+                dropped.insert((frame_index, event_index));
+            }
+        }
+    }
+
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(frame_index, mut frame)| {
+            let mut event_index = 0;
+            frame.events.retain(|_| {
+                event_index += 1;
+                !dropped.contains(&(frame_index, event_index - 1))
+            });
+            frame
+        })
+        .collect()
+}
+
+// Which points to keep: the ends, and recursively the point farthest from
+// the segment between the kept points around it, if it is farther than
+// `tolerance`.
+fn douglas_peucker(points: &[egui::Pos2], tolerance: f32) -> Vec<bool> {
+    let mut keep = vec![false; points.len()];
+    let Some(last) = points.len().checked_sub(1) else {
+        return keep;
+    };
+    keep[0] = true;
+    keep[last] = true;
+
+    let mut segments = vec![(0, last)];
+    while let Some((start, end)) = segments.pop() {
+        let farthest = (start + 1..end)
+            .map(|index| (index, distance_to_segment(points[index], points[start], points[end])))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                keep[index] = true;
+                segments.push((start, index));
+                segments.push((index, end));
+            }
+        }
+    }
+    keep
+}
+
+fn distance_to_segment(point: egui::Pos2, start: egui::Pos2, end: egui::Pos2) -> f32 {
+    let segment = end - start;
+    if segment.length_sq() == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(segment) / segment.length_sq()).clamp(0.0, 1.0);
+    point.distance(start + t * segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    fn moved(x: f32, y: f32) -> egui::Event {
+        egui::Event::PointerMoved(egui::pos2(x, y))
+    }
+
+    #[test]
+    fn every_nth_move_is_recorded() {
+        let recorded: Vec<usize> = (0..7)
+            .filter(|index| PointerSimplification::EveryNth(3).records_move(*index))
+            .collect();
+        assert_eq!(recorded, vec![0, 3, 6]);
+        assert!(!PointerSimplification::StartEnd.records_move(1));
+    }
+
+    #[test]
+    fn keeps_corners_of_the_path() {
+        let path = [
+            (0.0, 0.0),
+            (5.0, 0.5),
+            (10.0, 0.0),
+            (10.0, 5.0),
+            (10.5, 10.0),
+            (10.0, 20.0),
+        ];
+        let frames: Vec<FrameEvents> = path
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| FrameEvents::new(NanoTimestamp::from(i as i64), vec![moved(*x, *y)]))
+            .chain(std::iter::once(FrameEvents::new(
+                NanoTimestamp::from(10),
+                vec![egui::Event::Text("a".to_string())],
+            )))
+            .collect();
+
+        let simplified = simplify_pointer_path(frames, 1.0);
+
+        let events: Vec<egui::Event> = simplified.into_iter().flat_map(|frame| frame.events).collect();
+        assert_eq!(
+            events,
+            vec![
+                moved(0.0, 0.0),
+                moved(10.0, 0.0),
+                moved(10.0, 20.0),
+                egui::Event::Text("a".to_string())
+            ]
+        );
+    }
+}
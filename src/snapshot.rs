@@ -0,0 +1,46 @@
+//! Snapshots of the application state, so that replays start from the state
+//! the recording was made in.
+
+/// State of the application which can be saved when a recording starts, and
+/// restored before it is replayed. See [`crate::replay_events::ReplayManager::sync_state`].
+pub trait ReplayStateSnapshot {
+    fn save(&self) -> Vec<u8>;
+
+    /// Restore the state from bytes returned by [`ReplayStateSnapshot::save`].
+    fn restore(&mut self, snapshot: &[u8]);
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use super::*;
+    use crate::replay_events::ReplayManager;
+    use crate::timestamp::NanoTimestamp;
+
+    struct Counter(u8);
+
+    impl ReplayStateSnapshot for Counter {
+        fn save(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+
+        fn restore(&mut self, snapshot: &[u8]) {
+            self.0 = snapshot[0];
+        }
+    }
+
+    #[test]
+    fn replay_starts_from_the_recorded_state() {
+        let mut manager = ReplayManager::new();
+        let mut counter = Counter(3);
+
+        manager.start_recording(NanoTimestamp::from(0));
+        manager.sync_state(&mut counter);
+        counter.0 = 10;
+        manager.sync_state(&mut counter);
+        let recording = manager.recording().clone();
+        manager.start_replay_file(recording);
+        manager.sync_state(&mut counter);
+
+        assert_eq!(counter.0, 3);
+    }
+}
@@ -0,0 +1,94 @@
+//! Temporary storage of recorded frames on disk.
+//!
+//! Long recordings are spilled to a temporary file once they exceed the memory
+//! cap, and read back when the recording is saved.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::replay_events::FrameEvents;
+use crate::replay_file::ReplayError;
+use crate::timestamp::NanoTimestamp;
+
+/// Frames written to a temporary file. The file is removed on drop.
+pub struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    num_frames: usize,
+    num_events: usize,
+}
+
+impl SpillFile {
+    pub fn create(now: NanoTimestamp) -> Result<Self, ReplayError> {
+        let path = std::env::temp_dir().join(format!(
+            "egui_replay_spill_{}_{}.bin",
+            std::process::id(),
+            now.as_nanos()
+        ));
+        let writer = BufWriter::new(File::create(&path)?);
+        Ok(Self {
+            path,
+            writer,
+            num_frames: 0,
+            num_events: 0,
+        })
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    pub fn num_events(&self) -> usize {
+        self.num_events
+    }
+
+    pub fn append(&mut self, frames: &[FrameEvents]) -> Result<(), ReplayError> {
+        for frame in frames {
+            bincode::encode_into_std_write(frame, &mut self.writer, bincode::config::standard())?;
+            self.num_frames += 1;
+            self.num_events += frame.events.len();
+        }
+        Ok(())
+    }
+
+    /// Read back all spilled frames, in the order they were appended.
+    pub fn read_all(&mut self) -> Result<Vec<FrameEvents>, ReplayError> {
+        self.writer.flush()?;
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut frames = Vec::with_capacity(self.num_frames);
+        for _ in 0..self.num_frames {
+            frames.push(bincode::decode_from_std_read(&mut reader, bincode::config::standard())?);
+        }
+        Ok(frames)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove spill file {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spilled_frames_read_back_in_order() {
+        let frames = vec![
+            FrameEvents::new(NanoTimestamp::from(1), vec![egui::Event::Text("a".to_string())]),
+            FrameEvents::new(NanoTimestamp::from(2), vec![egui::Event::Copy, egui::Event::Cut]),
+        ];
+        let mut spill = SpillFile::create(NanoTimestamp::from(42)).unwrap();
+
+        spill.append(&frames[..1]).unwrap();
+        spill.append(&frames[1..]).unwrap();
+
+        assert_eq!(spill.num_frames(), 2);
+        assert_eq!(spill.num_events(), 3);
+        assert_eq!(spill.read_all().unwrap(), frames);
+    }
+}
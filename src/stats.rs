@@ -0,0 +1,126 @@
+//! Summary statistics of a recording.
+
+use std::collections::BTreeMap;
+
+use crate::replay_events::{event_kind, FrameEvents};
+use crate::timestamp::NanoDelta;
+
+/// Statistics computed from the frames of a recording.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayStats {
+    pub num_frames: usize,
+    pub num_events: usize,
+    /// Time between the first and the last frame.
+    pub duration: NanoDelta,
+    /// Number of events of each type, keyed by [`event_kind`].
+    pub events_per_type: BTreeMap<&'static str, usize>,
+    /// Total distance travelled by the pointer, in points.
+    pub pointer_path_length: f32,
+}
+
+impl ReplayStats {
+    pub fn from_frames(frames: &[FrameEvents]) -> Self {
+        let mut stats = Self {
+            num_frames: frames.len(),
+            ..Default::default()
+        };
+        if let (Some(first), Some(last)) = (frames.first(), frames.last()) {
+            stats.duration = last.time - first.time;
+        }
+
+        let mut last_pointer_pos: Option<egui::Pos2> = None;
+        for event in frames.iter().flat_map(|frame| frame.events.iter()) {
+            stats.num_events += 1;
+            *stats.events_per_type.entry(event_kind(event)).or_default() += 1;
+
+            let pointer_pos = match event {
+                egui::Event::PointerMoved(pos) => Some(*pos),
+                egui::Event::PointerButton { pos, .. } => Some(*pos),
+                _ => None,
+            };
+            if let Some(pos) = pointer_pos {
+                if let Some(last_pos) = last_pointer_pos {
+                    stats.pointer_path_length += last_pos.distance(pos);
+                }
+                last_pointer_pos = Some(pos);
+            }
+        }
+        stats
+    }
+
+    pub fn average_events_per_frame(&self) -> f32 {
+        if self.num_frames == 0 {
+            0.0
+        } else {
+            self.num_events as f32 / self.num_frames as f32
+        }
+    }
+
+    /// Show the statistics as a two column grid.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("replay_stats").num_columns(2).show(ui, |ui| {
+            ui.label("Frames");
+            ui.label(self.num_frames.to_string());
+            ui.end_row();
+            ui.label("Events");
+            ui.label(self.num_events.to_string());
+            ui.end_row();
+            ui.label("Duration");
+            ui.label(format!("{:.3} s", self.duration.as_millis() as f64 / 1000.0));
+            ui.end_row();
+            ui.label("Events / frame");
+            ui.label(format!("{:.2}", self.average_events_per_frame()));
+            ui.end_row();
+            ui.label("Pointer path");
+            ui.label(format!("{:.0} pt", self.pointer_path_length));
+            ui.end_row();
+            for (kind, count) in self.events_per_type.iter() {
+                ui.label(*kind);
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    fn frame(time: i64, events: Vec<egui::Event>) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time), events)
+    }
+
+    #[test]
+    fn stats_of_empty_recording() {
+        let stats = ReplayStats::from_frames(&[]);
+
+        assert_eq!(stats, ReplayStats::default());
+        assert_eq!(stats.average_events_per_frame(), 0.0);
+    }
+
+    #[test]
+    fn stats_of_recording() {
+        let frames = vec![
+            frame(1_000, vec![egui::Event::PointerMoved(egui::pos2(0.0, 0.0))]),
+            frame(
+                3_000,
+                vec![
+                    egui::Event::PointerMoved(egui::pos2(3.0, 4.0)),
+                    egui::Event::Text("a".to_string()),
+                ],
+            ),
+        ];
+
+        let stats = ReplayStats::from_frames(&frames);
+
+        assert_eq!(stats.num_frames, 2);
+        assert_eq!(stats.num_events, 3);
+        assert_eq!(stats.duration, NanoDelta::from(2_000));
+        assert_eq!(stats.events_per_type.get("PointerMoved"), Some(&2));
+        assert_eq!(stats.events_per_type.get("Text"), Some(&1));
+        assert_eq!(stats.pointer_path_length, 5.0);
+        assert_eq!(stats.average_events_per_frame(), 1.5);
+    }
+}
@@ -0,0 +1,138 @@
+//! Live streaming of recorded frames to another running instance of the app.
+//!
+//! The sending app records frames as usual and sends each of them over TCP,
+//! the receiving app injects them as soon as they arrive. Frames are encoded
+//! with bincode, one after another.
+
+use std::io::{BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::replay_events::FrameEvents;
+use crate::replay_file::ReplayError;
+
+/// Sends recorded frames to a [`StreamReceiver`]. Frames are written by a
+/// background thread, so that the UI never waits for the network.
+pub struct StreamSender {
+    frames: Sender<FrameEvents>,
+}
+
+impl StreamSender {
+    pub fn connect(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let (frames, receiver) = channel::<FrameEvents>();
+        std::thread::Builder::new()
+            .name("egui_replay_stream_sender".to_string())
+            .spawn(move || {
+                let mut writer = BufWriter::new(stream);
+                for frame in receiver {
+                    if let Err(err) = write_frame(&mut writer, &frame) {
+                        log::error!("Failed to stream UI events: {}", err);
+                        return;
+                    }
+                }
+            })?;
+        Ok(Self { frames })
+    }
+
+    pub fn send(&self, frame: FrameEvents) {
+        // The sender thread logs why it stopped.
+        let _ = self.frames.send(frame);
+    }
+}
+
+fn write_frame(writer: &mut impl Write, frame: &FrameEvents) -> Result<(), ReplayError> {
+    bincode::encode_into_std_write(frame, writer, bincode::config::standard())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Receives frames streamed by [`StreamSender`]s.
+pub struct StreamReceiver {
+    frames: Receiver<FrameEvents>,
+    local_addr: SocketAddr,
+}
+
+impl StreamReceiver {
+    /// Listen for senders on the given address. The context is repainted when
+    /// a frame arrives, so that it is injected even if the app is idle.
+    pub fn bind(addr: impl ToSocketAddrs, ctx: egui::Context) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, frames) = channel();
+        std::thread::Builder::new()
+            .name("egui_replay_stream_receiver".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            log::warn!("Failed to accept UI event stream: {}", err);
+                            continue;
+                        }
+                    };
+                    log::info!("Receiving UI events from {:?}", stream.peer_addr());
+                    let mut reader = BufReader::new(stream);
+                    loop {
+                        match bincode::decode_from_std_read::<FrameEvents, _, _>(
+                            &mut reader,
+                            bincode::config::standard(),
+                        ) {
+                            Ok(frame) => {
+                                if sender.send(frame).is_err() {
+                                    // The app was closed.
+                                    return;
+                                }
+                                ctx.request_repaint();
+                            }
+                            Err(err) => {
+                                log::info!("UI event stream ended: {}", err);
+                                break;
+                            }
+                        }
+                    }
+                }
+            })?;
+        log::info!("Listening for UI event streams on {}", local_addr);
+        Ok(Self { frames, local_addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The next received frame, if any.
+    pub fn try_recv(&self) -> Option<FrameEvents> {
+        self.frames.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::NanoTimestamp;
+
+    #[test]
+    fn frames_are_streamed_in_order() {
+        let receiver = StreamReceiver::bind("127.0.0.1:0", egui::Context::default()).unwrap();
+        let sender = StreamSender::connect(receiver.local_addr()).unwrap();
+        let frames = vec![
+            FrameEvents::new(NanoTimestamp::from(1), vec![egui::Event::Copy]),
+            FrameEvents::new(NanoTimestamp::from(2), vec![egui::Event::Text("a".to_string())]),
+        ];
+
+        for frame in frames.iter() {
+            sender.send(frame.clone());
+        }
+        let mut received = Vec::new();
+        while received.len() < frames.len() {
+            match receiver.try_recv() {
+                Some(frame) => received.push(frame),
+                None => std::thread::sleep(std::time::Duration::from_millis(1)),
+            }
+        }
+
+        assert_eq!(received, frames);
+    }
+}
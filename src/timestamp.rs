@@ -27,19 +27,30 @@
 //!
 //! The type `i64` was chosen over `u64` to allow for negative timestamps, which
 //! are useful for representing time deltas.
+//!
+//! Like the integer operators, `+` and `-` panic on overflow in debug builds
+//! and wrap around in release builds. Use the `checked_*` and `saturating_*`
+//! methods when the operands may be extreme, e.g. for synthetic offsets of
+//! long replays.
+//!
+//! Both types serialize as the bare `i64`. Fields can pick another
+//! representation with the modules in [`serde`](self::serde), e.g.
+//! `#[serde(with = "crate::timestamp::serde::rfc3339")]`.
 
 use core::fmt;
 use std::{
     convert::TryFrom,
     fmt::{Debug, Display},
     num::ParseIntError,
-    ops::{Add, Sub},
+    ops::{Add, Div, Mul, Sub},
     str::FromStr,
 };
 
-use chrono::{DateTime, FixedOffset, Local, TimeDelta, TimeZone, Utc};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
 use thiserror::Error;
 use zeroize::Zeroize;
+
+use crate::clock::Clock;
 /// A timestamp in nanoseconds in the UTC timezone.
 ///
 /// The dates that can be represented as nanoseconds are between
@@ -52,8 +63,8 @@ use zeroize::Zeroize;
     PartialOrd,
     Ord,
     Default,
-    serde::Serialize,
-    serde::Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
     Hash,
     Zeroize,
 )]
@@ -63,7 +74,7 @@ pub struct NanoTimestamp(i64);
 ///
 /// Any time you subtract two timestamps, you get a `NanoDelta`.
 #[derive(
-    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, serde::Serialize, serde::Deserialize, Hash,
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, ::serde::Serialize, ::serde::Deserialize, Hash,
 )]
 pub struct NanoDelta(i64);
 
@@ -87,9 +98,77 @@ pub const NANOS_PER_HOUR: i64 = NANOS_PER_MINUTE * 60;
 pub const NANOS_PER_DAY: i64 = NANOS_PER_HOUR * 24;
 
 impl NanoTimestamp {
+    /// The earliest representable time, 1677-09-21T00:12:43.145224192Z.
+    pub const MIN: Self = Self(i64::MIN);
+    /// The latest representable time, 2262-04-11T23:47:16.854775807Z.
+    pub const MAX: Self = Self(i64::MAX);
+
     pub const fn zero() -> Self {
         Self(0)
     }
+    /// Whether `dt` is between [`NanoTimestamp::MIN`] and
+    /// [`NanoTimestamp::MAX`].
+    pub fn is_representable<Tz: TimeZone>(dt: &DateTime<Tz>) -> bool {
+        dt.timestamp_nanos_opt().is_some()
+    }
+    /// The current time of the system clock.
+    pub fn now() -> Self {
+        // We use chrono here as it is platform agnostic.
+        Self::try_from(Utc::now()).expect("Current time is representable until the year 2262")
+    }
+    /// The current time of `clock`, e.g. a `ManualClock` in tests.
+    pub fn now_with<C: Clock + ?Sized>(clock: &C) -> Self {
+        clock.now()
+    }
+    /// `self + delta`, `None` on overflow.
+    pub const fn checked_add(self, delta: NanoDelta) -> Option<Self> {
+        match self.0.checked_add(delta.0) {
+            Some(nanos) => Some(Self(nanos)),
+            None => None,
+        }
+    }
+    /// `self - delta`, `None` on overflow.
+    pub const fn checked_sub(self, delta: NanoDelta) -> Option<Self> {
+        match self.0.checked_sub(delta.0) {
+            Some(nanos) => Some(Self(nanos)),
+            None => None,
+        }
+    }
+    /// `self + delta`, clamped to the representable range.
+    pub const fn saturating_add(self, delta: NanoDelta) -> Self {
+        Self(self.0.saturating_add(delta.0))
+    }
+    /// `self - delta`, clamped to the representable range.
+    pub const fn saturating_sub(self, delta: NanoDelta) -> Self {
+        Self(self.0.saturating_sub(delta.0))
+    }
+    /// The latest multiple of `unit` since the epoch at or before `self`, e.g.
+    /// the start of the minute with `NanoDelta::from_minutes_safe(1)`. Panics
+    /// if `unit` is not positive.
+    pub const fn floor_to(self, unit: NanoDelta) -> Self {
+        assert!(unit.0 > 0, "unit must be positive");
+        Self(self.0 - self.0.rem_euclid(unit.0))
+    }
+    /// The earliest multiple of `unit` since the epoch at or after `self`.
+    /// Panics if `unit` is not positive.
+    pub const fn ceil_to(self, unit: NanoDelta) -> Self {
+        let floor = self.floor_to(unit);
+        if floor.0 == self.0 {
+            floor
+        } else {
+            Self(floor.0 + unit.0)
+        }
+    }
+    /// The nearest multiple of `unit` since the epoch, rounding halfway cases
+    /// up. Panics if `unit` is not positive.
+    pub const fn round_to(self, unit: NanoDelta) -> Self {
+        let floor = self.floor_to(unit);
+        if self.0 - floor.0 >= unit.0 - (self.0 - floor.0) {
+            Self(floor.0 + unit.0)
+        } else {
+            floor
+        }
+    }
     pub const fn as_nanos(&self) -> i64 {
         self.0
     }
@@ -117,6 +196,11 @@ impl NanoTimestamp {
     pub fn as_rfc3339(&self) -> String {
         self.as_utc().to_rfc3339()
     }
+    /// Like RFC 3339 in UTC, with dashes instead of the colons which are
+    /// invalid in Windows file names, e.g. `2024-05-01T12-30-45.000000000Z`.
+    pub fn as_file_name(&self) -> String {
+        self.as_utc().format("%Y-%m-%dT%H-%M-%S%.9fZ").to_string()
+    }
 
     pub const fn from_nanos(nanos: i64) -> Self {
         Self(nanos)
@@ -176,16 +260,18 @@ impl NanoTimestamp {
 
     pub fn from_rfc2822(rfc2822: &str) -> Result<Self, TimestampError> {
         let dt = DateTime::<FixedOffset>::parse_from_rfc2822(rfc2822)?;
-        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| {
-            TimestampError::Overflow("DateTime value out of i64 nanosecond range".into())
-        })
+        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| out_of_range(&dt))
     }
 
     pub fn from_rfc3339(rfc3339: &str) -> Result<Self, TimestampError> {
         let dt = DateTime::<FixedOffset>::parse_from_rfc3339(rfc3339)?;
-        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| {
-            TimestampError::Overflow("DateTime value out of i64 nanosecond range".into())
-        })
+        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| out_of_range(&dt))
+    }
+
+    /// Parse a timestamp formatted by [`NanoTimestamp::as_file_name`].
+    pub fn from_file_name(file_name: &str) -> Result<Self, TimestampError> {
+        let dt = NaiveDateTime::parse_from_str(file_name, "%Y-%m-%dT%H-%M-%S%.fZ")?.and_utc();
+        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| out_of_range(&dt))
     }
 
     pub fn as_utc(&self) -> DateTime<Utc> {
@@ -205,6 +291,28 @@ impl NanoDelta {
     pub const fn zero() -> Self {
         Self(0)
     }
+    /// `self + other`, `None` on overflow.
+    pub const fn checked_add(self, other: NanoDelta) -> Option<Self> {
+        match self.0.checked_add(other.0) {
+            Some(nanos) => Some(Self(nanos)),
+            None => None,
+        }
+    }
+    /// `self - other`, `None` on overflow.
+    pub const fn checked_sub(self, other: NanoDelta) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(nanos) => Some(Self(nanos)),
+            None => None,
+        }
+    }
+    /// `self + other`, clamped to the representable range.
+    pub const fn saturating_add(self, other: NanoDelta) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+    /// `self - other`, clamped to the representable range.
+    pub const fn saturating_sub(self, other: NanoDelta) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
     pub const fn as_days(&self) -> i64 {
         NanoTimestamp::from_nanos(self.0).as_days()
     }
@@ -266,6 +374,24 @@ impl NanoDelta {
     pub const fn from_nanos(nanos: i64) -> Self {
         Self(nanos)
     }
+    /// `secs` seconds and `nanos` nanoseconds, like `std::time::Duration::new`.
+    pub const fn new(secs: i64, nanos: i64) -> Self {
+        Self(secs * NANOS_PER_SECOND + nanos)
+    }
+    /// The fractional part in whole milliseconds, negative for negative
+    /// deltas.
+    pub const fn subsec_millis(&self) -> i64 {
+        self.subsec_nanos() / NANOS_PER_MILLI
+    }
+    /// The fractional part in whole microseconds, negative for negative
+    /// deltas.
+    pub const fn subsec_micros(&self) -> i64 {
+        self.subsec_nanos() / NANOS_PER_MICRO
+    }
+    /// The fractional part in nanoseconds, negative for negative deltas.
+    pub const fn subsec_nanos(&self) -> i64 {
+        self.0 % NANOS_PER_SECOND
+    }
 }
 
 impl Display for NanoTimestamp {
@@ -353,9 +479,7 @@ impl TryFrom<DateTime<Utc>> for NanoTimestamp {
     type Error = TimestampError;
 
     fn try_from(dt: DateTime<Utc>) -> Result<Self, Self::Error> {
-        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| {
-            TimestampError::Overflow("DateTime<Utc> value out of i64 nanosecond range".into())
-        })
+        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| out_of_range(&dt))
     }
 }
 
@@ -363,15 +487,23 @@ impl TryFrom<DateTime<Local>> for NanoTimestamp {
     type Error = TimestampError;
 
     fn try_from(dt: DateTime<Local>) -> Result<Self, Self::Error> {
-        dt.with_timezone(&Utc)
-            .timestamp_nanos_opt()
-            .map(Self)
-            .ok_or_else(|| {
-                TimestampError::Overflow("DateTime<Local> value out of i64 nanosecond range".into())
-            })
+        dt.timestamp_nanos_opt().map(Self).ok_or_else(|| out_of_range(&dt))
     }
 }
 
+// The error for a date outside of the range of `NanoTimestamp`.
+fn out_of_range<Tz: TimeZone>(dt: &DateTime<Tz>) -> TimestampError
+where
+    Tz::Offset: Display,
+{
+    TimestampError::Overflow(format!(
+        "{} is outside of the representable range from {} to {}",
+        dt.to_rfc3339(),
+        NanoTimestamp::MIN.as_rfc3339(),
+        NanoTimestamp::MAX.as_rfc3339()
+    ))
+}
+
 impl From<NanoTimestamp> for DateTime<Utc> {
     fn from(ts: NanoTimestamp) -> Self {
         Utc.timestamp_nanos(ts.0)
@@ -448,6 +580,37 @@ impl Sub<NanoDelta> for NanoDelta {
         NanoDelta::from(self.0 - rhs.0)
     }
 }
+impl Mul<i64> for NanoDelta {
+    type Output = NanoDelta;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        NanoDelta::from(self.0 * rhs)
+    }
+}
+/// Scales by a factor, truncating towards zero and saturating at the
+/// representable range.
+impl Mul<f64> for NanoDelta {
+    type Output = NanoDelta;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        NanoDelta::from((self.0 as f64 * rhs) as i64)
+    }
+}
+impl Div<i64> for NanoDelta {
+    type Output = NanoDelta;
+
+    fn div(self, rhs: i64) -> Self::Output {
+        NanoDelta::from(self.0 / rhs)
+    }
+}
+/// The ratio of two deltas, e.g. the progress of a replay.
+impl Div<NanoDelta> for NanoDelta {
+    type Output = f64;
+
+    fn div(self, rhs: NanoDelta) -> Self::Output {
+        self.0 as f64 / rhs.0 as f64
+    }
+}
 
 impl TryFrom<TimeDelta> for NanoDelta {
     type Error = TimestampError;
@@ -479,6 +642,125 @@ impl TryFrom<NanoDelta> for std::time::Duration {
     }
 }
 
+impl TryFrom<std::time::Duration> for NanoDelta {
+    type Error = TimestampError;
+
+    fn try_from(duration: std::time::Duration) -> Result<Self, Self::Error> {
+        i64::try_from(duration.as_nanos())
+            .map(NanoDelta)
+            .map_err(|_| TimestampError::Overflow("Duration is too large to be represented as i64 nanoseconds".into()))
+    }
+}
+
+impl TryFrom<std::time::SystemTime> for NanoTimestamp {
+    type Error = TimestampError;
+
+    fn try_from(time: std::time::SystemTime) -> Result<Self, Self::Error> {
+        match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(since_epoch) => NanoDelta::try_from(since_epoch).map(|delta| NanoTimestamp(delta.0)),
+            Err(err) => NanoDelta::try_from(err.duration()).map(|delta| NanoTimestamp(-delta.0)),
+        }
+    }
+}
+
+impl From<NanoTimestamp> for std::time::SystemTime {
+    fn from(ts: NanoTimestamp) -> Self {
+        let since_epoch = std::time::Duration::from_nanos(ts.0.unsigned_abs());
+        if ts.0 < 0 {
+            std::time::UNIX_EPOCH - since_epoch
+        } else {
+            std::time::UNIX_EPOCH + since_epoch
+        }
+    }
+}
+
+/// Representations of [`NanoTimestamp`] fields for `#[serde(with = ...)]`.
+pub mod serde {
+    use std::fmt;
+
+    use ::serde::{de, Deserializer, Serializer};
+
+    use super::NanoTimestamp;
+
+    /// RFC 3339 strings in human-readable formats like JSON, e.g.
+    /// `2024-05-01T12:30:45.123+00:00`, and nanoseconds in the others, so that
+    /// bincode recordings don't grow. Both are accepted when deserializing
+    /// human-readable formats, for files written before.
+    pub mod rfc3339 {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(time: &NanoTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&time.as_rfc3339())
+            } else {
+                serializer.serialize_i64(time.as_nanos())
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NanoTimestamp, D::Error> {
+            if deserializer.is_human_readable() {
+                deserializer.deserialize_any(TimestampVisitor)
+            } else {
+                deserializer.deserialize_i64(TimestampVisitor)
+            }
+        }
+    }
+
+    /// Milliseconds since the epoch, for interop with JavaScript. Sub-millisecond
+    /// precision is lost.
+    pub mod millis {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(time: &NanoTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(time.as_millis())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NanoTimestamp, D::Error> {
+            let millis: i64 = ::serde::Deserialize::deserialize(deserializer)?;
+            NanoTimestamp::from_millis(millis).map_err(de::Error::custom)
+        }
+    }
+
+    /// Nanoseconds since the epoch, the default representation.
+    pub mod nanos {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(time: &NanoTimestamp, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(time.as_nanos())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NanoTimestamp, D::Error> {
+            let nanos: i64 = ::serde::Deserialize::deserialize(deserializer)?;
+            Ok(NanoTimestamp::from_nanos(nanos))
+        }
+    }
+
+    // Nanoseconds or an RFC 3339 string.
+    struct TimestampVisitor;
+
+    impl de::Visitor<'_> for TimestampVisitor {
+        type Value = NanoTimestamp;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("nanoseconds since the epoch or an RFC 3339 timestamp")
+        }
+
+        fn visit_i64<E: de::Error>(self, nanos: i64) -> Result<Self::Value, E> {
+            Ok(NanoTimestamp::from_nanos(nanos))
+        }
+
+        fn visit_u64<E: de::Error>(self, nanos: u64) -> Result<Self::Value, E> {
+            i64::try_from(nanos)
+                .map(NanoTimestamp::from_nanos)
+                .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(nanos), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, rfc3339: &str) -> Result<Self::Value, E> {
+            NanoTimestamp::from_rfc3339(rfc3339).map_err(E::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryInto;
@@ -491,6 +773,120 @@ mod tests {
         assert_eq!(ts.0, 1000);
     }
 
+    #[test]
+    fn file_name_timestamp_has_no_colons() {
+        let ts = NanoTimestamp::from_secs_safe(1_714_566_645) + NanoDelta::from_millis_safe(123);
+        assert_eq!(ts.as_file_name(), "2024-05-01T12-30-45.123000000Z");
+        assert_eq!(NanoTimestamp::from_file_name(&ts.as_file_name()).unwrap(), ts);
+    }
+
+    #[test]
+    fn now_with_clock() {
+        let clock = crate::clock::ManualClock::new();
+        clock.advance_to(NanoTimestamp::from(42));
+        assert_eq!(NanoTimestamp::now_with(&clock), NanoTimestamp::from(42));
+        assert!(NanoTimestamp::now() > NanoTimestamp::from_secs_safe(1_714_566_645));
+    }
+
+    #[test]
+    fn checked_and_saturating_arithmetic() {
+        let max = NanoTimestamp::from(i64::MAX);
+        let one = NanoDelta::from(1);
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(max.saturating_add(one), max);
+        assert_eq!(max.checked_sub(one), Some(NanoTimestamp::from(i64::MAX - 1)));
+        let min = NanoTimestamp::from(i64::MIN);
+        assert_eq!(min.saturating_sub(one), min);
+        assert_eq!(NanoDelta::from(i64::MIN).checked_sub(one), None);
+        assert_eq!(NanoDelta::from(i64::MAX).saturating_add(one), NanoDelta::from(i64::MAX));
+        assert_eq!(one.checked_add(one), Some(NanoDelta::from(2)));
+    }
+
+    #[test]
+    fn nano_delta_scaling_and_division() {
+        let delta = NanoDelta::from_millis_safe(10);
+        assert_eq!(delta * 3, NanoDelta::from_millis_safe(30));
+        assert_eq!(delta * 0.5, NanoDelta::from_millis_safe(5));
+        assert_eq!(delta / 4, NanoDelta::from_micros_safe(2_500));
+        assert_eq!(delta / NanoDelta::from_millis_safe(40), 0.25);
+    }
+
+    #[test]
+    fn nano_delta_mixed_units() {
+        let delta = NanoDelta::new(2, 345_678_901);
+        assert_eq!(delta.as_secs(), 2);
+        assert_eq!(delta.subsec_millis(), 345);
+        assert_eq!(delta.subsec_micros(), 345_678);
+        assert_eq!(delta.subsec_nanos(), 345_678_901);
+        let duration: std::time::Duration = delta.try_into().unwrap();
+        assert_eq!(duration, std::time::Duration::new(2, 345_678_901));
+        assert_eq!(NanoDelta::try_from(duration).unwrap(), delta);
+        assert!(NanoDelta::try_from(std::time::Duration::MAX).is_err());
+    }
+
+    #[test]
+    fn nano_timestamp_system_time_round_trip() {
+        for nanos in [1_714_566_645_123_456_789, -1_000_000_001] {
+            let ts = NanoTimestamp::from(nanos);
+            let system_time = std::time::SystemTime::from(ts);
+            assert_eq!(NanoTimestamp::try_from(system_time).unwrap(), ts);
+        }
+    }
+
+    #[derive(::serde::Serialize, ::serde::Deserialize, PartialEq, Debug)]
+    struct Record {
+        #[serde(with = "super::serde::rfc3339")]
+        time: NanoTimestamp,
+        #[serde(with = "super::serde::millis")]
+        time_millis: NanoTimestamp,
+    }
+
+    #[test]
+    fn serde_representations() {
+        let time = NanoTimestamp::from(1_714_566_645_123_456_789);
+        let time_millis = time.floor_to(NanoDelta::from_millis_safe(1));
+        let record = Record { time, time_millis };
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["time"], "2024-05-01T12:30:45.123456789+00:00");
+        assert_eq!(json["time_millis"], 1_714_566_645_123i64);
+        assert_eq!(serde_json::from_value::<Record>(json).unwrap(), record);
+        let legacy = serde_json::json!({"time": 1_714_566_645_123_456_789i64, "time_millis": 0});
+        assert_eq!(serde_json::from_value::<Record>(legacy).unwrap().time, time);
+
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&record, config).unwrap();
+        let plain = bincode::serde::encode_to_vec((time.as_nanos(), time.as_millis()), config).unwrap();
+        assert_eq!(bytes, plain);
+        let (decoded, _): (Record, usize) = bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(decoded, record);
+    }
+
+    #[test]
+    fn nano_timestamp_alignment() {
+        let second = NanoDelta::from_secs_safe(1);
+        let (twelve, thirteen) = (NanoTimestamp::from_secs_safe(12), NanoTimestamp::from_secs_safe(13));
+        let ts = NanoTimestamp::from_millis_safe(12_500);
+        assert_eq!(ts.floor_to(second), twelve);
+        assert_eq!(ts.ceil_to(second), thirteen);
+        assert_eq!(ts.round_to(second), thirteen);
+        assert_eq!(NanoTimestamp::from_millis_safe(12_499).round_to(second), twelve);
+        assert_eq!(twelve.ceil_to(second), twelve);
+        // Timestamps before the epoch are floored towards the past.
+        let before_epoch = NanoTimestamp::from_millis_safe(-500);
+        assert_eq!(before_epoch.floor_to(second), NanoTimestamp::from_secs_safe(-1));
+    }
+
+    #[test]
+    fn representable_range() {
+        assert_eq!(NanoTimestamp::MIN.as_rfc3339(), "1677-09-21T00:12:43.145224192+00:00");
+        assert_eq!(NanoTimestamp::MAX.as_rfc3339(), "2262-04-11T23:47:16.854775807+00:00");
+        let too_late = DateTime::parse_from_rfc3339("2300-01-01T00:00:00Z").unwrap();
+        assert!(!NanoTimestamp::is_representable(&too_late));
+        let err = NanoTimestamp::from_rfc3339("2300-01-01T00:00:00Z").unwrap_err();
+        assert!(err.to_string().contains("outside of the representable range"));
+    }
+
     #[test]
     fn nano_delta_creation() {
         let delta = NanoDelta::from(1000);
@@ -653,10 +1049,10 @@ mod tests {
         assert_eq!(dt.timestamp_nanos_opt().unwrap(), 1_123_456_789_000_000);
         assert_eq!(dt.to_rfc2822(), "Wed, 14 Jan 1970 00:04:16 +0000");
         assert_eq!(dt.to_rfc3339(), "1970-01-14T00:04:16.789+00:00");
-        let dt_paris = DateTime::<Utc>::from(dt).with_timezone(&chrono_tz::Europe::Paris);
+        let dt_paris = dt.with_timezone(&chrono_tz::Europe::Paris);
         assert_eq!(dt_paris.to_rfc2822(), "Wed, 14 Jan 1970 01:04:16 +0100");
         assert_eq!(dt_paris.to_rfc3339(), "1970-01-14T01:04:16.789+01:00");
-        let dt_newyork = DateTime::<Utc>::from(dt).with_timezone(&chrono_tz::America::New_York);
+        let dt_newyork = dt.with_timezone(&chrono_tz::America::New_York);
         assert_eq!(dt_newyork.to_rfc2822(), "Tue, 13 Jan 1970 19:04:16 -0500");
         assert_eq!(dt_newyork.to_rfc3339(), "1970-01-13T19:04:16.789-05:00");
     }
@@ -0,0 +1,439 @@
+//! Validation of recordings without replaying them.
+//!
+//! [`validate_replay`] loads a recording and looks for problems which would
+//! make its replay misbehave, e.g. to check recordings in CI before they are
+//! used as tests. [`repair_key_releases`] fixes keys which would stay
+//! pressed after the replay.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::codec::{binary_format_version, BINARY_FORMAT_VERSION};
+use crate::replay_file::{ReplayError, ReplayFile};
+use crate::timestamp::NanoTimestamp;
+
+/// A problem found by [`validate_replay`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayWarning {
+    /// The binary file was written in an older format version, `None` if it
+    /// was written before the format was versioned. It is still loadable, but
+    /// only saved again in the current version.
+    OutdatedFormat {
+        version: Option<u32>,
+    },
+    /// An event which failed to load, and would be skipped.
+    SkippedEvent {
+        frame_index: usize,
+        reason: String,
+    },
+    NoFrames,
+    /// A frame recorded before the frame preceding it.
+    TimeNotMonotonic {
+        frame_index: usize,
+        time: NanoTimestamp,
+        previous: NanoTimestamp,
+    },
+    /// A frame recorded at the limit of the representable time range, or too
+    /// far from the first frame to compute the time in between, likely a
+    /// corrupt or saturated timestamp.
+    TimeOutOfRange {
+        frame_index: usize,
+        time: NanoTimestamp,
+    },
+    /// The screen rectangle was not recorded, so pointer positions cannot be
+    /// checked.
+    NoScreenRect,
+    /// A pointer position of the root viewport outside of the recorded screen
+    /// rectangle.
+    PointerOutsideScreen {
+        frame_index: usize,
+        pos: egui::Pos2,
+    },
+    /// A key pressed and not released before it is pressed again or the
+    /// recording ends, e.g. because its release was filtered out or lost. It
+    /// would stay pressed after the replay, see [`repair_key_releases`].
+    KeyNotReleased {
+        frame_index: usize,
+        key: egui::Key,
+    },
+    /// A key released without being pressed, e.g. because the recording
+    /// started while it was held down.
+    KeyReleasedWithoutPress {
+        frame_index: usize,
+        key: egui::Key,
+    },
+}
+
+impl std::fmt::Display for ReplayWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayWarning::OutdatedFormat { version: Some(version) } => write!(
+                f,
+                "Written in format version {}, the current version is {}",
+                version, BINARY_FORMAT_VERSION
+            ),
+            ReplayWarning::OutdatedFormat { version: None } => write!(f, "Written before the format was versioned"),
+            ReplayWarning::SkippedEvent { frame_index, reason } => {
+                write!(f, "Frame {}: skipped an event: {}", frame_index, reason)
+            }
+            ReplayWarning::NoFrames => write!(f, "No frames were recorded"),
+            ReplayWarning::TimeNotMonotonic {
+                frame_index,
+                time,
+                previous,
+            } => write!(
+                f,
+                "Frame {}: recorded at {}, before the previous frame at {}",
+                frame_index,
+                time.as_rfc3339(),
+                previous.as_rfc3339()
+            ),
+            ReplayWarning::TimeOutOfRange { frame_index, time } => write!(
+                f,
+                "Frame {}: recorded at {}, at the limit of the time range from {} to {}",
+                frame_index,
+                time.as_rfc3339(),
+                NanoTimestamp::MIN.as_rfc3339(),
+                NanoTimestamp::MAX.as_rfc3339()
+            ),
+            ReplayWarning::NoScreenRect => write!(f, "No screen rectangle was recorded"),
+            ReplayWarning::PointerOutsideScreen { frame_index, pos } => {
+                write!(
+                    f,
+                    "Frame {}: pointer at {:?} is outside of the screen",
+                    frame_index, pos
+                )
+            }
+            ReplayWarning::KeyNotReleased { frame_index, key } => {
+                write!(
+                    f,
+                    "Frame {}: key {} is pressed but never released",
+                    frame_index,
+                    key.name()
+                )
+            }
+            ReplayWarning::KeyReleasedWithoutPress { frame_index, key } => {
+                write!(
+                    f,
+                    "Frame {}: key {} is released without a press",
+                    frame_index,
+                    key.name()
+                )
+            }
+        }
+    }
+}
+
+/// Load the recording at `path` and check it, see [`validate_recording`].
+/// Binary files are also checked to be of the current format version.
+/// Fails only if the file cannot be loaded at all.
+pub fn validate_replay(path: impl AsRef<Path>) -> Result<Vec<ReplayWarning>, ReplayError> {
+    let path = path.as_ref();
+    let mut warnings = Vec::new();
+    if path.extension().is_some_and(|extension| extension == "bin") {
+        let version = binary_format_version(&std::fs::read(path)?)?;
+        if version != Some(BINARY_FORMAT_VERSION) {
+            warnings.push(ReplayWarning::OutdatedFormat { version });
+        }
+    }
+
+    let (replay_file, diagnostics) = ReplayFile::load_lenient(path)?;
+    warnings.extend(
+        diagnostics
+            .skipped_events
+            .into_iter()
+            .map(|skipped| ReplayWarning::SkippedEvent {
+                frame_index: skipped.frame_index,
+                reason: skipped.reason,
+            }),
+    );
+    warnings.extend(validate_recording(&replay_file));
+    Ok(warnings)
+}
+
+/// Check that the frames are ordered by time within the representable range,
+/// that key presses and releases are paired, and that pointer positions are
+/// within the recorded screen rectangle.
+pub fn validate_recording(replay_file: &ReplayFile) -> Vec<ReplayWarning> {
+    let mut warnings = Vec::new();
+    if replay_file.frames.is_empty() {
+        warnings.push(ReplayWarning::NoFrames);
+    }
+
+    let start_time = replay_file.start_time().unwrap_or_default();
+    for (frame_index, frame) in replay_file.frames.iter().enumerate() {
+        let at_limit = frame.time == NanoTimestamp::MIN || frame.time == NanoTimestamp::MAX;
+        let offset = frame.time.as_nanos().checked_sub(start_time.as_nanos());
+        if at_limit || offset.is_none() {
+            warnings.push(ReplayWarning::TimeOutOfRange {
+                frame_index,
+                time: frame.time,
+            });
+        }
+    }
+
+    for (frame_index, pair) in replay_file.frames.windows(2).enumerate() {
+        if pair[1].time < pair[0].time {
+            warnings.push(ReplayWarning::TimeNotMonotonic {
+                frame_index: frame_index + 1,
+                time: pair[1].time,
+                previous: pair[0].time,
+            });
+        }
+    }
+
+    let (missing_releases, unpressed_releases) = unbalanced_keys(replay_file);
+    let mut key_warnings: Vec<_> = missing_releases
+        .iter()
+        .map(|release| ReplayWarning::KeyNotReleased {
+            frame_index: release.pressed_frame_index,
+            key: release.key,
+        })
+        .chain(
+            unpressed_releases
+                .into_iter()
+                .map(|(frame_index, key)| ReplayWarning::KeyReleasedWithoutPress { frame_index, key }),
+        )
+        .collect();
+    // Stable, so that missing releases come first within a frame.
+    key_warnings.sort_by_key(|warning| match warning {
+        ReplayWarning::KeyNotReleased { frame_index, .. } => *frame_index,
+        ReplayWarning::KeyReleasedWithoutPress { frame_index, .. } => *frame_index,
+        _ => 0,
+    });
+    warnings.extend(key_warnings);
+
+    let Some(screen_rect) = replay_file.screen_rect else {
+        if !replay_file.frames.is_empty() {
+            warnings.push(ReplayWarning::NoScreenRect);
+        }
+        return warnings;
+    };
+    for (frame_index, frame) in replay_file.frames.iter().enumerate() {
+        // Only the screen of the root viewport is recorded.
+        if frame.viewport_id != egui::ViewportId::ROOT {
+            continue;
+        }
+        for event in frame.events.iter() {
+            let pos = match event {
+                egui::Event::PointerMoved(pos) => *pos,
+                egui::Event::PointerButton { pos, .. } => *pos,
+                egui::Event::Touch { pos, .. } => *pos,
+                _ => continue,
+            };
+            if !screen_rect.contains(pos) {
+                warnings.push(ReplayWarning::PointerOutsideScreen { frame_index, pos });
+            }
+        }
+    }
+    warnings
+}
+
+/// Insert the key releases missing from the recording, see
+/// [`ReplayWarning::KeyNotReleased`], so that no key stays pressed during or
+/// after the replay. A key pressed again is released right before, a key
+/// never released at the end of the last frame of its viewport. Returns the
+/// number of inserted releases.
+pub fn repair_key_releases(replay_file: &mut ReplayFile) -> usize {
+    let (missing_releases, _) = unbalanced_keys(replay_file);
+    // Insert from the back, so that the positions of the others stay valid.
+    for release in missing_releases.iter().rev() {
+        let frame = &mut replay_file.frames[release.frame_index];
+        let event = egui::Event::Key {
+            key: release.key,
+            physical_key: release.physical_key,
+            pressed: false,
+            repeat: false,
+            modifiers: frame.modifiers,
+        };
+        frame.events.insert(release.event_index, event);
+    }
+    missing_releases.len()
+}
+
+// A key release missing from a recording, belonging before the event at
+// `event_index` of the frame at `frame_index`.
+struct MissingRelease {
+    pressed_frame_index: usize,
+    frame_index: usize,
+    event_index: usize,
+    key: egui::Key,
+    physical_key: Option<egui::Key>,
+}
+
+// The releases missing from the recording ordered by position, and the frame
+// index and key of releases without a press. Keys are tracked per viewport,
+// as each viewport has its own input.
+fn unbalanced_keys(replay_file: &ReplayFile) -> (Vec<MissingRelease>, Vec<(usize, egui::Key)>) {
+    let mut pressed_keys = HashMap::new();
+    let mut last_frames = HashMap::new();
+    let mut missing_releases = Vec::new();
+    let mut unpressed_releases = Vec::new();
+    for (frame_index, frame) in replay_file.frames.iter().enumerate() {
+        last_frames.insert(frame.viewport_id, frame_index);
+        for (event_index, event) in frame.events.iter().enumerate() {
+            let egui::Event::Key {
+                key,
+                physical_key,
+                pressed,
+                repeat,
+                ..
+            } = event
+            else {
+                continue;
+            };
+            let id = (frame.viewport_id, *key);
+            if !*pressed {
+                if pressed_keys.remove(&id).is_none() {
+                    unpressed_releases.push((frame_index, *key));
+                }
+                continue;
+            }
+            // Repeats are sent while the key is held down.
+            let previous = pressed_keys.insert(id, (frame_index, *physical_key));
+            if let (Some((pressed_frame_index, physical_key)), false) = (previous, *repeat) {
+                missing_releases.push(MissingRelease {
+                    pressed_frame_index,
+                    frame_index,
+                    event_index,
+                    key: *key,
+                    physical_key,
+                });
+            }
+        }
+    }
+    for ((viewport_id, key), (pressed_frame_index, physical_key)) in pressed_keys {
+        let frame_index = last_frames[&viewport_id];
+        missing_releases.push(MissingRelease {
+            pressed_frame_index,
+            frame_index,
+            event_index: replay_file.frames[frame_index].events.len(),
+            key,
+            physical_key,
+        });
+    }
+    missing_releases.sort_by_key(|release| {
+        (
+            release.frame_index,
+            release.event_index,
+            release.pressed_frame_index,
+            release.key.name(),
+        )
+    });
+    (missing_releases, unpressed_releases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_events::FrameEvents;
+
+    fn frame(time: i64, pos: egui::Pos2) -> FrameEvents {
+        FrameEvents::new(NanoTimestamp::from(time), vec![egui::Event::PointerMoved(pos)])
+    }
+
+    #[test]
+    fn finds_unordered_frames_and_pointers_outside_of_the_screen() {
+        let mut replay_file = ReplayFile::new(vec![
+            frame(1, egui::pos2(10.0, 10.0)),
+            frame(3, egui::pos2(10.0, 10.0)),
+            frame(2, egui::pos2(200.0, 10.0)),
+        ]);
+        assert!(validate_recording(&replay_file).contains(&ReplayWarning::NoScreenRect));
+
+        replay_file.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 100.0)));
+        assert_eq!(
+            validate_recording(&replay_file),
+            vec![
+                ReplayWarning::TimeNotMonotonic {
+                    frame_index: 2,
+                    time: NanoTimestamp::from(2),
+                    previous: NanoTimestamp::from(3),
+                },
+                ReplayWarning::PointerOutsideScreen {
+                    frame_index: 2,
+                    pos: egui::pos2(200.0, 10.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_frames_at_the_limit_of_the_time_range() {
+        let replay_file = ReplayFile::new(vec![
+            frame(1, egui::pos2(10.0, 10.0)),
+            FrameEvents::new(NanoTimestamp::MAX, vec![]),
+        ]);
+
+        let warning = ReplayWarning::TimeOutOfRange {
+            frame_index: 1,
+            time: NanoTimestamp::MAX,
+        };
+        assert!(validate_recording(&replay_file).contains(&warning));
+    }
+
+    fn key(key: egui::Key, pressed: bool) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn missing_key_releases_are_repaired() {
+        let mut replay_file = ReplayFile::new(vec![
+            FrameEvents::new(
+                NanoTimestamp::from(1),
+                vec![key(egui::Key::B, false), key(egui::Key::A, true)],
+            ),
+            FrameEvents::new(NanoTimestamp::from(2), vec![key(egui::Key::A, true)]),
+            FrameEvents::new(NanoTimestamp::from(3), vec![egui::Event::Text("a".to_string())]),
+        ]);
+        replay_file.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 100.0)));
+        assert_eq!(
+            validate_recording(&replay_file),
+            vec![
+                ReplayWarning::KeyNotReleased {
+                    frame_index: 0,
+                    key: egui::Key::A,
+                },
+                ReplayWarning::KeyReleasedWithoutPress {
+                    frame_index: 0,
+                    key: egui::Key::B,
+                },
+                ReplayWarning::KeyNotReleased {
+                    frame_index: 1,
+                    key: egui::Key::A,
+                },
+            ]
+        );
+
+        assert_eq!(repair_key_releases(&mut replay_file), 2);
+        assert_eq!(
+            replay_file.frames[1].events,
+            vec![key(egui::Key::A, false), key(egui::Key::A, true)]
+        );
+        assert_eq!(replay_file.frames[2].events[1], key(egui::Key::A, false));
+        assert_eq!(
+            validate_recording(&replay_file),
+            vec![ReplayWarning::KeyReleasedWithoutPress {
+                frame_index: 0,
+                key: egui::Key::B,
+            }]
+        );
+    }
+
+    #[test]
+    fn saved_recording_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("validate.bin");
+        let mut replay_file = ReplayFile::new(vec![frame(1, egui::pos2(10.0, 10.0))]);
+        replay_file.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 100.0)));
+        replay_file.save(&path).unwrap();
+
+        assert_eq!(validate_replay(&path).unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Replay for existing eframe apps without changing them.
+//!
+//! [`wrap_app`] decorates an `eframe::App` with a [`ReplayManager`], which
+//! gets the raw input before the app and draws its windows around the app's
+//! UI. Calls the wrapper doesn't need are forwarded to the app as they are.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::clock::{Clock, MonotonicClock};
+use crate::replay_events::ReplayManager;
+use crate::report::ReplayStatus;
+use crate::timestamp::NanoTimestamp;
+
+/// Record and replay the UI of `app` with a default manager, which starts
+/// replaying on startup when requested by the environment. Use
+/// [`ReplayWrapper::new`] to configure the manager.
+pub fn wrap_app(app: Box<dyn eframe::App>) -> impl eframe::App {
+    ReplayWrapper::new(app, ReplayManager::new().with_auto_replay_from_env())
+}
+
+/// An `eframe::App` with replay, see the module docs.
+pub struct ReplayWrapper {
+    app: Box<dyn eframe::App>,
+    replay_manager: ReplayManager,
+    // Outcome of the replay on startup, set when the app exits.
+    replay_status: Rc<Cell<Option<ReplayStatus>>>,
+}
+
+impl ReplayWrapper {
+    /// Wrap `app` with `replay_manager`, e.g. created with
+    /// [`ReplayManager::from_storage`] to restore its settings.
+    pub fn new(app: Box<dyn eframe::App>, replay_manager: ReplayManager) -> Self {
+        Self {
+            app,
+            replay_manager,
+            replay_status: Rc::default(),
+        }
+    }
+
+    /// Set `status` to the outcome of the replay on startup when the app
+    /// exits, e.g. to exit the process with [`ReplayStatus::exit_code`].
+    pub fn with_replay_status(mut self, status: Rc<Cell<Option<ReplayStatus>>>) -> Self {
+        self.replay_status = status;
+        self
+    }
+
+    pub fn replay_manager(&self) -> &ReplayManager {
+        &self.replay_manager
+    }
+
+    pub fn replay_manager_mut(&mut self) -> &mut ReplayManager {
+        &mut self.replay_manager
+    }
+}
+
+impl eframe::App for ReplayWrapper {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.replay_manager.on_frame_update(ctx);
+        self.app.update(ctx, frame);
+        self.replay_manager.show_hud(ctx);
+        self.replay_manager.on_frame_end(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.replay_manager.save_state(storage);
+        self.app.save(storage);
+    }
+
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        self.replay_status.set(self.replay_manager.auto_replay_status());
+        self.app.on_exit(gl);
+    }
+
+    fn auto_save_interval(&self) -> std::time::Duration {
+        self.app.auto_save_interval()
+    }
+
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        self.app.clear_color(visuals)
+    }
+
+    fn persist_egui_memory(&self) -> bool {
+        self.app.persist_egui_memory()
+    }
+
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        let now: NanoTimestamp = MonotonicClock::shared().now();
+        self.replay_manager.on_raw_input_update(now, ctx, raw_input);
+        self.app.raw_input_hook(ctx, raw_input);
+    }
+}
+
+#[cfg(all(test, feature = "replay"))]
+mod tests {
+    use eframe::App;
+
+    use super::*;
+
+    // Counts the events it gets in its raw input hook.
+    #[derive(Default)]
+    struct CountingApp {
+        num_events: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl eframe::App for CountingApp {
+        fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+
+        fn raw_input_hook(&mut self, _ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+            self.num_events.set(self.num_events.get() + raw_input.events.len());
+        }
+    }
+
+    #[test]
+    fn app_gets_the_raw_input_too() {
+        let app = CountingApp::default();
+        let num_events = app.num_events.clone();
+        let mut wrapper = ReplayWrapper::new(Box::new(app), ReplayManager::new());
+        let mut raw_input = egui::RawInput {
+            events: vec![
+                egui::Event::Key {
+                    key: egui::Key::F1,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: egui::Modifiers::NONE,
+                },
+                egui::Event::Text("a".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        wrapper.raw_input_hook(&egui::Context::default(), &mut raw_input);
+
+        assert!(wrapper.replay_manager().is_recording());
+        assert_eq!(num_events.get(), 2);
+    }
+}